@@ -10,6 +10,73 @@ fn from_rgb(c: &mut criterion::Criterion) {
     });
 }
 
+/// Returns colours in pseudo-random order.  Colours in sequential order make
+/// branches easy to predict.  Images don’t, so conversion of colours in
+/// random order is measured as well.
+fn shuffled_colours() -> Vec<u32> {
+    let mut state = 0x2545_f491u32;
+    (0..(1 << 16))
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state & 0xffffff
+        })
+        .collect()
+}
+
+fn from_rgb_shuffled(c: &mut criterion::Criterion) {
+    let colours = shuffled_colours();
+    c.bench_function("convert from True Colour in random order", move |b| {
+        b.iter(|| {
+            for &rgb in colours.iter() {
+                criterion::black_box(ansi_colours::ansi256_from_rgb(rgb));
+            }
+        })
+    });
+}
+
+fn from_u32_shuffled(c: &mut criterion::Criterion) {
+    // Packed colours go through the conversion without being unpacked while
+    // tuples need to be packed first.  Compare the two on the same colours.
+    let colours = shuffled_colours();
+    let triples = colours
+        .iter()
+        .map(|&rgb| ((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8))
+        .collect::<Vec<_>>();
+    c.bench_function("convert packed colours in random order", move |b| {
+        b.iter(|| {
+            for &rgb in colours.iter() {
+                criterion::black_box(ansi_colours::ansi256_from_u32(rgb));
+            }
+        })
+    });
+    c.bench_function("convert colour tuples in random order", move |b| {
+        b.iter(|| {
+            for &rgb in triples.iter() {
+                criterion::black_box(ansi_colours::ansi256_from_rgb(rgb));
+            }
+        })
+    });
+}
+
+fn from_rgb_many(c: &mut criterion::Criterion) {
+    let mut pixels = vec![[0; 3]; 1 << 16];
+    let mut output = vec![0; pixels.len()];
+    c.bench_function("convert from True Colour in batches", move |b| {
+        b.iter(|| {
+            for start in (0..(1 << 24)).step_by(pixels.len()) {
+                for (i, pixel) in pixels.iter_mut().enumerate() {
+                    let rgb = (start + i as u32).to_be_bytes();
+                    *pixel = [rgb[1], rgb[2], rgb[3]];
+                }
+                ansi_colours::ansi256_from_rgb_many(&pixels, &mut output);
+                criterion::black_box(&output);
+            }
+        })
+    });
+}
+
 fn to_rgb(c: &mut criterion::Criterion) {
     c.bench_function("convert to True Colour", move |b| {
         b.iter(|| {
@@ -20,5 +87,12 @@ fn to_rgb(c: &mut criterion::Criterion) {
     });
 }
 
-criterion_group!(benches, from_rgb, to_rgb);
+criterion_group!(
+    benches,
+    from_rgb,
+    from_rgb_shuffled,
+    from_u32_shuffled,
+    from_rgb_many,
+    to_rgb
+);
 criterion_main!(benches);
@@ -13,9 +13,66 @@
 //
 // You should have received a copy of the GNU Lesser General Public License
 // along with ansi_colours.  If not, see <http://www.gnu.org/licenses/>.
-/// The ANSI colour palette.
+/// The ANSI colour palette.  At run time colours are obtained with [`colour`]
+/// and the table is used in tests only.
+#[cfg(test)]
+pub(crate) static ANSI_COLOURS: [u32; 256] = COLOURS;
+
+/// Default values of the system colours, i.e. the first 16 colours of the
+/// palette.  Colours of the 6×6×6 cube and the greyscale ramp follow simple
+/// formulæ so only the system colours need to be stored.
+pub(crate) static SYSTEM_COLOURS: [u32; 16] = {
+    let mut colours = [0; 16];
+    let mut idx = 0;
+    while idx < 16 {
+        colours[idx] = COLOURS[idx];
+        idx += 1;
+    }
+    colours
+};
+
+/// Returns colour at given index in the palette using default values of the
+/// system colours.  Colours of the 6×6×6 cube and greyscale ramp are
+/// calculated rather than looked up.
+#[inline]
+pub(crate) fn colour(idx: u8) -> u32 {
+    if idx < 16 {
+        SYSTEM_COLOURS[usize::from(idx)]
+    } else {
+        extended_colour(idx)
+    }
+}
+
+/// Returns colour at given index in the 6×6×6 cube or the greyscale ramp.  The
+/// index must not be less than 16.
+#[inline]
+pub(crate) fn extended_colour(idx: u8) -> u32 {
+    debug_assert!(idx >= 16, "not an extended colour: {}", idx);
+    if idx < 232 {
+        let idx = idx - 16;
+        let level = |coord: u8| u32::from(CUBE_LEVELS[usize::from(coord)]);
+        (level(idx / 36) << 16) | (level(idx / 6 % 6) << 8) | level(idx % 6)
+    } else {
+        (u32::from(idx - 232) * 10 + 8) * 0x010101
+    }
+}
+
+/// The ANSI colour palette as components of each colour.
+pub(crate) const PALETTE: [[u8; 3]; 256] = {
+    let mut palette = [[0; 3]; 256];
+    let mut idx = 0;
+    while idx < 256 {
+        let rgb = COLOURS[idx];
+        palette[idx] = [(rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8];
+        idx += 1;
+    }
+    palette
+};
+
+/// The ANSI colour palette.  This is a constant so that it can be used in
+/// constant expressions.  Use [`colour`] at run time.
 #[rustfmt::skip]
-pub(crate) static ANSI_COLOURS: [u32; 256] = [
+const COLOURS: [u32; 256] = [
     // The 16 system colours as used by default by xterm.  Taken
     // from XTerm-col.ad distributed with xterm source code.
     0x000000, 0xcd0000, 0x00cd00, 0xcdcd00,
@@ -105,8 +162,12 @@ pub(crate) static ANSI_COLOURS: [u32; 256] = [
 /// conversion for grey colours blazing fast.
 ///
 /// There’s a unit test that verifies that those are the best indexes.
+///
+/// The table consists of 30 runs of equal indexes and is stored in that form
+/// in `GREY_RUNS`.  This constant is used to build it and in tests.
 #[rustfmt::skip]
-pub(crate) static ANSI256_FROM_GREY: [u8; 256] = [
+#[cfg_attr(feature = "tableless", allow(dead_code))]
+pub(crate) const ANSI256_FROM_GREY: [u8; 256] = [
      16,  16,  16,  16,  16, 232, 232, 232,
     232, 232, 232, 232, 232, 232, 233, 233,
     233, 233, 233, 233, 233, 233, 233, 233,
@@ -141,41 +202,275 @@ pub(crate) static ANSI256_FROM_GREY: [u8; 256] = [
     231, 231, 231, 231, 231, 231, 231, 231,
 ];
 
-fn to_triple(rgb: u32) -> (u8, u8, u8) {
+/// Returns iterator over colours of the palette other than the system colours,
+/// i.e. colours of the 6×6×6 cube and the greyscale ramp.
+pub(crate) fn extended_colours() -> impl Iterator<Item = u32> + Clone {
+    (16..=255).map(extended_colour)
+}
+
+/// Run-length encoded [`ANSI256_FROM_GREY`] table.
+///
+/// `values` lists index of each run of equal indexes and `levels` component
+/// value of shade of grey at that index.  Run containing given luminance is
+/// found as `bases[lum / 16] + offset` where the two-bit offset is stored at
+/// bit `lum % 4 × 2` of `offsets[lum / 4]`.  This takes 144 bytes rather than
+/// 256 bytes of the table (and 1 KiB of palette to look up the colour) while
+/// decoding with a few shifts.
+#[cfg(not(feature = "tableless"))]
+pub(crate) struct GreyRuns {
+    bases: [u8; 16],
+    offsets: [u8; 64],
+    values: [u8; 32],
+    levels: [u8; 32],
+}
+
+#[cfg(not(feature = "tableless"))]
+pub(crate) static GREY_RUNS: GreyRuns = {
+    let mut runs = GreyRuns {
+        bases: [0; 16],
+        offsets: [0; 64],
+        values: [0; 32],
+        levels: [0; 32],
+    };
+    let mut run = 0;
+    let mut lum = 0;
+    while lum < 256 {
+        let idx = ANSI256_FROM_GREY[lum];
+        if lum > 0 && idx != ANSI256_FROM_GREY[lum - 1] {
+            run += 1;
+        }
+        runs.values[run] = idx;
+        runs.levels[run] = COLOURS[idx as usize] as u8;
+        if lum % 16 == 0 {
+            runs.bases[lum / 16] = run as u8;
+        }
+        let offset = run - runs.bases[lum / 16] as usize;
+        assert!(offset < 4, "too many runs in a block");
+        runs.offsets[lum / 4] |= (offset << (lum % 4 * 2)) as u8;
+        lum += 1;
+    }
+    runs
+};
+
+/// Returns index of a colour in 256-colour ANSI palette approximating shade of
+/// grey with given luminance.
+#[cfg(not(feature = "tableless"))]
+#[inline]
+pub(crate) fn ansi256_from_grey(lum: u8) -> u8 {
+    nearest_grey(lum).0
+}
+
+/// Returns index and colour of a shade of grey in 256-colour ANSI palette
+/// approximating shade of grey with given luminance.
+#[cfg(not(feature = "tableless"))]
+#[inline]
+fn nearest_grey(lum: u8) -> (u8, u32) {
+    let lum = usize::from(lum);
+    let offset = (GREY_RUNS.offsets[lum / 4] >> (lum % 4 * 2)) & 3;
+    let run = usize::from(GREY_RUNS.bases[lum / 16] + offset) % 32;
+    (
+        GREY_RUNS.values[run],
+        u32::from(GREY_RUNS.levels[run]) * 0x010101,
+    )
+}
+
+/// Returns index and colour of a shade of grey in 256-colour ANSI palette
+/// approximating shade of grey with given luminance.
+#[cfg(feature = "tableless")]
+#[inline]
+fn nearest_grey(lum: u8) -> (u8, u32) {
+    let idx = ansi256_from_grey(lum);
+    (idx, colour(idx))
+}
+
+/// Returns index of a colour in 256-colour ANSI palette approximating shade of
+/// grey with given luminance.  Gives the same results as [`ANSI256_FROM_GREY`]
+/// lookup table but calculates them.
+#[cfg(feature = "tableless")]
+pub(crate) fn ansi256_from_grey(lum: u8) -> u8 {
+    if lum < 5 {
+        16
+    } else if lum >= 247 {
+        231
+    } else if (92..=216).contains(&lum) && (lum - 92) % 40 < 5 {
+        // Greys of the 6×6×6 cube, i.e. rgb(95, 95, 95) through rgb(215, 215,
+        // 215), approximate luminance within [-3, 1] range of their value.
+        59 + (lum - 92) / 40 * 43
+    } else {
+        // Elsewhere the greyscale ramp is used.  Past the middle of the ramp,
+        // ΔE*₀₀ favours lighter shade on ties.
+        let offset = if lum < 114 { 4 } else { 3 };
+        232 + ((lum - offset) / 10).min(23)
+    }
+}
+
+/// Returns index of a colour in 256-colour ANSI palette approximating shade of
+/// grey given with 16-bit precision.
+pub(crate) fn ansi256_from_grey16(value: u16) -> u8 {
+    let (lum, rem) = ((value / 257) as u8, value % 257);
+    let darker = ansi256_from_grey(lum);
+    if rem == 0 {
+        return darker;
+    }
+    // The value lies strictly between two 8-bit shades so the nearest grey is
+    // the one approximating either of them.  Since 257 is odd and grey levels
+    // are integers, the value is never equally distant from both.
+    let lighter = ansi256_from_grey(lum + 1);
+    let distance = |idx: u8| {
+        let level = i32::from(colour(idx) as u8) * 257;
+        (level - i32::from(value)).abs()
+    };
+    if distance(lighter) < distance(darker) {
+        lighter
+    } else {
+        darker
+    }
+}
+
+/// Values of components for each coordinate of the 6×6×6 colour cube.
+pub(crate) const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+pub(crate) fn to_triple(rgb: u32) -> (u8, u8, u8) {
     ((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8)
 }
 
 /// Returns index of a colour in 256-colour ANSI palette approximating given
 /// sRGB colour.
+///
+/// The colour is kept in its packed `0xRRGGBB` form throughout.  Luminance,
+/// cube coordinates and distances read the components with shifts and masks
+/// and candidate colours are built as packed integers, so nothing is unpacked
+/// into a tuple and repacked.
+///
+/// The grey candidate (found by luminance) and the cube candidate (found by
+/// [`cube_thresholds`]) are still determined separately and the better of the
+/// two is picked with a conditional move.  Unless `tableless` crate feature is
+/// enabled, neither lookup branches and so the whole conversion has no
+/// data-dependent branches which matters for unpredictable input such as
+/// pixels of an image.  With the feature, the grey candidate is calculated by
+/// [`ansi256_from_grey`] which does branch on the luminance.
 #[inline]
 pub(crate) fn ansi256_from_rgb(rgb: u32) -> u8 {
-    let (r, g, b) = to_triple(rgb);
+    let (grey_index, grey_rgb) = nearest_grey(packed_luminance(rgb));
+    let grey_distance = packed_distance(rgb, grey_rgb);
+    let (cube_index, cube_rgb) = cube_index(rgb);
+    if packed_distance(rgb, cube_rgb) < grey_distance {
+        cube_index
+    } else {
+        grey_index
+    }
+}
 
-    let grey_index = ANSI256_FROM_GREY[luminance(r, g, b) as usize];
-    let grey_distance = distance((r, g, b), ANSI_COLOURS[grey_index as usize]);
-    let (cube_index, cube_rgb) = cube_index(r, g, b);
-    if distance((r, g, b), cube_rgb) < grey_distance {
+/// Returns index of a colour in 256-colour ANSI palette approximating given
+/// sRGB colour with 16-bit components.
+///
+/// Candidates are chosen the same way as for the colour rounded to 8-bit
+/// components but distances to them are measured using full precision.  For
+/// colours whose components are multiples of 257 (i.e. exact 8-bit values)
+/// this gives the same result as [`ansi256_from_rgb`].
+pub(crate) fn ansi256_from_rgb16((r, g, b): (u16, u16, u16)) -> u8 {
+    let rgb8 = u32::from_be_bytes([0, round16(r), round16(g), round16(b)]);
+    let (grey_index, grey_rgb) = nearest_grey(packed_luminance(rgb8));
+    let (cube_index, cube_rgb) = cube_index(rgb8);
+    if distance16((r, g, b), cube_rgb) < distance16((r, g, b), grey_rgb) {
         cube_index
     } else {
         grey_index
     }
 }
 
-fn cube_index(r: u8, g: u8, b: u8) -> (u8, u32) {
-    let r = cube_index_red(r);
-    let g = cube_index_green(g);
-    let b = cube_index_blue(b);
+/// Converts 16-bit colour component into 8-bit one rounding to nearest.
+#[inline]
+pub(crate) fn round16(value: u16) -> u8 {
+    // Since 257 is odd, the exact quotient is never half-way between two
+    // integers and so this never needs to break ties.
+    ((u32::from(value) + 128) / 257) as u8
+}
+
+/// Like [`distance`] but takes colour with 16-bit components.  The result is
+/// 257³ times the value [`distance`] returns for colours whose components are
+/// exact 8-bit values.
+fn distance16((xr, xg, xb): (u16, u16, u16), y: u32) -> u64 {
+    let (yr, yg, yb) = to_triple(y);
+    let scale = |v: u8| i64::from(v) * 257;
+    let r_sum = i64::from(xr) + scale(yr);
+    let r = i64::from(xr) - scale(yr);
+    let g = i64::from(xg) - scale(yg);
+    let b = i64::from(xb) - scale(yb);
+    let d = (1024 * 257 + r_sum) * r * r
+        + 2048 * 257 * g * g
+        + (1534 * 257 - r_sum) * b * b;
+    d as u64
+}
+
+/// Returns index of a colour in 256-colour ANSI palette approximating given
+/// sRGB colour taking into account given values of the system colours.  In
+/// case of ties, colours from outside of the system colours range are
+/// preferred.
+pub(crate) fn ansi256_from_rgb_with_system(rgb: u32, system: &[u32; 16]) -> u8 {
+    let index = ansi256_from_rgb(rgb);
+    let best = packed_distance(rgb, colour(index));
+    let system_index = nearest(rgb, system.iter().copied());
+    if packed_distance(rgb, system[usize::from(system_index)]) < best {
+        system_index
+    } else {
+        index
+    }
+}
+
+/// Returns index of a system colour (i.e. one of the first 16 colours of the
+/// palette) which best approximates given sRGB colour.  Uses default XTerm
+/// colours for the system colours unless they have been overridden.
+pub(crate) fn ansi16_from_rgb(rgb: u32) -> u8 {
+    nearest(rgb, crate::system::snapshot().iter().copied())
+}
+
+/// Returns index of the colour from given sequence which best approximates
+/// given sRGB colour.  If there are multiple closest colours, returns index of
+/// the first one.  Returns zero if the sequence is empty.
+pub(crate) fn nearest(rgb: u32, colours: impl Iterator<Item = u32>) -> u8 {
+    let mut best = (u32::MAX, 0);
+    for (idx, colour) in colours.enumerate() {
+        let dist = packed_distance(rgb, colour);
+        if dist < best.0 {
+            best = (dist, idx as u8);
+        }
+    }
+    best.1
+}
+
+/// Returns index and colour of an entry in the 6×6×6 cube approximating given
+/// sRGB colour.
+fn cube_index(rgb: u32) -> (u8, u32) {
+    let r = cube_index_red((rgb >> 16) as u8);
+    let g = cube_index_green((rgb >> 8) as u8);
+    let b = cube_index_blue(rgb as u8);
     (r.0 + g.0 + b.0, r.1 + g.1 + b.1)
 }
 
+/// Thresholds at which components of red, green and blue channels switch to
+/// the next coordinate of the 6×6×6 cube.
 #[rustfmt::skip]
-fn cube_thresholds(v: u8, a: u8, b: u8, c: u8, d: u8, e: u8) -> (u8, u32) {
-    if      v < a { (0,   0) }
-    else if v < b { (1,  95) }
-    else if v < c { (2, 135) }
-    else if v < d { (3, 175) }
-    else if v < e { (4, 215) }
-    else          { (5, 255) }
+pub(crate) const CUBE_THRESHOLDS: [[u8; 5]; 3] = [
+    [38, 115, 155, 196, 235],
+    [36, 116, 154, 195, 235],
+    [35, 115, 155, 195, 235],
+];
+
+/// Returns coordinate in the 6×6×6 cube and corresponding component value for
+/// given component and channel’s thresholds.
+///
+/// The coordinate is the number of thresholds the component reaches.  It’s
+/// calculated without branching since for colours of an image the outcome of
+/// comparisons is hard to predict and mispredicted branches dominate the cost
+/// of the conversion.
+#[inline]
+pub(crate) fn cube_thresholds(v: u8, thresholds: [u8; 5]) -> (u8, u32) {
+    let mut coord = 0;
+    for &threshold in thresholds.iter() {
+        coord += u8::from(v >= threshold);
+    }
+    (coord, u32::from(CUBE_LEVELS[usize::from(coord)]))
 }
 
 // The next three functions approximate a pure colour by an entry in the 6×6×6
@@ -184,30 +479,37 @@ fn cube_thresholds(v: u8, a: u8, b: u8, c: u8, d: u8, e: u8) -> (u8, u32) {
 // important to better approximate dark colours than light colours.
 
 fn cube_index_red(v: u8) -> (u8, u32) {
-    let (i, v) = cube_thresholds(v, 38, 115, 155, 196, 235);
+    let (i, v) = cube_thresholds(v, CUBE_THRESHOLDS[0]);
     (i * 36 + 16, v << 16)
 }
 
 fn cube_index_green(v: u8) -> (u8, u32) {
-    let (i, v) = cube_thresholds(v, 36, 116, 154, 195, 235);
+    let (i, v) = cube_thresholds(v, CUBE_THRESHOLDS[1]);
     (i * 6, v << 8)
 }
 
 fn cube_index_blue(v: u8) -> (u8, u32) {
-    cube_thresholds(v, 35, 115, 155, 195, 235)
+    cube_thresholds(v, CUBE_THRESHOLDS[2])
 }
 
 /// Returns luminance of given sRGB colour.  The calculation favours speed over
 /// precision and so doesn’t correctly account for sRGB’s gamma correction.
-fn luminance(r: u8, g: u8, b: u8) -> u8 {
+#[inline]
+pub(crate) fn luminance(r: u8, g: u8, b: u8) -> u8 {
+    packed_luminance(u32::from_be_bytes([0, r, g, b]))
+}
+
+/// Like [`luminance`] but takes colour as an `0xRRGGBB` integer.
+#[inline]
+fn packed_luminance(rgb: u32) -> u8 {
     // The following weighted average is as fast as naive arithmetic mean and at
     // the same time noticeably more precise.  The coefficients are the second
     // row of the RGB->XYZ conversion matrix (i.e. values for calculating Y from
     // linear RGB) which I’ve calculated so that denominator is 2^24 to simplify
     // division.
-    let v = 3567664u32 * (r as u32) +
-        11998547u32 * (g as u32) +
-        1211005u32 * (b as u32);
+    let v = 3567664u32 * ((rgb >> 16) & 0xff)
+        + 11998547u32 * ((rgb >> 8) & 0xff)
+        + 1211005u32 * (rgb & 0xff);
     // Round to nearest rather than truncating when dividing.
     ((v + (1u32 << 23)) >> 24) as u8
 
@@ -227,15 +529,335 @@ fn luminance(r: u8, g: u8, b: u8) -> u8 {
 /// perceptual correctness.  It’s not a proper metric but two properties this
 /// function provides are: d(x, x) = 0 and d(x, y) < d(x, z) implies x being
 /// closer to y than to z.
-fn distance((xr, xg, xb): (u8, u8, u8), y: u32) -> u32 {
-    let (yr, yg, yb) = to_triple(y);
+#[inline]
+pub(crate) fn distance((xr, xg, xb): (u8, u8, u8), y: u32) -> u32 {
+    packed_distance(u32::from_be_bytes([0, xr, xg, xb]), y)
+}
+
+/// Like [`distance`] but takes both colours as `0xRRGGBB` integers.
+#[inline]
+fn packed_distance(x: u32, y: u32) -> u32 {
+    let component = |rgb: u32, shift: u32| ((rgb >> shift) & 0xff) as i32;
     // See <https://www.compuphase.com/cmetric.htm> though we’re doing a few
     // things to avoid some of the calculations.  We can do that since we only
     // care about some properties of the metric.
-    let r_sum = (xr as i32) + (yr as i32);
-    let r = (xr as i32) - (yr as i32);
-    let g = (xg as i32) - (yg as i32);
-    let b = (xb as i32) - (yb as i32);
+    let r_sum = component(x, 16) + component(y, 16);
+    let r = component(x, 16) - component(y, 16);
+    let g = component(x, 8) - component(y, 8);
+    let b = component(x, 0) - component(y, 0);
     let d = (1024 + r_sum) * r * r + 2048 * g * g + (1534 - r_sum) * b * b;
     d as u32
 }
+
+/// Returns ‘redmean’ distance between two colours.  Unlike [`distance`], this
+/// returns the actual distance rather than a value preserving its ordering.
+pub(crate) fn redmean(x: u32, y: u32) -> f32 {
+    // `distance` returns 512 times the square of redmean distance.  Scale it
+    // so that the integer square root has eight fractional bits.
+    let distance = packed_distance(x, y);
+    isqrt(u64::from(distance) * 128) as f32 / 256.0
+}
+
+/// Returns integer square root of given number, i.e. the largest integer whose
+/// square doesn’t exceed the number.
+pub(crate) fn isqrt(value: u64) -> u32 {
+    let mut rem = value;
+    let mut root = 0u64;
+    // Largest power of four not greater than the value.
+    let mut bit = 1u64 << ((63 - (value | 1).leading_zeros()) & !1);
+    while bit != 0 {
+        if rem >= root + bit {
+            rem -= root + bit;
+            root = (root >> 1) + bit;
+        } else {
+            root >>= 1;
+        }
+        bit >>= 2;
+    }
+    root as u32
+}
+
+/// Returns relative luminance, as defined by WCAG, of given sRGB colour.
+pub(crate) fn relative_luminance(rgb: u32) -> f32 {
+    let (r, g, b) = to_triple(rgb);
+    0.2126 * LINEAR[usize::from(r)]
+        + 0.7152 * LINEAR[usize::from(g)]
+        + 0.0722 * LINEAR[usize::from(b)]
+}
+
+/// Returns contrast ratio, as defined by WCAG, between two sRGB colours.  The
+/// ratio ranges from 1 (no contrast) to 21 (black and white).
+pub(crate) fn contrast_ratio(x: u32, y: u32) -> f32 {
+    let x = relative_luminance(x) + 0.05;
+    let y = relative_luminance(y) + 0.05;
+    if x < y {
+        y / x
+    } else {
+        x / y
+    }
+}
+
+/// Composites sRGB colour with given alpha over an opaque background.
+/// Blending is done in linear light.
+pub(crate) fn composite(rgb: u32, alpha: u8, background: u32) -> u32 {
+    let alpha = f32::from(alpha) / 255.0;
+    let blend = |shift: u32| {
+        let fg = LINEAR[((rgb >> shift) & 0xff) as usize];
+        let bg = LINEAR[((background >> shift) & 0xff) as usize];
+        u32::from(encode_linear(fg * alpha + bg * (1.0 - alpha))) << shift
+    };
+    blend(16) | blend(8) | blend(0)
+}
+
+/// Returns gamma-encoded sRGB component value whose linear intensity is the
+/// closest to given one.
+pub(crate) fn encode_linear(value: f32) -> u8 {
+    let idx = LINEAR.partition_point(|&v| v < value);
+    if idx == 0 {
+        0
+    } else if idx == LINEAR.len()
+        || value - LINEAR[idx - 1] < LINEAR[idx] - value
+    {
+        (idx - 1) as u8
+    } else {
+        idx as u8
+    }
+}
+
+/// Applies sRGB transfer function to given linear intensity and returns the
+/// gamma-encoded component value rounded to nearest integer.  Intensities
+/// outside of the 0–1 range are clamped.
+pub(crate) fn encode_srgb(value: f32) -> u8 {
+    LINEAR_MIDPOINTS.partition_point(|&v| v < value) as u8
+}
+
+/// Returns CIE lightness (L*), in the range from 0 to 100, of given sRGB
+/// colour.
+pub(crate) fn lightness(rgb: u32) -> u8 {
+    let y = relative_luminance(rgb);
+    let lightness = if y <= 216.0 / 24389.0 {
+        y * (24389.0 / 27.0)
+    } else {
+        116.0 * cbrt(y) - 16.0
+    };
+    (lightness + 0.5).clamp(0.0, 100.0) as u8
+}
+
+/// Returns cube root of given number which must be in the [0.008, 1.1]
+/// range.  This avoids dependency on `libm` in `no_std` builds.
+pub(crate) fn cbrt(value: f32) -> f32 {
+    // Newton’s method.  In the supported range starting at one converges
+    // within a few iterations.
+    let mut root = 1.0f32;
+    for _ in 0..8 {
+        root = (2.0 * root + value / (root * root)) / 3.0;
+    }
+    root
+}
+
+/// Returns CIE lightness (L*), in the range from 0 to 100, of colour at given
+/// index in the palette using default values of the system colours.
+#[inline]
+pub(crate) fn palette_lightness(idx: u8) -> u8 {
+    let bit = usize::from(idx) * 7;
+    let bytes = [PACKED_LIGHTNESS[bit / 8], PACKED_LIGHTNESS[bit / 8 + 1]];
+    (u16::from_le_bytes(bytes) >> (bit % 8)) as u8 & 0x7f
+}
+
+/// [`LIGHTNESS`] table with each value packed into seven bits.  Value for
+/// index `idx` starts at bit `idx × 7` counting from least significant bit of
+/// the first byte.  The last byte is padding so that two bytes can always be
+/// read.
+static PACKED_LIGHTNESS: [u8; 225] = {
+    let mut packed = [0; 225];
+    let mut idx = 0;
+    while idx < 256 {
+        let bit = idx * 7;
+        let value = (LIGHTNESS[idx] as u16) << (bit % 8);
+        packed[bit / 8] |= value as u8;
+        packed[bit / 8 + 1] |= (value >> 8) as u8;
+        idx += 1;
+    }
+    packed
+};
+
+/// CIE lightness (L*), in the range from 0 to 100, of colours in the ANSI
+/// palette.  The values are stored in [`PACKED_LIGHTNESS`]; this constant is
+/// used to build it and in tests.
+#[rustfmt::skip]
+pub(crate) const LIGHTNESS: [u8; 256] = [
+      0,  43,  72,  80,  30,  49,  75,  91,
+     53,  53,  88,  97,  48,  60,  91, 100,
+      0,   7,  14,  20,  26,  32,  34,  36,
+     38,  40,  43,  46,  49,  50,  51,  52,
+     54,  57,  62,  63,  64,  65,  66,  68,
+     75,  76,  76,  77,  78,  80,  88,  88,
+     89,  89,  90,  91,  18,  21,  24,  28,
+     33,  37,  39,  40,  42,  44,  46,  49,
+     52,  52,  54,  55,  57,  59,  64,  65,
+     66,  67,  68,  70,  77,  77,  78,  79,
+     80,  81,  89,  89,  90,  90,  91,  92,
+     27,  29,  32,  34,  38,  42,  43,  44,
+     46,  48,  50,  52,  55,  55,  56,  58,
+     59,  61,  66,  67,  68,  69,  70,  72,
+     78,  79,  79,  80,  81,  82,  90,  91,
+     91,  92,  92,  93,  36,  38,  39,  42,
+     44,  47,  49,  50,  51,  52,  54,  57,
+     58,  59,  60,  61,  63,  65,  69,  70,
+     71,  71,  73,  74,  81,  81,  82,  82,
+     83,  84,  92,  92,  93,  93,  94,  95,
+     45,  46,  47,  49,  51,  54,  55,  56,
+     56,  58,  59,  62,  63,  64,  65,  66,
+     67,  69,  73,  74,  74,  75,  76,  77,
+     83,  84,  84,  85,  86,  87,  94,  95,
+     95,  96,  96,  97,  53,  54,  55,  56,
+     58,  60,  61,  62,  63,  64,  65,  67,
+     68,  69,  70,  71,  72,  73,  77,  78,
+     78,  79,  80,  81,  87,  87,  88,  88,
+     89,  90,  97,  97,  98,  98,  99, 100,
+      2,   5,  10,  15,  20,  24,  29,  33,
+     37,  42,  46,  50,  54,  57,  61,  65,
+     69,  73,  76,  80,  83,  87,  91,  94,
+];
+
+/// Linear intensity, in the range from zero to one, of gamma-encoded sRGB
+/// component values.
+#[rustfmt::skip]
+#[allow(clippy::excessive_precision)]
+pub(crate) static LINEAR: [f32; 256] = [
+    0.00000000, 0.00030353, 0.00060705, 0.00091058,
+    0.00121411, 0.00151763, 0.00182116, 0.00212469,
+    0.00242822, 0.00273174, 0.00303527, 0.00334654,
+    0.00367651, 0.00402472, 0.00439144, 0.00477695,
+    0.00518152, 0.00560539, 0.00604883, 0.00651209,
+    0.00699541, 0.00749903, 0.00802319, 0.00856813,
+    0.00913406, 0.00972122, 0.01032982, 0.01096009,
+    0.01161225, 0.01228649, 0.01298303, 0.01370208,
+    0.01444384, 0.01520851, 0.01599629, 0.01680738,
+    0.01764195, 0.01850022, 0.01938236, 0.02028856,
+    0.02121901, 0.02217388, 0.02315337, 0.02415763,
+    0.02518686, 0.02624122, 0.02732089, 0.02842604,
+    0.02955683, 0.03071344, 0.03189603, 0.03310477,
+    0.03433981, 0.03560131, 0.03688945, 0.03820437,
+    0.03954624, 0.04091520, 0.04231141, 0.04373503,
+    0.04518620, 0.04666509, 0.04817182, 0.04970657,
+    0.05126946, 0.05286065, 0.05448028, 0.05612849,
+    0.05780543, 0.05951124, 0.06124605, 0.06301002,
+    0.06480327, 0.06662594, 0.06847817, 0.07036010,
+    0.07227185, 0.07421357, 0.07618538, 0.07818742,
+    0.08021982, 0.08228271, 0.08437621, 0.08650046,
+    0.08865559, 0.09084171, 0.09305896, 0.09530747,
+    0.09758735, 0.09989873, 0.10224173, 0.10461648,
+    0.10702310, 0.10946171, 0.11193243, 0.11443537,
+    0.11697067, 0.11953843, 0.12213877, 0.12477182,
+    0.12743768, 0.13013648, 0.13286832, 0.13563333,
+    0.13843162, 0.14126329, 0.14412847, 0.14702727,
+    0.14995979, 0.15292615, 0.15592646, 0.15896084,
+    0.16202938, 0.16513219, 0.16826940, 0.17144110,
+    0.17464740, 0.17788842, 0.18116424, 0.18447499,
+    0.18782077, 0.19120168, 0.19461783, 0.19806932,
+    0.20155625, 0.20507874, 0.20863687, 0.21223076,
+    0.21586050, 0.21952620, 0.22322796, 0.22696587,
+    0.23074005, 0.23455058, 0.23839757, 0.24228112,
+    0.24620133, 0.25015828, 0.25415209, 0.25818285,
+    0.26225066, 0.26635560, 0.27049779, 0.27467731,
+    0.27889426, 0.28314874, 0.28744084, 0.29177065,
+    0.29613827, 0.30054379, 0.30498731, 0.30946892,
+    0.31398871, 0.31854678, 0.32314321, 0.32777810,
+    0.33245154, 0.33716362, 0.34191442, 0.34670406,
+    0.35153260, 0.35640014, 0.36130678, 0.36625260,
+    0.37123768, 0.37626212, 0.38132601, 0.38642943,
+    0.39157248, 0.39675523, 0.40197778, 0.40724021,
+    0.41254261, 0.41788507, 0.42326767, 0.42869050,
+    0.43415364, 0.43965717, 0.44520119, 0.45078578,
+    0.45641102, 0.46207700, 0.46778380, 0.47353150,
+    0.47932018, 0.48514994, 0.49102085, 0.49693300,
+    0.50288646, 0.50888132, 0.51491767, 0.52099557,
+    0.52711513, 0.53327640, 0.53947949, 0.54572446,
+    0.55201140, 0.55834039, 0.56471151, 0.57112483,
+    0.57758044, 0.58407842, 0.59061884, 0.59720179,
+    0.60382734, 0.61049557, 0.61720656, 0.62396039,
+    0.63075714, 0.63759687, 0.64447968, 0.65140564,
+    0.65837482, 0.66538730, 0.67244316, 0.67954247,
+    0.68668531, 0.69387176, 0.70110189, 0.70837578,
+    0.71569350, 0.72305513, 0.73046074, 0.73791041,
+    0.74540421, 0.75294222, 0.76052450, 0.76815115,
+    0.77582222, 0.78353779, 0.79129794, 0.79910274,
+    0.80695226, 0.81484657, 0.82278575, 0.83076988,
+    0.83879901, 0.84687323, 0.85499261, 0.86315721,
+    0.87136712, 0.87962240, 0.88792312, 0.89626935,
+    0.90466117, 0.91309865, 0.92158186, 0.93011086,
+    0.93868573, 0.94730654, 0.95597335, 0.96468625,
+    0.97344529, 0.98225055, 0.99110210, 1.00000000,
+];
+
+/// Linear intensities half-way, in gamma-encoded space, between consecutive
+/// sRGB component values.  Entry `i` is the intensity of value `i + 0.5`.
+#[rustfmt::skip]
+#[allow(clippy::excessive_precision)]
+static LINEAR_MIDPOINTS: [f32; 255] = [
+    0.00015176, 0.00045529, 0.00075882, 0.00106234,
+    0.00136587, 0.00166940, 0.00197293, 0.00227645,
+    0.00257998, 0.00288351, 0.00318830, 0.00350926,
+    0.00384831, 0.00420575, 0.00458183, 0.00497684,
+    0.00539102, 0.00582465, 0.00627797, 0.00675123,
+    0.00724467, 0.00775853, 0.00829305, 0.00884845,
+    0.00942497, 0.01002283, 0.01064224, 0.01128342,
+    0.01194659, 0.01263196, 0.01333973, 0.01407011,
+    0.01482330, 0.01559950, 0.01639891, 0.01722172,
+    0.01806811, 0.01893829, 0.01983244, 0.02075074,
+    0.02169338, 0.02266054, 0.02365239, 0.02466911,
+    0.02571089, 0.02677788, 0.02787027, 0.02898822,
+    0.03013190, 0.03130148, 0.03249712, 0.03371899,
+    0.03496724, 0.03624204, 0.03754355, 0.03887193,
+    0.04022732, 0.04160989, 0.04301978, 0.04445716,
+    0.04592217, 0.04741496, 0.04893569, 0.05048448,
+    0.05206151, 0.05366690, 0.05530080, 0.05696336,
+    0.05865472, 0.06037501, 0.06212438, 0.06390297,
+    0.06571092, 0.06754835, 0.06941541, 0.07131224,
+    0.07323896, 0.07519570, 0.07718262, 0.07919982,
+    0.08124744, 0.08332562, 0.08543449, 0.08757416,
+    0.08974477, 0.09194644, 0.09417930, 0.09644348,
+    0.09873909, 0.10106627, 0.10342513, 0.10581580,
+    0.10823840, 0.11069305, 0.11317986, 0.11569897,
+    0.11825048, 0.12083452, 0.12345120, 0.12610064,
+    0.12878295, 0.13149826, 0.13424667, 0.13702831,
+    0.13984327, 0.14269169, 0.14557366, 0.14848931,
+    0.15143873, 0.15442206, 0.15743939, 0.16049083,
+    0.16357649, 0.16669649, 0.16985093, 0.17303992,
+    0.17626356, 0.17952197, 0.18281525, 0.18614350,
+    0.18950683, 0.19290535, 0.19633915, 0.19980835,
+    0.20331305, 0.20685334, 0.21042934, 0.21404114,
+    0.21768885, 0.22137256, 0.22509239, 0.22884842,
+    0.23264076, 0.23646951, 0.24033477, 0.24423664,
+    0.24817521, 0.25215058, 0.25616285, 0.26021212,
+    0.26429848, 0.26842204, 0.27258288, 0.27678110,
+    0.28101681, 0.28529008, 0.28960102, 0.29394973,
+    0.29833629, 0.30276080, 0.30722335, 0.31172404,
+    0.31626296, 0.32084019, 0.32545584, 0.33010999,
+    0.33480274, 0.33953417, 0.34430438, 0.34911346,
+    0.35396149, 0.35884857, 0.36377478, 0.36874022,
+    0.37374498, 0.37878913, 0.38387278, 0.38899600,
+    0.39415889, 0.39936153, 0.40460401, 0.40988641,
+    0.41520883, 0.42057135, 0.42597405, 0.43141702,
+    0.43690035, 0.44242412, 0.44798841, 0.45359332,
+    0.45923891, 0.46492529, 0.47065253, 0.47642071,
+    0.48222992, 0.48808025, 0.49397176, 0.49990456,
+    0.50587871, 0.51189430, 0.51795142, 0.52405014,
+    0.53019054, 0.53637272, 0.54259673, 0.54886268,
+    0.55517063, 0.56152068, 0.56791289, 0.57434734,
+    0.58082413, 0.58734332, 0.59390499, 0.60050923,
+    0.60715611, 0.61384572, 0.62057812, 0.62735339,
+    0.63417163, 0.64103289, 0.64793726, 0.65488482,
+    0.66187564, 0.66890980, 0.67598738, 0.68310845,
+    0.69027308, 0.69748136, 0.70473336, 0.71202916,
+    0.71936882, 0.72675243, 0.73418006, 0.74165179,
+    0.74916768, 0.75672782, 0.76433228, 0.77198112,
+    0.77967444, 0.78741229, 0.79519475, 0.80302190,
+    0.81089381, 0.81881055, 0.82677219, 0.83477881,
+    0.84283048, 0.85092727, 0.85906925, 0.86725650,
+    0.87548908, 0.88376707, 0.89209054, 0.90045956,
+    0.90887420, 0.91733453, 0.92584063, 0.93439256,
+    0.94299039, 0.95163419, 0.96032404, 0.96906000,
+    0.97784214, 0.98667053, 0.99554525,
+];
@@ -0,0 +1,115 @@
+// ansi_colours – true-colour ↔ ANSI terminal palette converter
+// Copyright 2018 by Michał Nazarewicz <mina86@mina86.com>
+//
+// ansi_colours is free software: you can redistribute it and/or modify it
+// under the terms of the GNU Lesser General Public License as published by
+// the Free Software Foundation; either version 3 of the License, or (at
+// your option) any later version.
+//
+// ansi_colours is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Lesser
+// General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with ansi_colours.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Support for the 88-colour palette used by rxvt-unicode.
+
+use crate::ansi256::{distance, luminance, to_triple};
+
+/// Colours of the 88-colour palette as 24-bit `0xRRGGBB` integers.
+#[rustfmt::skip]
+pub(crate) static ANSI88_COLOURS: [u32; 88] = [
+    // The 16 system colours as used by default by xterm.
+    0x000000, 0xcd0000, 0x00cd00, 0xcdcd00,
+    0x0000ee, 0xcd00cd, 0x00cdcd, 0xe5e5e5,
+    0x7f7f7f, 0xff0000, 0x00ff00, 0xffff00,
+    0x5c5cff, 0xff00ff, 0x00ffff, 0xffffff,
+
+    // 4×4×4 cube.  On each axis, the four indices map to [0, 139, 205, 255]
+    // RGB component values.
+    0x000000, 0x00008b, 0x0000cd, 0x0000ff,
+    0x008b00, 0x008b8b, 0x008bcd, 0x008bff,
+    0x00cd00, 0x00cd8b, 0x00cdcd, 0x00cdff,
+    0x00ff00, 0x00ff8b, 0x00ffcd, 0x00ffff,
+    0x8b0000, 0x8b008b, 0x8b00cd, 0x8b00ff,
+    0x8b8b00, 0x8b8b8b, 0x8b8bcd, 0x8b8bff,
+    0x8bcd00, 0x8bcd8b, 0x8bcdcd, 0x8bcdff,
+    0x8bff00, 0x8bff8b, 0x8bffcd, 0x8bffff,
+    0xcd0000, 0xcd008b, 0xcd00cd, 0xcd00ff,
+    0xcd8b00, 0xcd8b8b, 0xcd8bcd, 0xcd8bff,
+    0xcdcd00, 0xcdcd8b, 0xcdcdcd, 0xcdcdff,
+    0xcdff00, 0xcdff8b, 0xcdffcd, 0xcdffff,
+    0xff0000, 0xff008b, 0xff00cd, 0xff00ff,
+    0xff8b00, 0xff8b8b, 0xff8bcd, 0xff8bff,
+    0xffcd00, 0xffcd8b, 0xffcdcd, 0xffcdff,
+    0xffff00, 0xffff8b, 0xffffcd, 0xffffff,
+
+    // Greyscale ramp.  Black and white are left out since they are present in
+    // the cube.
+    0x2e2e2e, 0x5c5c5c, 0x737373, 0x8b8b8b,
+    0xa2a2a2, 0xb9b9b9, 0xd0d0d0, 0xe7e7e7,
+];
+
+/// Shades of grey present in the palette (outside of system colours) as
+/// `(component, index)` pairs sorted by the component value.  Where the same
+/// shade is present in the cube and the greyscale ramp, the cube is used.
+static GREYS: [(u8, u8); 11] = [
+    (0, 16),
+    (46, 80),
+    (92, 81),
+    (115, 82),
+    (139, 37),
+    (162, 84),
+    (185, 85),
+    (205, 58),
+    (208, 86),
+    (231, 87),
+    (255, 79),
+];
+
+/// Returns index of a colour in 88-colour palette approximating given sRGB
+/// colour.
+pub(crate) fn ansi88_from_rgb(rgb: u32) -> u8 {
+    let (r, g, b) = to_triple(rgb);
+
+    let grey_index = ansi88_from_grey(luminance(r, g, b));
+    let grey_distance =
+        distance((r, g, b), ANSI88_COLOURS[usize::from(grey_index)]);
+    let cube_index =
+        16 + 16 * cube_level(r) + 4 * cube_level(g) + cube_level(b);
+    let cube_distance =
+        distance((r, g, b), ANSI88_COLOURS[usize::from(cube_index)]);
+    if cube_distance < grey_distance {
+        cube_index
+    } else {
+        grey_index
+    }
+}
+
+/// Returns index of a colour in 88-colour palette approximating given shade of
+/// grey.
+fn ansi88_from_grey(component: u8) -> u8 {
+    let mut best = GREYS[0];
+    for &(value, idx) in GREYS[1..].iter() {
+        if value.abs_diff(component) < best.0.abs_diff(component) {
+            best = (value, idx);
+        }
+    }
+    best.1
+}
+
+/// Returns index of the level on a cube axis which best approximates given
+/// component value.  The thresholds are midpoints between the levels.
+fn cube_level(v: u8) -> u8 {
+    if v < 70 {
+        0
+    } else if v < 172 {
+        1
+    } else if v < 230 {
+        2
+    } else {
+        3
+    }
+}
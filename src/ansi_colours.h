@@ -14,6 +14,7 @@
    You should have received a copy of the GNU Lesser General Public License
    along with ansi_colours.  If not, see <http://www.gnu.org/licenses/>. */
 
+#include <stddef.h>
 #include <stdint.h>
 
 #ifdef __cplusplus
@@ -42,6 +43,21 @@ extern "C" {
 uint32_t rgb_from_ansi256(uint8_t index);
 
 
+/* Copies all colours of the 256-colour ANSI palette into the out buffer which
+ * must have room for 256 elements.  Colours are stored in 24-bit 0xrrggbb
+ * format with out[i] being equal to rgb_from_ansi256(i).
+ *
+ * For example:
+ *
+ *     uint32_t palette[256];
+ *     ansi256_palette(palette);
+ *     assert(0x000000 == palette[ 16]);
+ *     assert(0x5f87af == palette[ 67]);
+ *     assert(0xeeeeee == palette[255]);
+ */
+void ansi256_palette(uint32_t out[256]);
+
+
 /* Returns index of a colour in 256-colour ANSI palette approximating given sRGB
  * colour.  The sRGB colour is expected in 24-bit 0xrrggbb format.  (Most
  * significant eight bits of the argument are ignored).
@@ -59,6 +75,85 @@ uint32_t rgb_from_ansi256(uint8_t index);
  */
 uint8_t ansi256_from_rgb(uint32_t rgb);
 
+
+/* Returns index of a colour in 256-colour ANSI palette approximating given
+ * shade of grey.  This gives the same results as ansi256_from_rgb(component *
+ * 0x010101) but is faster.
+ *
+ * For example:
+ *
+ *     assert( 16 == ansi256_from_grey(  0));
+ *     assert( 16 == ansi256_from_grey(  1));
+ *     assert(232 == ansi256_from_grey(  8));
+ *     assert(244 == ansi256_from_grey(128));
+ *     assert(231 == ansi256_from_grey(255));
+ */
+uint8_t ansi256_from_grey(uint8_t component);
+
+
+/* Approximates multiple sRGB colours by colours in 256-colour ANSI palette.
+ * The rgb argument points at n colours each specified as three consecutive
+ * bytes holding red, green and blue components (i.e. 3 * n bytes in total).
+ * Index of a colour approximating i-th colour is stored in out[i].  Results
+ * are the same as calling ansi256_from_rgb for each colour individually.
+ *
+ * For example:
+ *
+ *     const uint8_t rgb[] = { 0, 0, 0,  95, 135, 175,  255, 255, 255 };
+ *     uint8_t out[3];
+ *     ansi256_from_rgb_many(rgb, 3, out);
+ *     assert( 16 == out[0]);
+ *     assert( 67 == out[1]);
+ *     assert(231 == out[2]);
+ */
+void ansi256_from_rgb_many(const uint8_t *rgb, size_t n, uint8_t *out);
+
+
+/* Returns index of a colour in 256-colour ANSI palette approximating given sRGB
+ * colour taking into account the 16 system colours.  The system argument must
+ * point at 16 colours in 24-bit 0xrrggbb format specifying the actual values of
+ * system colours used by the terminal (e.g. as configured by the user’s
+ * theme).
+ *
+ * The function works like ansi256_from_rgb except that if one of the system
+ * colours is closer to the given colour than the colour picked by
+ * ansi256_from_rgb, index of that system colour is returned.
+ *
+ * For example:
+ *
+ *     const uint32_t system[16] = {
+ *         0x000000, 0xcc0000, 0x4e9a06, 0xc4a000,
+ *         0x3465a4, 0x75507b, 0x06989a, 0xd3d7cf,
+ *         0x555753, 0xef2929, 0x8ae234, 0xfce94f,
+ *         0x729fcf, 0xad7fa8, 0x34e2e2, 0xeeeeec,
+ *     };
+ *     assert( 16 == ansi256_from_rgb_with_system(0x000000, system));
+ *     assert(  1 == ansi256_from_rgb_with_system(0xcc0000, system));
+ *     assert(  4 == ansi256_from_rgb_with_system(0x3465a4, system));
+ *     assert( 67 == ansi256_from_rgb_with_system(0x5f87af, system));
+ */
+uint8_t ansi256_from_rgb_with_system(uint32_t rgb, const uint32_t system[16]);
+
+
+/* Returns index of a colour in given palette which is closest to given sRGB
+ * colour.  The palette argument must point at n colours (where n is at least
+ * one) in 24-bit 0xrrggbb format.  If there are multiple closest colours,
+ * index of the first one is returned.
+ *
+ * This makes it possible to approximate colours using a custom palette, e.g.
+ * full 256-colour palette as reported by the terminal.  Note that the function
+ * performs a linear search and is thus considerably slower than
+ * ansi256_from_rgb.
+ *
+ * For example:
+ *
+ *     const uint32_t palette[] = { 0x000000, 0xff0000, 0xffffff };
+ *     assert(0 == palette_index_from_rgb(0x101010, palette, 3));
+ *     assert(1 == palette_index_from_rgb(0xc01010, palette, 3));
+ *     assert(2 == palette_index_from_rgb(0xe0e0e0, palette, 3));
+ */
+size_t palette_index_from_rgb(uint32_t rgb, const uint32_t *palette, size_t n);
+
 #ifdef __cplusplus
 }
 #endif
@@ -0,0 +1,133 @@
+// ansi_colours – true-colour ↔ ANSI terminal palette converter
+// Copyright 2018 by Michał Nazarewicz <mina86@mina86.com>
+//
+// ansi_colours is free software: you can redistribute it and/or modify it
+// under the terms of the GNU Lesser General Public License as published by
+// the Free Software Foundation; either version 3 of the License, or (at
+// your option) any later version.
+//
+// ansi_colours is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Lesser
+// General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with ansi_colours.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Cache of recent approximations.
+
+use crate::AsRGB;
+
+/// Bit set in keys of occupied entries.  sRGB colours use only 24 bits so the
+/// bit distinguishes black from an empty entry.
+const OCCUPIED: u32 = 1 << 24;
+
+/// A fixed-size cache of approximations of sRGB colours by colours in the
+/// 256-colour ANSI palette.
+///
+/// Real workloads, such as syntax highlighting, often convert a handful of
+/// distinct colours over and over again.  The cache remembers results of
+/// recent conversions so that repeated colours aren’t approximated again.
+/// It’s a direct-mapped hash table with `N` entries (which must be a power
+/// of two) stored inline so it doesn’t allocate memory.  When two colours
+/// map to the same entry, the more recent one replaces the other.
+///
+/// Colours are converted to sRGB before the lookup, i.e. the result is the
+/// same as `ansi256_from_rgb(rgb.as_u32())`.  Cached results aren’t updated
+/// when system colours considered by the approximation change (see
+/// [`set_system_colours_in_approximation`]); call [`Self::clear`] in such
+/// case.
+///
+/// This type is present only if `cache` crate feature is enabled.
+///
+/// [`set_system_colours_in_approximation`]: crate::set_system_colours_in_approximation
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::Cache;
+///
+/// let mut cache = Cache::<64>::new();
+/// assert_eq!( 16, cache.nearest(0x000000));
+/// assert_eq!(231, cache.nearest((255, 255, 255)));
+/// assert_eq!( 67, cache.nearest([95, 135, 175]));
+/// assert_eq!( 67, cache.nearest([95, 135, 175]));
+/// assert_eq!(3, cache.len());
+///
+/// cache.clear();
+/// assert_eq!(0, cache.len());
+/// ```
+#[derive(Clone)]
+pub struct Cache<const N: usize = 256> {
+    /// sRGB colours with [`OCCUPIED`] bit set or zero for empty entries.
+    keys: [u32; N],
+    /// Indexes in the palette approximating corresponding colours.
+    values: [u8; N],
+}
+
+impl<const N: usize> Cache<N> {
+    /// Constructs an empty cache.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `N` isn’t a power of two.
+    #[inline]
+    pub const fn new() -> Self {
+        assert!(N.is_power_of_two(), "cache size must be a power of two");
+        Self {
+            keys: [0; N],
+            values: [0; N],
+        }
+    }
+
+    /// Returns index of a colour in the 256-colour ANSI palette approximating
+    /// given sRGB colour.  Uses cached result if the colour was approximated
+    /// recently.
+    #[inline]
+    pub fn nearest<C: AsRGB>(&mut self, rgb: C) -> u8 {
+        let key = rgb.as_u32() | OCCUPIED;
+        // Fibonacci hashing spreads similar colours across the entries.  The
+        // top bits of the product are the best mixed so the slot is made of
+        // the top log₂(N) bits.  Shifting a 64-bit value avoids overflowing
+        // the shift when N is one.
+        let hash = u64::from(key.wrapping_mul(0x9E37_79B9));
+        let slot = ((hash << N.trailing_zeros()) >> 32) as usize;
+        if self.keys[slot] != key {
+            self.keys[slot] = key;
+            self.values[slot] = crate::system::ansi256_from_rgb(key & 0xffffff);
+        }
+        self.values[slot]
+    }
+
+    /// Returns number of colours in the cache.
+    pub fn len(&self) -> usize {
+        self.keys.iter().filter(|&&key| key != 0).count()
+    }
+
+    /// Returns whether the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.keys.iter().all(|&key| key == 0)
+    }
+
+    /// Removes all colours from the cache.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.keys = [0; N];
+    }
+}
+
+impl<const N: usize> Default for Cache<N> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> core::fmt::Debug for Cache<N> {
+    fn fmt(&self, fmtr: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        fmtr.debug_struct("Cache")
+            .field("capacity", &N)
+            .field("len", &self.len())
+            .finish()
+    }
+}
@@ -0,0 +1,119 @@
+// ansi_colours – true-colour ↔ ANSI terminal palette converter
+// Copyright 2018 by Michał Nazarewicz <mina86@mina86.com>
+//
+// ansi_colours is free software: you can redistribute it and/or modify it
+// under the terms of the GNU Lesser General Public License as published by
+// the Free Software Foundation; either version 3 of the License, or (at
+// your option) any later version.
+//
+// ansi_colours is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Lesser
+// General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with ansi_colours.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Iterator over palette colours closest to given colour.
+
+use crate::ansi256::{distance, extended_colour, to_triple};
+
+/// Iterator over indexes of colours in 256-colour ANSI palette ordered by
+/// their distance to a colour.
+///
+/// The iterator is returned by
+/// [`ansi256_candidates`](crate::ansi256_candidates) function.  See its
+/// documentation for details.
+#[derive(Clone, Debug)]
+pub struct Candidates {
+    /// Indexes of the candidates ordered by distance.
+    indexes: [u8; 256],
+    /// Position of the next candidate to return.
+    start: u16,
+    /// Position past the last candidate to return.
+    end: u16,
+}
+
+impl Candidates {
+    /// Returns `k` colours of the palette, excluding system colours, closest
+    /// to given colour.
+    pub(crate) fn new(rgb: u32, k: usize) -> Self {
+        Self::build(rgb, k, None)
+    }
+
+    /// Returns all colours of the palette which the approximation considers
+    /// ordered by their distance to given colour.
+    ///
+    /// Those are the same colours [`crate::system::ansi256_from_rgb`] chooses
+    /// from, i.e. system colours with their current values are included if
+    /// enabled with [`crate::set_system_colours_in_approximation`].  On ties,
+    /// system colours come last.
+    pub(crate) fn approximation(rgb: u32) -> Self {
+        let system = crate::system::approximate().then(crate::system::snapshot);
+        Self::build(rgb, usize::MAX, system)
+    }
+
+    fn build(rgb: u32, k: usize, system: Option<[u32; 16]>) -> Self {
+        let rgb = to_triple(rgb);
+        let mut entries = [(0, false, 0); 256];
+        for (entry, idx) in entries.iter_mut().zip(16..=255) {
+            *entry = (distance(rgb, extended_colour(idx)), false, idx);
+        }
+        let mut len = 240;
+        if let Some(system) = system {
+            for (entry, idx) in entries[240..].iter_mut().zip(0..16) {
+                let colour = system[usize::from(idx)];
+                *entry = (distance(rgb, colour), true, idx);
+            }
+            len = 256;
+        }
+        let entries = &mut entries[..len];
+        let k = k.min(len);
+        if k > 0 && k < len {
+            entries.select_nth_unstable(k - 1);
+        }
+        entries[..k].sort_unstable();
+        let mut indexes = [0; 256];
+        for (index, entry) in indexes.iter_mut().zip(&entries[..k]) {
+            *index = entry.2;
+        }
+        Self {
+            indexes,
+            start: 0,
+            end: k as u16,
+        }
+    }
+}
+
+impl Iterator for Candidates {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        if self.start < self.end {
+            self.start += 1;
+            Some(self.indexes[usize::from(self.start - 1)])
+        } else {
+            None
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = usize::from(self.end - self.start);
+        (len, Some(len))
+    }
+}
+
+impl DoubleEndedIterator for Candidates {
+    fn next_back(&mut self) -> Option<u8> {
+        if self.start < self.end {
+            self.end -= 1;
+            Some(self.indexes[usize::from(self.end)])
+        } else {
+            None
+        }
+    }
+}
+
+impl ExactSizeIterator for Candidates {}
+
+impl core::iter::FusedIterator for Candidates {}
@@ -0,0 +1,159 @@
+// ansi_colours – true-colour ↔ ANSI terminal palette converter
+// Copyright 2018 by Michał Nazarewicz <mina86@mina86.com>
+//
+// ansi_colours is free software: you can redistribute it and/or modify it
+// under the terms of the GNU Lesser General Public License as published by
+// the Free Software Foundation; either version 3 of the License, or (at
+// your option) any later version.
+//
+// ansi_colours is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Lesser
+// General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with ansi_colours.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Exact approximation of colours using CIEDE2000 colour difference.
+//!
+//! This module is present only if `ciede2000` crate feature is enabled.
+
+use crate::ansi256::to_triple;
+
+/// A colour in CIELAB colour space.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Lab {
+    pub(crate) l: f64,
+    pub(crate) a: f64,
+    pub(crate) b: f64,
+}
+
+impl Lab {
+    /// Converts sRGB colour given as `0xRRGGBB` integer into CIELAB colour
+    /// assuming D65 reference white.
+    pub(crate) fn from_rgb(rgb: u32) -> Self {
+        let (r, g, b) = to_triple(rgb);
+        let (r, g, b) = (linearise(r), linearise(g), linearise(b));
+        let x = 0.4124564 * r + 0.3575761 * g + 0.1804375 * b;
+        let y = 0.2126729 * r + 0.7151522 * g + 0.0721750 * b;
+        let z = 0.0193339 * r + 0.1191920 * g + 0.9503041 * b;
+        let x = lab_f(x / 0.95047);
+        let y = lab_f(y);
+        let z = lab_f(z / 1.08883);
+        Self {
+            l: 116.0 * y - 16.0,
+            a: 500.0 * (x - y),
+            b: 200.0 * (y - z),
+        }
+    }
+}
+
+/// Converts gamma-encoded sRGB component into linear intensity in the range
+/// from zero to one.
+fn linearise(value: u8) -> f64 {
+    let value = f64::from(value) / 255.0;
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        libm::pow((value + 0.055) / 1.055, 2.4)
+    }
+}
+
+/// The non-linear function used when converting from CIEXYZ to CIELAB.
+fn lab_f(t: f64) -> f64 {
+    const DELTA: f64 = 6.0 / 29.0;
+    if t > DELTA * DELTA * DELTA {
+        libm::cbrt(t)
+    } else {
+        t / (3.0 * DELTA * DELTA) + 4.0 / 29.0
+    }
+}
+
+/// Returns hue angle, in degrees in the range [0, 360), of a point with given
+/// coordinates.
+fn hue(b: f64, a: f64) -> f64 {
+    if a == 0.0 && b == 0.0 {
+        return 0.0;
+    }
+    let hue = libm::atan2(b, a).to_degrees();
+    if hue < 0.0 {
+        hue + 360.0
+    } else {
+        hue
+    }
+}
+
+/// Returns `x / (x + 25⁷)` where `x = c⁷`.
+fn c7_ratio(c: f64) -> f64 {
+    const POW25_7: f64 = 6103515625.0;
+    let c7 = libm::pow(c, 7.0);
+    c7 / (c7 + POW25_7)
+}
+
+/// Calculates ΔE*₀₀ difference between two colours.
+///
+/// The implementation follows ‘The CIEDE2000 Color-Difference Formula:
+/// Implementation Notes, Supplementary Test Data, and Mathematical
+/// Observations’ by Gaurav Sharma, Wencheng Wu and Edul N. Dalal.
+pub(crate) fn diff(x: Lab, y: Lab) -> f64 {
+    let c1 = libm::hypot(x.a, x.b);
+    let c2 = libm::hypot(y.a, y.b);
+    let g = 0.5 * (1.0 - libm::sqrt(c7_ratio((c1 + c2) / 2.0)));
+    let a1 = (1.0 + g) * x.a;
+    let a2 = (1.0 + g) * y.a;
+    let c1 = libm::hypot(a1, x.b);
+    let c2 = libm::hypot(a2, y.b);
+    let h1 = hue(x.b, a1);
+    let h2 = hue(y.b, a2);
+
+    let delta_l = y.l - x.l;
+    let delta_c = c2 - c1;
+    let delta_h = if c1 * c2 == 0.0 {
+        0.0
+    } else if h2 - h1 > 180.0 {
+        h2 - h1 - 360.0
+    } else if h2 - h1 < -180.0 {
+        h2 - h1 + 360.0
+    } else {
+        h2 - h1
+    };
+    let delta_h =
+        2.0 * libm::sqrt(c1 * c2) * libm::sin((delta_h / 2.0).to_radians());
+
+    let l = (x.l + y.l) / 2.0;
+    let c = (c1 + c2) / 2.0;
+    let h = if c1 * c2 == 0.0 {
+        h1 + h2
+    } else if libm::fabs(h1 - h2) <= 180.0 {
+        (h1 + h2) / 2.0
+    } else if h1 + h2 < 360.0 {
+        (h1 + h2 + 360.0) / 2.0
+    } else {
+        (h1 + h2 - 360.0) / 2.0
+    };
+
+    let cos = |deg: f64| libm::cos(deg.to_radians());
+    let t = 1.0 - 0.17 * cos(h - 30.0)
+        + 0.24 * cos(2.0 * h)
+        + 0.32 * cos(3.0 * h + 6.0)
+        - 0.20 * cos(4.0 * h - 63.0);
+    let delta_theta = 30.0 * libm::exp(-libm::pow((h - 275.0) / 25.0, 2.0));
+    let r_c = 2.0 * libm::sqrt(c7_ratio(c));
+    let l50 = (l - 50.0) * (l - 50.0);
+    let s_l = 1.0 + 0.015 * l50 / libm::sqrt(20.0 + l50);
+    let s_c = 1.0 + 0.045 * c;
+    let s_h = 1.0 + 0.015 * c * t;
+    let r_t = -libm::sin((2.0 * delta_theta).to_radians()) * r_c;
+
+    let l = delta_l / s_l;
+    let c = delta_c / s_c;
+    let h = delta_h / s_h;
+    libm::sqrt(l * l + c * c + h * h + r_t * c * h)
+}
+
+/// Returns index of a colour in 256-colour ANSI palette which has the smallest
+/// ΔE*₀₀ difference to given sRGB colour.  System colours are not considered.
+pub(crate) fn ansi256_from_rgb(rgb: u32) -> u8 {
+    let colours = crate::ansi256::extended_colours();
+    crate::metric::nearest(&crate::metric::De2000, rgb, colours) + 16
+}
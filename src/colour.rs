@@ -0,0 +1,568 @@
+// ansi_colours – true-colour ↔ ANSI terminal palette converter
+// Copyright 2018 by Michał Nazarewicz <mina86@mina86.com>
+//
+// ansi_colours is free software: you can redistribute it and/or modify it
+// under the terms of the GNU Lesser General Public License as published by
+// the Free Software Foundation; either version 3 of the License, or (at
+// your option) any later version.
+//
+// ansi_colours is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Lesser
+// General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with ansi_colours.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Crate-native colour representation.
+
+use core::fmt;
+use core::str::FromStr;
+
+use crate::ansi256::to_triple;
+use crate::system::palette_colour;
+use crate::{AsRGB, ColourExt};
+
+/// One of the eight basic colours of the ANSI palette.
+///
+/// Discriminants of the variants match indexes of the colours in the palette.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum NamedColour {
+    /// Black, i.e. colour at index 0 in the palette.
+    Black = 0,
+    /// Red, i.e. colour at index 1 in the palette.
+    Red = 1,
+    /// Green, i.e. colour at index 2 in the palette.
+    Green = 2,
+    /// Yellow, i.e. colour at index 3 in the palette.
+    Yellow = 3,
+    /// Blue, i.e. colour at index 4 in the palette.
+    Blue = 4,
+    /// Magenta, i.e. colour at index 5 in the palette.
+    Magenta = 5,
+    /// Cyan, i.e. colour at index 6 in the palette.
+    Cyan = 6,
+    /// White, i.e. colour at index 7 in the palette.  Despite the name, it’s
+    /// usually rendered as light grey.
+    White = 7,
+}
+
+impl NamedColour {
+    /// All named colours in order of their indexes in the palette.
+    pub const ALL: [Self; 8] = [
+        Self::Black,
+        Self::Red,
+        Self::Green,
+        Self::Yellow,
+        Self::Blue,
+        Self::Magenta,
+        Self::Cyan,
+        Self::White,
+    ];
+
+    /// Returns index of the colour in the ANSI palette.
+    #[inline]
+    pub fn index(self) -> u8 {
+        self as u8
+    }
+
+    /// Returns index of the bright variant of the colour in the ANSI palette.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ansi_colours::NamedColour;
+    ///
+    /// assert_eq!( 8, NamedColour::Black.bright_index());
+    /// assert_eq!( 9, NamedColour::Red.bright_index());
+    /// assert_eq!(15, NamedColour::White.bright_index());
+    /// ```
+    #[inline]
+    pub fn bright_index(self) -> u8 {
+        self as u8 + 8
+    }
+
+    /// Returns lower-case name of the colour.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ansi_colours::NamedColour;
+    ///
+    /// assert_eq!("black", NamedColour::Black.name());
+    /// assert_eq!("magenta", NamedColour::Magenta.name());
+    /// ```
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Black => "black",
+            Self::Red => "red",
+            Self::Green => "green",
+            Self::Yellow => "yellow",
+            Self::Blue => "blue",
+            Self::Magenta => "magenta",
+            Self::Cyan => "cyan",
+            Self::White => "white",
+        }
+    }
+}
+
+/// Index of a colour in the 256-colour ANSI palette.
+///
+/// A typed wrapper around `u8` which prevents mixing palette indexes with
+/// arbitrary bytes and offers methods classifying the entry the index refers
+/// to.  The palette consists of 16 system colours (indexes 0–15), 6×6×6
+/// colour cube (indexes 16–231) and 24 shades of the greyscale ramp (indexes
+/// 232–255).
+///
+/// The type is `#[repr(transparent)]` and thus has the same layout as `u8`.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::{AsRGB, PaletteIndex};
+///
+/// let idx = PaletteIndex::from(67);
+/// assert!(!idx.is_system());
+/// assert!(idx.is_cube());
+/// assert!(!idx.is_grey());
+/// assert_eq!((95, 135, 175), idx.to_rgb());
+/// assert_eq!(55, idx.luminance());
+/// assert_eq!(67, u8::from(idx));
+///
+/// assert_eq!(PaletteIndex(67), PaletteIndex::approx((95, 135, 175)));
+/// assert_eq!(0x5f87af, idx.as_u32());
+/// assert_eq!(67, idx.to_ansi256());
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "bytemuck", derive(bytemuck::Pod, bytemuck::Zeroable))]
+#[cfg_attr(
+    feature = "zerocopy",
+    derive(
+        zerocopy::FromBytes,
+        zerocopy::IntoBytes,
+        zerocopy::Immutable,
+        zerocopy::KnownLayout
+    )
+)]
+#[repr(transparent)]
+pub struct PaletteIndex(pub u8);
+
+impl PaletteIndex {
+    /// Returns index of a colour in the palette approximating given sRGB
+    /// colour.  See [`ansi256_from_rgb`](crate::ansi256_from_rgb).
+    #[inline]
+    pub fn approx<C: AsRGB>(rgb: C) -> Self {
+        Self(rgb.to_ansi256())
+    }
+
+    /// Returns whether the index refers to one of the 16 system colours.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ansi_colours::PaletteIndex;
+    ///
+    /// assert!(PaletteIndex(0).is_system());
+    /// assert!(PaletteIndex(15).is_system());
+    /// assert!(!PaletteIndex(16).is_system());
+    /// ```
+    #[inline]
+    pub const fn is_system(self) -> bool {
+        self.0 < 16
+    }
+
+    /// Returns whether the index refers to an entry of the 6×6×6 colour cube.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ansi_colours::PaletteIndex;
+    ///
+    /// assert!(!PaletteIndex(15).is_cube());
+    /// assert!(PaletteIndex(16).is_cube());
+    /// assert!(PaletteIndex(231).is_cube());
+    /// assert!(!PaletteIndex(232).is_cube());
+    /// ```
+    #[inline]
+    pub const fn is_cube(self) -> bool {
+        self.0 >= 16 && self.0 < 232
+    }
+
+    /// Returns whether the index refers to a shade of the greyscale ramp.
+    ///
+    /// Note that shades of grey on the diagonal of the colour cube (including
+    /// black and white) as well as grey system colours are not part of the
+    /// ramp.  See also [`grey_level`](crate::grey_level).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ansi_colours::PaletteIndex;
+    ///
+    /// assert!(!PaletteIndex(231).is_grey());
+    /// assert!(PaletteIndex(232).is_grey());
+    /// assert!(PaletteIndex(255).is_grey());
+    /// ```
+    #[inline]
+    pub const fn is_grey(self) -> bool {
+        self.0 >= 232
+    }
+
+    /// Returns sRGB colour at the index.  See
+    /// [`rgb_from_ansi256`](crate::rgb_from_ansi256).
+    #[inline]
+    pub fn to_rgb(self) -> (u8, u8, u8) {
+        crate::rgb_from_ansi256(self.0)
+    }
+
+    /// Returns perceptual luminance of colour at the index.  See
+    /// [`luminance_from_ansi256`](crate::luminance_from_ansi256).
+    #[inline]
+    pub fn luminance(self) -> u8 {
+        crate::luminance_from_ansi256(self.0)
+    }
+}
+
+impl From<u8> for PaletteIndex {
+    #[inline]
+    fn from(idx: u8) -> Self {
+        Self(idx)
+    }
+}
+
+impl From<PaletteIndex> for u8 {
+    #[inline]
+    fn from(idx: PaletteIndex) -> Self {
+        idx.0
+    }
+}
+
+impl AsRGB for PaletteIndex {
+    /// Returns sRGB colour at the index as a 24-bit `0xRRGGBB` integer.
+    #[inline]
+    fn as_u32(&self) -> u32 {
+        palette_colour(self.0)
+    }
+
+    /// Returns the index.
+    #[inline]
+    fn to_ansi256(&self) -> u8 {
+        self.0
+    }
+}
+
+impl fmt::Display for PaletteIndex {
+    fn fmt(&self, fmtr: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(fmtr)
+    }
+}
+
+/// Policy describing how bold text affects its colour.
+///
+/// Historically terminals rendered bold text in one of the eight basic
+/// colours using bright variant of the colour.  Many terminals still do that
+/// by default (e.g. XTerm’s `boldColors` resource) while others render bold
+/// text in the same colour as normal text.  See
+/// [`ansi256_from_sgr`](crate::ansi256_from_sgr).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum BrightPolicy {
+    /// Bold text in one of the eight basic foreground colours uses bright
+    /// variant of the colour.
+    #[default]
+    BoldIsBright,
+    /// Boldness doesn’t affect colour.
+    BoldIsBold,
+}
+
+/// A terminal colour.
+///
+/// The type can be parsed from and formatted as a string which makes it
+/// suitable for use in configuration files.  Three forms are supported:
+/// - name of one of the eight basic colours, e.g. `red` (parsing is
+///   case-insensitive),
+/// - index in the 256-colour palette written as a decimal number, e.g. `208`
+///   and
+/// - 24-bit sRGB colour written in `#RRGGBB` hexadecimal notation, e.g.
+///   `#5f87af` (parsing is case-insensitive).
+///
+/// Formatting a colour produces a string which parses back to the same
+/// colour.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::{Colour, NamedColour};
+///
+/// assert_eq!(Ok(Colour::Named(NamedColour::Red)), "red".parse());
+/// assert_eq!(Ok(Colour::Named(NamedColour::Cyan)), "Cyan".parse());
+/// assert_eq!(Ok(Colour::Fixed(208)), "208".parse());
+/// assert_eq!(Ok(Colour::Rgb(95, 135, 175)), "#5F87AF".parse());
+/// assert!("256".parse::<Colour>().is_err());
+/// assert!("#fff".parse::<Colour>().is_err());
+/// assert!("purple".parse::<Colour>().is_err());
+///
+/// assert_eq!("red", Colour::Named(NamedColour::Red).to_string());
+/// assert_eq!("208", Colour::Fixed(208).to_string());
+/// assert_eq!("#5f87af", Colour::Rgb(95, 135, 175).to_string());
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Colour {
+    /// One of the eight basic colours.
+    Named(NamedColour),
+    /// Colour at given index in the 256-colour palette.
+    Fixed(u8),
+    /// A 24-bit sRGB colour.
+    Rgb(u8, u8, u8),
+}
+
+impl Colour {
+    /// Returns colour for given system colour index which must be less than
+    /// 16.  The eight basic colours are represented by `Named` variant while
+    /// the bright ones by `Fixed` variant.
+    #[cfg(any(
+        feature = "anes",
+        feature = "anstyle",
+        feature = "crossterm",
+        feature = "paint"
+    ))]
+    pub(crate) fn from_system(idx: u8) -> Self {
+        match NamedColour::ALL.get(usize::from(idx)) {
+            Some(&colour) => Self::Named(colour),
+            None => Self::Fixed(idx),
+        }
+    }
+
+    /// Returns the colour as a 24-bit `0xRRGGBB` integer.  Named and fixed
+    /// colours are looked up in the palette.
+    pub(crate) fn rgb_u32(self) -> u32 {
+        match self {
+            Self::Named(colour) => palette_colour(colour.index()),
+            Self::Fixed(idx) => palette_colour(idx),
+            Self::Rgb(r, g, b) => {
+                (u32::from(r) << 16) | (u32::from(g) << 8) | u32::from(b)
+            }
+        }
+    }
+}
+
+impl From<NamedColour> for Colour {
+    #[inline]
+    fn from(colour: NamedColour) -> Self {
+        Self::Named(colour)
+    }
+}
+
+impl From<u8> for Colour {
+    #[inline]
+    fn from(idx: u8) -> Self {
+        Self::Fixed(idx)
+    }
+}
+
+impl From<PaletteIndex> for Colour {
+    #[inline]
+    fn from(idx: PaletteIndex) -> Self {
+        Self::Fixed(idx.0)
+    }
+}
+
+impl From<(u8, u8, u8)> for Colour {
+    #[inline]
+    fn from((r, g, b): (u8, u8, u8)) -> Self {
+        Self::Rgb(r, g, b)
+    }
+}
+
+impl From<[u8; 3]> for Colour {
+    #[inline]
+    fn from([r, g, b]: [u8; 3]) -> Self {
+        Self::Rgb(r, g, b)
+    }
+}
+
+impl From<u32> for Colour {
+    /// Converts 24-bit `0xRRGGBB` integer into an sRGB colour.  The most
+    /// significant byte is ignored.
+    #[inline]
+    fn from(rgb: u32) -> Self {
+        Self::Rgb((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8)
+    }
+}
+
+impl AsRGB for Colour {
+    /// Returns sRGB colour as a 24-bit `0xRRGGBB` integer.
+    ///
+    /// Named and fixed colours are looked up in the palette taking into
+    /// account values set with
+    /// [`set_system_colours`](crate::set_system_colours).
+    #[inline]
+    fn as_u32(&self) -> u32 {
+        self.rgb_u32()
+    }
+
+    /// Returns index of a colour in 256-colour ANSI palette approximating the
+    /// colour.
+    ///
+    /// For named and fixed colours returns their index.  RGB colours are
+    /// approximated.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ansi_colours::{AsRGB, Colour, NamedColour};
+    ///
+    /// assert_eq!(  1, Colour::Named(NamedColour::Red).to_ansi256());
+    /// assert_eq!( 42, Colour::Fixed(42).to_ansi256());
+    /// assert_eq!( 67, Colour::Rgb(95, 135, 175).to_ansi256());
+    /// assert_eq!(231, Colour::Rgb(255, 255, 255).to_ansi256());
+    /// ```
+    #[inline]
+    fn to_ansi256(&self) -> u8 {
+        match *self {
+            Self::Named(colour) => colour.index(),
+            Self::Fixed(idx) => idx,
+            Self::Rgb(r, g, b) => (r, g, b).to_ansi256(),
+        }
+    }
+}
+
+impl ColourExt for Colour {
+    /// Constructs a `Fixed` colour which approximates given sRGB colour.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ansi_colours::{Colour, ColourExt};
+    ///
+    /// assert_eq!(Colour::Fixed( 16), Colour::approx_rgb(  0,   0,   0));
+    /// assert_eq!(Colour::Fixed( 67), Colour::approx_rgb( 95, 135, 175));
+    /// assert_eq!(Colour::Fixed(231), Colour::approx_rgb(255, 255, 255));
+    /// ```
+    #[inline]
+    fn approx_rgb(r: u8, g: u8, b: u8) -> Self {
+        Self::Fixed(crate::ansi256_from_rgb((r, g, b)))
+    }
+
+    /// Converts the colour into 256-colour-compatible format.
+    ///
+    /// `Rgb` colours are approximated and converted into `Fixed` variant.
+    /// Other colours are returned unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ansi_colours::{Colour, ColourExt, NamedColour};
+    ///
+    /// let red = Colour::Named(NamedColour::Red);
+    /// assert_eq!(red, red.to_256());
+    /// assert_eq!(Colour::Fixed(11), Colour::Fixed(11).to_256());
+    /// assert_eq!(Colour::Fixed(67), Colour::Rgb(95, 135, 175).to_256());
+    /// ```
+    #[inline]
+    fn to_256(&self) -> Self {
+        match *self {
+            Self::Rgb(r, g, b) => Self::approx_rgb(r, g, b),
+            colour => colour,
+        }
+    }
+
+    /// Converts the colour into sRGB.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ansi_colours::{Colour, ColourExt, NamedColour};
+    ///
+    /// assert_eq!((205,   0,   0), Colour::Named(NamedColour::Red).to_rgb());
+    /// assert_eq!(( 95, 135, 175), Colour::Fixed( 67).to_rgb());
+    /// assert_eq!((238, 238, 238), Colour::Fixed(255).to_rgb());
+    /// assert_eq!(( 42,  24,   0), Colour::Rgb(42, 24, 0).to_rgb());
+    /// ```
+    #[inline]
+    fn to_rgb(&self) -> (u8, u8, u8) {
+        to_triple(self.rgb_u32())
+    }
+}
+
+/// Error returned when parsing a [`Colour`] or [`NamedColour`] fails.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ParseColourError;
+
+/// Error returned when converting a colour which has no equivalent
+/// [`Colour`], such as terminal’s default colour, fails.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct UnsupportedColourError;
+
+impl fmt::Display for UnsupportedColourError {
+    fn fmt(&self, fmtr: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmtr.write_str("colour cannot be represented")
+    }
+}
+
+/// This implementation is present only if `std` crate feature is enabled.
+#[cfg(feature = "std")]
+impl std::error::Error for UnsupportedColourError {}
+
+impl fmt::Display for ParseColourError {
+    fn fmt(&self, fmtr: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmtr.write_str("invalid colour; expected name, index or #RRGGBB")
+    }
+}
+
+/// This implementation is present only if `std` crate feature is enabled.
+#[cfg(feature = "std")]
+impl std::error::Error for ParseColourError {}
+
+impl FromStr for NamedColour {
+    type Err = ParseColourError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|colour| colour.name().eq_ignore_ascii_case(s))
+            .ok_or(ParseColourError)
+    }
+}
+
+impl fmt::Display for NamedColour {
+    fn fmt(&self, fmtr: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmtr.write_str(self.name())
+    }
+}
+
+impl FromStr for Colour {
+    type Err = ParseColourError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(hex) = s.strip_prefix('#') {
+            if hex.len() != 6 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+                return Err(ParseColourError);
+            }
+            let rgb =
+                u32::from_str_radix(hex, 16).map_err(|_| ParseColourError)?;
+            Ok(Self::Rgb((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8))
+        } else if s.starts_with(|chr: char| chr.is_ascii_digit()) {
+            s.parse().map(Self::Fixed).map_err(|_| ParseColourError)
+        } else {
+            s.parse().map(Self::Named)
+        }
+    }
+}
+
+impl fmt::Display for Colour {
+    fn fmt(&self, fmtr: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Named(colour) => colour.fmt(fmtr),
+            Self::Fixed(idx) => idx.fmt(fmtr),
+            Self::Rgb(r, g, b) => write!(fmtr, "#{:02x}{:02x}{:02x}", r, g, b),
+        }
+    }
+}
@@ -0,0 +1,347 @@
+// ansi_colours – true-colour ↔ ANSI terminal palette converter
+// Copyright 2018 by Michał Nazarewicz <mina86@mina86.com>
+//
+// ansi_colours is free software: you can redistribute it and/or modify it
+// under the terms of the GNU Lesser General Public License as published by
+// the Free Software Foundation; either version 3 of the License, or (at
+// your option) any later version.
+//
+// ansi_colours is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Lesser
+// General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with ansi_colours.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Conversion of colours using configurable distance metric.
+
+use crate::ansi256::{self, to_triple, CUBE_LEVELS};
+#[cfg(any(feature = "ciede2000", feature = "oklab"))]
+use crate::metric;
+use crate::AsRGB;
+
+/// Metric used to measure distance between colours when approximating them by
+/// colours in the 256-colour ANSI palette.
+///
+/// Metrics other than [`Metric::Heuristic`] compare approximated colour with
+/// every colour in the palette (except for the first 16 system colours) and
+/// are thus slower.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[non_exhaustive]
+pub enum Metric {
+    /// The formula used by [`ansi256_from_rgb`](crate::ansi256_from_rgb).
+    ///
+    /// It considers only two candidates, the closest colour in the colour cube
+    /// and the closest shade of grey, and picks the one with smaller redmean
+    /// distance.  This is the fastest metric.
+    #[default]
+    Heuristic,
+
+    /// ‘Redmean’ weighted Euclidean distance in sRGB space.
+    ///
+    /// Squared differences of the components are weighted depending on the
+    /// mean value of the red component as described in [Colour metric
+    /// article](https://www.compuphase.com/cmetric.htm).  All colours of the
+    /// palette are compared using that metric which can produce better
+    /// matches for some colours (e.g. skin tones) at small cost.
+    Redmean,
+
+    /// Euclidean distance in OKLab colour space.
+    ///
+    /// Uses [`ansi256_from_rgb_oklab`](crate::ansi256_from_rgb_oklab).  This
+    /// variant is present only if `oklab` crate feature is enabled.
+    #[cfg(feature = "oklab")]
+    Oklab,
+
+    /// CIEDE2000 colour difference.
+    ///
+    /// Uses [`ansi256_from_rgb_exact`](crate::ansi256_from_rgb_exact).  This
+    /// variant is present only if `ciede2000` crate feature is enabled.
+    #[cfg(feature = "ciede2000")]
+    Ciede2000,
+
+    /// Weighted Euclidean distance in CIELAB colour space.
+    ///
+    /// The weights allow biasing the approximation towards preserving hue or
+    /// lightness of the colour.  See [`metric::Weighted`] for details.  This
+    /// variant is present only if `ciede2000` crate feature is enabled.
+    #[cfg(feature = "ciede2000")]
+    Weighted(metric::Weighted),
+}
+
+/// Policy for choosing between near-duplicate shades of grey.
+///
+/// Four shades of grey on the diagonal of the colour cube have almost
+/// identical counterparts in the greyscale ramp.  With default XTerm palette
+/// those are indexes 59 and 241 (`#5f5f5f` and `#626262`), 102 and 245
+/// (`#878787` and `#8a8a8a`), 145 and 249 (`#afafaf` and `#b2b2b2`) and 188
+/// and 253 (`#d7d7d7` and `#dadada`).  Tools which compare or deduplicate
+/// colours may want to always use one member of each pair.  See
+/// [`canonical_grey`](crate::canonical_grey) and
+/// [`Converter::with_grey_policy`].
+///
+/// Two shades are considered near-duplicates if their components differ by
+/// less than five, i.e. half of the greyscale ramp’s step.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum GreyPolicy {
+    /// Either member of a near-duplicate pair may be used.  The approximation
+    /// returns whichever is closer to the approximated colour.
+    #[default]
+    Any,
+    /// Shades from the greyscale ramp (indexes 232–255) are preferred.
+    PreferRamp,
+    /// Shades from the diagonal of the colour cube are preferred.
+    PreferCube,
+}
+
+/// Returns canonical index of a shade of grey according to given policy.
+/// Colour of entries of the palette is determined by `colour` function.
+// `u8::is_multiple_of` is too new for compilers the crate supports.
+#[allow(clippy::manual_is_multiple_of)]
+pub(crate) fn canonical_grey(
+    idx: u8,
+    policy: GreyPolicy,
+    colour: impl Fn(u8) -> u32,
+) -> u8 {
+    let candidate = match policy {
+        GreyPolicy::Any => return idx,
+        GreyPolicy::PreferRamp if (16..232).contains(&idx) => {
+            if (idx - 16) % 43 != 0 {
+                return idx;
+            }
+            let value = colour(idx) & 0xff;
+            232 + (value.saturating_sub(3) / 10).min(23) as u8
+        }
+        GreyPolicy::PreferCube if idx >= 232 => {
+            let value = colour(idx) & 0xff;
+            (0..6)
+                .map(|pos| 16 + 43 * pos)
+                .min_by_key(|idx| (colour(*idx) & 0xff).abs_diff(value))
+                .unwrap_or(idx)
+        }
+        _ => return idx,
+    };
+    let diff = (colour(candidate) & 0xff).abs_diff(colour(idx) & 0xff);
+    if diff < 5 {
+        candidate
+    } else {
+        idx
+    }
+}
+
+/// Converter of sRGB colours into indexes in the 256-colour ANSI palette using
+/// chosen distance [`Metric`] and colour cube levels.
+///
+/// By default the converter assumes the palette uses the same colour cube as
+/// XTerm, i.e. one with component levels 0, 95, 135, 175, 215 and 255.  Some
+/// terminals use different levels (e.g. evenly spaced 0, 51, 102, 153, 204 and
+/// 255) which can be configured with [`Converter::with_cube_levels`].
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::{Converter, Metric};
+///
+/// let heuristic = Converter::default();
+/// let redmean = Converter::new(Metric::Redmean);
+/// assert_eq!(Metric::Redmean, redmean.metric());
+///
+/// assert_eq!( 67, heuristic.ansi256_from_rgb(( 95, 135, 175)));
+/// assert_eq!( 67, redmean.ansi256_from_rgb(( 95, 135, 175)));
+///
+/// assert_eq!(143, heuristic.ansi256_from_rgb(0xb49b5a));
+/// assert_eq!(137, redmean.ansi256_from_rgb(0xb49b5a));
+///
+/// let levels = [0, 51, 102, 153, 204, 255];
+/// let linear = Converter::default().with_cube_levels(levels);
+/// assert_eq!(levels, linear.cube_levels());
+/// assert_eq!(( 51, 102, 153), linear.rgb_from_ansi256(67));
+/// assert_eq!( 67, linear.ansi256_from_rgb(( 51, 102, 153)));
+/// assert_eq!(109, linear.ansi256_from_rgb(( 95, 135, 175)));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Converter {
+    metric: Metric,
+    levels: [u8; 6],
+    greys: GreyPolicy,
+}
+
+impl Default for Converter {
+    #[inline]
+    fn default() -> Self {
+        Self::new(Metric::default())
+    }
+}
+
+impl Converter {
+    /// Constructs a new converter using given metric and default XTerm colour
+    /// cube levels.
+    #[inline]
+    pub const fn new(metric: Metric) -> Self {
+        Self {
+            metric,
+            levels: CUBE_LEVELS,
+            greys: GreyPolicy::Any,
+        }
+    }
+
+    /// Returns converter using given colour cube levels.
+    ///
+    /// The levels are values of a component for each of the six coordinates
+    /// of the colour cube.  They should be given in ascending order.
+    #[inline]
+    pub const fn with_cube_levels(self, levels: [u8; 6]) -> Self {
+        Self { levels, ..self }
+    }
+
+    /// Returns converter using given policy for choosing between
+    /// near-duplicate shades of grey.
+    ///
+    /// With policy other than [`GreyPolicy::Any`], whenever the approximation
+    /// results in a shade of grey which has a near-duplicate, the preferred
+    /// member of the pair is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ansi_colours::{Converter, GreyPolicy};
+    ///
+    /// let any = Converter::default();
+    /// let ramp = any.with_grey_policy(GreyPolicy::PreferRamp);
+    /// let cube = any.with_grey_policy(GreyPolicy::PreferCube);
+    /// assert_eq!(GreyPolicy::PreferRamp, ramp.grey_policy());
+    ///
+    /// assert_eq!( 59, any.ansi256_from_rgb(0x5f5f5f));
+    /// assert_eq!(241, ramp.ansi256_from_rgb(0x5f5f5f));
+    /// assert_eq!( 59, cube.ansi256_from_rgb(0x5f5f5f));
+    ///
+    /// assert_eq!(241, any.ansi256_from_rgb(0x626262));
+    /// assert_eq!(241, ramp.ansi256_from_rgb(0x626262));
+    /// assert_eq!( 59, cube.ansi256_from_rgb(0x626262));
+    /// ```
+    #[inline]
+    pub const fn with_grey_policy(self, greys: GreyPolicy) -> Self {
+        Self { greys, ..self }
+    }
+
+    /// Returns metric used by the converter.
+    #[inline]
+    pub const fn metric(&self) -> Metric {
+        self.metric
+    }
+
+    /// Returns colour cube levels used by the converter.
+    #[inline]
+    pub const fn cube_levels(&self) -> [u8; 6] {
+        self.levels
+    }
+
+    /// Returns policy for choosing between near-duplicate shades of grey used
+    /// by the converter.
+    #[inline]
+    pub const fn grey_policy(&self) -> GreyPolicy {
+        self.greys
+    }
+
+    /// Returns sRGB colour corresponding to the index in the 256-colour ANSI
+    /// palette taking into account converter’s colour cube levels.
+    ///
+    /// System colours and the greyscale ramp are the same as ones returned by
+    /// [`rgb_from_ansi256`](crate::rgb_from_ansi256).
+    #[inline]
+    pub fn rgb_from_ansi256(&self, idx: u8) -> (u8, u8, u8) {
+        to_triple(self.colour(idx))
+    }
+
+    /// Returns index of a colour in 256-colour ANSI palette approximating
+    /// given sRGB colour.
+    ///
+    /// Just like [`ansi256_from_rgb`](crate::ansi256_from_rgb), the method
+    /// never returns indexes of the 16 system colours.
+    pub fn ansi256_from_rgb<C: AsRGB>(&self, rgb: C) -> u8 {
+        let idx = self.approximate(rgb.as_u32() & 0xffffff);
+        canonical_grey(idx, self.greys, |idx| self.colour(idx))
+    }
+
+    /// Approximates colour using converter’s metric and colour cube levels.
+    fn approximate(&self, rgb: u32) -> u8 {
+        if self.levels == CUBE_LEVELS {
+            return match self.metric {
+                Metric::Heuristic => ansi256::ansi256_from_rgb(rgb),
+                Metric::Redmean => {
+                    let colours = ansi256::extended_colours();
+                    ansi256::nearest(rgb, colours) + 16
+                }
+                #[cfg(feature = "oklab")]
+                Metric::Oklab => crate::oklab::ansi256_from_rgb(rgb),
+                #[cfg(feature = "ciede2000")]
+                Metric::Ciede2000 => crate::ciede2000::ansi256_from_rgb(rgb),
+                #[cfg(feature = "ciede2000")]
+                Metric::Weighted(weighted) => {
+                    let colours = ansi256::extended_colours();
+                    metric::nearest(&weighted, rgb, colours) + 16
+                }
+            };
+        }
+
+        let colours = (16..=255).map(|idx| self.colour(idx));
+        match self.metric {
+            Metric::Heuristic => self.heuristic(rgb),
+            Metric::Redmean => ansi256::nearest(rgb, colours) + 16,
+            #[cfg(feature = "oklab")]
+            Metric::Oklab => metric::nearest(&metric::Oklab, rgb, colours) + 16,
+            #[cfg(feature = "ciede2000")]
+            Metric::Ciede2000 => {
+                metric::nearest(&metric::De2000, rgb, colours) + 16
+            }
+            #[cfg(feature = "ciede2000")]
+            Metric::Weighted(weighted) => {
+                metric::nearest(&weighted, rgb, colours) + 16
+            }
+        }
+    }
+
+    /// Returns colour at given index in the palette as `0xRRGGBB` integer.
+    fn colour(&self, idx: u8) -> u32 {
+        if (16..232).contains(&idx) {
+            let idx = idx - 16;
+            let r = self.levels[usize::from(idx / 36)];
+            let g = self.levels[usize::from(idx / 6 % 6)];
+            let b = self.levels[usize::from(idx % 6)];
+            (u32::from(r) << 16) | (u32::from(g) << 8) | u32::from(b)
+        } else {
+            ansi256::colour(idx)
+        }
+    }
+
+    /// Returns position of the cube level closest to given component value.
+    fn nearest_level(&self, value: u8) -> u8 {
+        (0..6)
+            .min_by_key(|pos| self.levels[usize::from(*pos)].abs_diff(value))
+            .unwrap_or(0)
+    }
+
+    /// Approximates colour using custom cube levels.  Like the default
+    /// heuristic, considers the closest colour in the cube and the closest
+    /// shades of grey only.
+    fn heuristic(&self, rgb: u32) -> u8 {
+        let (r, g, b) = to_triple(rgb);
+        let cube = 16
+            + 36 * self.nearest_level(r)
+            + 6 * self.nearest_level(g)
+            + self.nearest_level(b);
+        let y = ansi256::luminance(r, g, b);
+        let ramp = 232 + (y.saturating_sub(3) / 10).min(23);
+        let diagonal = 16 + 43 * self.nearest_level(y);
+        let triple = (r, g, b);
+        [cube, ramp, diagonal]
+            .iter()
+            .copied()
+            .min_by_key(|idx| ansi256::distance(triple, self.colour(*idx)))
+            .unwrap_or(cube)
+    }
+}
@@ -0,0 +1,33 @@
+// ansi_colours – true-colour ↔ ANSI terminal palette converter
+// Copyright 2018 by Michał Nazarewicz <mina86@mina86.com>
+//
+// ansi_colours is free software: you can redistribute it and/or modify it
+// under the terms of the GNU Lesser General Public License as published by
+// the Free Software Foundation; either version 3 of the License, or (at
+// your option) any later version.
+//
+// ansi_colours is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Lesser
+// General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with ansi_colours.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Structure of the 6×6×6 colour cube.
+
+/// A channel of an sRGB colour.
+///
+/// The crate approximates each channel of a colour by a coordinate of the
+/// 6×6×6 cube using slightly different thresholds for each channel.  See
+/// [`nearest_cube_value`](crate::nearest_cube_value).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Channel {
+    /// The red channel, i.e. the most significant coordinate of the cube.
+    Red = 0,
+    /// The green channel.
+    Green = 1,
+    /// The blue channel, i.e. the least significant coordinate of the cube.
+    Blue = 2,
+}
@@ -0,0 +1,66 @@
+// ansi_colours – true-colour ↔ ANSI terminal palette converter
+// Copyright 2018 by Michał Nazarewicz <mina86@mina86.com>
+//
+// ansi_colours is free software: you can redistribute it and/or modify it
+// under the terms of the GNU Lesser General Public License as published by
+// the Free Software Foundation; either version 3 of the License, or (at
+// your option) any later version.
+//
+// ansi_colours is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Lesser
+// General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with ansi_colours.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Colour depth supported by terminals.
+
+/// Number of colours supported by a terminal.
+///
+/// Variants are ordered by number of colours so they can be compared, e.g.
+/// `depth >= ColourDepth::Ansi256` checks whether 256-colour palette can be
+/// used.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[non_exhaustive]
+pub enum ColourDepth {
+    /// Terminal doesn’t support colours or user requested no colours.
+    Monochrome,
+    /// Terminal supports the eight basic colours.  See
+    /// [`ansi8_from_rgb`](crate::ansi8_from_rgb).
+    Ansi8,
+    /// Terminal supports the 16 system colours.  See
+    /// [`ansi16_from_rgb`](crate::ansi16_from_rgb).
+    Ansi16,
+    /// Terminal supports the 256-colour palette.  See
+    /// [`ansi256_from_rgb`](crate::ansi256_from_rgb).
+    Ansi256,
+    /// Terminal supports 24-bit true-colour.
+    TrueColour,
+}
+
+impl ColourDepth {
+    /// Returns colour depth corresponding to given number of colours as
+    /// reported by terminfo `colors` capability.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ansi_colours::ColourDepth;
+    ///
+    /// assert_eq!(ColourDepth::Monochrome, ColourDepth::from_colours(0));
+    /// assert_eq!(ColourDepth::Ansi8, ColourDepth::from_colours(8));
+    /// assert_eq!(ColourDepth::Ansi256, ColourDepth::from_colours(256));
+    /// assert_eq!(ColourDepth::TrueColour, ColourDepth::from_colours(1 << 24));
+    /// ```
+    pub fn from_colours(colours: u32) -> Self {
+        match colours {
+            0..=7 => Self::Monochrome,
+            8..=15 => Self::Ansi8,
+            16..=255 => Self::Ansi16,
+            256..=0xffffff => Self::Ansi256,
+            _ => Self::TrueColour,
+        }
+    }
+}
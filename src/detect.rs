@@ -0,0 +1,188 @@
+// ansi_colours – true-colour ↔ ANSI terminal palette converter
+// Copyright 2018 by Michał Nazarewicz <mina86@mina86.com>
+//
+// ansi_colours is free software: you can redistribute it and/or modify it
+// under the terms of the GNU Lesser General Public License as published by
+// the Free Software Foundation; either version 3 of the License, or (at
+// your option) any later version.
+//
+// ansi_colours is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Lesser
+// General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with ansi_colours.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Detection of colour depth supported by the terminal.
+//!
+//! The detection is based on environment variables and terminfo database.
+//! It doesn’t communicate with the terminal.
+//!
+//! This module is present only if `detect` crate feature is enabled.
+
+use core::convert::TryFrom;
+use std::env;
+use std::ffi::OsStr;
+use std::path::PathBuf;
+use std::vec::Vec;
+
+pub use crate::ColourDepth;
+
+/// Detects colour depth supported by the terminal.
+///
+/// The function looks at `NO_COLOR`, `COLORTERM` and `TERM` environment
+/// variables and `colors` capability of the terminal in terminfo database.
+/// See [`detect_from`] for details.
+pub fn detect() -> ColourDepth {
+    let no_colour = env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+    if no_colour {
+        return ColourDepth::Monochrome;
+    }
+    let colorterm = env::var("COLORTERM").ok();
+    let term = env::var("TERM").ok();
+    // Terminal whose terminfo entry doesn’t specify colours has none.
+    let colours = term
+        .as_deref()
+        .and_then(terminfo_colours)
+        .map(|colours| colours.unwrap_or(0));
+    detect_from(colorterm.as_deref(), term.as_deref(), colours)
+}
+
+/// Determines colour depth supported by the terminal based on given values of
+/// `COLORTERM` and `TERM` environment variables and terminfo `colors`
+/// capability.
+///
+/// * If `COLORTERM` is `truecolor` or `24bit`, returns
+///   [`ColourDepth::TrueColour`].
+/// * Otherwise, if `TERM` is not set or is `dumb`, returns
+///   [`ColourDepth::Monochrome`].
+/// * Otherwise, if number of colours from terminfo is known, returns depth
+///   corresponding to it (see [`ColourDepth::from_colours`]).  Terminal
+///   whose terminfo entry lacks `colors` capability should be passed zero.
+/// * Otherwise, guesses depth based on `TERM`: names ending with `-direct` or
+///   containing `truecolor` indicate true-colour, names containing `256color`
+///   indicate 256 colours and all other names indicate 16 colours.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::detect::{detect_from, ColourDepth};
+///
+/// let depth = detect_from(Some("truecolor"), Some("xterm"), Some(8));
+/// assert_eq!(ColourDepth::TrueColour, depth);
+/// let depth = detect_from(None, Some("xterm-256color"), Some(256));
+/// assert_eq!(ColourDepth::Ansi256, depth);
+/// let depth = detect_from(None, Some("xterm-256color"), None);
+/// assert_eq!(ColourDepth::Ansi256, depth);
+/// let depth = detect_from(None, Some("vt100"), Some(0));
+/// assert_eq!(ColourDepth::Monochrome, depth);
+/// let depth = detect_from(None, Some("dumb"), None);
+/// assert_eq!(ColourDepth::Monochrome, depth);
+/// ```
+pub fn detect_from(
+    colorterm: Option<&str>,
+    term: Option<&str>,
+    terminfo_colours: Option<u32>,
+) -> ColourDepth {
+    if matches!(colorterm, Some("truecolor" | "24bit")) {
+        return ColourDepth::TrueColour;
+    }
+    let term = match term {
+        None | Some("") | Some("dumb") => return ColourDepth::Monochrome,
+        Some(term) => term,
+    };
+    if let Some(colours) = terminfo_colours {
+        ColourDepth::from_colours(colours)
+    } else if term.ends_with("-direct") || term.contains("truecolor") {
+        ColourDepth::TrueColour
+    } else if term.contains("256color") {
+        ColourDepth::Ansi256
+    } else {
+        ColourDepth::Ansi16
+    }
+}
+
+/// Returns value of `colors` capability of given terminal from the terminfo
+/// database.
+///
+/// Returns `None` if the terminal description couldn’t be found or read and
+/// `Some(None)` if the description was found but it doesn’t specify the
+/// capability (which means the terminal doesn’t support colours).  The
+/// database is searched in
+/// directories given by `TERMINFO` and `TERMINFO_DIRS` environment variables,
+/// `~/.terminfo` and standard system locations.
+pub fn terminfo_colours(term: &str) -> Option<Option<u32>> {
+    let first = term.chars().next()?;
+    if term.contains('/') || term.starts_with('.') {
+        return None;
+    }
+    let mut buf = [0; 4];
+    let letter: &str = first.encode_utf8(&mut buf);
+    let hex = std::format!("{:x}", u32::from(first));
+    let subdirs = [PathBuf::from(letter), PathBuf::from(hex)];
+    terminfo_dirs().find_map(|dir| {
+        subdirs.iter().find_map(|subdir| {
+            let data = std::fs::read(dir.join(subdir).join(term)).ok()?;
+            parse_terminfo_colours(&data)
+        })
+    })
+}
+
+/// Returns iterator over directories which may contain terminfo database.
+fn terminfo_dirs() -> impl Iterator<Item = PathBuf> {
+    let terminfo = env::var_os("TERMINFO").map(PathBuf::from);
+    let home =
+        env::var_os("HOME").map(|home| PathBuf::from(home).join(".terminfo"));
+    let dirs = env::var_os("TERMINFO_DIRS").unwrap_or_default();
+    let dirs = env::split_paths(&dirs)
+        .filter(|dir| dir.as_os_str() != OsStr::new(""))
+        .collect::<Vec<_>>();
+    static SYSTEM: [&str; 4] = [
+        "/etc/terminfo",
+        "/lib/terminfo",
+        "/usr/share/terminfo",
+        "/usr/lib/terminfo",
+    ];
+    terminfo
+        .into_iter()
+        .chain(home)
+        .chain(dirs)
+        .chain(SYSTEM.iter().map(PathBuf::from))
+}
+
+/// Parses compiled terminfo entry and returns value of its `colors`
+/// capability.
+///
+/// Returns `None` if the entry is malformed and `Some(None)` if it doesn’t
+/// specify the capability.  Both legacy format (with 16-bit numbers) and
+/// extended format (with 32-bit numbers) are supported.
+pub(crate) fn parse_terminfo_colours(data: &[u8]) -> Option<Option<u32>> {
+    /// Index of the `colors` capability in the numbers section.
+    const COLORS: usize = 13;
+
+    let header = |idx: usize| -> Option<usize> {
+        let bytes = data.get(idx * 2..idx * 2 + 2)?;
+        usize::try_from(i16::from_le_bytes([bytes[0], bytes[1]])).ok()
+    };
+    let width = match header(0)? {
+        0o432 => 2,
+        0o1036 => 4,
+        _ => return None,
+    };
+    let (names, bools, nums) = (header(1)?, header(2)?, header(3)?);
+    if nums <= COLORS {
+        return Some(None);
+    }
+    let mut offset = 12 + names + bools;
+    offset += offset % 2;
+    let offset = offset + COLORS * width;
+    let bytes = data.get(offset..offset + width)?;
+    let value = if width == 2 {
+        i32::from(i16::from_le_bytes([bytes[0], bytes[1]]))
+    } else {
+        i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+    };
+    // Negative values mark absent or cancelled capabilities.
+    Some(u32::try_from(value).ok())
+}
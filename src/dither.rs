@@ -0,0 +1,70 @@
+// ansi_colours – true-colour ↔ ANSI terminal palette converter
+// Copyright 2018 by Michał Nazarewicz <mina86@mina86.com>
+//
+// ansi_colours is free software: you can redistribute it and/or modify it
+// under the terms of the GNU Lesser General Public License as published by
+// the Free Software Foundation; either version 3 of the License, or (at
+// your option) any later version.
+//
+// ansi_colours is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Lesser
+// General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with ansi_colours.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Floyd–Steinberg error diffusion.
+
+use alloc::vec;
+
+use crate::system;
+
+/// Converts a rectangle of pixels into indexes in the 256-colour ANSI palette
+/// using Floyd–Steinberg error diffusion.  `pixel` returns sRGB colour of
+/// a pixel at given column and row.  Height of the rectangle is determined by
+/// length of `output` which must be a multiple of non-zero `width`.
+///
+/// Rows are processed in serpentine order, i.e. every other row is scanned
+/// from right to left, which avoids directional artefacts.  The error is
+/// diffused on gamma-encoded components.
+pub(crate) fn dither(
+    width: usize,
+    output: &mut [u8],
+    pixel: impl Fn(usize, usize) -> u32,
+) {
+    // Errors are kept in 1/16 units for the current and the next row.  The
+    // rows have an extra entry on each side so that pixels at the edges don’t
+    // need special handling.
+    let mut errors = vec![[0i32; 3]; 2 * (width + 2)];
+    for (y, output) in output.chunks_exact_mut(width).enumerate() {
+        let (current, next) = errors.split_at_mut(width + 2);
+        current.copy_from_slice(next);
+        next.fill([0; 3]);
+        let ltr = y % 2 == 0;
+        for i in 0..width {
+            let x = if ltr { i } else { width - 1 - i };
+            let (ahead, behind) = if ltr { (x + 2, x) } else { (x, x + 2) };
+            let rgb = pixel(x, y);
+            let error = current[x + 1];
+            let mut value = [0i32; 3];
+            let mut target = 0;
+            for (chan, &shift) in [16, 8, 0].iter().enumerate() {
+                let component = ((rgb >> shift) & 0xff) as i32;
+                let component = component + (error[chan] + 8).div_euclid(16);
+                value[chan] = component.clamp(0, 255);
+                target |= (value[chan] as u32) << shift;
+            }
+            let index = system::ansi256_from_rgb(target);
+            output[x] = index;
+            let colour = system::palette_colour(index);
+            for (chan, &shift) in [16, 8, 0].iter().enumerate() {
+                let err = value[chan] - ((colour >> shift) & 0xff) as i32;
+                current[ahead][chan] += err * 7;
+                next[behind][chan] += err * 3;
+                next[x + 1][chan] += err * 5;
+                next[ahead][chan] += err;
+            }
+        }
+    }
+}
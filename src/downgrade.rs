@@ -0,0 +1,1992 @@
+// ansi_colours – true-colour ↔ ANSI terminal palette converter
+// Copyright 2018 by Michał Nazarewicz <mina86@mina86.com>
+//
+// ansi_colours is free software: you can redistribute it and/or modify it
+// under the terms of the GNU Lesser General Public License as published by
+// the Free Software Foundation; either version 3 of the License, or (at
+// your option) any later version.
+//
+// ansi_colours is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Lesser
+// General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with ansi_colours.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Downgrading of true-colour escape sequences into 256-colour ones.
+//!
+//! Functions in this module walk through text with embedded terminal escape
+//! sequences and replace every true-colour Select Graphic Rendition (SGR)
+//! parameter, i.e. `38;2;r;g;b` foreground and `48;2;r;g;b` background colour,
+//! with a `38;5;n` or `48;5;n` parameter where `n` is index of the colour in
+//! the 256-colour palette approximating the original colour.  Everything else
+//! (including SGR sequences which don’t specify true-colour colours) is passed
+//! through untouched.  Colours specified with colon-separated sub-parameters
+//! (e.g. `38:2::r:g:b`) are recognised as well; see [`Separator`].  So is
+//! the underline colour (i.e. `58;2;r;g;b`) which can also be removed instead;
+//! see [`UnderlineColour`].
+//!
+//! Operating System Command (OSC) sequences which change the palette or the
+//! default colours can be removed or used to track the actual values of the
+//! system colours; see [`OscPolicy`].
+//!
+//! To find out how lossy the rewriting was, [`Downscaler`] keeps [`Stats`]
+//! with number of processed sequences and errors of approximated colours.
+//!
+//! The rewriting can be configured with [`Target`] to perform other
+//! conversions, e.g. to replace 256-colour colours with their true-colour
+//! values, approximate colours with the 16 system colours or remove colours
+//! altogether.  With `detect` crate feature enabled, the target can be chosen
+//! based on capabilities of the terminal with [`Downscaler::detect`].
+//!
+//! The rewriting is done by [`Downscaler`] state machine which processes data
+//! incrementally (so that escape sequences can be split across chunks of data)
+//! and doesn’t allocate memory.  [`downgrade_str`] and [`downgrade_bytes`]
+//! functions are convenience wrappers around it which process a whole string
+//! at once while [`DowngradeWriter`] and [`DowngradeFmtWriter`] apply it to
+//! data written to [`std::io::Write`] and [`core::fmt::Write`] writers
+//! respectively.  With `tokio` crate feature enabled, [`AsyncDowngradeWriter`]
+//! does the same for asynchronous writers.
+//!
+//! With `anstyle-parse` crate feature enabled, [`parse_and_rewrite_bytes`]
+//! recognises escape sequences with the parser from [`anstyle-parse`
+//! crate](https://crates.io/crates/anstyle-parse) instead.
+//!
+//! This module is present only if `downgrade` crate feature is enabled.  The
+//! `downgrade_str` and `downgrade_bytes` functions additionally require the
+//! `alloc` crate feature and `DowngradeWriter` requires the `std` crate
+//! feature.
+
+#[cfg(feature = "alloc")]
+use alloc::{string::String, vec::Vec};
+
+use crate::SystemPreset;
+
+/// Maximum length of a control sequence, including the introducer and the
+/// final byte, which can be rewritten.  Longer sequences are passed through
+/// untouched.
+const MAX_LENGTH: usize = 128;
+
+/// The escape character.
+const ESC: u8 = 0x1b;
+
+/// The bell character which terminates OSC sequences.
+const BEL: u8 = 0x07;
+
+/// The cancel character which aborts escape sequences.
+const CAN: u8 = 0x18;
+
+/// The substitute character which aborts escape sequences.
+const SUB: u8 = 0x1a;
+
+/// Replaces true-colour SGR parameters in given string with their 256-colour
+/// approximations.
+///
+/// See [module documentation](self) for details.
+///
+/// This function is present only if `alloc` crate feature is enabled.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::downgrade::downgrade_str;
+///
+/// assert_eq!("\x1b[38;5;67mfoo\x1b[0m",
+///            downgrade_str("\x1b[38;2;95;135;175mfoo\x1b[0m"));
+/// assert_eq!("\x1b[1;48;5;231;4mbar",
+///            downgrade_str("\x1b[1;48;2;255;255;255;4mbar"));
+/// assert_eq!("\x1b[38;5;42mbaz\x1b[2J",
+///            downgrade_str("\x1b[38;5;42mbaz\x1b[2J"));
+/// ```
+#[cfg(feature = "alloc")]
+pub fn downgrade_str(input: &str) -> String {
+    rewrite_str(input, Downscaler::new())
+}
+
+/// Replaces true-colour SGR parameters in given byte string with their
+/// 256-colour approximations.
+///
+/// See [module documentation](self) for details.
+///
+/// This function is present only if `alloc` crate feature is enabled.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::downgrade::downgrade_bytes;
+///
+/// assert_eq!(&b"\x1b[38;5;67mfoo\x1b[0m"[..],
+///            downgrade_bytes(b"\x1b[38;2;95;135;175mfoo\x1b[0m"));
+/// assert_eq!(&b"\xff\x1b[48;5;16m\xfe"[..],
+///            downgrade_bytes(b"\xff\x1b[48;2;0;0;0m\xfe"));
+/// ```
+#[cfg(feature = "alloc")]
+pub fn downgrade_bytes(input: &[u8]) -> Vec<u8> {
+    rewrite_bytes(input, Downscaler::new())
+}
+
+/// Rewrites colours in SGR sequences in given string using given state
+/// machine.
+///
+/// This is a generalisation of [`downgrade_str`] which allows the rewriting
+/// to be configured.  At the end, the state machine is finished, i.e. an
+/// incomplete escape sequence at the end of the input is passed unchanged.
+///
+/// This function is present only if `alloc` crate feature is enabled.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::downgrade::{rewrite_str, Downscaler, Target};
+///
+/// let upscaler = Downscaler::new().with_target(Target::TrueColour);
+/// assert_eq!("\x1b[1;38;2;95;135;175mfoo\x1b[m",
+///            rewrite_str("\x1b[1;38;5;67mfoo\x1b[m", upscaler));
+/// ```
+#[cfg(feature = "alloc")]
+pub fn rewrite_str(input: &str, downscaler: Downscaler) -> String {
+    let output = rewrite_bytes(input.as_bytes(), downscaler);
+    // Only sequences consisting of ASCII characters are replaced so the
+    // output is still valid UTF-8.
+    String::from_utf8(output).expect("rewriting produced invalid UTF-8")
+}
+
+/// Rewrites colours in SGR sequences in given byte string using given state
+/// machine.
+///
+/// This is a generalisation of [`downgrade_bytes`] which allows the rewriting
+/// to be configured.  See [`rewrite_str`].
+///
+/// This function is present only if `alloc` crate feature is enabled.
+#[cfg(feature = "alloc")]
+pub fn rewrite_bytes(input: &[u8], mut downscaler: Downscaler) -> Vec<u8> {
+    let mut output = Vec::with_capacity(input.len());
+    downscaler
+        .feed_bytes(input, |bytes| {
+            output.extend_from_slice(bytes);
+            Ok(())
+        })
+        .unwrap_or_else(|err: core::convert::Infallible| match err {});
+    output.extend_from_slice(downscaler.finish());
+    output
+}
+
+/// Rewrites colours in SGR sequences in given byte string using configuration
+/// of given state machine, recognising escape sequences with the parser from
+/// [`anstyle-parse` crate](https://crates.io/crates/anstyle-parse).
+///
+/// This is an alternative to [`rewrite_bytes`] for code which already uses
+/// `anstyle-parse` and wants sequences to be recognised the same way.  Only
+/// SGR sequences are rewritten; OSC sequences are passed through regardless
+/// of the state machine’s [`OscPolicy`].
+///
+/// This function is present only if `anstyle-parse` and `alloc` crate
+/// features are enabled.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::downgrade::{parse_and_rewrite_bytes, Downscaler};
+///
+/// assert_eq!(&b"\x1b[38;5;67mfoo\x1b[0m"[..],
+///            parse_and_rewrite_bytes(b"\x1b[38;2;95;135;175mfoo\x1b[0m",
+///                                    Downscaler::new()));
+/// ```
+#[cfg(all(feature = "anstyle-parse", feature = "alloc"))]
+pub fn parse_and_rewrite_bytes(
+    input: &[u8],
+    downscaler: Downscaler,
+) -> Vec<u8> {
+    let mut output = Vec::with_capacity(input.len());
+    let mut performer = Performer {
+        config: downscaler.config,
+        input,
+        output: &mut output,
+        position: 0,
+        start: 0,
+        flushed: 0,
+        interrupted: true,
+    };
+    let mut parser =
+        anstyle_parse::Parser::<anstyle_parse::DefaultCharAccumulator>::new();
+    for (position, &byte) in input.iter().enumerate() {
+        performer.position = position;
+        if byte == ESC {
+            performer.start = position;
+            performer.interrupted = false;
+        }
+        parser.advance(&mut performer, byte);
+    }
+    let flushed = performer.flushed;
+    output.extend_from_slice(&input[flushed..]);
+    output
+}
+
+/// `anstyle-parse` performer which rewrites SGR sequences according to
+/// a [`Downscaler`]’s configuration.
+#[cfg(all(feature = "anstyle-parse", feature = "alloc"))]
+struct Performer<'a> {
+    /// Configuration of the rewriting.
+    config: Config,
+    /// The whole input.
+    input: &'a [u8],
+    /// Output buffer.
+    output: &'a mut Vec<u8>,
+    /// Position in the input of the byte currently being processed.
+    position: usize,
+    /// Position in the input of the most recent escape character.
+    start: usize,
+    /// Position in the input up to which data has been copied to the output.
+    flushed: usize,
+    /// Whether any action has been performed since the most recent escape
+    /// character.  If so, the escape character doesn’t start sequence which
+    /// is being dispatched.
+    interrupted: bool,
+}
+
+#[cfg(all(feature = "anstyle-parse", feature = "alloc"))]
+impl anstyle_parse::Perform for Performer<'_> {
+    fn print(&mut self, _chr: char) {
+        self.interrupted = true;
+    }
+    fn execute(&mut self, _byte: u8) {
+        self.interrupted = true;
+    }
+    fn put(&mut self, _byte: u8) {
+        self.interrupted = true;
+    }
+    fn unhook(&mut self) {
+        self.interrupted = true;
+    }
+
+    fn hook(&mut self, _: &anstyle_parse::Params, _: &[u8], _: bool, _: u8) {
+        self.interrupted = true;
+    }
+
+    fn osc_dispatch(&mut self, _params: &[&[u8]], _bell_terminated: bool) {
+        self.interrupted = true;
+    }
+
+    fn esc_dispatch(&mut self, _intermediates: &[u8], _ignore: bool, _: u8) {
+        self.interrupted = true;
+    }
+
+    fn csi_dispatch(
+        &mut self,
+        params: &anstyle_parse::Params,
+        intermediates: &[u8],
+        ignore: bool,
+        action: u8,
+    ) {
+        // The sequence may have been interrupted by a control character in
+        // which case we can’t replace it as a single unit.  This also skips
+        // sequences started with a C1 control rather than escape character.
+        if core::mem::replace(&mut self.interrupted, true)
+            || action != b'm'
+            || ignore
+            || !intermediates.is_empty()
+        {
+            return;
+        }
+        // Serialise the parameters back so they can be handled the same way
+        // Downscaler handles them.
+        let mut buf = [0; MAX_LENGTH];
+        let mut out = Output {
+            buf: &mut buf,
+            len: 0,
+            params: 0,
+        };
+        for param in params {
+            let written = param.iter().enumerate().try_for_each(|(idx, n)| {
+                if idx == 0 {
+                    out.start_param()?;
+                } else {
+                    out.push(b":")?;
+                }
+                out.push(crate::sgr::write_decimal(&mut [0; 5], *n))
+            });
+            if written.is_none() {
+                return;
+            }
+        }
+        let len = out.len;
+        let mut rewritten = [0; 2 * MAX_LENGTH];
+        let mut stats = Stats::default();
+        let params = &buf[..len];
+        if let Some(len) =
+            rewrite_sgr(&self.config, params, &mut rewritten, &mut stats)
+        {
+            let output = &mut *self.output;
+            output.extend_from_slice(&self.input[self.flushed..self.start]);
+            output.extend_from_slice(&rewritten[..len]);
+            self.flushed = self.position + 1;
+        }
+    }
+}
+
+/// A state machine which rewrites true-colour SGR sequences into 256-colour
+/// ones as data is pushed into it.
+///
+/// The state machine doesn’t allocate memory and handles escape sequences
+/// split across multiple chunks of data which makes it usable for processing
+/// streams, e.g. data sent to a serial console.  Bytes which may be part of an
+/// escape sequence are held until the sequence is complete; [`finish`] method
+/// must be called at the end of the stream to flush them.
+///
+/// Sequences longer than 128 bytes are passed through untouched.
+///
+/// [`finish`]: Self::finish
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::downgrade::Downscaler;
+///
+/// let mut downscaler = Downscaler::new();
+/// let mut output = Vec::new();
+/// for chunk in ["foo \x1b[38;2;95", ";135;175mbar\x1b[", "0m baz\x1b"] {
+///     for &byte in chunk.as_bytes() {
+///         output.extend_from_slice(downscaler.feed(byte));
+///     }
+/// }
+/// output.extend_from_slice(downscaler.finish());
+/// assert_eq!(&b"foo \x1b[38;5;67mbar\x1b[0m baz\x1b"[..], output);
+/// ```
+#[derive(Clone, Debug)]
+pub struct Downscaler {
+    /// Current state of the state machine.
+    state: State,
+    /// Buffer holding the control sequence being processed.  Also used to
+    /// hold data returned from [`Self::feed`].
+    buf: [u8; MAX_LENGTH],
+    /// Length of the control sequence held in the buffer.
+    len: usize,
+    /// Buffer holding rewritten control sequence.
+    out: [u8; 2 * MAX_LENGTH],
+    /// Configuration of the rewriting.
+    config: Config,
+    /// Statistics of the rewriting.
+    stats: Stats,
+}
+
+/// Statistics of rewriting done by a [`Downscaler`].
+///
+/// Errors are distances between original colours and colours they were
+/// replaced with as measured by [`distance`](crate::distance) function.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::downgrade::Downscaler;
+///
+/// let mut downscaler = Downscaler::new();
+/// let input = b"\x1b[38;2;95;135;175mfoo\x1b[m \x1b[1;48;2;100;140;170mbar";
+/// for &byte in input {
+///     downscaler.feed(byte);
+/// }
+///
+/// let stats = downscaler.stats();
+/// assert_eq!(3, stats.sequences);
+/// assert_eq!(2, stats.rewritten);
+/// assert_eq!(2, stats.colours);
+/// assert!(14.9 < stats.max_error && stats.max_error < 15.1);
+/// assert!(7.4 < stats.mean_error() && stats.mean_error() < 7.6);
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Stats {
+    /// Number of processed SGR sequences.
+    pub sequences: u64,
+    /// Number of SGR sequences which were changed or removed.
+    pub rewritten: u64,
+    /// Number of colours which were converted.
+    pub colours: u64,
+    /// Sum of errors of converted colours.
+    pub total_error: f64,
+    /// Largest error of a converted colour.
+    pub max_error: f32,
+}
+
+impl Stats {
+    /// Returns mean error of converted colours or zero if no colours were
+    /// converted.
+    pub fn mean_error(&self) -> f32 {
+        if self.colours == 0 {
+            0.0
+        } else {
+            (self.total_error / self.colours as f64) as f32
+        }
+    }
+
+    /// Records conversion of a colour with given error.
+    fn record(&mut self, error: f32) {
+        self.colours += 1;
+        self.total_error += f64::from(error);
+        self.max_error = self.max_error.max(error);
+    }
+}
+
+/// Colour depth escape sequences are rewritten to by a [`Downscaler`].
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::downgrade::{Downscaler, Target};
+/// use ansi_colours::SystemPreset;
+///
+/// let rewrite = |downscaler: &mut Downscaler, input: &str| {
+///     let mut output = Vec::new();
+///     for &byte in input.as_bytes() {
+///         output.extend_from_slice(downscaler.feed(byte));
+///     }
+///     String::from_utf8(output).unwrap()
+/// };
+///
+/// let mut ansi256 = Downscaler::new();
+/// assert_eq!(Target::Ansi256, ansi256.target());
+/// assert_eq!("\x1b[38;5;67m", rewrite(&mut ansi256, "\x1b[38;2;95;135;175m"));
+/// assert_eq!("\x1b[38;5;1m", rewrite(&mut ansi256, "\x1b[38;5;1m"));
+///
+/// let mut true_colour = Downscaler::new().with_target(Target::TrueColour);
+/// assert_eq!("\x1b[38;2;95;135;175m",
+///            rewrite(&mut true_colour, "\x1b[38;5;67m"));
+/// assert_eq!("\x1b[48;2;205;0;0m", rewrite(&mut true_colour, "\x1b[48;5;1m"));
+///
+/// let mut tango = true_colour.with_system_colours(SystemPreset::Tango);
+/// assert_eq!("\x1b[48;2;204;0;0m", rewrite(&mut tango, "\x1b[48;5;1m"));
+///
+/// let mut ansi16 = Downscaler::new().with_target(Target::Ansi16);
+/// assert_eq!("\x1b[90;41m",
+///            rewrite(&mut ansi16, "\x1b[38;2;95;135;175;48;5;1m"));
+/// let mut tango = ansi16.with_system_colours(SystemPreset::Tango);
+/// assert_eq!("\x1b[94;41m",
+///            rewrite(&mut tango, "\x1b[38;2;95;135;175;48;5;1m"));
+///
+/// let mut monochrome = Downscaler::new().with_target(Target::Monochrome);
+/// assert_eq!("\x1b[1;4mfoo\x1b[0m",
+///            rewrite(&mut monochrome, "\x1b[1;31;4mfoo\x1b[0m"));
+/// assert_eq!("foo", rewrite(&mut monochrome, "\x1b[38;5;1;42mfoo"));
+///
+/// let mut plain = Downscaler::new().with_target(Target::Plain);
+/// assert_eq!("foo\x1b[2J", rewrite(&mut plain, "\x1b[1;31mfoo\x1b[2J\x1b[m"));
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[non_exhaustive]
+pub enum Target {
+    /// True-colour colours (i.e. `38;2;r;g;b` and `48;2;r;g;b` parameters)
+    /// are replaced by their approximations in the 256-colour palette (i.e.
+    /// `38;5;n` and `48;5;n` parameters).  This is the default.
+    #[default]
+    Ansi256,
+    /// Colours from the 256-colour palette are replaced by their true-colour
+    /// values.  The values are ones returned by
+    /// [`rgb_from_ansi256`](crate::rgb_from_ansi256) except for the system
+    /// colours whose values can be set with
+    /// [`Downscaler::with_system_colours`].
+    ///
+    /// This is useful for normalising output before further processing of
+    /// the colours, e.g. when converting it to HTML.
+    TrueColour,
+    /// True-colour colours and colours from the 256-colour palette are
+    /// replaced by their approximations among the 16 system colours
+    /// specified with `30`–`37` and `90`–`97` parameters for foreground and
+    /// `40`–`47` and `100`–`107` parameters for background.  Values of the
+    /// system colours can be set with [`Downscaler::with_system_colours`].
+    ///
+    /// This is useful for legacy Windows consoles and minimal terminals.
+    Ansi16,
+    /// Parameters setting foreground and background colours are removed while
+    /// other attributes (such as bold or underline) are kept.  If all
+    /// parameters of an SGR sequence are removed, the whole sequence is
+    /// removed.
+    ///
+    /// This is useful for producing monochrome variant of the output.
+    Monochrome,
+    /// All SGR sequences are removed.
+    ///
+    /// This is useful for producing plain text output without any styling.
+    /// Note that other escape sequences (e.g. ones moving the cursor) are
+    /// still passed unchanged.
+    Plain,
+    /// Colours are passed unchanged.  Underline colour and OSC sequences are
+    /// still treated according to [`UnderlineColour`] and [`OscPolicy`].
+    ///
+    /// This is useful for terminals supporting true-colour colours.
+    Passthrough,
+}
+
+/// Returns target appropriate for a terminal supporting given colour depth.
+///
+/// True-colour terminals get the output unchanged, 256-colour and 16-colour
+/// terminals get colours approximated in their palettes and monochrome
+/// terminals get colours removed.  Since there’s no target specific to
+/// terminals supporting only the eight basic colours, [`Target::Ansi16`] is
+/// used for them.
+///
+/// This implementation is present only if `detect` crate feature is enabled.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::detect::ColourDepth;
+/// use ansi_colours::downgrade::Target;
+///
+/// assert_eq!(Target::Passthrough, Target::from(ColourDepth::TrueColour));
+/// assert_eq!(Target::Ansi256, Target::from(ColourDepth::Ansi256));
+/// assert_eq!(Target::Ansi16, Target::from(ColourDepth::Ansi8));
+/// assert_eq!(Target::Monochrome, Target::from(ColourDepth::Monochrome));
+/// ```
+#[cfg(feature = "detect")]
+impl From<crate::detect::ColourDepth> for Target {
+    fn from(depth: crate::detect::ColourDepth) -> Self {
+        use crate::detect::ColourDepth;
+        match depth {
+            ColourDepth::Monochrome => Self::Monochrome,
+            ColourDepth::Ansi8 | ColourDepth::Ansi16 => Self::Ansi16,
+            ColourDepth::Ansi256 => Self::Ansi256,
+            ColourDepth::TrueColour => Self::Passthrough,
+        }
+    }
+}
+
+/// Form of colours written by a [`Downscaler`].
+///
+/// Extended colours can be specified with semicolon-separated parameters (e.g.
+/// `38;2;r;g;b`) or with colon-separated sub-parameters (e.g. `38:2::r:g:b`)
+/// as defined by ITU T.416.  The former is more widely supported while the
+/// latter is unambiguous and used by modern terminals.  Both forms are
+/// recognised on input regardless of this setting.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::downgrade::{Downscaler, Separator, Target};
+///
+/// let rewrite = |downscaler: &mut Downscaler, input: &str| {
+///     let mut output = Vec::new();
+///     for &byte in input.as_bytes() {
+///         output.extend_from_slice(downscaler.feed(byte));
+///     }
+///     String::from_utf8(output).unwrap()
+/// };
+///
+/// let mut preserve = Downscaler::new();
+/// assert_eq!(Separator::Preserve, preserve.separator());
+/// assert_eq!("\x1b[38:5:67;48;5;16m",
+///            rewrite(&mut preserve, "\x1b[38:2::95:135:175;48;2;0;0;0m"));
+///
+/// let mut semicolon = Downscaler::new().with_separator(Separator::Semicolon);
+/// assert_eq!("\x1b[38;5;67;48;5;16m",
+///            rewrite(&mut semicolon, "\x1b[38:2::95:135:175;48;2;0;0;0m"));
+///
+/// let mut colon = Downscaler::new()
+///     .with_target(Target::TrueColour)
+///     .with_separator(Separator::Colon);
+/// assert_eq!("\x1b[38:2::95:135:175;48:2::0:0:0m",
+///            rewrite(&mut colon, "\x1b[38:5:67;48;5;16m"));
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Separator {
+    /// Rewritten colours use the same form as the original ones.  This is
+    /// the default.
+    #[default]
+    Preserve,
+    /// Rewritten colours use semicolon-separated parameters, e.g.
+    /// `38;5;n`.
+    Semicolon,
+    /// Rewritten colours use colon-separated sub-parameters, e.g. `38:5:n`.
+    Colon,
+}
+
+/// Treatment of the underline colour by a [`Downscaler`].
+///
+/// Underline colour is set with `58` parameter which takes the same
+/// arguments as the foreground and background colour parameters (e.g.
+/// `58;2;r;g;b` or `58;5;n`) and reset to the default with `59` parameter.
+/// It’s used for example by spell-checkers and linters to draw coloured
+/// undercurls.  Since there’s no short form for the underline colour, with
+/// [`Target::Ansi16`] target it’s written as `58;5;n` where `n` is below 16.
+///
+/// Terminals which don’t support underline colour may misinterpret the
+/// parameters (e.g. treat the colour components as separate attributes) so
+/// it may be desirable to remove them altogether.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::downgrade::{Downscaler, Target, UnderlineColour};
+///
+/// let rewrite = |downscaler: &mut Downscaler, input: &str| {
+///     let mut output = Vec::new();
+///     for &byte in input.as_bytes() {
+///         output.extend_from_slice(downscaler.feed(byte));
+///     }
+///     String::from_utf8(output).unwrap()
+/// };
+///
+/// let mut convert = Downscaler::new();
+/// assert_eq!(UnderlineColour::Convert, convert.underline_colour());
+/// assert_eq!("\x1b[4:3;58;5;196m",
+///            rewrite(&mut convert, "\x1b[4:3;58;2;255;0;0m"));
+///
+/// let mut ansi16 = Downscaler::new().with_target(Target::Ansi16);
+/// assert_eq!("\x1b[4:3;58;5;9m",
+///            rewrite(&mut ansi16, "\x1b[4:3;58;2;255;0;0m"));
+///
+/// let mut drop = Downscaler::new()
+///     .with_underline_colour(UnderlineColour::Drop);
+/// assert_eq!("\x1b[4:3m", rewrite(&mut drop, "\x1b[4:3;58;2;255;0;0m"));
+/// assert_eq!("\x1b[24m", rewrite(&mut drop, "\x1b[24;59m"));
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum UnderlineColour {
+    /// Underline colour is converted in the same way as foreground and
+    /// background colours.  With [`Target::Monochrome`] target it’s removed.
+    /// This is the default.
+    #[default]
+    Convert,
+    /// Parameters setting or resetting underline colour are removed.  If all
+    /// parameters of an SGR sequence are removed, the whole sequence is
+    /// removed.
+    Drop,
+}
+
+/// Treatment of OSC sequences changing colours by a [`Downscaler`].
+///
+/// The policy applies to sequences which set the palette (`OSC 4`), the
+/// default foreground (`OSC 10`), background (`OSC 11`) and cursor (`OSC 12`)
+/// colours as well as ones which reset them (`OSC 104`, `OSC 110`, `OSC 111`
+/// and `OSC 112`).  Other OSC sequences (e.g. ones setting window title) are
+/// always passed unchanged.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::downgrade::{Downscaler, OscPolicy, Target};
+///
+/// let rewrite = |downscaler: &mut Downscaler, input: &str| {
+///     let mut output = Vec::new();
+///     for &byte in input.as_bytes() {
+///         output.extend_from_slice(downscaler.feed(byte));
+///     }
+///     String::from_utf8(output).unwrap()
+/// };
+///
+/// let mut drop = Downscaler::new().with_osc_policy(OscPolicy::Drop);
+/// assert_eq!("foo", rewrite(&mut drop, "\x1b]4;1;rgb:cc/00/00\x07foo"));
+/// assert_eq!("\x1b]11;?\x07", rewrite(&mut drop, "\x1b]11;?\x07"));
+/// assert_eq!("\x1b]2;title\x07", rewrite(&mut drop, "\x1b]2;title\x07"));
+///
+/// let mut record = Downscaler::new()
+///     .with_target(Target::TrueColour)
+///     .with_osc_policy(OscPolicy::Record);
+/// assert_eq!("\x1b[31m\x1b[38;2;205;0;0m",
+///            rewrite(&mut record, "\x1b[31m\x1b[38;5;1m"));
+/// assert_eq!("\x1b]4;1;#cc0000\x1b\\\x1b[38;2;204;0;0m",
+///            rewrite(&mut record, "\x1b]4;1;#cc0000\x1b\\\x1b[38;5;1m"));
+/// assert_eq!("\x1b]104\x07\x1b[38;2;205;0;0m",
+///            rewrite(&mut record, "\x1b]104\x07\x1b[38;5;1m"));
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum OscPolicy {
+    /// The sequences are passed unchanged.  This is the default.
+    #[default]
+    Pass,
+    /// The sequences are removed.  Queries (i.e. sequences with `?` in place
+    /// of a colour) are still passed since otherwise application waiting for
+    /// the response would hang.
+    Drop,
+    /// The sequences are passed unchanged and values of system colours (i.e.
+    /// the first 16 colours of the palette) they set are used for subsequent
+    /// conversions in place of values set with
+    /// [`Downscaler::with_system_colours`].  Values of other colours are
+    /// ignored.
+    Record,
+}
+
+/// Configuration of a [`Downscaler`].
+#[derive(Clone, Copy, Debug)]
+struct Config {
+    target: Target,
+    system: Option<SystemPreset>,
+    separator: Separator,
+    underline: UnderlineColour,
+    osc: OscPolicy,
+    /// Values of system colours recorded from OSC sequences.  If set, they
+    /// are used in place of `system`.
+    recorded: Option<[u32; 16]>,
+}
+
+impl Config {
+    /// Returns values of system colours to use in conversions or `None` if
+    /// default values should be used.
+    fn palette(&self) -> Option<[u32; 16]> {
+        self.recorded
+            .or_else(|| self.system.map(SystemPreset::colours_rgb))
+    }
+
+    /// Returns values of system colours in effect before any were recorded.
+    fn initial_palette(&self) -> [u32; 16] {
+        self.system
+            .map_or_else(crate::system::snapshot, SystemPreset::colours_rgb)
+    }
+}
+
+/// State of the [`Downscaler`] state machine.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum State {
+    /// Outside of an escape sequence.
+    Ground,
+    /// After an escape character.
+    Escape,
+    /// Inside of a control sequence which is being buffered.
+    Csi,
+    /// Inside of a control sequence which was too long to be buffered and is
+    /// being passed through.
+    Overflow,
+    /// Inside of an OSC sequence which is being buffered.
+    Osc,
+    /// After an escape character inside of an OSC sequence, i.e. possibly at
+    /// the end of the String Terminator.
+    OscEscape,
+    /// Inside of an OSC sequence which was too long to be buffered and is
+    /// being passed through.
+    OscOverflow,
+}
+
+impl Downscaler {
+    /// Creates a new state machine which rewrites true-colour colours into
+    /// 256-colour ones.
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            state: State::Ground,
+            buf: [0; MAX_LENGTH],
+            len: 0,
+            out: [0; 2 * MAX_LENGTH],
+            config: Config {
+                target: Target::Ansi256,
+                system: None,
+                separator: Separator::Preserve,
+                underline: UnderlineColour::Convert,
+                osc: OscPolicy::Pass,
+                recorded: None,
+            },
+            stats: Stats {
+                sequences: 0,
+                rewritten: 0,
+                colours: 0,
+                total_error: 0.0,
+                max_error: 0.0,
+            },
+        }
+    }
+
+    /// Creates a new state machine which rewrites colours to the colour depth
+    /// supported by the terminal.
+    ///
+    /// The colour depth is determined with [`crate::detect::detect`] and
+    /// converted into a [`Target`] with its `From<ColourDepth>`
+    /// implementation.
+    ///
+    /// This function is present only if `detect` crate feature is enabled.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::io::Write;
+    ///
+    /// use ansi_colours::downgrade::{Downscaler, DowngradeWriter};
+    ///
+    /// let downscaler = Downscaler::detect();
+    /// let stdout = std::io::stdout();
+    /// let mut out = DowngradeWriter::with_downscaler(stdout, downscaler);
+    /// writeln!(out, "\x1b[38;2;255;95;0mwarning:\x1b[m disk almost full")
+    ///     .unwrap();
+    /// ```
+    #[cfg(feature = "detect")]
+    pub fn detect() -> Self {
+        Self::new().with_target(crate::detect::detect().into())
+    }
+
+    /// Returns state machine which rewrites colours to given colour depth.
+    ///
+    /// This should be called before any data is processed.
+    #[inline]
+    pub const fn with_target(self, target: Target) -> Self {
+        let config = Config {
+            target,
+            ..self.config
+        };
+        Self { config, ..self }
+    }
+
+    /// Returns state machine which assumes system colours (i.e. the first 16
+    /// colours of the palette) have values of given preset.
+    ///
+    /// By default, values returned by
+    /// [`rgb_from_ansi256`](crate::rgb_from_ansi256) are used.
+    ///
+    /// This should be called before any data is processed.
+    #[inline]
+    pub const fn with_system_colours(self, preset: SystemPreset) -> Self {
+        let config = Config {
+            system: Some(preset),
+            ..self.config
+        };
+        Self { config, ..self }
+    }
+
+    /// Returns state machine which writes rewritten colours in given form.
+    ///
+    /// This should be called before any data is processed.
+    #[inline]
+    pub const fn with_separator(self, separator: Separator) -> Self {
+        let config = Config {
+            separator,
+            ..self.config
+        };
+        Self { config, ..self }
+    }
+
+    /// Returns state machine which treats underline colour according to given
+    /// policy.
+    ///
+    /// This should be called before any data is processed.
+    #[inline]
+    pub const fn with_underline_colour(self, policy: UnderlineColour) -> Self {
+        let config = Config {
+            underline: policy,
+            ..self.config
+        };
+        Self { config, ..self }
+    }
+
+    /// Returns state machine which treats OSC sequences changing colours
+    /// according to given policy.
+    ///
+    /// This should be called before any data is processed.
+    #[inline]
+    pub const fn with_osc_policy(self, policy: OscPolicy) -> Self {
+        let config = Config {
+            osc: policy,
+            ..self.config
+        };
+        Self { config, ..self }
+    }
+
+    /// Returns colour depth colours are rewritten to.
+    #[inline]
+    pub const fn target(&self) -> Target {
+        self.config.target
+    }
+
+    /// Returns form in which rewritten colours are written.
+    #[inline]
+    pub const fn separator(&self) -> Separator {
+        self.config.separator
+    }
+
+    /// Returns policy of treating underline colour.
+    #[inline]
+    pub const fn underline_colour(&self) -> UnderlineColour {
+        self.config.underline
+    }
+
+    /// Returns policy of treating OSC sequences changing colours.
+    #[inline]
+    pub const fn osc_policy(&self) -> OscPolicy {
+        self.config.osc
+    }
+
+    /// Returns statistics of the rewriting done so far.
+    #[inline]
+    pub const fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    /// Returns statistics of the rewriting done so far and resets them.
+    ///
+    /// This is useful for periodically reporting statistics of a long-running
+    /// stream.
+    #[inline]
+    pub fn take_stats(&mut self) -> Stats {
+        core::mem::take(&mut self.stats)
+    }
+
+    /// Returns preset of system colours values set with
+    /// [`Self::with_system_colours`] if any.
+    #[inline]
+    pub const fn system_colours(&self) -> Option<SystemPreset> {
+        self.config.system
+    }
+
+    /// Processes a single byte of data and returns bytes which should be
+    /// output as a result.
+    ///
+    /// The returned slice is often a single byte which has been passed.  It is
+    /// empty if the byte might be part of an escape sequence in which case it
+    /// is held until the rest of the sequence arrives.
+    pub fn feed(&mut self, byte: u8) -> &[u8] {
+        let len = self.len;
+        match self.state {
+            State::Ground | State::Overflow | State::OscOverflow
+                if byte == ESC =>
+            {
+                self.start_escape();
+                &[]
+            }
+            State::Ground => self.emit_byte(byte),
+            State::Overflow => {
+                if !(0x20..=0x3f).contains(&byte) {
+                    self.state = State::Ground;
+                }
+                self.emit_byte(byte)
+            }
+            // Previous escape character didn’t start a sequence we handle.
+            // Emit it and start a new one.  buf[0] is already ESC.
+            State::Escape if byte == ESC => &self.buf[..1],
+            State::Escape if byte == b'[' => {
+                self.buf[1] = byte;
+                self.len = 2;
+                self.state = State::Csi;
+                &[]
+            }
+            // OSC sequences are buffered only if they may need to be handled.
+            State::Escape
+                if byte == b']' && self.config.osc != OscPolicy::Pass =>
+            {
+                self.buf[1] = byte;
+                self.len = 2;
+                self.state = State::Osc;
+                &[]
+            }
+            State::Escape => {
+                self.buf[1] = byte;
+                self.state = State::Ground;
+                &self.buf[..2]
+            }
+            // Sequence has been aborted by another escape character.  Emit
+            // what we have buffered so far and start a new one.  buf[0] is
+            // ESC so it’s enough to reset the length.
+            State::Csi if byte == ESC => {
+                self.len = 1;
+                self.state = State::Escape;
+                &self.buf[..len]
+            }
+            State::Csi => {
+                self.buf[len] = byte;
+                self.len += 1;
+                match byte {
+                    // Parameter and intermediate bytes.
+                    0x20..=0x3f if self.len < MAX_LENGTH => &[],
+                    0x20..=0x3f => {
+                        self.state = State::Overflow;
+                        &self.buf[..MAX_LENGTH]
+                    }
+                    // Final byte.
+                    b'm' => {
+                        self.state = State::Ground;
+                        self.stats.sequences += 1;
+                        let params = &self.buf[2..len];
+                        // Errors are recorded in a copy so that they aren’t
+                        // counted if the sequence ends up not being rewritten.
+                        let mut stats = self.stats;
+                        let (config, out) = (&self.config, &mut self.out);
+                        match rewrite_sgr(config, params, out, &mut stats) {
+                            Some(len) => {
+                                self.stats = stats;
+                                self.stats.rewritten += 1;
+                                &self.out[..len]
+                            }
+                            None => &self.buf[..len + 1],
+                        }
+                    }
+                    // Other final bytes and bytes which interrupt the
+                    // sequence (such as control characters) result in the
+                    // buffered data being passed unchanged.
+                    _ => {
+                        self.state = State::Ground;
+                        &self.buf[..len + 1]
+                    }
+                }
+            }
+            State::OscOverflow => {
+                if matches!(byte, BEL | CAN | SUB) {
+                    self.state = State::Ground;
+                }
+                self.emit_byte(byte)
+            }
+            State::Osc if byte == ESC => {
+                self.buf[len] = byte;
+                self.len += 1;
+                self.state = State::OscEscape;
+                &[]
+            }
+            State::Osc if byte == BEL => {
+                self.buf[len] = byte;
+                self.state = State::Ground;
+                let keep = apply_osc(&mut self.config, &self.buf[2..len]);
+                &self.buf[..if keep { len + 1 } else { 0 }]
+            }
+            // The sequence has been cancelled.  Pass it unchanged.
+            State::Osc if matches!(byte, CAN | SUB) => {
+                self.buf[len] = byte;
+                self.state = State::Ground;
+                &self.buf[..len + 1]
+            }
+            State::Osc => {
+                self.buf[len] = byte;
+                self.len += 1;
+                // Leave space for the two-byte String Terminator.
+                if self.len < MAX_LENGTH - 1 {
+                    &[]
+                } else {
+                    self.state = State::OscOverflow;
+                    &self.buf[..self.len]
+                }
+            }
+            State::OscEscape if byte == b'\\' => {
+                self.buf[len] = byte;
+                self.state = State::Ground;
+                let keep = apply_osc(&mut self.config, &self.buf[2..len - 1]);
+                &self.buf[..if keep { len + 1 } else { 0 }]
+            }
+            // Sequence has been aborted by another escape sequence.  Emit what
+            // we have buffered so far followed by output of processing the
+            // new sequence.  In Escape state, feed returns data from the start
+            // of the buffer only.
+            State::OscEscape => {
+                let aborted = len - 1;
+                self.out[..aborted].copy_from_slice(&self.buf[..aborted]);
+                self.start_escape();
+                let n = self.feed(byte).len();
+                self.out[aborted..aborted + n].copy_from_slice(&self.buf[..n]);
+                &self.out[..aborted + n]
+            }
+        }
+    }
+
+    /// Processes a chunk of data passing bytes which should be output as
+    /// a result to the `sink` function.
+    ///
+    /// This is equivalent to calling [`Self::feed`] for each byte of the
+    /// input but is faster since text outside of escape sequences is passed
+    /// to the `sink` in as large pieces as possible.  If the `sink` returns
+    /// an error, processing stops and the error is returned.  In that case
+    /// the state machine is left in unspecified (but valid) state.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ansi_colours::downgrade::Downscaler;
+    ///
+    /// let mut downscaler = Downscaler::new();
+    /// let mut output = Vec::new();
+    /// let mut sink = |bytes: &[u8]| {
+    ///     output.extend_from_slice(bytes);
+    ///     Ok::<(), core::convert::Infallible>(())
+    /// };
+    /// downscaler.feed_bytes(b"\x1b[1;38;2;0;0;", &mut sink).unwrap();
+    /// downscaler.feed_bytes(b"0mfoo\x1b[m", &mut sink).unwrap();
+    /// output.extend_from_slice(downscaler.finish());
+    /// assert_eq!(&b"\x1b[1;38;5;16mfoo\x1b[m"[..], output);
+    /// ```
+    pub fn feed_bytes<E>(
+        &mut self,
+        mut input: &[u8],
+        mut sink: impl FnMut(&[u8]) -> Result<(), E>,
+    ) -> Result<(), E> {
+        while let Some((&byte, rest)) = input.split_first() {
+            if self.state == State::Ground && byte != ESC {
+                let pos = input
+                    .iter()
+                    .position(|&byte| byte == ESC)
+                    .unwrap_or(input.len());
+                sink(&input[..pos])?;
+                input = &input[pos..];
+                continue;
+            }
+            let output = self.feed(byte);
+            if !output.is_empty() {
+                sink(output)?;
+            }
+            input = rest;
+        }
+        Ok(())
+    }
+
+    /// Returns bytes held in the state machine and resets it to its initial
+    /// state.  Statistics of the rewriting are kept.
+    ///
+    /// This should be called at the end of the stream to flush incomplete
+    /// escape sequence if any.  Those bytes are returned unchanged.
+    pub fn finish(&mut self) -> &[u8] {
+        let len = match self.state {
+            State::Ground | State::Overflow | State::OscOverflow => 0,
+            State::Escape => 1,
+            State::Csi | State::Osc | State::OscEscape => self.len,
+        };
+        self.state = State::Ground;
+        self.len = 0;
+        &self.buf[..len]
+    }
+
+    /// Starts a new escape sequence.
+    fn start_escape(&mut self) {
+        self.buf[0] = ESC;
+        self.len = 1;
+        self.state = State::Escape;
+    }
+
+    /// Returns a slice containing just the given byte.
+    fn emit_byte(&mut self, byte: u8) -> &[u8] {
+        self.buf[0] = byte;
+        &self.buf[..1]
+    }
+}
+
+impl Default for Downscaler {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A writer which rewrites true-colour SGR sequences written to it into
+/// 256-colour ones before passing the data to the inner writer.
+///
+/// Data written to the writer is processed by a [`Downscaler`] so escape
+/// sequences may be split across multiple writes.  Incomplete escape sequence
+/// at the end of written data is held until the rest of it is written.  To
+/// flush it at the end of the stream, call [`finish`] method.  Just like
+/// [`flush`], dropping the writer doesn’t output held bytes.
+///
+/// If writing to the inner writer fails, part of the data may have been
+/// written and the state of the rewriting is unspecified.
+///
+/// This type is present only if `std` crate feature is enabled.
+///
+/// [`finish`]: Self::finish
+/// [`flush`]: std::io::Write::flush
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Write;
+/// use ansi_colours::downgrade::DowngradeWriter;
+///
+/// let mut writer = DowngradeWriter::new(Vec::new());
+/// write!(writer, "\x1b[38;2;{};{};{}mfoo", 95, 135, 175).unwrap();
+/// writer.write_all(b"\x1b[0m").unwrap();
+/// let output = writer.finish().unwrap();
+/// assert_eq!(&b"\x1b[38;5;67mfoo\x1b[0m"[..], output);
+/// ```
+///
+/// To rewrite everything an application prints to standard output, wrap the
+/// [`std::io::Stdout`] object:
+///
+/// ```no_run
+/// use std::io::Write;
+///
+/// use ansi_colours::downgrade::DowngradeWriter;
+///
+/// let mut out = DowngradeWriter::new(std::io::stdout());
+/// writeln!(out, "\x1b[1;38;2;255;0;0mError:\x1b[m something went wrong")
+///     .unwrap();
+/// ```
+#[cfg(feature = "std")]
+#[derive(Clone, Debug)]
+pub struct DowngradeWriter<W> {
+    inner: W,
+    downscaler: Downscaler,
+}
+
+#[cfg(feature = "std")]
+impl<W: std::io::Write> DowngradeWriter<W> {
+    /// Creates a new writer which passes rewritten data to `inner`.
+    #[inline]
+    pub fn new(inner: W) -> Self {
+        Self::with_downscaler(inner, Downscaler::new())
+    }
+
+    /// Creates a new writer which rewrites data using given state machine
+    /// and passes the result to `inner`.
+    #[inline]
+    pub fn with_downscaler(inner: W, downscaler: Downscaler) -> Self {
+        Self { inner, downscaler }
+    }
+
+    /// Returns reference to the inner writer.
+    #[inline]
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Returns mutable reference to the inner writer.
+    ///
+    /// Writing directly to the inner writer while an escape sequence is held
+    /// in the rewriter will cause the data to be output out of order.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Returns reference to the state machine rewriting the data, e.g. to
+    /// inspect its [`Stats`].
+    #[inline]
+    pub fn downscaler(&self) -> &Downscaler {
+        &self.downscaler
+    }
+
+    /// Returns mutable reference to the state machine rewriting the data,
+    /// e.g. to [take its statistics](Downscaler::take_stats).
+    ///
+    /// Feeding data directly to the state machine will cause it to be lost.
+    #[inline]
+    pub fn downscaler_mut(&mut self) -> &mut Downscaler {
+        &mut self.downscaler
+    }
+
+    /// Writes held bytes of incomplete escape sequence (if any), flushes the
+    /// inner writer and returns it.
+    pub fn finish(mut self) -> std::io::Result<W> {
+        self.inner.write_all(self.downscaler.finish())?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: std::io::Write> std::io::Write for DowngradeWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.write_all(buf).map(|()| buf.len())
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> std::io::Result<()> {
+        let Self { inner, downscaler } = self;
+        downscaler.feed_bytes(buf, |bytes| inner.write_all(bytes))
+    }
+
+    #[inline]
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// An asynchronous writer which rewrites true-colour SGR sequences written to
+/// it into 256-colour ones before passing the data to the inner writer.
+///
+/// This is a counterpart of [`DowngradeWriter`] implementing [`AsyncWrite`]
+/// trait from [`tokio` crate](https://crates.io/crates/tokio).  Rewritten data
+/// is buffered in the writer until the inner writer accepts it.  Incomplete
+/// escape sequence at the end of written data is held until the rest of it is
+/// written or the writer is shut down.
+///
+/// This type is present only if `tokio` crate feature is enabled.
+///
+/// [`AsyncWrite`]: tokio::io::AsyncWrite
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::downgrade::AsyncDowngradeWriter;
+/// use tokio::io::{AsyncWrite, AsyncWriteExt};
+///
+/// async fn send_log(
+///     output: impl AsyncWrite + Unpin,
+///     line: &str,
+/// ) -> std::io::Result<()> {
+///     let mut output = AsyncDowngradeWriter::new(output);
+///     output.write_all(b"\x1b[38;2;255;95;0mwarning:\x1b[m ").await?;
+///     output.write_all(line.as_bytes()).await?;
+///     output.shutdown().await
+/// }
+/// ```
+#[cfg(feature = "tokio")]
+#[derive(Debug)]
+pub struct AsyncDowngradeWriter<W> {
+    inner: W,
+    downscaler: Downscaler,
+    /// Rewritten data which hasn’t been accepted by the inner writer yet.
+    buf: Vec<u8>,
+    /// Position in `buf` up to which the data has been written.
+    written: usize,
+}
+
+#[cfg(feature = "tokio")]
+impl<W: tokio::io::AsyncWrite + Unpin> AsyncDowngradeWriter<W> {
+    /// Creates a new writer which passes rewritten data to `inner`.
+    #[inline]
+    pub fn new(inner: W) -> Self {
+        Self::with_downscaler(inner, Downscaler::new())
+    }
+
+    /// Creates a new writer which rewrites data using given state machine
+    /// and passes the result to `inner`.
+    #[inline]
+    pub fn with_downscaler(inner: W, downscaler: Downscaler) -> Self {
+        Self {
+            inner,
+            downscaler,
+            buf: Vec::new(),
+            written: 0,
+        }
+    }
+
+    /// Returns reference to the inner writer.
+    #[inline]
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Returns mutable reference to the inner writer.
+    ///
+    /// Writing directly to the inner writer while data is buffered in the
+    /// rewriter will cause the data to be output out of order.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Returns reference to the state machine rewriting the data, e.g. to
+    /// inspect its [`Stats`].
+    #[inline]
+    pub fn downscaler(&self) -> &Downscaler {
+        &self.downscaler
+    }
+
+    /// Returns mutable reference to the state machine rewriting the data,
+    /// e.g. to [take its statistics](Downscaler::take_stats).
+    ///
+    /// Feeding data directly to the state machine will cause it to be lost.
+    #[inline]
+    pub fn downscaler_mut(&mut self) -> &mut Downscaler {
+        &mut self.downscaler
+    }
+
+    /// Writes buffered data to the inner writer.
+    fn poll_drain(
+        &mut self,
+        cx: &mut core::task::Context<'_>,
+    ) -> core::task::Poll<std::io::Result<()>> {
+        use core::task::Poll;
+
+        while self.written < self.buf.len() {
+            let data = &self.buf[self.written..];
+            let inner = core::pin::Pin::new(&mut self.inner);
+            match inner.poll_write(cx, data) {
+                Poll::Ready(Ok(0)) => {
+                    return Poll::Ready(Err(
+                        std::io::ErrorKind::WriteZero.into()
+                    ))
+                }
+                Poll::Ready(Ok(len)) => self.written += len,
+                Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+        self.buf.clear();
+        self.written = 0;
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<W: tokio::io::AsyncWrite + Unpin> tokio::io::AsyncWrite
+    for AsyncDowngradeWriter<W>
+{
+    fn poll_write(
+        self: core::pin::Pin<&mut Self>,
+        cx: &mut core::task::Context<'_>,
+        data: &[u8],
+    ) -> core::task::Poll<std::io::Result<usize>> {
+        let this = self.get_mut();
+        if let Err(err) = core::task::ready!(this.poll_drain(cx)) {
+            return core::task::Poll::Ready(Err(err));
+        }
+        let Self {
+            downscaler, buf, ..
+        } = this;
+        downscaler
+            .feed_bytes(data, |bytes| {
+                buf.extend_from_slice(bytes);
+                Ok(())
+            })
+            .unwrap_or_else(|err: core::convert::Infallible| match err {});
+        // Try to pass the data on right away.  Even if the inner writer isn’t
+        // ready, the data has been accepted and will be written by subsequent
+        // calls.
+        if let core::task::Poll::Ready(Err(err)) = this.poll_drain(cx) {
+            return core::task::Poll::Ready(Err(err));
+        }
+        core::task::Poll::Ready(Ok(data.len()))
+    }
+
+    fn poll_flush(
+        self: core::pin::Pin<&mut Self>,
+        cx: &mut core::task::Context<'_>,
+    ) -> core::task::Poll<std::io::Result<()>> {
+        let this = self.get_mut();
+        core::task::ready!(this.poll_drain(cx))?;
+        core::pin::Pin::new(&mut this.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(
+        self: core::pin::Pin<&mut Self>,
+        cx: &mut core::task::Context<'_>,
+    ) -> core::task::Poll<std::io::Result<()>> {
+        let this = self.get_mut();
+        let held = this.downscaler.finish();
+        this.buf.extend_from_slice(held);
+        core::task::ready!(this.poll_drain(cx))?;
+        core::pin::Pin::new(&mut this.inner).poll_shutdown(cx)
+    }
+}
+
+/// A [`core::fmt::Write`] writer which rewrites true-colour SGR sequences
+/// written to it into 256-colour ones before passing the data to the inner
+/// writer.
+///
+/// This is a counterpart of [`DowngradeWriter`] which doesn’t need `std` and
+/// can be used inside of [`Display`](core::fmt::Display) implementations.
+/// Just like it, it holds incomplete escape sequence at the end of written
+/// data until the rest of it is written or [`finish`] method is called.
+///
+/// [`finish`]: Self::finish
+///
+/// # Examples
+///
+/// ```
+/// use core::fmt::{self, Write};
+/// use ansi_colours::downgrade::DowngradeFmtWriter;
+///
+/// struct Warning<'a>(&'a str);
+///
+/// impl fmt::Display for Warning<'_> {
+///     fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+///         let mut writer = DowngradeFmtWriter::new(fmtr);
+///         write!(writer, "\x1b[38;2;255;175;0m{}\x1b[m", self.0)?;
+///         writer.finish().map(|_| ())
+///     }
+/// }
+///
+/// assert_eq!("\x1b[38;5;214mzażółć\x1b[m", Warning("zażółć").to_string());
+/// ```
+#[derive(Clone, Debug)]
+pub struct DowngradeFmtWriter<W> {
+    inner: W,
+    downscaler: Downscaler,
+    /// Leading bytes of a multi-byte UTF-8 sequence split between chunks
+    /// returned by the downscaler.
+    partial: [u8; 4],
+    /// Number of bytes in the `partial` buffer.
+    partial_len: usize,
+}
+
+impl<W: core::fmt::Write> DowngradeFmtWriter<W> {
+    /// Creates a new writer which passes rewritten data to `inner`.
+    #[inline]
+    pub fn new(inner: W) -> Self {
+        Self::with_downscaler(inner, Downscaler::new())
+    }
+
+    /// Creates a new writer which rewrites data using given state machine
+    /// and passes the result to `inner`.
+    #[inline]
+    pub fn with_downscaler(inner: W, downscaler: Downscaler) -> Self {
+        Self {
+            inner,
+            downscaler,
+            partial: [0; 4],
+            partial_len: 0,
+        }
+    }
+
+    /// Returns reference to the inner writer.
+    #[inline]
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Returns mutable reference to the inner writer.
+    ///
+    /// Writing directly to the inner writer while an escape sequence is held
+    /// in the rewriter will cause the data to be output out of order.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Returns reference to the state machine rewriting the data, e.g. to
+    /// inspect its [`Stats`].
+    #[inline]
+    pub fn downscaler(&self) -> &Downscaler {
+        &self.downscaler
+    }
+
+    /// Returns mutable reference to the state machine rewriting the data,
+    /// e.g. to [take its statistics](Downscaler::take_stats).
+    ///
+    /// Feeding data directly to the state machine will cause it to be lost.
+    #[inline]
+    pub fn downscaler_mut(&mut self) -> &mut Downscaler {
+        &mut self.downscaler
+    }
+
+    /// Writes held bytes of incomplete escape sequence (if any) and returns
+    /// the inner writer.
+    pub fn finish(mut self) -> Result<W, core::fmt::Error> {
+        let Self {
+            inner,
+            downscaler,
+            partial,
+            partial_len,
+        } = &mut self;
+        write_utf8(inner, partial, partial_len, downscaler.finish())?;
+        if *partial_len != 0 {
+            return Err(core::fmt::Error);
+        }
+        Ok(self.inner)
+    }
+}
+
+impl<W: core::fmt::Write> core::fmt::Write for DowngradeFmtWriter<W> {
+    fn write_str(&mut self, data: &str) -> core::fmt::Result {
+        let Self {
+            inner,
+            downscaler,
+            partial,
+            partial_len,
+        } = self;
+        downscaler.feed_bytes(data.as_bytes(), |bytes| {
+            write_utf8(inner, partial, partial_len, bytes)
+        })
+    }
+}
+
+/// Writes bytes which are part of a valid UTF-8 stream to given writer.
+///
+/// Rewriting only changes ASCII characters so output of the [`Downscaler`] is
+/// valid UTF-8 if its input is.  However, slices it returns may split
+/// multi-byte characters.  Leading bytes of such characters are stored in the
+/// `partial` buffer until the rest of them arrives.
+fn write_utf8(
+    inner: &mut impl core::fmt::Write,
+    partial: &mut [u8; 4],
+    partial_len: &mut usize,
+    mut bytes: &[u8],
+) -> core::fmt::Result {
+    if *partial_len != 0 {
+        let width = match partial[0] {
+            0xc0..=0xdf => 2,
+            0xe0..=0xef => 3,
+            _ => 4,
+        };
+        let take = (width - *partial_len).min(bytes.len());
+        partial[*partial_len..*partial_len + take]
+            .copy_from_slice(&bytes[..take]);
+        *partial_len += take;
+        bytes = &bytes[take..];
+        if *partial_len < width {
+            return Ok(());
+        }
+        *partial_len = 0;
+        let chr = core::str::from_utf8(&partial[..width])
+            .map_err(|_| core::fmt::Error)?;
+        inner.write_str(chr)?;
+    }
+    let (valid, rest) = match core::str::from_utf8(bytes) {
+        Ok(_) => (bytes, &[][..]),
+        Err(err) if err.error_len().is_none() => {
+            bytes.split_at(err.valid_up_to())
+        }
+        Err(_) => return Err(core::fmt::Error),
+    };
+    let valid = core::str::from_utf8(valid).map_err(|_| core::fmt::Error)?;
+    inner.write_str(valid)?;
+    partial[..rest.len()].copy_from_slice(rest);
+    *partial_len = rest.len();
+    Ok(())
+}
+
+/// Rewrites SGR sequence with given parameters according to the
+/// configuration.  Writes the whole rewritten sequence to `out` and returns
+/// its length.  Records errors of converted colours in `stats`.
+///
+/// Returns `None` if the sequence doesn’t need to be changed, if any of its
+/// parameters isn’t a decimal number (e.g. it has a private marker or
+/// intermediate bytes) or if the rewritten sequence doesn’t fit in the output
+/// buffer.  In those cases, the sequence should be passed unchanged.
+fn rewrite_sgr(
+    config: &Config,
+    params: &[u8],
+    out: &mut [u8],
+    stats: &mut Stats,
+) -> Option<usize> {
+    let valid = |byte: &u8| byte.is_ascii_digit() || b";:".contains(byte);
+    if !params.iter().all(valid) {
+        return None;
+    } else if config.target == Target::Plain {
+        return Some(0);
+    }
+    let strip = config.target == Target::Monochrome;
+    let strip_underline = strip || config.underline == UnderlineColour::Drop;
+    let mut out = Output {
+        buf: out,
+        len: 0,
+        params: 0,
+    };
+    let mut changed = false;
+    out.push(b"\x1b[")?;
+    let mut rest = Some(params);
+    while let Some(params) = rest {
+        let len = match colour_at(params) {
+            Some((selector, _, len, _))
+                if strip || (strip_underline && selector == 58) =>
+            {
+                changed = true;
+                len
+            }
+            Some((selector, colour, len, colon)) => {
+                match convert(config, colour) {
+                    Some(converted) => {
+                        stats.record(crate::ansi256::redmean(
+                            spec_rgb(config, colour),
+                            spec_rgb(config, converted),
+                        ));
+                        let colon = match config.separator {
+                            Separator::Preserve => colon,
+                            Separator::Semicolon => false,
+                            Separator::Colon => true,
+                        };
+                        out.push_colour(selector, converted, colon)?;
+                        changed = true;
+                    }
+                    None => out.push_param(&params[..len])?,
+                }
+                len
+            }
+            None => {
+                let len = param_len(params);
+                let param = &params[..len];
+                if (strip && is_colour_param(param))
+                    || (strip_underline && parse_param(param) == Some(59))
+                {
+                    changed = true;
+                } else {
+                    out.push_param(&params[..len])?;
+                }
+                len
+            }
+        };
+        rest = params[len..].split_first().map(|(_, rest)| rest);
+    }
+    if !changed {
+        None
+    } else if out.params == 0 {
+        // All parameters have been removed.  Sequence without parameters
+        // would reset all attributes so it must be removed entirely.
+        Some(0)
+    } else {
+        out.push(b"m")?;
+        Some(out.len)
+    }
+}
+
+/// Applies OSC policy from the configuration to OSC sequence with given
+/// contents (i.e. excluding the introducer and the terminator).  Returns
+/// whether the sequence should be output.
+fn apply_osc(config: &mut Config, contents: &[u8]) -> bool {
+    let mut args = contents.split(|&byte| byte == b';');
+    let command = args.next().unwrap_or_default();
+    if !matches!(
+        command,
+        b"4" | b"10" | b"11" | b"12" | b"104" | b"110" | b"111" | b"112"
+    ) {
+        return true;
+    }
+    match config.osc {
+        OscPolicy::Pass => true,
+        OscPolicy::Drop => contents.contains(&b'?'),
+        OscPolicy::Record if command == b"4" => {
+            while let (Some(idx), Some(spec)) = (args.next(), args.next()) {
+                let rgb = crate::formats::parse_colour_spec(spec);
+                if let (Some(idx), Some(rgb)) = (osc_index(idx), rgb) {
+                    let initial = config.initial_palette();
+                    let palette = config.recorded.get_or_insert(initial);
+                    palette[idx] = crate::AsRGB::as_u32(&rgb);
+                }
+            }
+            true
+        }
+        OscPolicy::Record if command == b"104" => {
+            let mut args = args.filter(|arg| !arg.is_empty()).peekable();
+            if args.peek().is_none() {
+                config.recorded = None;
+            }
+            let initial = config.initial_palette();
+            for idx in args.filter_map(osc_index) {
+                if let Some(palette) = config.recorded.as_mut() {
+                    palette[idx] = initial[idx];
+                }
+            }
+            true
+        }
+        OscPolicy::Record => true,
+    }
+}
+
+/// Parses index of a system colour in an OSC sequence.  Returns `None` if
+/// it’s not a decimal number or it’s not an index of a system colour.
+fn osc_index(digits: &[u8]) -> Option<usize> {
+    let valid = !digits.is_empty() && digits.iter().all(u8::is_ascii_digit);
+    let idx = if valid { parse_number(digits) } else { 16 };
+    (idx < 16).then_some(idx as usize)
+}
+
+/// Checks whether given SGR parameter sets foreground or background colour
+/// to one of the system colours or resets it to the default.
+fn is_colour_param(param: &[u8]) -> bool {
+    matches!(
+        parse_param(param),
+        Some(30..=37 | 39 | 40..=47 | 49 | 90..=97 | 100..=107)
+    )
+}
+
+/// Colour specified in an SGR sequence.
+#[derive(Clone, Copy)]
+enum Spec {
+    /// Index in the 256-colour palette, i.e. `5;n` parameters.
+    Indexed(u8),
+    /// True-colour colour as `0xRRGGBB` integer, i.e. `2;r;g;b` parameters.
+    Rgb(u32),
+    /// Index of a system colour specified with a single parameter, e.g. `31`
+    /// or `101`.
+    System(u8),
+}
+
+/// Checks whether SGR parameters start with an extended foreground, background
+/// or underline colour specification, i.e. `38;2;r;g;b`, `38;5;n` or their
+/// `48` background and `58` underline colour equivalents.  Colon-separated
+/// forms (i.e. `38:2::r:g:b`, `38:2:r:g:b` and `38:5:n`) are recognised as
+/// well.  If they do, returns the selector (i.e. 38, 48 or 58), the colour,
+/// length of the
+/// specification (not including the following separator) and whether it uses
+/// the colon-separated form.
+fn colour_at(params: &[u8]) -> Option<(u8, Spec, usize, bool)> {
+    let len = param_len(params);
+    if params[..len].contains(&b':') {
+        let (selector, colour) = colon_colour(&params[..len])?;
+        return Some((selector, colour, len, true));
+    }
+    let mut values = [0; 5];
+    let mut pos = 0;
+    for count in 1..=values.len() {
+        if count > 1 {
+            if params.get(pos) != Some(&b';') {
+                break;
+            }
+            pos += 1;
+        }
+        values[count - 1] = parse_param(&params[pos..])?;
+        pos += param_len(&params[pos..]);
+        let colour = match values[..count] {
+            [38 | 48 | 58] | [38 | 48 | 58, 2 | 5] => continue,
+            [_, 2, _] | [_, 2, _, _] => continue,
+            [_, 5, idx] if idx < 256 => Spec::Indexed(idx as u8),
+            [_, 2, r, g, b] => rgb_spec(r, g, b)?,
+            _ => return None,
+        };
+        return Some((values[0] as u8, colour, pos, false));
+    }
+    None
+}
+
+/// Parses colour specified by a single SGR parameter with colon-separated
+/// sub-parameters, i.e. `38:5:n`, `38:2:r:g:b` or `38:2:cs:r:g:b` where `cs`
+/// is (ignored) colour space identifier.  Returns the selector (i.e. 38, 48 or
+/// 58) and the colour.
+fn colon_colour(param: &[u8]) -> Option<(u8, Spec)> {
+    let mut values = [0; 6];
+    let mut count = 0;
+    for value in param.split(|&byte| byte == b':') {
+        *values.get_mut(count)? = parse_number(value);
+        count += 1;
+    }
+    let colour = match values[..count] {
+        [38 | 48 | 58, 5, idx] if idx < 256 => Spec::Indexed(idx as u8),
+        [38 | 48 | 58, 2, r, g, b] | [38 | 48 | 58, 2, _, r, g, b] => {
+            rgb_spec(r, g, b)?
+        }
+        _ => return None,
+    };
+    Some((values[0] as u8, colour))
+}
+
+/// Returns true-colour colour with given components if they are all in range.
+fn rgb_spec(r: u32, g: u32, b: u32) -> Option<Spec> {
+    let in_range = r < 256 && g < 256 && b < 256;
+    in_range.then_some(Spec::Rgb((r << 16) | (g << 8) | b))
+}
+
+/// Returns sRGB value of given colour.  Values of system colours are taken
+/// from the configuration.
+fn spec_rgb(config: &Config, colour: Spec) -> u32 {
+    match (colour, config.palette()) {
+        (Spec::Rgb(rgb), _) => rgb,
+        (Spec::Indexed(idx) | Spec::System(idx), Some(palette)) if idx < 16 => {
+            palette[usize::from(idx)]
+        }
+        (Spec::Indexed(idx) | Spec::System(idx), _) => {
+            crate::AsRGB::as_u32(&crate::rgb_from_ansi256(idx))
+        }
+    }
+}
+
+/// Converts colour according to the configuration.  Returns `None` if the
+/// colour doesn’t need to be changed.
+fn convert(config: &Config, colour: Spec) -> Option<Spec> {
+    match (config.target, colour) {
+        (Target::Ansi256, Spec::Rgb(rgb)) => {
+            Some(Spec::Indexed(crate::ansi256_from_rgb(rgb)))
+        }
+        (Target::TrueColour, Spec::Indexed(idx)) => {
+            let rgb = match config.palette() {
+                Some(palette) if idx < 16 => palette[usize::from(idx)],
+                _ => crate::AsRGB::as_u32(&crate::rgb_from_ansi256(idx)),
+            };
+            Some(Spec::Rgb(rgb))
+        }
+        (Target::Ansi16, colour) => {
+            let idx = match (colour, config.palette()) {
+                (Spec::Indexed(idx), _) if idx < 16 => idx,
+                (Spec::Indexed(idx), Some(palette)) => {
+                    let rgb = crate::rgb_from_ansi256(idx);
+                    crate::ansi16_from_rgb_with_palette(rgb, &palette)
+                }
+                (Spec::Indexed(idx), None) => crate::ansi16_from_ansi256(idx),
+                (Spec::Rgb(rgb), Some(palette)) => {
+                    crate::ansi16_from_rgb_with_palette(rgb, &palette)
+                }
+                (Spec::Rgb(rgb), None) => crate::ansi16_from_rgb(rgb),
+                (Spec::System(_), _) => return None,
+            };
+            Some(Spec::System(idx))
+        }
+        _ => None,
+    }
+}
+
+/// Parses a numeric parameter at the start of given SGR parameters.  Empty
+/// parameter is treated as zero.  Returns `None` if the parameter contains
+/// sub-parameters.  Otherwise returns value of the parameter saturated at
+/// 256.
+fn parse_param(params: &[u8]) -> Option<u32> {
+    let param = &params[..param_len(params)];
+    (!param.contains(&b':')).then(|| parse_number(param))
+}
+
+/// Parses a decimal number saturating it at 256.  Empty string is treated as
+/// zero.
+fn parse_number(digits: &[u8]) -> u32 {
+    digits.iter().fold(0, |value, &digit| {
+        (value * 10 + u32::from(digit - b'0')).min(256)
+    })
+}
+
+/// Returns length of the parameter at the start of given SGR parameters.
+fn param_len(params: &[u8]) -> usize {
+    params
+        .iter()
+        .position(|&byte| byte == b';')
+        .unwrap_or(params.len())
+}
+
+/// A fixed-size output buffer.  All writing methods return `None` if the
+/// buffer overflows.
+struct Output<'a> {
+    buf: &'a mut [u8],
+    len: usize,
+    /// Number of SGR parameters written so far.
+    params: usize,
+}
+
+impl Output<'_> {
+    /// Appends given SGR parameter to the buffer preceded by a separator if
+    /// necessary.
+    fn push_param(&mut self, param: &[u8]) -> Option<()> {
+        self.start_param()?;
+        self.push(param)
+    }
+
+    /// Appends separator if the parameter which is about to be written isn’t
+    /// the first one.
+    fn start_param(&mut self) -> Option<()> {
+        self.params += 1;
+        if self.params > 1 {
+            self.push(b";")?;
+        }
+        Some(())
+    }
+
+    /// Appends given bytes to the buffer.
+    fn push(&mut self, bytes: &[u8]) -> Option<()> {
+        let end = self.len + bytes.len();
+        self.buf.get_mut(self.len..end)?.copy_from_slice(bytes);
+        self.len = end;
+        Some(())
+    }
+
+    /// Appends decimal representation of given number to the buffer.
+    fn push_number(&mut self, value: u8) -> Option<()> {
+        self.push(crate::sgr::write_decimal(&mut [0; 5], value.into()))
+    }
+
+    /// Appends SGR parameters specifying given colour to the buffer.
+    /// `selector` specifies whether it’s foreground (38), background (48) or
+    /// underline (58) colour.  `colon` specifies whether colon-separated form
+    /// should be used.
+    fn push_colour(
+        &mut self,
+        selector: u8,
+        colour: Spec,
+        colon: bool,
+    ) -> Option<()> {
+        self.start_param()?;
+        let sep = if colon { b":" } else { b";" };
+        match colour {
+            Spec::System(idx) if selector != 58 => {
+                let base = if selector == 38 { 30 } else { 40 };
+                let bright = if idx < 8 { 0 } else { 60 - 8 };
+                self.push_number(base + bright + idx)
+            }
+            // There’s no short form for underline colour so system colours
+            // are written as indexes in the palette.
+            Spec::Indexed(idx) | Spec::System(idx) => {
+                self.push_number(selector)?;
+                self.push(sep)?;
+                self.push(b"5")?;
+                self.push(sep)?;
+                self.push_number(idx)
+            }
+            Spec::Rgb(rgb) => {
+                let (r, g, b) = crate::ansi256::to_triple(rgb);
+                self.push_number(selector)?;
+                // Colon-separated form includes (empty) colour space
+                // identifier.
+                self.push(if colon { b":2::" } else { b";2;" })?;
+                self.push_number(r)?;
+                self.push(sep)?;
+                self.push_number(g)?;
+                self.push(sep)?;
+                self.push_number(b)
+            }
+        }
+    }
+}
@@ -0,0 +1,786 @@
+// ansi_colours – true-colour ↔ ANSI terminal palette converter
+// Copyright 2018 by Michał Nazarewicz <mina86@mina86.com>
+//
+// ansi_colours is free software: you can redistribute it and/or modify it
+// under the terms of the GNU Lesser General Public License as published by
+// the Free Software Foundation; either version 3 of the License, or (at
+// your option) any later version.
+//
+// ansi_colours is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Lesser
+// General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with ansi_colours.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Wrappers for less common colour representations.
+
+use crate::AsRGB;
+
+/// Expands an `bits`-bit colour component into an 8-bit one.
+///
+/// The component is shifted into the most significant bits and its high bits
+/// are replicated into the low bits so that the full range is covered, i.e.
+/// zero maps to zero and maximum value maps to 255.
+#[inline(always)]
+fn expand(value: u16, bits: u32) -> u32 {
+    let value = u32::from(value) & ((1 << bits) - 1);
+    (value << (8 - bits)) | (value >> (2 * bits - 8))
+}
+
+/// An sRGB colour packed into 16-bit `0bRRRRRGGGGGGBBBBB` integer.
+///
+/// This is a format commonly used by embedded displays and framebuffers.  Five
+/// bits are used for red and blue components and six bits for green component.
+/// When converting to 24-bit representation the components are expanded by
+/// replicating their most significant bits so that full intensity of
+/// a component maps to 255.
+///
+/// The type is `#[repr(transparent)]` and thus has the same layout as `u16`.
+/// With `bytemuck` or `zerocopy` crate feature enabled, it implements those
+/// crates’ marker traits so that slices of pixels can be cast without copying.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::{ansi256_from_rgb, AsRGB, Rgb565};
+///
+/// assert_eq!(0x000000, Rgb565(0x0000).as_u32());
+/// assert_eq!(0xff0000, Rgb565(0xf800).as_u32());
+/// assert_eq!(0x00ff00, Rgb565(0x07e0).as_u32());
+/// assert_eq!(0x0000ff, Rgb565(0x001f).as_u32());
+/// assert_eq!(0x6b8aad, Rgb565(0x6c55).as_u32());
+///
+/// assert_eq!( 16, ansi256_from_rgb(Rgb565(0x0000)));
+/// assert_eq!( 67, ansi256_from_rgb(Rgb565(0x5c35)));
+/// assert_eq!(231, ansi256_from_rgb(Rgb565(0xffff)));
+#[cfg_attr(
+    feature = "bytemuck",
+    doc = r#"
+
+let pixels: &[u16] = &[0x0000, 0x5c35, 0xffff];
+let pixels: &[Rgb565] = bytemuck::cast_slice(pixels);
+let indexes = pixels.iter().map(ansi256_from_rgb);
+assert_eq!(vec![16, 67, 231], indexes.collect::<Vec<_>>());
+"#
+)]
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "bytemuck", derive(bytemuck::Pod, bytemuck::Zeroable))]
+#[cfg_attr(
+    feature = "zerocopy",
+    derive(
+        zerocopy::FromBytes,
+        zerocopy::IntoBytes,
+        zerocopy::Immutable,
+        zerocopy::KnownLayout
+    )
+)]
+#[repr(transparent)]
+pub struct Rgb565(pub u16);
+
+impl AsRGB for Rgb565 {
+    #[inline]
+    fn as_u32(&self) -> u32 {
+        let v = self.0;
+        (expand(v >> 11, 5) << 16) | (expand(v >> 5, 6) << 8) | expand(v, 5)
+    }
+}
+
+/// An sRGB colour packed into 16-bit `0bXRRRRRGGGGGBBBBB` integer.
+///
+/// Each component uses five bits and the most significant bit is ignored.
+/// When converting to 24-bit representation the components are expanded by
+/// replicating their most significant bits so that full intensity of
+/// a component maps to 255.
+///
+/// The type is `#[repr(transparent)]` and thus has the same layout as `u16`.
+/// With `bytemuck` or `zerocopy` crate feature enabled, it implements those
+/// crates’ marker traits so that slices of pixels can be cast without copying.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::{ansi256_from_rgb, AsRGB, Rgb555};
+///
+/// assert_eq!(0x000000, Rgb555(0x0000).as_u32());
+/// assert_eq!(0xff0000, Rgb555(0x7c00).as_u32());
+/// assert_eq!(0x00ff00, Rgb555(0x03e0).as_u32());
+/// assert_eq!(0x0000ff, Rgb555(0x001f).as_u32());
+/// assert_eq!(0xffffff, Rgb555(0xffff).as_u32());
+///
+/// assert_eq!( 16, ansi256_from_rgb(Rgb555(0x0000)));
+/// assert_eq!( 67, ansi256_from_rgb(Rgb555(0x2e15)));
+/// assert_eq!(231, ansi256_from_rgb(Rgb555(0x7fff)));
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "bytemuck", derive(bytemuck::Pod, bytemuck::Zeroable))]
+#[cfg_attr(
+    feature = "zerocopy",
+    derive(
+        zerocopy::FromBytes,
+        zerocopy::IntoBytes,
+        zerocopy::Immutable,
+        zerocopy::KnownLayout
+    )
+)]
+#[repr(transparent)]
+pub struct Rgb555(pub u16);
+
+impl AsRGB for Rgb555 {
+    #[inline]
+    fn as_u32(&self) -> u32 {
+        let v = self.0;
+        (expand(v >> 10, 5) << 16) | (expand(v >> 5, 5) << 8) | expand(v, 5)
+    }
+}
+
+/// A colour specified in CMYK colour space.
+///
+/// Each component is an 8-bit value with zero meaning no ink and 255 meaning
+/// full coverage.  The conversion to sRGB is the naïve device-independent one
+/// without any colour profile applied, i.e. `r = 255 × (1 - c) × (1 - k)` and
+/// similarly for the green and blue components (with `c`, `m`, `y` and `k`
+/// normalised to the `[0, 1]` range).  Results are rounded to the nearest
+/// integer.  As such, it’s suitable for previewing ink colours rather than for
+/// accurate colour reproduction.
+///
+/// The type is `#[repr(C)]` with the components laid out in `c`, `m`, `y`, `k`
+/// order.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::{ansi256_from_rgb, AsRGB, Cmyk};
+///
+/// assert_eq!(0xffffff, Cmyk { c:   0, m:   0, y:   0, k:   0 }.as_u32());
+/// assert_eq!(0x000000, Cmyk { c:   0, m:   0, y:   0, k: 255 }.as_u32());
+/// assert_eq!(0x00ffff, Cmyk { c: 255, m:   0, y:   0, k:   0 }.as_u32());
+/// assert_eq!(0x7f7f7f, Cmyk { c:   0, m:   0, y:   0, k: 128 }.as_u32());
+/// assert_eq!(0x5f87af, Cmyk { c: 117, m:  58, y:   0, k:  80 }.as_u32());
+///
+/// assert_eq!( 16, ansi256_from_rgb(Cmyk { c:   0, m:   0, y:   0, k: 255 }));
+/// assert_eq!( 67, ansi256_from_rgb(Cmyk { c: 117, m:  58, y:   0, k:  80 }));
+/// assert_eq!(231, ansi256_from_rgb(Cmyk { c:   0, m:   0, y:   0, k:   0 }));
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "bytemuck", derive(bytemuck::Pod, bytemuck::Zeroable))]
+#[cfg_attr(
+    feature = "zerocopy",
+    derive(
+        zerocopy::FromBytes,
+        zerocopy::IntoBytes,
+        zerocopy::Immutable,
+        zerocopy::KnownLayout
+    )
+)]
+#[repr(C)]
+pub struct Cmyk {
+    /// Cyan component.
+    pub c: u8,
+    /// Magenta component.
+    pub m: u8,
+    /// Yellow component.
+    pub y: u8,
+    /// Black (key) component.
+    pub k: u8,
+}
+
+impl AsRGB for Cmyk {
+    #[inline]
+    fn as_u32(&self) -> u32 {
+        let white = 255 - u32::from(self.k);
+        let component = |ink: u8| ((255 - u32::from(ink)) * white + 127) / 255;
+        (component(self.c) << 16) | (component(self.m) << 8) | component(self.y)
+    }
+}
+
+/// Returns sRGB colour with given hue, chroma and offset as a 24-bit
+/// `0xRRGGBB` integer.  This is common part of HSL and HSV conversions.
+///
+/// Hue is in degrees and is normalised into the `[0, 360)` range.  Chroma and
+/// offset are in the 0–1 range.
+fn from_hue(hue: f32, chroma: f32, offset: f32) -> u32 {
+    let mut hue = hue % 360.0;
+    if hue < 0.0 {
+        hue += 360.0;
+    }
+    let hue = hue / 60.0;
+    let mid = chroma * (1.0 - (hue % 2.0 - 1.0).abs());
+    let (r, g, b) = match hue as u8 {
+        0 => (chroma, mid, 0.0),
+        1 => (mid, chroma, 0.0),
+        2 => (0.0, chroma, mid),
+        3 => (0.0, mid, chroma),
+        4 => (mid, 0.0, chroma),
+        _ => (chroma, 0.0, mid),
+    };
+    let component =
+        |value: f32| ((value + offset) * 255.0 + 0.5).clamp(0.0, 255.0) as u32;
+    (component(r) << 16) | (component(g) << 8) | component(b)
+}
+
+/// An sRGB colour with linear-light components.
+///
+/// Components are linear intensities in the 0–1 range, as used by graphics
+/// pipelines which do their computations in linear space.  Values outside of
+/// the range are clamped.  The sRGB transfer function is applied when
+/// converting the colour into the usual gamma-encoded 24-bit representation
+/// so there’s no need to encode the colour before approximating it.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::{ansi256_from_rgb, AsRGB, LinearRgb};
+///
+/// assert_eq!(0x000000, LinearRgb { r: 0.0, g: 0.0, b: 0.0 }.as_u32());
+/// assert_eq!(0xffffff, LinearRgb { r: 1.0, g: 1.0, b: 1.0 }.as_u32());
+/// assert_eq!(0xbcbcbc, LinearRgb { r: 0.5, g: 0.5, b: 0.5 }.as_u32());
+/// assert_eq!(0xff0000, LinearRgb { r: 2.0, g: -1.0, b: 0.0 }.as_u32());
+///
+/// let linear = LinearRgb { r: 0.114, g: 0.242, b: 0.429 };
+/// assert_eq!(0x5f87af, linear.as_u32());
+/// assert_eq!( 67, ansi256_from_rgb(linear));
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct LinearRgb {
+    /// Linear intensity of the red component in the 0–1 range.
+    pub r: f32,
+    /// Linear intensity of the green component in the 0–1 range.
+    pub g: f32,
+    /// Linear intensity of the blue component in the 0–1 range.
+    pub b: f32,
+}
+
+impl AsRGB for LinearRgb {
+    fn as_u32(&self) -> u32 {
+        let encode = |value: f32| u32::from(crate::ansi256::encode_srgb(value));
+        (encode(self.r) << 16) | (encode(self.g) << 8) | encode(self.b)
+    }
+}
+
+/// Colour of a black-body radiator at given temperature in kelvins.
+///
+/// The colour is calculated using cubic spline approximation of the Planckian
+/// locus by Kim et al., which is valid for temperatures from 1667 K to
+/// 25000 K.  Temperatures outside of that range are clamped.  The colour is
+/// scaled so that its brightest component is at full intensity.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::{ansi256_from_rgb, AsRGB, Kelvin};
+///
+/// assert_eq!(0xff7400, Kelvin(1000.0).as_u32());
+/// assert_eq!(0xffad59, Kelvin(2700.0).as_u32());
+/// assert_eq!(0xfff9fe, Kelvin(6500.0).as_u32());
+/// assert_eq!(0xcdd9ff, Kelvin(10000.0).as_u32());
+///
+/// assert_eq!(215, ansi256_from_rgb(Kelvin(2700.0)));
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Kelvin(pub f32);
+
+impl AsRGB for Kelvin {
+    fn as_u32(&self) -> u32 {
+        let t = self.0.clamp(1667.0, 25000.0);
+        let (t1, t2, t3) = (1e3 / t, 1e6 / (t * t), 1e9 / (t * t * t));
+        // Chromaticity coordinates of the Planckian locus.
+        let x = if t <= 4000.0 {
+            -0.266_123_9 * t3 - 0.234_358_9 * t2 + 0.877_695_6 * t1 + 0.179_91
+        } else {
+            -3.025_847 * t3 + 2.107_037_9 * t2 + 0.222_634_7 * t1 + 0.240_39
+        };
+        let [a, b, c, d] = if t <= 2222.0 {
+            [-1.106_381_4, -1.348_110_2, 2.185_558_3, -0.202_196_83]
+        } else if t <= 4000.0 {
+            [-0.954_947_6, -1.374_185_9, 2.091_37, -0.167_488_67]
+        } else {
+            [3.081_758, -5.873_387, 3.751_13, -0.370_014_83]
+        };
+        let y = ((a * x + b) * x + c) * x + d;
+        // CIE XYZ with Y = 1 converted into linear sRGB.
+        let (cx, cz) = (x / y, (1.0 - x - y) / y);
+        let r = 3.240_454_2 * cx - 1.537_138_5 - 0.498_531_4 * cz;
+        let g = -0.969_266 * cx + 1.876_010_8 + 0.041_556 * cz;
+        let b = 0.055_643_4 * cx - 0.204_025_9 + 1.057_225_2 * cz;
+        let max = r.max(g).max(b);
+        let encode = |v: f32| u32::from(crate::ansi256::encode_srgb(v / max));
+        (encode(r) << 16) | (encode(g) << 8) | encode(b)
+    }
+}
+
+/// A colour specified by its hue, saturation and lightness.
+///
+/// Hue is given in degrees and values outside of the `[0, 360)` range wrap
+/// around.  Saturation and lightness are in the 0–1 range and values outside
+/// of it are clamped.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::{ansi256_from_rgb, AsRGB, Hsl};
+///
+/// assert_eq!(0x000000, Hsl { h:   0.0, s: 0.0, l: 0.0 }.as_u32());
+/// assert_eq!(0xffffff, Hsl { h:   0.0, s: 0.0, l: 1.0 }.as_u32());
+/// assert_eq!(0xff0000, Hsl { h:   0.0, s: 1.0, l: 0.5 }.as_u32());
+/// assert_eq!(0xff0000, Hsl { h: 360.0, s: 1.0, l: 0.5 }.as_u32());
+/// assert_eq!(0x0000ff, Hsl { h: -120.0, s: 1.0, l: 0.5 }.as_u32());
+/// assert_eq!(0x5f87af, Hsl { h: 210.0, s: 1.0 / 3.0, l: 0.53 }.as_u32());
+///
+/// assert_eq!( 67, ansi256_from_rgb(Hsl { h: 210.0, s: 0.33, l: 0.53 }));
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Hsl {
+    /// Hue in degrees.
+    pub h: f32,
+    /// Saturation in the 0–1 range.
+    pub s: f32,
+    /// Lightness in the 0–1 range.
+    pub l: f32,
+}
+
+impl AsRGB for Hsl {
+    fn as_u32(&self) -> u32 {
+        let s = self.s.clamp(0.0, 1.0);
+        let l = self.l.clamp(0.0, 1.0);
+        let chroma = (1.0 - (2.0 * l - 1.0).abs()) * s;
+        from_hue(self.h, chroma, l - chroma / 2.0)
+    }
+}
+
+/// A colour specified by its hue, saturation and value.
+///
+/// Hue is given in degrees and values outside of the `[0, 360)` range wrap
+/// around.  Saturation and value are in the 0–1 range and values outside of
+/// it are clamped.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::{ansi256_from_rgb, AsRGB, Hsv};
+///
+/// assert_eq!(0x000000, Hsv { h:   0.0, s: 0.0, v: 0.0 }.as_u32());
+/// assert_eq!(0xffffff, Hsv { h:   0.0, s: 0.0, v: 1.0 }.as_u32());
+/// assert_eq!(0x00ff00, Hsv { h: 120.0, s: 1.0, v: 1.0 }.as_u32());
+/// let hsv = Hsv { h: 210.0, s: 80.0 / 175.0, v: 175.0 / 255.0 };
+/// assert_eq!(0x5f87af, hsv.as_u32());
+///
+/// assert_eq!( 67, ansi256_from_rgb(Hsv { h: 210.0, s: 0.46, v: 0.69 }));
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Hsv {
+    /// Hue in degrees.
+    pub h: f32,
+    /// Saturation in the 0–1 range.
+    pub s: f32,
+    /// Value in the 0–1 range.
+    pub v: f32,
+}
+
+impl AsRGB for Hsv {
+    fn as_u32(&self) -> u32 {
+        let s = self.s.clamp(0.0, 1.0);
+        let v = self.v.clamp(0.0, 1.0);
+        let chroma = v * s;
+        from_hue(self.h, chroma, v - chroma)
+    }
+}
+
+/// Standard describing how a [`YCbCr`] colour is encoded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum YCbCrStandard {
+    /// ITU-R BT.601 coefficients with limited (a.k.a. studio or TV) range,
+    /// i.e. luma going from 16 to 235 and chroma from 16 to 240.  This is what
+    /// standard-definition video uses.
+    Bt601,
+    /// ITU-R BT.709 coefficients with limited range.  This is what
+    /// high-definition video uses.
+    Bt709,
+    /// ITU-R BT.601 coefficients with full range, i.e. all components going
+    /// from 0 to 255.  This is what JPEG images use.
+    Jpeg,
+}
+
+/// A colour specified in Y′CbCr colour space.
+///
+/// The conversion to sRGB depends on the standard the colour is encoded with
+/// (see [`YCbCrStandard`]).  Colours outside of the sRGB gamut (which is
+/// possible since not all Y′CbCr triples map to valid RGB colours) are clamped.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::{ansi256_from_rgb, AsRGB, YCbCr};
+///
+/// assert_eq!(0x000000, YCbCr::bt601( 16, 128, 128).as_u32());
+/// assert_eq!(0xffffff, YCbCr::bt601(235, 128, 128).as_u32());
+/// assert_eq!(0xfe0000, YCbCr::bt601( 81,  90, 240).as_u32());
+/// assert_eq!(0xff1800, YCbCr::bt709( 81,  90, 240).as_u32());
+/// assert_eq!(0xee0e0e, YCbCr::jpeg ( 81,  90, 240).as_u32());
+///
+/// assert_eq!( 16, ansi256_from_rgb(YCbCr::bt601( 16, 128, 128)));
+/// assert_eq!( 67, ansi256_from_rgb(YCbCr::bt601(126, 157, 110)));
+/// assert_eq!( 67, ansi256_from_rgb(YCbCr::bt709(126, 157, 110)));
+/// assert_eq!(231, ansi256_from_rgb(YCbCr::jpeg (255, 128, 128)));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct YCbCr {
+    /// Luma component.
+    pub y: u8,
+    /// Blue-difference chroma component.
+    pub cb: u8,
+    /// Red-difference chroma component.
+    pub cr: u8,
+    /// Standard the colour is encoded with.
+    pub standard: YCbCrStandard,
+}
+
+impl YCbCr {
+    /// Constructs a colour encoded using BT.601 limited-range standard.
+    #[inline]
+    pub fn bt601(y: u8, cb: u8, cr: u8) -> Self {
+        Self {
+            y,
+            cb,
+            cr,
+            standard: YCbCrStandard::Bt601,
+        }
+    }
+
+    /// Constructs a colour encoded using BT.709 limited-range standard.
+    #[inline]
+    pub fn bt709(y: u8, cb: u8, cr: u8) -> Self {
+        Self {
+            y,
+            cb,
+            cr,
+            standard: YCbCrStandard::Bt709,
+        }
+    }
+
+    /// Constructs a colour encoded using full-range BT.601 standard as used by
+    /// JPEG.
+    #[inline]
+    pub fn jpeg(y: u8, cb: u8, cr: u8) -> Self {
+        Self {
+            y,
+            cb,
+            cr,
+            standard: YCbCrStandard::Jpeg,
+        }
+    }
+}
+
+impl AsRGB for YCbCr {
+    fn as_u32(&self) -> u32 {
+        // Coefficients in 16.16 fixed-point format.  In order: luma scale,
+        // Cr contribution to red, Cb and Cr contributions to green and Cb
+        // contribution to blue.  The latter four incorporate chroma scale.
+        let (offset, [ky, r_cr, g_cb, g_cr, b_cb]) = match self.standard {
+            YCbCrStandard::Bt601 => (16, [76309, 104597, 25675, 53279, 132201]),
+            YCbCrStandard::Bt709 => (16, [76309, 117489, 13975, 34925, 138438]),
+            YCbCrStandard::Jpeg => (0, [65536, 91881, 22553, 46802, 116130]),
+        };
+        let y = (i32::from(self.y) - offset) * ky + (1 << 15);
+        let cb = i32::from(self.cb) - 128;
+        let cr = i32::from(self.cr) - 128;
+        let clamp = |v: i32| (v >> 16).clamp(0, 255) as u32;
+        let r = clamp(y + r_cr * cr);
+        let g = clamp(y - g_cb * cb - g_cr * cr);
+        let b = clamp(y + b_cb * cb);
+        (r << 16) | (g << 8) | b
+    }
+}
+
+/// Policy describing how the alpha channel of [`Argb`] and [`Rgba`] colours is
+/// handled when converting them to opaque sRGB colours.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum AlphaPolicy {
+    /// The alpha channel is ignored and the colour is treated as opaque.
+    Ignore,
+    /// The colour is composited over an opaque background colour given as
+    /// a 24-bit `0xRRGGBB` integer.  Blending is done on gamma-encoded
+    /// components.
+    Over(u32),
+}
+
+impl AlphaPolicy {
+    /// Applies the policy to an sRGB colour with given alpha and returns the
+    /// resulting opaque colour as a 24-bit `0xRRGGBB` integer.
+    fn apply(self, rgb: u32, alpha: u8) -> u32 {
+        let background = match self {
+            Self::Ignore => return rgb & 0xffffff,
+            Self::Over(background) => background,
+        };
+        let alpha = u32::from(alpha);
+        let blend = |shift: u32| {
+            let fg = (rgb >> shift) & 0xff;
+            let bg = (background >> shift) & 0xff;
+            ((fg * alpha + bg * (255 - alpha) + 127) / 255) << shift
+        };
+        blend(16) | blend(8) | blend(0)
+    }
+}
+
+/// An sRGB colour with alpha channel packed into 32-bit `0xAARRGGBB` integer.
+///
+/// This is a format used by many GUI toolkits.  How the alpha channel is
+/// handled is determined by the [`AlphaPolicy`].
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::{ansi256_from_rgb, AlphaPolicy, Argb, AsRGB};
+///
+/// let ignore = |value| Argb::new(value, AlphaPolicy::Ignore);
+/// let over_white = |value| Argb::new(value, AlphaPolicy::Over(0xffffff));
+///
+/// assert_eq!(0x123456, ignore(0xff123456).as_u32());
+/// assert_eq!(0x123456, ignore(0x00123456).as_u32());
+/// assert_eq!(0x123456, over_white(0xff123456).as_u32());
+/// assert_eq!(0xffffff, over_white(0x00123456).as_u32());
+/// assert_eq!(0x8899aa, over_white(0x80123456).as_u32());
+///
+/// assert_eq!( 16, ansi256_from_rgb(ignore(0x80000000)));
+/// assert_eq!( 67, ansi256_from_rgb(ignore(0x005f87af)));
+/// assert_eq!(231, ansi256_from_rgb(over_white(0x00000000)));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Argb {
+    /// The colour as a 32-bit `0xAARRGGBB` integer.
+    pub value: u32,
+    /// Policy for handling the alpha channel.
+    pub policy: AlphaPolicy,
+}
+
+impl Argb {
+    /// Constructs new colour from given `0xAARRGGBB` integer and alpha policy.
+    #[inline]
+    pub fn new(value: u32, policy: AlphaPolicy) -> Self {
+        Self { value, policy }
+    }
+}
+
+impl AsRGB for Argb {
+    #[inline]
+    fn as_u32(&self) -> u32 {
+        self.policy.apply(self.value, (self.value >> 24) as u8)
+    }
+}
+
+/// An sRGB colour with alpha channel packed into 32-bit `0xRRGGBBAA` integer.
+///
+/// How the alpha channel is handled is determined by the [`AlphaPolicy`].
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::{ansi256_from_rgb, AlphaPolicy, AsRGB, Rgba};
+///
+/// let ignore = |value| Rgba::new(value, AlphaPolicy::Ignore);
+/// let over_white = |value| Rgba::new(value, AlphaPolicy::Over(0xffffff));
+///
+/// assert_eq!(0x123456, ignore(0x123456ff).as_u32());
+/// assert_eq!(0x123456, ignore(0x12345600).as_u32());
+/// assert_eq!(0x123456, over_white(0x123456ff).as_u32());
+/// assert_eq!(0xffffff, over_white(0x12345600).as_u32());
+/// assert_eq!(0x8899aa, over_white(0x12345680).as_u32());
+///
+/// assert_eq!( 16, ansi256_from_rgb(ignore(0x00000080)));
+/// assert_eq!( 67, ansi256_from_rgb(ignore(0x5f87af00)));
+/// assert_eq!(231, ansi256_from_rgb(over_white(0x00000000)));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Rgba {
+    /// The colour as a 32-bit `0xRRGGBBAA` integer.
+    pub value: u32,
+    /// Policy for handling the alpha channel.
+    pub policy: AlphaPolicy,
+}
+
+impl Rgba {
+    /// Constructs new colour from given `0xRRGGBBAA` integer and alpha policy.
+    #[inline]
+    pub fn new(value: u32, policy: AlphaPolicy) -> Self {
+        Self { value, policy }
+    }
+}
+
+impl AsRGB for Rgba {
+    #[inline]
+    fn as_u32(&self) -> u32 {
+        self.policy.apply(self.value >> 8, self.value as u8)
+    }
+}
+
+/// Layout of pixels in an image buffer converted with
+/// [`ansi256_from_image`](crate::ansi256_from_image).
+///
+/// Each component takes a single byte.  Alpha channel, if present, is ignored.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum PixelLayout {
+    /// Three bytes per pixel in red, green, blue order.
+    Rgb,
+    /// Three bytes per pixel in blue, green, red order.
+    Bgr,
+    /// Four bytes per pixel in red, green, blue, alpha order.
+    Rgba,
+    /// Four bytes per pixel in blue, green, red, alpha order.  This is
+    /// a common layout of framebuffers (often with unused alpha byte).
+    Bgra,
+}
+
+impl PixelLayout {
+    /// Returns number of bytes each pixel takes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ansi_colours::PixelLayout;
+    ///
+    /// assert_eq!(3, PixelLayout::Bgr.bytes_per_pixel());
+    /// assert_eq!(4, PixelLayout::Rgba.bytes_per_pixel());
+    /// ```
+    #[inline]
+    pub const fn bytes_per_pixel(self) -> usize {
+        match self {
+            Self::Rgb | Self::Bgr => 3,
+            Self::Rgba | Self::Bgra => 4,
+        }
+    }
+
+    /// Returns sRGB colour of a pixel given as a 24-bit `0xRRGGBB` integer.
+    /// `pixel` must have at least three bytes.
+    #[inline]
+    pub(crate) fn rgb(self, pixel: &[u8]) -> u32 {
+        let (r, g, b) = match self {
+            Self::Rgb | Self::Rgba => (pixel[0], pixel[1], pixel[2]),
+            Self::Bgr | Self::Bgra => (pixel[2], pixel[1], pixel[0]),
+        };
+        (u32::from(r) << 16) | (u32::from(g) << 8) | u32::from(b)
+    }
+}
+
+/// Opaque background colour over which translucent colours are composited by
+/// [`ansi256_from_rgba`](crate::ansi256_from_rgba).
+///
+/// The background can be given either as an sRGB colour or as an index in the
+/// 256-colour ANSI palette.  The latter is convenient when the background is
+/// the terminal’s own (e.g. when drawing a shadow over text whose background
+/// has been set with an SGR sequence).
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::Background;
+///
+/// assert_eq!(Background::Rgb(0x5f87af), Background::from(0x5f87afu32));
+/// assert_eq!(Background::Rgb(0x5f87af), Background::from([95u8, 135, 175]));
+/// assert_eq!(Background::Index(67), Background::from(67u8));
+///
+/// assert_eq!(0x5f87af, Background::Index(67).to_u32());
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Background {
+    /// Background given as a 24-bit `0xRRGGBB` integer.
+    Rgb(u32),
+    /// Background given as an index in the 256-colour ANSI palette.  Colour
+    /// at the index is determined with
+    /// [`rgb_from_ansi256`](crate::rgb_from_ansi256).
+    Index(u8),
+}
+
+impl Background {
+    /// Returns the background as a 24-bit `0xRRGGBB` integer.
+    #[inline]
+    pub fn to_u32(self) -> u32 {
+        match self {
+            Self::Rgb(rgb) => rgb & 0xffffff,
+            Self::Index(idx) => crate::system::palette_colour(idx),
+        }
+    }
+}
+
+impl From<u32> for Background {
+    #[inline]
+    fn from(rgb: u32) -> Self {
+        Self::Rgb(rgb)
+    }
+}
+
+impl From<(u8, u8, u8)> for Background {
+    #[inline]
+    fn from(rgb: (u8, u8, u8)) -> Self {
+        Self::Rgb(rgb.as_u32())
+    }
+}
+
+impl From<[u8; 3]> for Background {
+    #[inline]
+    fn from(rgb: [u8; 3]) -> Self {
+        Self::Rgb(rgb.as_u32())
+    }
+}
+
+impl From<u8> for Background {
+    #[inline]
+    fn from(idx: u8) -> Self {
+        Self::Index(idx)
+    }
+}
+
+/// Parses X11 colour specification as used in OSC colour sequences, i.e.
+/// `rgb:R/G/B` with one to four hexadecimal digits per component or `#RGB`
+/// with all components having the same number of digits.
+#[cfg(any(feature = "downgrade", feature = "terminal"))]
+pub(crate) fn parse_colour_spec(spec: &[u8]) -> Option<(u8, u8, u8)> {
+    if let Some(spec) = spec.strip_prefix(b"rgb:") {
+        let mut parts = spec.split(|b| *b == b'/');
+        let r = parse_component(parts.next()?)?;
+        let g = parse_component(parts.next()?)?;
+        let b = parse_component(parts.next()?)?;
+        parts.next().is_none().then_some((r, g, b))
+    } else if let Some(spec) = spec.strip_prefix(b"#") {
+        if spec.is_empty() || spec.len() % 3 != 0 || spec.len() > 12 {
+            return None;
+        }
+        let len = spec.len() / 3;
+        let r = parse_component(&spec[..len])?;
+        let g = parse_component(&spec[len..2 * len])?;
+        let b = parse_component(&spec[2 * len..])?;
+        Some((r, g, b))
+    } else {
+        None
+    }
+}
+
+/// Parses a component of one to four hexadecimal digits scaling it to eight
+/// bits.
+#[cfg(any(feature = "downgrade", feature = "terminal"))]
+fn parse_component(digits: &[u8]) -> Option<u8> {
+    if digits.is_empty() || digits.len() > 4 {
+        return None;
+    }
+    let mut value = 0u32;
+    for digit in digits {
+        value = value * 16 + char::from(*digit).to_digit(16)?;
+    }
+    let max = (1u32 << (4 * digits.len())) - 1;
+    Some(((value * 255 + max / 2) / max) as u8)
+}
@@ -0,0 +1,188 @@
+// ansi_colours – true-colour ↔ ANSI terminal palette converter
+// Copyright 2018 by Michał Nazarewicz <mina86@mina86.com>
+//
+// ansi_colours is free software: you can redistribute it and/or modify it
+// under the terms of the GNU Lesser General Public License as published by
+// the Free Software Foundation; either version 3 of the License, or (at
+// your option) any later version.
+//
+// ansi_colours is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Lesser
+// General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with ansi_colours.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Gradients quantised to the ANSI palette.
+
+use crate::ansi256::{cbrt, encode_linear, to_triple, LINEAR};
+
+/// Iterator over indexes of colours in 256-colour ANSI palette approximating
+/// a gradient between two colours.
+///
+/// The iterator is returned by [`gradient`](crate::gradient) function.  See
+/// its documentation for details.
+#[derive(Clone, Debug)]
+pub struct Gradient {
+    /// Colour space the interpolation is done in.
+    space: Space,
+    /// The starting colour in the colour space.
+    from: [f32; 3],
+    /// The ending colour in the colour space.
+    to: [f32; 3],
+    /// Index of the next step.
+    step: usize,
+    /// Total number of steps.
+    steps: usize,
+    /// Most recently returned index.  Used to skip duplicates.
+    last: Option<u8>,
+}
+
+/// Colour space in which [`Gradient`] interpolates colours.
+#[derive(Clone, Copy, Debug)]
+enum Space {
+    /// CIE L*a*b* colour space.
+    Lab,
+    /// OKLCH colour space, i.e. polar representation of OKLab.
+    #[cfg(feature = "oklab")]
+    Oklch,
+}
+
+impl Gradient {
+    /// Constructs gradient interpolating in CIE L*a*b* colour space.
+    pub(crate) fn new(from: u32, to: u32, steps: usize) -> Self {
+        Self {
+            space: Space::Lab,
+            from: lab_from_rgb(from),
+            to: lab_from_rgb(to),
+            step: 0,
+            steps,
+            last: None,
+        }
+    }
+
+    /// Constructs gradient interpolating in OKLCH colour space.
+    #[cfg(feature = "oklab")]
+    pub(crate) fn new_oklch(from: u32, to: u32, steps: usize) -> Self {
+        use core::f32::consts::PI;
+
+        let mut from = crate::oklab::Oklab::from_rgb(from).to_lch();
+        let mut to = crate::oklab::Oklab::from_rgb(to).to_lch();
+        // Hue of achromatic colours is meaningless.  Use hue of the other end
+        // so that the hue doesn’t change along the gradient.
+        const ACHROMATIC: f32 = 1e-4;
+        if from[1] < ACHROMATIC {
+            from[2] = to[2];
+        } else if to[1] < ACHROMATIC {
+            to[2] = from[2];
+        }
+        // Go along the shorter arc.
+        if to[2] - from[2] > PI {
+            to[2] -= 2.0 * PI;
+        } else if from[2] - to[2] > PI {
+            to[2] += 2.0 * PI;
+        }
+        Self {
+            space: Space::Oklch,
+            from,
+            to,
+            step: 0,
+            steps,
+            last: None,
+        }
+    }
+
+    /// Returns index of colour approximating the gradient at given step.
+    fn index_at(&self, step: usize) -> u8 {
+        let t = if self.steps > 1 {
+            step as f32 / (self.steps - 1) as f32
+        } else {
+            0.0
+        };
+        let mut colour = [0.0; 3];
+        for (chan, value) in colour.iter_mut().enumerate() {
+            *value = self.from[chan] + (self.to[chan] - self.from[chan]) * t;
+        }
+        match self.space {
+            Space::Lab => crate::system::ansi256_from_rgb(rgb_from_lab(colour)),
+            #[cfg(feature = "oklab")]
+            Space::Oklch => {
+                crate::oklab::nearest(crate::oklab::Oklab::from_lch(colour))
+            }
+        }
+    }
+}
+
+impl Iterator for Gradient {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        while self.step < self.steps {
+            let idx = self.index_at(self.step);
+            self.step += 1;
+            if self.last != Some(idx) {
+                self.last = Some(idx);
+                return Some(idx);
+            }
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // Unless nothing has been returned yet, all remaining steps may turn
+        // out to be duplicates.
+        let remaining = self.steps - self.step;
+        let lower = remaining != 0 && self.last.is_none();
+        (usize::from(lower), Some(remaining))
+    }
+}
+
+impl core::iter::FusedIterator for Gradient {}
+
+/// D65 reference white in CIE XYZ colour space.
+const WHITE: [f32; 3] = [0.950_47, 1.0, 1.088_83];
+
+/// Converts sRGB colour into CIE L*a*b* colour space.
+pub(crate) fn lab_from_rgb(rgb: u32) -> [f32; 3] {
+    let (r, g, b) = to_triple(rgb);
+    let r = LINEAR[usize::from(r)];
+    let g = LINEAR[usize::from(g)];
+    let b = LINEAR[usize::from(b)];
+    let xyz = [
+        0.412_456_4 * r + 0.357_576_1 * g + 0.180_437_5 * b,
+        0.212_672_9 * r + 0.715_152_2 * g + 0.072_175 * b,
+        0.019_333_9 * r + 0.119_192 * g + 0.950_304_1 * b,
+    ];
+    let f = |idx: usize| {
+        let t = xyz[idx] / WHITE[idx];
+        if t > 216.0 / 24389.0 {
+            cbrt(t)
+        } else {
+            (24389.0 / 27.0 * t + 16.0) / 116.0
+        }
+    };
+    let (fx, fy, fz) = (f(0), f(1), f(2));
+    [116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz)]
+}
+
+/// Converts CIE L*a*b* colour into sRGB colour space.  Colours outside of the
+/// sRGB gamut are clamped.
+pub(crate) fn rgb_from_lab([l, a, b]: [f32; 3]) -> u32 {
+    let fy = (l + 16.0) / 116.0;
+    let finv = |f: f32| {
+        if f > 6.0 / 29.0 {
+            f * f * f
+        } else {
+            (116.0 * f - 16.0) * (27.0 / 24389.0)
+        }
+    };
+    let x = finv(fy + a / 500.0) * WHITE[0];
+    let y = finv(fy) * WHITE[1];
+    let z = finv(fy - b / 200.0) * WHITE[2];
+    let r = 3.240_454_2 * x - 1.537_138_5 * y - 0.498_531_4 * z;
+    let g = -0.969_266 * x + 1.876_010_8 * y + 0.041_556 * z;
+    let b = 0.055_643_4 * x - 0.204_025_9 * y + 1.057_225_2 * z;
+    let encode = |v: f32| u32::from(encode_linear(v));
+    (encode(r) << 16) | (encode(g) << 8) | encode(b)
+}
@@ -0,0 +1,142 @@
+// ansi_colours – true-colour ↔ ANSI terminal palette converter
+// Copyright 2018 by Michał Nazarewicz <mina86@mina86.com>
+//
+// ansi_colours is free software: you can redistribute it and/or modify it
+// under the terms of the GNU Lesser General Public License as published by
+// the Free Software Foundation; either version 3 of the License, or (at
+// your option) any later version.
+//
+// ansi_colours is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Lesser
+// General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with ansi_colours.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Support for colours specified in HSLuv colour space.
+//!
+//! This module is present only if `hsluv` crate feature is enabled.
+
+// Coefficients are copied verbatim from the reference implementation.
+#![allow(clippy::excessive_precision)]
+
+use core::f32::consts::PI;
+
+use crate::AsRGB;
+
+/// A colour specified in [HSLuv](https://www.hsluv.org/) colour space.
+///
+/// HSLuv is a human-friendly alternative to HSL built on top of CIELUV colour
+/// space.  Unlike in HSL, colours with the same lightness appear equally
+/// bright which makes it popular for designing terminal themes.
+///
+/// Hue is given in degrees and values outside of the `[0, 360)` range wrap
+/// around.  Saturation and lightness are in the 0–100 range (as in the
+/// reference implementation) and values outside of it are clamped.
+///
+/// This type is present only if `hsluv` crate feature is enabled.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::{ansi256_from_rgb, AsRGB, Hsluv};
+///
+/// let hsluv = |h, s, l| Hsluv { h, s, l };
+///
+/// assert_eq!(0x000000, hsluv(  0.0,     0.0,   0.0).as_u32());
+/// assert_eq!(0xffffff, hsluv(  0.0,     0.0, 100.0).as_u32());
+/// assert_eq!(0xff0000, hsluv( 12.177, 100.0,  53.237).as_u32());
+/// assert_eq!(0x00ff00, hsluv(127.715, 100.0,  87.737).as_u32());
+/// assert_eq!(0x0000ff, hsluv(265.874, 100.0,  32.301).as_u32());
+///
+/// assert_eq!(67, ansi256_from_rgb(hsluv(250.0, 50.0, 55.0)));
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Hsluv {
+    /// Hue in degrees.
+    pub h: f32,
+    /// Saturation in the 0–100 range.
+    pub s: f32,
+    /// Lightness in the 0–100 range.
+    pub l: f32,
+}
+
+impl AsRGB for Hsluv {
+    fn as_u32(&self) -> u32 {
+        let s = self.s.clamp(0.0, 100.0);
+        let l = self.l.clamp(0.0, 100.0);
+        let hue = self.h * (PI / 180.0);
+        let (sin, cos) = (libm::sinf(hue), libm::cosf(hue));
+        let chroma = if l <= 0.0 || l >= 100.0 {
+            0.0
+        } else {
+            max_chroma(l, sin, cos) / 100.0 * s
+        };
+        let [r, g, b] = rgb_from_luv(l, chroma * cos, chroma * sin);
+        let encode = |v: f32| u32::from(crate::ansi256::encode_srgb(v));
+        (encode(r) << 16) | (encode(g) << 8) | encode(b)
+    }
+}
+
+/// Matrix converting CIE XYZ into linear sRGB.
+const XYZ_TO_RGB: [[f32; 3]; 3] = [
+    [3.240969941904521, -1.537383177570093, -0.498610760293],
+    [-0.96924363628087, 1.87596750150772, 0.041555057407175],
+    [0.055630079696993, -0.20397695888897, 1.056971514242878],
+];
+
+/// Chromaticity coordinates of the D65 white point in CIELUV.
+const REF_U: f32 = 0.19783000664283;
+const REF_V: f32 = 0.46831999493879;
+
+/// CIE constants ε and κ.
+const EPSILON: f32 = 216.0 / 24389.0;
+const KAPPA: f32 = 24389.0 / 27.0;
+
+/// Returns maximum chroma representable in sRGB for colour with given
+/// lightness and hue (given as its sine and cosine).
+fn max_chroma(l: f32, sin: f32, cos: f32) -> f32 {
+    let sub1 = (l + 16.0) * (l + 16.0) * (l + 16.0) / 1560896.0;
+    let sub2 = if sub1 > EPSILON { sub1 } else { l / KAPPA };
+    let mut min = f32::INFINITY;
+    for [m1, m2, m3] in XYZ_TO_RGB.iter().copied() {
+        for t in [0.0, 1.0] {
+            let top1 = (284517.0 * m1 - 94839.0 * m3) * sub2;
+            let top2 =
+                (838422.0 * m3 + 769860.0 * m2 + 731718.0 * m1) * l * sub2
+                    - 769860.0 * t * l;
+            let bottom = (632260.0 * m3 - 126452.0 * m2) * sub2 + 126452.0 * t;
+            // Distance from the origin to the gamut boundary line along the
+            // hue direction.
+            let length = (top2 / bottom) / (sin - top1 / bottom * cos);
+            if length >= 0.0 && length < min {
+                min = length;
+            }
+        }
+    }
+    if min.is_finite() {
+        min
+    } else {
+        0.0
+    }
+}
+
+/// Converts CIELUV colour into linear sRGB.
+fn rgb_from_luv(l: f32, u: f32, v: f32) -> [f32; 3] {
+    if l <= 0.0 {
+        return [0.0; 3];
+    }
+    let var_u = u / (13.0 * l) + REF_U;
+    let var_v = v / (13.0 * l) + REF_V;
+    let y = if l <= 8.0 {
+        l / KAPPA
+    } else {
+        let f = (l + 16.0) / 116.0;
+        f * f * f
+    };
+    let x = -(9.0 * y * var_u) / ((var_u - 4.0) * var_v - var_u * var_v);
+    let z = (9.0 * y - 15.0 * var_v * y - var_v * x) / (3.0 * var_v);
+    XYZ_TO_RGB.map(|[m1, m2, m3]| m1 * x + m2 * y + m3 * z)
+}
@@ -0,0 +1,576 @@
+// ansi_colours – true-colour ↔ ANSI terminal palette converter
+// Copyright 2018 by Michał Nazarewicz <mina86@mina86.com>
+//
+// ansi_colours is free software: you can redistribute it and/or modify it
+// under the terms of the GNU Lesser General Public License as published by
+// the Free Software Foundation; either version 3 of the License, or (at
+// your option) any later version.
+//
+// ansi_colours is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Lesser
+// General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with ansi_colours.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Rendering of images as text.
+//!
+//! [`render`] function draws an [`Image`] with half-block characters: each
+//! character cell shows two pixels stacked vertically, the upper one as the
+//! foreground colour of `▀` character and the lower one as its background
+//! colour.  Colours are set with 256-colour or true-colour escape sequences;
+//! see [`RenderOptions`].  Other [modes](RenderMode) draw more pixels per
+//! character with quadrant blocks (`▚`) or Braille patterns (`⣿`) at the cost
+//! of fewer colours.
+//!
+//! [`quantize_image`] converts an image into indexes of colours in the
+//! 256-colour palette using error diffusion which gives noticeably better
+//! results for photographs than approximating each pixel separately.  With
+//! `rayon` feature, `quantize_image_par` does the same on multiple threads.
+//!
+//! This module is present only if `image` crate feature is enabled.  The
+//! feature doesn’t pull any dependencies; images are given as raw buffers of
+//! pixels which can be obtained from most image crates.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::{Colour, PixelLayout, Sgr};
+
+/// An image given as a buffer of pixels.
+///
+/// The image has `width × height` pixels stored row by row with rows starting
+/// `stride` bytes apart.  By default the rows aren’t padded, i.e. the stride
+/// is width of a row in bytes.  See also
+/// [`ansi256_from_image`](crate::ansi256_from_image).
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::image::Image;
+/// use ansi_colours::PixelLayout;
+///
+/// let data = [255, 0, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255];
+/// let image = Image::new(&data, 2, 2, PixelLayout::Rgb).with_stride(7);
+/// assert_eq!((2, 2), (image.width(), image.height()));
+/// assert_eq!(0x0000ff, image.pixel(1, 0));
+/// assert_eq!(0xffffff, image.pixel(1, 1));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Image<'a> {
+    data: &'a [u8],
+    width: usize,
+    height: usize,
+    stride: usize,
+    layout: PixelLayout,
+}
+
+impl<'a> Image<'a> {
+    /// Constructs an image whose rows aren’t padded.
+    #[inline]
+    pub fn new(
+        data: &'a [u8],
+        width: usize,
+        height: usize,
+        layout: PixelLayout,
+    ) -> Self {
+        let stride = width * layout.bytes_per_pixel();
+        Self {
+            data,
+            width,
+            height,
+            stride,
+            layout,
+        }
+    }
+
+    /// Sets distance between starts of consecutive rows in bytes.
+    #[inline]
+    pub fn with_stride(self, stride: usize) -> Self {
+        Self { stride, ..self }
+    }
+
+    /// Returns width of the image in pixels.
+    #[inline]
+    pub const fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns height of the image in pixels.
+    #[inline]
+    pub const fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns colour of pixel at given coordinates as a 24-bit `0xRRGGBB`
+    /// integer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the coordinates are outside of the image or if the pixel
+    /// lies outside of the buffer.
+    pub fn pixel(&self, x: usize, y: usize) -> u32 {
+        assert!(x < self.width && y < self.height, "pixel out of bounds");
+        let bpp = self.layout.bytes_per_pixel();
+        let offset = y * self.stride + x * bpp;
+        self.layout.rgb(&self.data[offset..offset + bpp])
+    }
+}
+
+/// Characters used to draw images.
+///
+/// Modes differ in how many pixels each character cell covers and how many
+/// colours it can show.  Drawing more pixels per cell gives better spatial
+/// resolution at the cost of colour resolution.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RenderMode {
+    /// Half blocks, e.g. `▀`.  Each cell covers one column and two rows of
+    /// pixels and shows each pixel in its own colour.
+    HalfBlocks,
+    /// Quadrant blocks, e.g. `▚`.  Each cell covers two columns and two rows
+    /// of pixels.  The pixels are split into lighter and darker group and
+    /// each group is drawn in average colour of its pixels.
+    Quadrants,
+    /// Braille patterns, e.g. `⣿`.  Each cell covers two columns and four
+    /// rows of pixels.  Image is drawn with default colours and a dot is
+    /// drawn for each pixel whose luminance is at least the threshold.
+    Braille,
+    /// Like [`Self::Braille`] but luminance error of each pixel is diffused
+    /// onto its neighbours with Floyd–Steinberg algorithm.  This preserves
+    /// shading of the image.
+    DitheredBraille,
+}
+
+impl Default for RenderMode {
+    #[inline]
+    fn default() -> Self {
+        Self::HalfBlocks
+    }
+}
+
+/// Options controlling how [`render`] draws images.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::image::{RenderMode, RenderOptions};
+///
+/// let options = RenderOptions::new();
+/// assert!(!options.true_colour());
+/// assert_eq!(RenderMode::HalfBlocks, options.mode());
+/// assert_eq!(128, options.threshold());
+///
+/// let options = options.with_true_colour(true).with_mode(RenderMode::Braille);
+/// assert!(options.true_colour());
+/// assert_eq!(RenderMode::Braille, options.mode());
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct RenderOptions {
+    true_colour: bool,
+    mode: RenderMode,
+    threshold: u8,
+}
+
+impl RenderOptions {
+    /// Constructs default options which draw images with half blocks using
+    /// colours of the 256-colour palette.
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            true_colour: false,
+            mode: RenderMode::HalfBlocks,
+            threshold: 128,
+        }
+    }
+
+    /// Sets whether colours of pixels are output as they are with true-colour
+    /// escape sequences rather than approximated with colours of the
+    /// 256-colour palette.  Braille modes don’t output any colours and ignore
+    /// this option.
+    #[inline]
+    pub fn with_true_colour(self, true_colour: bool) -> Self {
+        Self {
+            true_colour,
+            ..self
+        }
+    }
+
+    /// Sets characters used to draw the image.
+    #[inline]
+    pub fn with_mode(self, mode: RenderMode) -> Self {
+        Self { mode, ..self }
+    }
+
+    /// Sets luminance from which pixels are drawn as dots in Braille modes.
+    /// Other modes ignore this option.
+    #[inline]
+    pub fn with_threshold(self, threshold: u8) -> Self {
+        Self { threshold, ..self }
+    }
+
+    /// Returns whether colours are output with true-colour escape sequences.
+    #[inline]
+    pub const fn true_colour(&self) -> bool {
+        self.true_colour
+    }
+
+    /// Returns characters used to draw the image.
+    #[inline]
+    pub const fn mode(&self) -> RenderMode {
+        self.mode
+    }
+
+    /// Returns luminance from which pixels are drawn as dots in Braille modes.
+    #[inline]
+    pub const fn threshold(&self) -> u8 {
+        self.threshold
+    }
+
+    /// Returns colour used to draw pixel of given sRGB colour.
+    fn colour(&self, rgb: u32) -> Colour {
+        if self.true_colour {
+            Colour::from(rgb)
+        } else {
+            Colour::Fixed(crate::system::ansi256_from_rgb(rgb))
+        }
+    }
+}
+
+impl Default for RenderOptions {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Draws image as text.
+///
+/// Each line of the output covers as many rows of pixels as a character cell
+/// of the [mode](RenderMode) does and ends with a new line character.  For
+/// example, with the default half blocks the output has `height / 2` lines
+/// (rounded up) of `width` characters.
+///
+/// In modes which use colours, each line ends with an escape sequence which
+/// restores default colours.  Escape sequences are output only when colours
+/// change.  If the colours of a cell are the current colours swapped, the
+/// cell is drawn with inverted character instead, e.g. `▄` rather than `▀`.
+///
+/// If height of the image is odd, lower halves of half blocks in the last
+/// line are left with default background colour.  In quadrant mode, pixels
+/// missing in the last row or column are assumed to repeat the edge of the
+/// image.  In Braille modes, missing pixels are left without dots.
+///
+/// # Panics
+///
+/// Panics if a pixel of the image lies outside of its buffer.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::image::{render, Image, RenderMode, RenderOptions};
+/// use ansi_colours::PixelLayout;
+///
+/// let data = [255, 0, 0,  0, 0, 255,
+///             0, 0, 255,  0, 0, 255];
+/// let image = Image::new(&data, 2, 2, PixelLayout::Rgb);
+///
+/// assert_eq!("\x1b[38;5;196m\x1b[48;5;21m▀\x1b[38;5;21m▀\x1b[39;49m\n",
+///            render(&image, &RenderOptions::new()));
+///
+/// let options = RenderOptions::new().with_mode(RenderMode::Quadrants);
+/// assert_eq!("\x1b[38;5;196m\x1b[48;5;21m▘\x1b[39;49m\n",
+///            render(&image, &options));
+///
+/// let options = RenderOptions::new().with_mode(RenderMode::Braille);
+/// assert_eq!("⠀\n", render(&image, &options));
+/// assert_eq!("⠁\n", render(&image, &options.with_threshold(50)));
+///
+/// let options = RenderOptions::new().with_true_colour(true);
+/// let image = Image::new(&data[..3], 1, 1, PixelLayout::Rgb);
+/// assert_eq!("\x1b[38;2;255;0;0m▀\x1b[39m\n", render(&image, &options));
+/// ```
+pub fn render(image: &Image<'_>, options: &RenderOptions) -> String {
+    let mut out = String::new();
+    match options.mode {
+        RenderMode::HalfBlocks => render_half_blocks(&mut out, image, options),
+        RenderMode::Quadrants => render_quadrants(&mut out, image, options),
+        RenderMode::Braille => {
+            let threshold = u32::from(options.threshold);
+            render_braille(&mut out, image, |x, y| {
+                let rgb = image.pixel(x, y);
+                u32::from(luminance(rgb)) >= threshold
+            })
+        }
+        RenderMode::DitheredBraille => {
+            let dots = dither_dots(image, options.threshold);
+            render_braille(&mut out, image, |x, y| dots[y * image.width + x])
+        }
+    }
+    out
+}
+
+fn render_half_blocks(
+    out: &mut String,
+    image: &Image,
+    options: &RenderOptions,
+) {
+    for y in (0..image.height).step_by(2) {
+        let mut line = Line::new(out);
+        for x in 0..image.width {
+            let upper = options.colour(image.pixel(x, y));
+            let lower = if y + 1 < image.height {
+                Some(options.colour(image.pixel(x, y + 1)))
+            } else {
+                None
+            };
+            line.cell(upper, lower, '▀', '▄');
+        }
+        line.finish();
+    }
+}
+
+/// Quadrant characters indexed by a bit mask of drawn quadrants.  Bits from
+/// the least significant are upper-left, upper-right, lower-left and
+/// lower-right quadrant.
+const QUADRANTS: [char; 16] = [
+    ' ', '▘', '▝', '▀', '▖', '▌', '▞', '▛', '▗', '▚', '▐', '▜', '▄', '▙', '▟',
+    '█',
+];
+
+fn render_quadrants(out: &mut String, image: &Image, options: &RenderOptions) {
+    for y in (0..image.height).step_by(2) {
+        let mut line = Line::new(out);
+        for x in (0..image.width).step_by(2) {
+            let mut pixels = [0; 4];
+            for (i, rgb) in pixels.iter_mut().enumerate() {
+                let px = (x + (i & 1)).min(image.width - 1);
+                let py = (y + (i >> 1)).min(image.height - 1);
+                *rgb = image.pixel(px, py);
+            }
+            // Quadrants lighter than the average are drawn with foreground
+            // colour and the rest with background colour.
+            let lum = pixels.map(luminance);
+            let sum = lum.iter().map(|&l| u32::from(l)).sum::<u32>();
+            let mask = (0..4)
+                .filter(|&i| u32::from(lum[i]) * 4 > sum)
+                .fold(0, |mask, i| mask | (1 << i));
+            if mask == 0 {
+                let colour = options.colour(average(&pixels, 15));
+                line.cell(colour, None, '█', ' ');
+            } else {
+                let fg = options.colour(average(&pixels, mask));
+                let bg = options.colour(average(&pixels, 15 ^ mask));
+                line.cell(fg, Some(bg), QUADRANTS[mask], QUADRANTS[15 ^ mask]);
+            }
+        }
+        line.finish();
+    }
+}
+
+/// Returns average sRGB colour of pixels selected by a bit mask.
+fn average(pixels: &[u32; 4], mask: usize) -> u32 {
+    let count = mask.count_ones();
+    let mut rgb = 0;
+    for shift in [16, 8, 0] {
+        let sum = (0..4)
+            .filter(|&i| mask & (1 << i) != 0)
+            .map(|i| (pixels[i] >> shift) & 0xff)
+            .sum::<u32>();
+        rgb |= ((sum + count / 2) / count) << shift;
+    }
+    rgb
+}
+
+fn render_braille(
+    out: &mut String,
+    image: &Image,
+    dot: impl Fn(usize, usize) -> bool,
+) {
+    // Bits of Braille dots indexed by row and column within the cell.
+    const DOTS: [[u32; 2]; 4] =
+        [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+    for y in (0..image.height).step_by(4) {
+        for x in (0..image.width).step_by(2) {
+            let mut bits = 0;
+            for (dy, row) in DOTS.iter().enumerate() {
+                for (dx, &bit) in row.iter().enumerate() {
+                    let (px, py) = (x + dx, y + dy);
+                    if px < image.width && py < image.height && dot(px, py) {
+                        bits |= bit;
+                    }
+                }
+            }
+            out.push(char::from_u32(0x2800 + bits).unwrap());
+        }
+        out.push('\n');
+    }
+}
+
+/// Decides which pixels are drawn as dots using Floyd–Steinberg error
+/// diffusion of their luminance.  Returns `width × height` flags in row-major
+/// order.  Like [`crate::dither::dither`], rows are processed in serpentine
+/// order.
+fn dither_dots(image: &Image, threshold: u8) -> Vec<bool> {
+    let width = image.width;
+    let mut dots = alloc::vec![false; width * image.height];
+    // Errors are kept in 1/16 units for the current and the next row with an
+    // extra entry on each side.
+    let mut errors = alloc::vec![0i32; 2 * (width + 2)];
+    for y in 0..image.height {
+        let (current, next) = errors.split_at_mut(width + 2);
+        current.copy_from_slice(next);
+        next.fill(0);
+        let ltr = y % 2 == 0;
+        for i in 0..width {
+            let x = if ltr { i } else { width - 1 - i };
+            let (ahead, behind) = if ltr { (x + 2, x) } else { (x, x + 2) };
+            let value = i32::from(luminance(image.pixel(x, y)))
+                + (current[x + 1] + 8).div_euclid(16);
+            let value = value.clamp(0, 255);
+            let dot = value >= i32::from(threshold);
+            dots[y * width + x] = dot;
+            let err = value - if dot { 255 } else { 0 };
+            current[ahead] += err * 7;
+            next[behind] += err * 3;
+            next[x + 1] += err * 5;
+            next[ahead] += err;
+        }
+    }
+    dots
+}
+
+/// Returns luminance of given sRGB colour.
+fn luminance(rgb: u32) -> u8 {
+    let [_, r, g, b] = rgb.to_be_bytes();
+    crate::ansi256::luminance(r, g, b)
+}
+
+/// A line of output which tracks colours set by escape sequences.
+struct Line<'a> {
+    out: &'a mut String,
+    fg: Option<Colour>,
+    bg: Option<Colour>,
+}
+
+impl<'a> Line<'a> {
+    fn new(out: &'a mut String) -> Self {
+        Self {
+            out,
+            fg: None,
+            bg: None,
+        }
+    }
+
+    /// Draws a cell with character `chr` whose foreground is drawn with `fg`
+    /// and background with `bg` colour.  If `bg` is `None`, background is
+    /// left as it is.  If the colours are the current colours swapped, draws
+    /// `inverse` character instead.
+    fn cell(
+        &mut self,
+        fg: Colour,
+        bg: Option<Colour>,
+        chr: char,
+        inverse: char,
+    ) {
+        if bg.is_some() && (self.fg, self.bg) == (bg, Some(fg)) {
+            self.out.push(inverse);
+            return;
+        }
+        if self.fg != Some(fg) {
+            self.fg = Some(fg);
+            write_sgr(self.out, Sgr::fg(fg));
+        }
+        if let Some(bg) = bg.filter(|&bg| self.bg != Some(bg)) {
+            self.bg = Some(bg);
+            write_sgr(self.out, Sgr::bg(bg));
+        }
+        self.out.push(chr);
+    }
+
+    /// Restores default colours and ends the line.
+    fn finish(self) {
+        self.out.push_str(match (self.fg, self.bg) {
+            (Some(_), Some(_)) => "\x1b[39;49m\n",
+            (Some(_), None) => "\x1b[39m\n",
+            (None, Some(_)) => "\x1b[49m\n",
+            (None, None) => "\n",
+        });
+    }
+}
+
+/// Appends escape sequence to the output.
+fn write_sgr(out: &mut String, sgr: Sgr) {
+    out.push_str(sgr.encode(&mut [0; Sgr::MAX_LEN]));
+}
+
+/// Converts image into indexes of colours in 256-colour ANSI palette using
+/// Floyd–Steinberg error diffusion.
+///
+/// Returns `width × height` indexes in row-major order.  Like
+/// [`ansi256_from_rgb_dithered`](crate::ansi256_from_rgb_dithered), rows are
+/// processed in serpentine order and error of approximation of each pixel is
+/// spread onto its neighbours.  To approximate each pixel separately use
+/// [`ansi256_from_image`](crate::ansi256_from_image).
+///
+/// # Panics
+///
+/// Panics if a pixel of the image lies outside of its buffer.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::image::{quantize_image, Image};
+/// use ansi_colours::{ansi256_from_rgb, PixelLayout};
+///
+/// let data = [0x70; 4 * 4 * 3];
+/// let image = Image::new(&data, 4, 4, PixelLayout::Rgb);
+/// let indexes = quantize_image(&image);
+/// assert_eq!(16, indexes.len());
+/// assert_eq!(242, ansi256_from_rgb((0x70, 0x70, 0x70)));
+/// assert!(indexes.contains(&242));
+/// assert!(indexes.iter().any(|&idx| idx != 242));
+/// ```
+pub fn quantize_image(image: &Image<'_>) -> Vec<u8> {
+    let mut output = alloc::vec![0; image.width * image.height];
+    if !output.is_empty() {
+        let pixel = |x, y| image.pixel(x, y);
+        crate::dither::dither(image.width, &mut output, pixel);
+    }
+    output
+}
+
+/// Converts image into indexes of colours in 256-colour ANSI palette using
+/// Floyd–Steinberg error diffusion and multiple threads.
+///
+/// This is a parallel version of [`quantize_image`] which splits the image
+/// into bands of 64 rows dithered independently on threads of
+/// [rayon’s](https://docs.rs/rayon) global thread pool.  Since error isn’t
+/// diffused across borders of the bands, for taller images the result differs
+/// slightly from that of the sequential version.  It doesn’t depend on number
+/// of threads though.
+///
+/// This function is present only if `rayon` crate feature is enabled.
+///
+/// # Panics
+///
+/// Panics if a pixel of the image lies outside of its buffer.
+#[cfg(feature = "rayon")]
+pub fn quantize_image_par(image: &Image<'_>) -> Vec<u8> {
+    use rayon::prelude::*;
+
+    /// Number of rows dithered in a single task.  It’s even so that rows are
+    /// processed in the same direction as in the sequential version.
+    const BAND: usize = 64;
+
+    let mut output = alloc::vec![0; image.width * image.height];
+    if !output.is_empty() {
+        let chunks = output.par_chunks_mut(BAND * image.width);
+        chunks.enumerate().for_each(|(band, output)| {
+            let top = band * BAND;
+            let pixel = |x, y| image.pixel(x, top + y);
+            crate::dither::dither(image.width, output, pixel);
+        });
+    }
+    output
+}
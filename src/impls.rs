@@ -1,6 +1,9 @@
 use crate::*;
 
 /// Representation of an RGB colour as 24-bit `0xRRGGBB` integer.
+///
+/// The most significant byte is ignored.  To handle packed colours with alpha
+/// channel use [`Argb`] or [`Rgba`] types.
 impl AsRGB for u32 {
     fn as_u32(&self) -> u32 { *self }
 }
@@ -20,6 +23,38 @@ impl AsRGB for [u8; 3] {
     fn as_u32(&self) -> u32 { to_u32(self[0], self[1], self[2]) }
 }
 
+/// Representation of an RGB colour as a slice of `[r, g, b, ...]` bytes.
+///
+/// Only the first three bytes are used and any remaining ones (e.g. alpha
+/// channel of an RGBA pixel) are ignored.  This makes it possible to convert
+/// pixels of raw image buffers without copying them into arrays.
+///
+/// # Panics
+///
+/// Panics if the slice has fewer than three elements.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::{ansi256_from_rgb, AsRGB};
+///
+/// let pixels: &[u8] = &[95, 135, 175, 255, 0, 0, 0, 255];
+/// assert_eq!(0x5f87af, pixels.as_u32());
+///
+/// let indexes = pixels.chunks_exact(4).map(ansi256_from_rgb);
+/// assert_eq!(vec![67, 16], indexes.collect::<Vec<_>>());
+/// ```
+impl AsRGB for [u8] {
+    #[inline]
+    fn as_u32(&self) -> u32 {
+        match *self {
+            [r, g, b, ..] => to_u32(r, g, b),
+            _ => panic!("RGB colour needs at least 3 components"),
+        }
+    }
+}
+
+#[allow(clippy::needless_lifetimes)]
 impl<'a, T: AsRGB + ?Sized> AsRGB for &'a T {
     fn as_u32(&self) -> u32 { (*self).as_u32() }
 }
@@ -27,16 +62,36 @@ impl<'a, T: AsRGB + ?Sized> AsRGB for &'a T {
 #[cfg(feature = "rgb")]
 trait Component: Copy {
     fn into_u8(self) -> u8;
+    fn to_ansi256(r: Self, g: Self, b: Self) -> u8;
+    fn grey_to_ansi256(self) -> u8;
 }
 #[cfg(feature = "rgb")]
 impl Component for u8 {
     #[inline(always)]
     fn into_u8(self) -> u8 { self }
+    #[inline(always)]
+    fn to_ansi256(r: u8, g: u8, b: u8) -> u8 {
+        (r, g, b).to_ansi256()
+    }
+    #[inline(always)]
+    fn grey_to_ansi256(self) -> u8 {
+        crate::ansi256_from_grey(self)
+    }
 }
 #[cfg(feature = "rgb")]
 impl Component for u16 {
     #[inline(always)]
-    fn into_u8(self) -> u8 { (self >> 8) as u8 }
+    fn into_u8(self) -> u8 {
+        ansi256::round16(self)
+    }
+    #[inline(always)]
+    fn to_ansi256(r: u16, g: u16, b: u16) -> u8 {
+        ansi256_from_rgb16((r, g, b))
+    }
+    #[inline(always)]
+    fn grey_to_ansi256(self) -> u8 {
+        crate::ansi256_from_grey16(self)
+    }
 }
 
 #[cfg(feature = "rgb")]
@@ -59,17 +114,27 @@ impl<C: Component> AsRGB for rgb::RGB<C> {
     /// assert_eq!( 67, ansi256_from_rgb(rgb::RGB8::new( 95, 135, 175)));
     /// assert_eq!(231, ansi256_from_rgb(rgb::RGB8::new(255, 255, 255)));
     ///
-    /// assert_eq!(0x123456, rgb::RGB16::new(0x12ab, 0x34cd, 0x56ef).as_u32());
+    /// assert_eq!(0x123456, rgb::RGB16::new(0x1212, 0x3434, 0x5656).as_u32());
+    /// assert_eq!(0x133557, rgb::RGB16::new(0x12ab, 0x34cd, 0x56ef).as_u32());
     ///
     /// assert_eq!( 16, ansi256_from_rgb(rgb::RGB16::new(  256,   511,   256)));
     /// assert_eq!( 16, ansi256_from_rgb(rgb::RGB16::new(  128,   256,   512)));
     /// assert_eq!( 67, ansi256_from_rgb(rgb::RGB16::new(24500, 34600, 44800)));
     /// assert_eq!(231, ansi256_from_rgb(rgb::RGB16::new(65535, 65535, 65535)));
     /// ```
+    ///
+    /// 16-bit components are rounded to nearest 8-bit value.  The
+    /// approximation uses [`ansi256_from_rgb16`] which takes the full
+    /// precision into account.
     #[inline(always)]
     fn as_u32(&self) -> u32 {
         to_u32(self.r.into_u8(), self.g.into_u8(), self.b.into_u8())
     }
+
+    #[inline(always)]
+    fn to_ansi256(&self) -> u8 {
+        C::to_ansi256(self.r, self.g, self.b)
+    }
 }
 
 #[cfg(feature = "rgb")]
@@ -90,9 +155,71 @@ impl<C: Component> AsRGB for rgb::alt::Gray<C> {
     ///
     /// assert_eq!(244, ansi256_from_rgb(rgb::alt::Gray::<u8>(128)));
     /// assert_eq!(244, ansi256_from_rgb(rgb::alt::Gray::<u16>(33023)));
+    /// assert_eq!(244, ansi256_from_rgb(rgb::Gray::<u8>(128)));
+    /// ```
+    ///
+    /// For `u16` components this is equivalent to [`ansi256_from_grey16`].
+    #[inline(always)]
+    fn to_ansi256(&self) -> u8 {
+        (**self).grey_to_ansi256()
+    }
+}
+
+#[cfg(feature = "rgb")]
+impl<C: Component, A> AsRGB for rgb::alt::GrayAlpha<C, A> {
+    /// Returns representation of the shade of grey as a 24-bit `0xRRGGBB`
+    /// integer.  The alpha channel is ignored.
+    ///
+    /// This implementation is present only if `rgb` crate feature is enabled.
+    /// Implementation is provided for `u8` and `u16` colour component types.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ansi_colours::AsRGB;
+    /// use rgb::alt::GrayAlpha;
+    ///
+    /// assert_eq!(0x808080, GrayAlpha::<u8>(128, 255).as_u32());
+    /// assert_eq!(0x808080, GrayAlpha::<u8>(128, 0).as_u32());
+    /// assert_eq!(0x808080, GrayAlpha::<u16>(33023, 65535).as_u32());
+    /// ```
+    #[inline(always)]
+    fn as_u32(&self) -> u32 {
+        grey_alpha_value(self) as u32 * 0x010101
+    }
+
+    /// Returns index of a colour in 256-colour ANSI palette approximating given
+    /// shade of grey.  The alpha channel is ignored.
+    ///
+    /// This implementation is present only if `rgb` crate feature is enabled.
+    /// Implementation is provided for `u8` and `u16` colour component types.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ansi_colours::ansi256_from_rgb;
+    /// use rgb::alt::GrayAlpha;
+    ///
+    /// assert_eq!(244, ansi256_from_rgb(GrayAlpha::<u8>(128, 255)));
+    /// assert_eq!(244, ansi256_from_rgb(GrayAlpha::<u8>(128, 0)));
+    /// assert_eq!(244, ansi256_from_rgb(GrayAlpha::<u16>(33023, 65535)));
     /// ```
     #[inline(always)]
-    fn to_ansi256(&self) -> u8 { crate::ansi256_from_grey(self.into_u8()) }
+    fn to_ansi256(&self) -> u8 {
+        crate::ansi256_from_grey(grey_alpha_value(self))
+    }
+}
+
+/// Returns value of the grey component of given `GrayAlpha` colour.
+///
+/// Newer versions of `rgb` crate deprecate access to the tuple field in favour
+/// of `v` field accessible through `Deref` but the latter isn’t available in
+/// older versions of the crate.
+#[cfg(feature = "rgb")]
+#[allow(deprecated)]
+#[inline(always)]
+fn grey_alpha_value<C: Component, A>(colour: &rgb::alt::GrayAlpha<C, A>) -> u8 {
+    colour.0.into_u8()
 }
 
 #[cfg(feature = "rgb")]
@@ -116,17 +243,26 @@ impl<C: Component> AsRGB for rgb::alt::BGR<C> {
     /// assert_eq!( 67, ansi256_from_rgb(BGR8 { r:  95, g: 135, b: 175 }));
     /// assert_eq!(231, ansi256_from_rgb(BGR8 { r: 255, g: 255, b: 255 }));
     ///
-    /// assert_eq!(0x123456, BGR16 { b: 0x56ef, g: 0x34cd, r: 0x12ab }.as_u32());
+    /// let bgr = BGR16 { b: 0x5656, g: 0x3434, r: 0x1212 };
+    /// assert_eq!(0x123456, bgr.as_u32());
+    /// let bgr = BGR16 { b: 0x56ef, g: 0x34cd, r: 0x12ab };
+    /// assert_eq!(0x133557, bgr.as_u32());
     ///
-    /// assert_eq!( 16, ansi256_from_rgb(BGR16 { r:   256, g:   511, b:   256 }));
-    /// assert_eq!( 16, ansi256_from_rgb(BGR16 { r:   128, g:   256, b:   512 }));
-    /// assert_eq!( 67, ansi256_from_rgb(BGR16 { r: 24500, g: 34600, b: 44800 }));
-    /// assert_eq!(231, ansi256_from_rgb(BGR16 { r: 65535, g: 65535, b: 65535 }));
+    /// let approx = |r, g, b| ansi256_from_rgb(BGR16 { r, g, b });
+    /// assert_eq!( 16, approx(  256,   511,   256));
+    /// assert_eq!( 16, approx(  128,   256,   512));
+    /// assert_eq!( 67, approx(24500, 34600, 44800));
+    /// assert_eq!(231, approx(65535, 65535, 65535));
     /// ```
     #[inline(always)]
     fn as_u32(&self) -> u32 {
         to_u32(self.r.into_u8(), self.g.into_u8(), self.b.into_u8())
     }
+
+    #[inline(always)]
+    fn to_ansi256(&self) -> u8 {
+        C::to_ansi256(self.r, self.g, self.b)
+    }
 }
 
 #[cfg(feature = "ansi_term")]
@@ -146,15 +282,15 @@ impl AsRGB for ansi_term::Colour {
     #[inline]
     fn as_u32(&self) -> u32 {
         match *self {
-            Self::Black => ansi256::ANSI_COLOURS[0],
-            Self::Red => ansi256::ANSI_COLOURS[1],
-            Self::Green => ansi256::ANSI_COLOURS[2],
-            Self::Yellow => ansi256::ANSI_COLOURS[3],
-            Self::Blue => ansi256::ANSI_COLOURS[4],
-            Self::Purple => ansi256::ANSI_COLOURS[5],
-            Self::Cyan => ansi256::ANSI_COLOURS[6],
-            Self::White => ansi256::ANSI_COLOURS[7],
-            Self::Fixed(idx) => ansi256::ANSI_COLOURS[usize::from(idx)],
+            Self::Black => system::palette_colour(0),
+            Self::Red => system::palette_colour(1),
+            Self::Green => system::palette_colour(2),
+            Self::Yellow => system::palette_colour(3),
+            Self::Blue => system::palette_colour(4),
+            Self::Purple => system::palette_colour(5),
+            Self::Cyan => system::palette_colour(6),
+            Self::White => system::palette_colour(7),
+            Self::Fixed(idx) => system::palette_colour(idx),
             Self::RGB(r, g, b) => (r, g, b).as_u32(),
         }
     }
@@ -296,6 +432,75 @@ impl super::ColourExt for ansi_term::Colour {
     }
 }
 
+#[cfg(feature = "ansi_term")]
+impl From<ansi_term::Colour> for Colour {
+    /// Converts `ansi_term` colour into crate’s own colour representation.
+    ///
+    /// This implementation is present only if `ansi_term` crate feature is
+    /// enabled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ansi_colours::{Colour, NamedColour};
+    ///
+    /// assert_eq!(Colour::Named(NamedColour::Magenta),
+    ///            Colour::from(ansi_term::Colour::Purple));
+    /// assert_eq!(Colour::Fixed(42),
+    ///            Colour::from(ansi_term::Colour::Fixed(42)));
+    /// assert_eq!(Colour::Rgb(95, 135, 175),
+    ///            Colour::from(ansi_term::Colour::RGB(95, 135, 175)));
+    /// ```
+    fn from(colour: ansi_term::Colour) -> Self {
+        match colour {
+            ansi_term::Colour::Black => Self::Named(NamedColour::Black),
+            ansi_term::Colour::Red => Self::Named(NamedColour::Red),
+            ansi_term::Colour::Green => Self::Named(NamedColour::Green),
+            ansi_term::Colour::Yellow => Self::Named(NamedColour::Yellow),
+            ansi_term::Colour::Blue => Self::Named(NamedColour::Blue),
+            ansi_term::Colour::Purple => Self::Named(NamedColour::Magenta),
+            ansi_term::Colour::Cyan => Self::Named(NamedColour::Cyan),
+            ansi_term::Colour::White => Self::Named(NamedColour::White),
+            ansi_term::Colour::Fixed(idx) => Self::Fixed(idx),
+            ansi_term::Colour::RGB(r, g, b) => Self::Rgb(r, g, b),
+        }
+    }
+}
+
+#[cfg(feature = "ansi_term")]
+impl From<Colour> for ansi_term::Colour {
+    /// Converts crate’s own colour representation into `ansi_term` colour.
+    ///
+    /// This implementation is present only if `ansi_term` crate feature is
+    /// enabled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ansi_colours::{Colour, NamedColour};
+    ///
+    /// assert_eq!(ansi_term::Colour::Purple,
+    ///            Colour::Named(NamedColour::Magenta).into());
+    /// assert_eq!(ansi_term::Colour::Fixed(42), Colour::Fixed(42).into());
+    /// assert_eq!(ansi_term::Colour::RGB(95, 135, 175),
+    ///            Colour::Rgb(95, 135, 175).into());
+    /// ```
+    fn from(colour: Colour) -> Self {
+        match colour {
+            Colour::Named(NamedColour::Black) => Self::Black,
+            Colour::Named(NamedColour::Red) => Self::Red,
+            Colour::Named(NamedColour::Green) => Self::Green,
+            Colour::Named(NamedColour::Yellow) => Self::Yellow,
+            Colour::Named(NamedColour::Blue) => Self::Blue,
+            Colour::Named(NamedColour::Magenta) => Self::Purple,
+            Colour::Named(NamedColour::Cyan) => Self::Cyan,
+            Colour::Named(NamedColour::White) => Self::White,
+            Colour::Fixed(idx) => Self::Fixed(idx),
+            Colour::Rgb(r, g, b) => Self::RGB(r, g, b),
+        }
+    }
+}
+
 #[cfg(feature = "termcolor")]
 impl AsRGB for termcolor::Color {
     /// Returns sRGB colour corresponding to escape code represented by
@@ -313,15 +518,15 @@ impl AsRGB for termcolor::Color {
     #[inline]
     fn as_u32(&self) -> u32 {
         match *self {
-            Self::Black => ansi256::ANSI_COLOURS[0],
-            Self::Blue => ansi256::ANSI_COLOURS[4],
-            Self::Green => ansi256::ANSI_COLOURS[2],
-            Self::Red => ansi256::ANSI_COLOURS[1],
-            Self::Cyan => ansi256::ANSI_COLOURS[6],
-            Self::Magenta => ansi256::ANSI_COLOURS[5],
-            Self::Yellow => ansi256::ANSI_COLOURS[3],
-            Self::White => ansi256::ANSI_COLOURS[7],
-            Self::Ansi256(idx) => ansi256::ANSI_COLOURS[usize::from(idx)],
+            Self::Black => system::palette_colour(0),
+            Self::Blue => system::palette_colour(4),
+            Self::Green => system::palette_colour(2),
+            Self::Red => system::palette_colour(1),
+            Self::Cyan => system::palette_colour(6),
+            Self::Magenta => system::palette_colour(5),
+            Self::Yellow => system::palette_colour(3),
+            Self::White => system::palette_colour(7),
+            Self::Ansi256(idx) => system::palette_colour(idx),
             Self::Rgb(r, g, b) => (r, g, b).as_u32(),
             _ => unreachable!(),
         }
@@ -466,6 +671,76 @@ impl super::ColourExt for termcolor::Color {
     }
 }
 
+#[cfg(feature = "termcolor")]
+impl From<termcolor::Color> for Colour {
+    /// Converts `termcolor` colour into crate’s own colour representation.
+    ///
+    /// This implementation is present only if `termcolor` crate feature is
+    /// enabled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ansi_colours::{Colour, NamedColour};
+    ///
+    /// assert_eq!(Colour::Named(NamedColour::Red),
+    ///            Colour::from(termcolor::Color::Red));
+    /// assert_eq!(Colour::Fixed(42),
+    ///            Colour::from(termcolor::Color::Ansi256(42)));
+    /// assert_eq!(Colour::Rgb(95, 135, 175),
+    ///            Colour::from(termcolor::Color::Rgb(95, 135, 175)));
+    /// ```
+    fn from(colour: termcolor::Color) -> Self {
+        match colour {
+            termcolor::Color::Black => Self::Named(NamedColour::Black),
+            termcolor::Color::Red => Self::Named(NamedColour::Red),
+            termcolor::Color::Green => Self::Named(NamedColour::Green),
+            termcolor::Color::Yellow => Self::Named(NamedColour::Yellow),
+            termcolor::Color::Blue => Self::Named(NamedColour::Blue),
+            termcolor::Color::Magenta => Self::Named(NamedColour::Magenta),
+            termcolor::Color::Cyan => Self::Named(NamedColour::Cyan),
+            termcolor::Color::White => Self::Named(NamedColour::White),
+            termcolor::Color::Ansi256(idx) => Self::Fixed(idx),
+            termcolor::Color::Rgb(r, g, b) => Self::Rgb(r, g, b),
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[cfg(feature = "termcolor")]
+impl From<Colour> for termcolor::Color {
+    /// Converts crate’s own colour representation into `termcolor` colour.
+    ///
+    /// This implementation is present only if `termcolor` crate feature is
+    /// enabled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ansi_colours::{Colour, NamedColour};
+    ///
+    /// assert_eq!(termcolor::Color::Red,
+    ///            Colour::Named(NamedColour::Red).into());
+    /// assert_eq!(termcolor::Color::Ansi256(42), Colour::Fixed(42).into());
+    /// assert_eq!(termcolor::Color::Rgb(95, 135, 175),
+    ///            Colour::Rgb(95, 135, 175).into());
+    /// ```
+    fn from(colour: Colour) -> Self {
+        match colour {
+            Colour::Named(NamedColour::Black) => Self::Black,
+            Colour::Named(NamedColour::Red) => Self::Red,
+            Colour::Named(NamedColour::Green) => Self::Green,
+            Colour::Named(NamedColour::Yellow) => Self::Yellow,
+            Colour::Named(NamedColour::Blue) => Self::Blue,
+            Colour::Named(NamedColour::Magenta) => Self::Magenta,
+            Colour::Named(NamedColour::Cyan) => Self::Cyan,
+            Colour::Named(NamedColour::White) => Self::White,
+            Colour::Fixed(idx) => Self::Ansi256(idx),
+            Colour::Rgb(r, g, b) => Self::Rgb(r, g, b),
+        }
+    }
+}
+
 #[cfg(feature = "anstyle")]
 impl AsRGB for anstyle::RgbColor {
     /// Returns representation of the sRGB colour as a 24-bit `0xRRGGBB`
@@ -620,3 +895,678 @@ impl ColourExt for anstyle::Color {
         })
     }
 }
+
+#[cfg(feature = "anstyle")]
+impl From<anstyle::Color> for Colour {
+    /// Converts `anstyle` colour into crate’s own colour representation.
+    ///
+    /// The eight basic `AnsiColor` colours are converted into `Named` variant
+    /// while their bright counterparts into `Fixed` variant with indexes 8
+    /// through 15.
+    ///
+    /// This implementation is present only if `anstyle` crate feature is
+    /// enabled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ansi_colours::{Colour, NamedColour};
+    /// use anstyle::{Ansi256Color, AnsiColor, Color, RgbColor};
+    ///
+    /// assert_eq!(Colour::Named(NamedColour::Red),
+    ///            Colour::from(Color::Ansi(AnsiColor::Red)));
+    /// assert_eq!(Colour::Fixed(9),
+    ///            Colour::from(Color::Ansi(AnsiColor::BrightRed)));
+    /// assert_eq!(Colour::Fixed(42),
+    ///            Colour::from(Color::Ansi256(Ansi256Color(42))));
+    /// assert_eq!(Colour::Rgb(95, 135, 175),
+    ///            Colour::from(Color::Rgb(RgbColor(95, 135, 175))));
+    /// ```
+    fn from(colour: anstyle::Color) -> Self {
+        match colour {
+            anstyle::Color::Ansi(colour) => Self::from_system(colour as u8),
+            anstyle::Color::Ansi256(colour) => Self::Fixed(colour.0),
+            anstyle::Color::Rgb(anstyle::RgbColor(r, g, b)) => {
+                Self::Rgb(r, g, b)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "anstyle")]
+impl From<Colour> for anstyle::Color {
+    /// Converts crate’s own colour representation into `anstyle` colour.
+    ///
+    /// This implementation is present only if `anstyle` crate feature is
+    /// enabled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ansi_colours::{Colour, NamedColour};
+    /// use anstyle::{Ansi256Color, AnsiColor, Color, RgbColor};
+    ///
+    /// assert_eq!(Color::Ansi(AnsiColor::Red),
+    ///            Colour::Named(NamedColour::Red).into());
+    /// assert_eq!(Color::Ansi256(Ansi256Color(42)), Colour::Fixed(42).into());
+    /// assert_eq!(Color::Rgb(RgbColor(95, 135, 175)),
+    ///            Colour::Rgb(95, 135, 175).into());
+    /// ```
+    fn from(colour: Colour) -> Self {
+        use anstyle::AnsiColor;
+        match colour {
+            Colour::Named(colour) => Self::Ansi(match colour {
+                NamedColour::Black => AnsiColor::Black,
+                NamedColour::Red => AnsiColor::Red,
+                NamedColour::Green => AnsiColor::Green,
+                NamedColour::Yellow => AnsiColor::Yellow,
+                NamedColour::Blue => AnsiColor::Blue,
+                NamedColour::Magenta => AnsiColor::Magenta,
+                NamedColour::Cyan => AnsiColor::Cyan,
+                NamedColour::White => AnsiColor::White,
+            }),
+            Colour::Fixed(idx) => Self::Ansi256(anstyle::Ansi256Color(idx)),
+            Colour::Rgb(r, g, b) => Self::Rgb(anstyle::RgbColor(r, g, b)),
+        }
+    }
+}
+
+#[cfg(feature = "colorgrad")]
+impl AsRGB for colorgrad::Color {
+    /// Returns representation of the sRGB colour as a 24-bit `0xRRGGBB`
+    /// integer.  The alpha channel is ignored.
+    ///
+    /// This implementation is present only if `colorgrad` crate feature is
+    /// enabled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ansi_colours::{AsRGB, ansi256_from_rgb};
+    /// use colorgrad::Color;
+    ///
+    /// assert_eq!(0x123456, Color::from_rgba8(0x12, 0x34, 0x56, 255).as_u32());
+    /// assert_eq!(0x123456, Color::from_rgba8(0x12, 0x34, 0x56, 0).as_u32());
+    ///
+    /// assert_eq!( 16, ansi256_from_rgb(Color::new(0.0, 0.0, 0.0, 1.0)));
+    /// assert_eq!( 67, ansi256_from_rgb(Color::from_rgba8(95, 135, 175, 255)));
+    /// assert_eq!(231, ansi256_from_rgb(Color::new(1.0, 1.0, 1.0, 1.0)));
+    /// ```
+    #[inline]
+    fn as_u32(&self) -> u32 {
+        let [r, g, b, _] = self.to_rgba8();
+        to_u32(r, g, b)
+    }
+}
+
+#[cfg(feature = "syntect")]
+impl AsRGB for syntect::highlighting::Color {
+    /// Returns sRGB colour corresponding to the syntect colour.
+    ///
+    /// Alpha channel is normally ignored.  The exception is colour with alpha
+    /// equal zero which, following convention used by `ansi` themes of
+    /// syntax highlighters such as `bat`, is interpreted as an index in the
+    /// 256-colour ANSI palette stored in the red component.  For such colours,
+    /// returns colour at that index.  See
+    /// [`rgb_from_ansi256`](`rgb_from_ansi256`).
+    ///
+    /// This implementation is present only if `syntect` crate feature is
+    /// enabled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ansi_colours::AsRGB;
+    /// use syntect::highlighting::Color;
+    ///
+    /// let colour = Color { r: 0x12, g: 0x34, b: 0x56, a: 0xff };
+    /// assert_eq!(0x123456, colour.as_u32());
+    /// let colour = Color { r: 0x12, g: 0x34, b: 0x56, a: 0x80 };
+    /// assert_eq!(0x123456, colour.as_u32());
+    /// let colour = Color { r: 67, g: 0, b: 0, a: 0 };
+    /// assert_eq!(0x5f87af, colour.as_u32());
+    /// ```
+    #[inline]
+    fn as_u32(&self) -> u32 {
+        if self.a == 0 {
+            system::palette_colour(self.r)
+        } else {
+            to_u32(self.r, self.g, self.b)
+        }
+    }
+
+    /// Returns index of a colour in 256-colour ANSI palette approximating given
+    /// sRGB colour.
+    ///
+    /// If alpha channel of the colour is zero, the colour is interpreted as an
+    /// index in the palette stored in the red component and that index is
+    /// returned.  Otherwise, alpha channel is ignored and the colour is
+    /// approximated.
+    ///
+    /// This implementation is present only if `syntect` crate feature is
+    /// enabled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ansi_colours::ansi256_from_rgb;
+    /// use syntect::highlighting::Color;
+    ///
+    /// let colour = |r, g, b, a| Color { r, g, b, a };
+    /// assert_eq!( 16, ansi256_from_rgb(colour( 0,   0,   0, 255)));
+    /// assert_eq!( 67, ansi256_from_rgb(colour(95, 135, 175, 255)));
+    /// assert_eq!( 67, ansi256_from_rgb(colour(95, 135, 175,  10)));
+    /// assert_eq!(  1, ansi256_from_rgb(colour( 1,   0,   0,   0)));
+    /// assert_eq!( 42, ansi256_from_rgb(colour(42,   0,   0,   0)));
+    /// ```
+    #[inline]
+    fn to_ansi256(&self) -> u8 {
+        if self.a == 0 {
+            self.r
+        } else {
+            (self.r, self.g, self.b).to_ansi256()
+        }
+    }
+}
+
+#[cfg(feature = "anes")]
+impl AsRGB for anes::Color {
+    /// Returns sRGB colour corresponding to escape code represented by
+    /// [`anes::Color`].
+    ///
+    /// Behaves slightly differently depending on the variant of the enum.
+    /// - For named colour variants (`Black`, `DarkRed` etc. up till `White`),
+    ///   returns corresponding system colour with indexes going from 0 to 15.
+    /// - For `Default` variant, whose actual colour depends on the terminal,
+    ///   returns system colour with index 0.
+    /// - For `Ansi` variant returns colour corresponding to specified index.
+    ///   See [`rgb_from_ansi256`](`rgb_from_ansi256`).
+    /// - For `Rgb` variant converts it to 24-bit `0xRRGGBB` representation.
+    ///
+    /// This implementation is present only if `anes` crate feature is enabled.
+    #[inline]
+    fn as_u32(&self) -> u32 {
+        match *self {
+            Self::Rgb(r, g, b) => (r, g, b).as_u32(),
+            _ => system::palette_colour(self.to_ansi256()),
+        }
+    }
+
+    /// Returns index of a colour in 256-colour ANSI palette approximating given
+    /// sRGB colour.
+    ///
+    /// Behaves slightly differently depending on the variant of the enum.
+    /// - For named colour variants (`Black`, `DarkRed` etc. up till `White`),
+    ///   returns index going from 0 to 15.
+    /// - For `Default` variant returns 0.
+    /// - For `Ansi` variant simply returns index encoded in the variant.
+    /// - Lastly, for `Rgb` variant, approximates the colour and returns index
+    ///   of closest colour in 256-colour palette.
+    ///
+    /// This implementation is present only if `anes` crate feature is enabled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ansi_colours::AsRGB;
+    ///
+    /// assert_eq!(  0, anes::Color::Black.to_ansi256());
+    /// assert_eq!(  7, anes::Color::Gray.to_ansi256());
+    /// assert_eq!( 15, anes::Color::White.to_ansi256());
+    /// assert_eq!( 42, anes::Color::Ansi(42).to_ansi256());
+    /// assert_eq!( 16, anes::Color::Rgb(  0,   0,   0).to_ansi256());
+    /// assert_eq!( 16, anes::Color::Rgb(  1,   1,   1).to_ansi256());
+    /// assert_eq!( 16, anes::Color::Rgb(  0,   1,   2).to_ansi256());
+    /// assert_eq!( 67, anes::Color::Rgb( 95, 135, 175).to_ansi256());
+    /// assert_eq!(231, anes::Color::Rgb(255, 255, 255).to_ansi256());
+    /// ```
+    #[inline]
+    fn to_ansi256(&self) -> u8 {
+        match *self {
+            Self::Default => 0,
+            Self::Black => 0,
+            Self::DarkRed => 1,
+            Self::DarkGreen => 2,
+            Self::DarkYellow => 3,
+            Self::DarkBlue => 4,
+            Self::DarkMagenta => 5,
+            Self::DarkCyan => 6,
+            Self::Gray => 7,
+            Self::DarkGray => 8,
+            Self::Red => 9,
+            Self::Green => 10,
+            Self::Yellow => 11,
+            Self::Blue => 12,
+            Self::Magenta => 13,
+            Self::Cyan => 14,
+            Self::White => 15,
+            Self::Ansi(idx) => idx,
+            Self::Rgb(r, g, b) => (r, g, b).to_ansi256(),
+        }
+    }
+}
+
+#[cfg(feature = "anes")]
+impl super::ColourExt for anes::Color {
+    /// Constructs an `Ansi` colour which approximates given sRGB colour.
+    ///
+    /// This implementation is present only if `anes` crate feature is enabled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ansi_colours::ColourExt;
+    /// use anes::Color;
+    ///
+    /// assert_eq!(Color::Ansi( 16), Color::approx_rgb(  0,   0,   0));
+    /// assert_eq!(Color::Ansi( 16), Color::approx_rgb(  0,   1,   2));
+    /// assert_eq!(Color::Ansi( 67), Color::approx_rgb( 95, 135, 175));
+    /// assert_eq!(Color::Ansi(231), Color::approx_rgb(255, 255, 255));
+    /// ```
+    #[inline]
+    fn approx_rgb(r: u8, g: u8, b: u8) -> Self {
+        Self::Ansi(ansi256_from_rgb((r, g, b)))
+    }
+
+    /// Converts the colour into 256-colour-compatible format.
+    ///
+    /// If the colour represents an RGB colour, converts it into an `Ansi`
+    /// variant using [`ansi256_from_rgb`] function.  Otherwise, returns the
+    /// colour unchanged.
+    ///
+    /// This implementation is present only if `anes` crate feature is enabled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ansi_colours::ColourExt;
+    /// use anes::Color;
+    ///
+    /// assert_eq!(Color::Red,       Color::Red.to_256());
+    /// assert_eq!(Color::Default,   Color::Default.to_256());
+    /// assert_eq!(Color::Ansi( 11), Color::Ansi(11).to_256());
+    /// assert_eq!(Color::Ansi( 16), Color::Rgb(  0,   0,   0).to_256());
+    /// assert_eq!(Color::Ansi( 16), Color::Rgb(  0,   1,   2).to_256());
+    /// assert_eq!(Color::Ansi( 67), Color::Rgb( 95, 135, 175).to_256());
+    /// assert_eq!(Color::Ansi(231), Color::Rgb(255, 255, 255).to_256());
+    /// ```
+    #[inline]
+    fn to_256(&self) -> Self {
+        match *self {
+            Self::Rgb(r, g, b) => Self::approx_rgb(r, g, b),
+            colour => colour,
+        }
+    }
+
+    /// Converts the colour into sRGB.
+    ///
+    /// Named colours (`Black`, `DarkRed` etc. through `White`) are treated
+    /// like `Ansi` colours with indexes 0 through 15 and `Default` is treated
+    /// like `Ansi` colour with index 0.  `Ansi` colours are converted into sRGB
+    /// using [`rgb_from_ansi256`] function.  `Rgb` colours are returned
+    /// unchanged.
+    ///
+    /// This implementation is present only if `anes` crate feature is enabled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ansi_colours::ColourExt;
+    /// use anes::Color;
+    ///
+    /// assert_eq!((  0,   0,   0), Color::Ansi( 16).to_rgb());
+    /// assert_eq!(( 95, 135, 175), Color::Ansi( 67).to_rgb());
+    /// assert_eq!((255, 255, 255), Color::Ansi(231).to_rgb());
+    /// assert_eq!((238, 238, 238), Color::Ansi(255).to_rgb());
+    /// assert_eq!(( 42,  24,   0), Color::Rgb(42, 24, 0).to_rgb());
+    /// ```
+    #[inline]
+    fn to_rgb(&self) -> (u8, u8, u8) {
+        match *self {
+            Self::Rgb(r, g, b) => (r, g, b),
+            _ => rgb_from_ansi256(self.to_ansi256()),
+        }
+    }
+}
+
+#[cfg(feature = "anes")]
+impl core::convert::TryFrom<anes::Color> for Colour {
+    type Error = UnsupportedColourError;
+
+    /// Converts `anes` colour into crate’s own colour representation.
+    ///
+    /// The eight basic colours (`Black`, `DarkRed` etc. through `Gray`) are
+    /// converted into `Named` variant while their bright counterparts
+    /// (`DarkGray`, `Red` etc. through `White`) into `Fixed` variant with
+    /// indexes 8 through 15.  `Default` colour, whose actual value depends on
+    /// the terminal, cannot be represented and results in an error.
+    ///
+    /// This implementation is present only if `anes` crate feature is enabled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ansi_colours::{Colour, NamedColour};
+    /// use core::convert::TryFrom;
+    ///
+    /// assert_eq!(Ok(Colour::Named(NamedColour::Red)),
+    ///            Colour::try_from(anes::Color::DarkRed));
+    /// assert_eq!(Ok(Colour::Fixed(9)), Colour::try_from(anes::Color::Red));
+    /// assert_eq!(Ok(Colour::Fixed(42)),
+    ///            Colour::try_from(anes::Color::Ansi(42)));
+    /// assert_eq!(Ok(Colour::Rgb(95, 135, 175)),
+    ///            Colour::try_from(anes::Color::Rgb(95, 135, 175)));
+    /// assert!(Colour::try_from(anes::Color::Default).is_err());
+    /// ```
+    fn try_from(colour: anes::Color) -> Result<Self, Self::Error> {
+        match colour {
+            anes::Color::Default => Err(UnsupportedColourError),
+            anes::Color::Ansi(idx) => Ok(Self::Fixed(idx)),
+            anes::Color::Rgb(r, g, b) => Ok(Self::Rgb(r, g, b)),
+            colour => Ok(Self::from_system(colour.to_ansi256())),
+        }
+    }
+}
+
+#[cfg(feature = "anes")]
+impl From<Colour> for anes::Color {
+    /// Converts crate’s own colour representation into `anes` colour.
+    ///
+    /// This implementation is present only if `anes` crate feature is enabled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ansi_colours::{Colour, NamedColour};
+    ///
+    /// assert_eq!(anes::Color::DarkRed,
+    ///            Colour::Named(NamedColour::Red).into());
+    /// assert_eq!(anes::Color::Ansi(42), Colour::Fixed(42).into());
+    /// assert_eq!(anes::Color::Rgb(95, 135, 175),
+    ///            Colour::Rgb(95, 135, 175).into());
+    /// ```
+    fn from(colour: Colour) -> Self {
+        match colour {
+            Colour::Named(NamedColour::Black) => Self::Black,
+            Colour::Named(NamedColour::Red) => Self::DarkRed,
+            Colour::Named(NamedColour::Green) => Self::DarkGreen,
+            Colour::Named(NamedColour::Yellow) => Self::DarkYellow,
+            Colour::Named(NamedColour::Blue) => Self::DarkBlue,
+            Colour::Named(NamedColour::Magenta) => Self::DarkMagenta,
+            Colour::Named(NamedColour::Cyan) => Self::DarkCyan,
+            Colour::Named(NamedColour::White) => Self::Gray,
+            Colour::Fixed(idx) => Self::Ansi(idx),
+            Colour::Rgb(r, g, b) => Self::Rgb(r, g, b),
+        }
+    }
+}
+
+#[cfg(feature = "crossterm")]
+impl core::convert::TryFrom<crossterm::style::Color> for Colour {
+    type Error = UnsupportedColourError;
+
+    /// Converts `crossterm` colour into crate’s own colour representation.
+    ///
+    /// The eight basic colours (`Black`, `DarkRed` etc. through `Grey`) are
+    /// converted into `Named` variant while their bright counterparts
+    /// (`DarkGrey`, `Red` etc. through `White`) into `Fixed` variant with
+    /// indexes 8 through 15.  `Reset` colour, whose actual value depends on
+    /// the terminal, cannot be represented and results in an error.
+    ///
+    /// This implementation is present only if `crossterm` crate feature is
+    /// enabled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ansi_colours::{Colour, NamedColour};
+    /// use core::convert::TryFrom;
+    /// use crossterm::style::Color;
+    ///
+    /// assert_eq!(Ok(Colour::Named(NamedColour::Red)),
+    ///            Colour::try_from(Color::DarkRed));
+    /// assert_eq!(Ok(Colour::Fixed(9)), Colour::try_from(Color::Red));
+    /// assert_eq!(Ok(Colour::Fixed(42)),
+    ///            Colour::try_from(Color::AnsiValue(42)));
+    /// assert_eq!(Ok(Colour::Rgb(95, 135, 175)),
+    ///            Colour::try_from(Color::Rgb { r: 95, g: 135, b: 175 }));
+    /// assert!(Colour::try_from(Color::Reset).is_err());
+    /// ```
+    fn try_from(colour: crossterm::style::Color) -> Result<Self, Self::Error> {
+        use crossterm::style::Color;
+        let idx = match colour {
+            Color::Reset => return Err(UnsupportedColourError),
+            Color::AnsiValue(idx) => return Ok(Self::Fixed(idx)),
+            Color::Rgb { r, g, b } => return Ok(Self::Rgb(r, g, b)),
+            Color::Black => 0,
+            Color::DarkRed => 1,
+            Color::DarkGreen => 2,
+            Color::DarkYellow => 3,
+            Color::DarkBlue => 4,
+            Color::DarkMagenta => 5,
+            Color::DarkCyan => 6,
+            Color::Grey => 7,
+            Color::DarkGrey => 8,
+            Color::Red => 9,
+            Color::Green => 10,
+            Color::Yellow => 11,
+            Color::Blue => 12,
+            Color::Magenta => 13,
+            Color::Cyan => 14,
+            Color::White => 15,
+        };
+        Ok(Self::from_system(idx))
+    }
+}
+
+#[cfg(feature = "crossterm")]
+impl From<Colour> for crossterm::style::Color {
+    /// Converts crate’s own colour representation into `crossterm` colour.
+    ///
+    /// This implementation is present only if `crossterm` crate feature is
+    /// enabled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ansi_colours::{Colour, NamedColour};
+    /// use crossterm::style::Color;
+    ///
+    /// assert_eq!(Color::DarkRed, Colour::Named(NamedColour::Red).into());
+    /// assert_eq!(Color::AnsiValue(42), Colour::Fixed(42).into());
+    /// assert_eq!(Color::Rgb { r: 95, g: 135, b: 175 },
+    ///            Colour::Rgb(95, 135, 175).into());
+    /// ```
+    fn from(colour: Colour) -> Self {
+        match colour {
+            Colour::Named(NamedColour::Black) => Self::Black,
+            Colour::Named(NamedColour::Red) => Self::DarkRed,
+            Colour::Named(NamedColour::Green) => Self::DarkGreen,
+            Colour::Named(NamedColour::Yellow) => Self::DarkYellow,
+            Colour::Named(NamedColour::Blue) => Self::DarkBlue,
+            Colour::Named(NamedColour::Magenta) => Self::DarkMagenta,
+            Colour::Named(NamedColour::Cyan) => Self::DarkCyan,
+            Colour::Named(NamedColour::White) => Self::Grey,
+            Colour::Fixed(idx) => Self::AnsiValue(idx),
+            Colour::Rgb(r, g, b) => Self::Rgb { r, g, b },
+        }
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+use arbitrary::{Arbitrary, Unstructured};
+
+#[cfg(feature = "arbitrary")]
+impl<'a> Arbitrary<'a> for Rgb565 {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        u.arbitrary().map(Self)
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> Arbitrary<'a> for Rgb555 {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        u.arbitrary().map(Self)
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> Arbitrary<'a> for Cmyk {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        let [c, m, y, k] = u.arbitrary()?;
+        Ok(Self { c, m, y, k })
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> Arbitrary<'a> for YCbCrStandard {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        u.choose(&[Self::Bt601, Self::Bt709, Self::Jpeg]).copied()
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> Arbitrary<'a> for YCbCr {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        let [y, cb, cr] = u.arbitrary()?;
+        Ok(Self {
+            y,
+            cb,
+            cr,
+            standard: u.arbitrary()?,
+        })
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> Arbitrary<'a> for AlphaPolicy {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(u.arbitrary::<Option<u32>>()?
+            .map_or(Self::Ignore, Self::Over))
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> Arbitrary<'a> for Argb {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(Self::new(u.arbitrary()?, u.arbitrary()?))
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> Arbitrary<'a> for Rgba {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(Self::new(u.arbitrary()?, u.arbitrary()?))
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> Arbitrary<'a> for NamedColour {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        u.choose(&Self::ALL).copied()
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> Arbitrary<'a> for Colour {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(match u.int_in_range(0..=2)? {
+            0 => Self::Named(u.arbitrary()?),
+            1 => Self::Fixed(u.arbitrary()?),
+            _ => Self::Rgb(u.arbitrary()?, u.arbitrary()?, u.arbitrary()?),
+        })
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> Arbitrary<'a> for PaletteIndex {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        u.arbitrary().map(Self)
+    }
+}
+
+#[cfg(all(feature = "arbitrary", feature = "windows"))]
+impl<'a> Arbitrary<'a> for windows::ColorRef {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        u.arbitrary().map(Self)
+    }
+}
+
+/// Implements `proptest::arbitrary::Arbitrary` for given type using given
+/// strategy.
+#[cfg(feature = "proptest")]
+macro_rules! impl_proptest_arbitrary {
+    ($ty:ty, $strategy:expr) => {
+        impl proptest::arbitrary::Arbitrary for $ty {
+            type Parameters = ();
+            type Strategy = proptest::strategy::BoxedStrategy<Self>;
+
+            fn arbitrary_with((): ()) -> Self::Strategy {
+                use proptest::prelude::*;
+                $strategy.boxed()
+            }
+        }
+    };
+}
+
+#[cfg(feature = "proptest")]
+impl_proptest_arbitrary!(Rgb565, any::<u16>().prop_map(Self));
+#[cfg(feature = "proptest")]
+impl_proptest_arbitrary!(Rgb555, any::<u16>().prop_map(Self));
+#[cfg(feature = "proptest")]
+impl_proptest_arbitrary!(
+    Cmyk,
+    any::<[u8; 4]>().prop_map(|[c, m, y, k]| Self { c, m, y, k })
+);
+#[cfg(feature = "proptest")]
+impl_proptest_arbitrary!(
+    YCbCrStandard,
+    prop_oneof![Just(Self::Bt601), Just(Self::Bt709), Just(Self::Jpeg)]
+);
+#[cfg(feature = "proptest")]
+impl_proptest_arbitrary!(
+    YCbCr,
+    any::<([u8; 3], YCbCrStandard)>().prop_map(|([y, cb, cr], standard)| {
+        Self {
+            y,
+            cb,
+            cr,
+            standard,
+        }
+    })
+);
+#[cfg(feature = "proptest")]
+impl_proptest_arbitrary!(
+    AlphaPolicy,
+    any::<Option<u32>>().prop_map(|bg| bg.map_or(Self::Ignore, Self::Over))
+);
+#[cfg(feature = "proptest")]
+impl_proptest_arbitrary!(
+    Argb,
+    any::<(u32, AlphaPolicy)>()
+        .prop_map(|(value, policy)| Self::new(value, policy))
+);
+#[cfg(feature = "proptest")]
+impl_proptest_arbitrary!(
+    Rgba,
+    any::<(u32, AlphaPolicy)>()
+        .prop_map(|(value, policy)| Self::new(value, policy))
+);
+#[cfg(feature = "proptest")]
+impl_proptest_arbitrary!(
+    NamedColour,
+    proptest::sample::select(&NamedColour::ALL[..])
+);
+#[cfg(feature = "proptest")]
+impl_proptest_arbitrary!(
+    Colour,
+    prop_oneof![
+        any::<NamedColour>().prop_map(Self::Named),
+        any::<u8>().prop_map(Self::Fixed),
+        any::<(u8, u8, u8)>().prop_map(|(r, g, b)| Self::Rgb(r, g, b)),
+    ]
+);
+#[cfg(feature = "proptest")]
+impl_proptest_arbitrary!(PaletteIndex, any::<u8>().prop_map(Self));
+#[cfg(all(feature = "proptest", feature = "windows"))]
+impl_proptest_arbitrary!(windows::ColorRef, any::<u32>().prop_map(Self));
@@ -31,15 +31,155 @@
 //! feature (enabled by default).  It adds support for the `RGB` type from
 //! [`rgb` crate](https://crates.io/crates/rgb).  Specifically, `RGB8`
 //! (a.k.a. `RGB<u8>`) as well as `RGB16` (a.k.a. `RGB<u16>`) types are
-//! supported.
+//! supported.  So are `BGR`, `Gray` and `GrayAlpha` types with the latter two
+//! using the faster greyscale approximation.
+//! Slices of `RGB8` pixels can be converted in batches without copying with
+//! `ansi256_from_rgb8_many` function.
 //!
-//! Furthermore, `ansi_term` and `termcolor` features are available.  They
-//! add support for `Colour` type from [`ansi_term`
+//! Furthermore, `anes`, `ansi_term` and `termcolor` features are available.
+//! They add support for `Color` type from [`anes`
+//! crate](https://crates.io/crates/anes), `Colour` type from [`ansi_term`
 //! crate](https://crates.io/crates/ansi_term) and `Color` type from
 //! [`termcolor` crate](https://crates.io/crates/termcolor) respectively.
 //! This includes support for calling `ansi256_from_rgb` with arguments of
 //! those types and implementation of `ColourExt` trait which extends the
 //! types with additional conversion methods.
+//! Applications which don’t want to depend on those crates can use `Colour`
+//! type defined by this crate which offers the same conversions.
+//!
+//! The `crossterm` feature adds conversions between `Colour` and `Color` type
+//! from [`crossterm` crate](https://crates.io/crates/crossterm).
+//!
+//! The `simd` feature makes `ansi256_from_rgb_many` batch conversion use
+//! SIMD instructions on x86 and x86-64 processors which support SSE4.1.  The
+//! feature doesn’t pull any dependencies.
+//!
+//! Image buffers whose rows are padded or whose pixels are stored in BGR or
+//! RGBA order can be converted directly with `ansi256_from_image` function.
+//! The `image` feature adds `image` module which draws such images as text
+//! using half-block, quadrant-block or Braille characters and quantizes them
+//! with error diffusion.  The feature doesn’t pull any dependencies.
+//!
+//! The `rayon` feature adds `ansi256_from_rgb_many_par` and
+//! `ansi256_from_image_par` functions (and `image::quantize_image_par` if
+//! `image` feature is enabled) which convert colours in parallel using
+//! [`rayon` crate](https://crates.io/crates/rayon).
+//!
+//! With the `alloc` feature, `Lut3d` type offers a precomputed table of
+//! approximations which trades small loss of accuracy for faster batch
+//! conversion without the cost of a table covering all sRGB colours.
+//!
+//! The `cache` feature adds `Cache` type which remembers recent
+//! approximations in a fixed-size hash table.  It speeds up workloads which
+//! convert a handful of distinct colours many times.  The feature doesn’t
+//! pull any dependencies and doesn’t need to allocate memory.
+//!
+//! Tables embedded in the crate are kept small: colours of the palette other
+//! than the 16 system colours are calculated, approximations of greys are
+//! stored run-length encoded and lightness of colours is packed into seven
+//! bits per value.  The `tableless` feature additionally replaces the table
+//! of approximations of greys with arithmetic.  This further reduces size of
+//! the binary, which matters on embedded targets, at the cost of slightly
+//! slower conversion.  Functions working in linear light (such as
+//! `contrast_ratio`) still use a table of linear intensities.
+//!
+//! The `windows` feature adds `windows` module with conversions between
+//! Windows `COLORREF` values, legacy console colours and the ANSI palette.
+//! The feature doesn’t pull any dependencies.
+//!
+//! The `downgrade` feature adds `downgrade` module which rewrites true-colour
+//! escape sequences embedded in text into their 256-colour approximations.
+//! The rewriting is done by an incremental state machine which doesn’t
+//! allocate memory and thus the feature doesn’t pull any dependencies.  With
+//! `detect` feature also enabled, the rewriting can target colour depth
+//! detected for the terminal.  The `tokio` feature additionally adds an
+//! adapter for asynchronous writers from [`tokio`
+//! crate](https://crates.io/crates/tokio).  With `anstyle-parse` feature,
+//! escape sequences can alternatively be recognised with the parser from
+//! [`anstyle-parse` crate](https://crates.io/crates/anstyle-parse).
+//!
+//! The `colorgrad` feature adds support for `Color` type from [`colorgrad`
+//! crate](https://crates.io/crates/colorgrad) as well as
+//! `ansi256_from_gradient` function which quantises a whole gradient into
+//! a sequence of palette indexes.
+//!
+//! The `syntect` feature adds support for `Color` type from [`syntect`
+//! crate](https://crates.io/crates/syntect) used by syntax highlighting
+//! themes.
+//!
+//! The `arbitrary` and `proptest` features implement `Arbitrary` traits from
+//! [`arbitrary`](https://crates.io/crates/arbitrary) and
+//! [`proptest`](https://crates.io/crates/proptest) crates respectively for
+//! colour types defined by this crate so that they can be generated in fuzz
+//! and property tests.
+//!
+//! The `bytemuck` and `zerocopy` features implement marker traits from
+//! [`bytemuck`](https://crates.io/crates/bytemuck) and
+//! [`zerocopy`](https://crates.io/crates/zerocopy) crates for plain colour
+//! types (such as `Rgb565`) so that buffers can be cast without copying.
+//!
+//! The `defmt` feature implements `Format` trait from [`defmt`
+//! crate](https://crates.io/crates/defmt) for colour types defined by this
+//! crate so that they can be logged by embedded firmware.
+//!
+//! The `python` feature adds `python` module with Python bindings built using
+//! [PyO3](https://pyo3.rs/).
+//!
+//! The `wasm` feature adds `wasm` module which exports the conversion
+//! functions to JavaScript using
+//! [wasm-bindgen](https://crates.io/crates/wasm-bindgen).
+//!
+//! The `uniffi` feature adds `uniffi_bindings` module which exports the
+//! conversion functions through [UniFFI](https://mozilla.github.io/uniffi-rs/)
+//! so that Swift and Kotlin bindings can be generated.
+//!
+//! The `ciede2000` feature adds `ansi256_from_rgb_exact` function which
+//! finds the colour in the palette with the smallest ΔE*₀₀ difference.  It
+//! uses [`libm` crate](https://crates.io/crates/libm) for floating point
+//! arithmetic.
+//!
+//! The `oklab` feature adds `ansi256_from_rgb_oklab` function which finds the
+//! colour in the palette closest to given colour in [OKLab colour
+//! space](https://bottosson.github.io/posts/oklab/) and `gradient_oklch`
+//! function which interpolates between colours in OKLCH colour space.  Like
+//! `ciede2000`, it uses `libm` crate for floating point arithmetic.
+//!
+//! The `hsluv` feature adds `Hsluv` type and `ansi256_from_hsluv` function
+//! which accept colours specified in [HSLuv colour
+//! space](https://www.hsluv.org/).  It uses `libm` crate for floating point
+//! arithmetic.
+//!
+//! Functions which search through whole palette can be parameterised by
+//! a distance metric implementing `DistanceMetric` trait.  Some metrics are
+//! available only with `ciede2000` or `oklab` feature enabled.
+//!
+//! Values of the system colours assumed by the crate can be changed at run
+//! time with `set_system_colours` function on targets which support 32-bit
+//! atomic operations.
+//!
+//! The `names` feature adds `names` module which maps X11 and CSS colour
+//! names (such as `rebeccapurple` or `dodgerblue`) to sRGB colours and indexes
+//! in the palette.  The feature doesn’t pull any dependencies.
+//!
+//! The `detect` feature adds `detect` module which determines colour depth
+//! supported by the terminal based on environment variables and terminfo
+//! database.
+//!
+//! The `terminal` feature adds `terminal` module which queries terminal for
+//! its actual palette using OSC 4 escape sequences.
+//!
+//! To output colours, [`Sgr`] type writes escape sequences setting colour of
+//! the text without allocating memory.  The `paint` feature adds `Painted`
+//! type which displays a value in colour converted to one supported by the
+//! terminal and `Swatch` type which displays a colour with its index and
+//! sRGB value.  Together with the `alloc` feature it also adds
+//! `palette_chart` function which renders the whole palette.  The feature
+//! doesn’t pull any dependencies.
+//!
+//! The crate is `no_std`.  Functionality which needs to allocate memory is
+//! available only if the `alloc` feature is enabled and functionality which
+//! needs the standard library is available only if the `std` feature is
+//! enabled.
 //!
 //! ## Usage
 //!
@@ -73,10 +213,132 @@
 
 #![no_std]
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(any(feature = "python", feature = "std", feature = "uniffi"))]
+extern crate std;
+
 mod ansi256;
+mod ansi88;
+#[cfg(feature = "cache")]
+mod cache;
+mod candidates;
+#[cfg(feature = "ciede2000")]
+mod ciede2000;
+mod colour;
+mod converter;
+mod cube;
+mod depth;
+#[cfg(feature = "detect")]
+pub mod detect;
+#[cfg(feature = "alloc")]
+mod dither;
+#[cfg(feature = "downgrade")]
+pub mod downgrade;
+mod formats;
+mod gradient;
+#[cfg(feature = "hsluv")]
+mod hsluv;
+#[cfg(feature = "image")]
+pub mod image;
 mod impls;
+#[cfg(feature = "alloc")]
+mod lut;
+pub mod metric;
+#[cfg(feature = "names")]
+pub mod names;
+#[cfg(feature = "oklab")]
+mod oklab;
+#[cfg(feature = "paint")]
+mod paint;
+mod palette;
+#[cfg(feature = "python")]
+pub mod python;
+mod sgr;
+#[cfg(feature = "simd")]
+mod simd;
+mod system;
+#[cfg(feature = "terminal")]
+pub mod terminal;
 #[cfg(test)]
 mod test;
+#[cfg(feature = "uniffi")]
+pub mod uniffi_bindings;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "windows")]
+pub mod windows;
+
+#[cfg(feature = "cache")]
+pub use cache::Cache;
+pub use candidates::Candidates;
+pub use colour::{
+    BrightPolicy, Colour, NamedColour, PaletteIndex, ParseColourError,
+    UnsupportedColourError,
+};
+pub use converter::{Converter, GreyPolicy, Metric};
+pub use cube::Channel;
+pub use depth::ColourDepth;
+pub use formats::{
+    AlphaPolicy, Argb, Background, Cmyk, Hsl, Hsv, Kelvin, LinearRgb,
+    PixelLayout, Rgb555, Rgb565, Rgba, YCbCr, YCbCrStandard,
+};
+pub use gradient::Gradient;
+#[cfg(feature = "hsluv")]
+pub use hsluv::Hsluv;
+#[cfg(feature = "alloc")]
+pub use lut::Lut3d;
+pub use metric::DistanceMetric;
+#[cfg(all(feature = "paint", feature = "alloc"))]
+pub use paint::palette_chart;
+#[cfg(feature = "paint")]
+pub use paint::{Painted, Swatch};
+pub use palette::Palette;
+pub use sgr::{Layer, Sgr};
+#[cfg(target_has_atomic = "32")]
+pub use system::{
+    reset_system_colours, set_system_colours,
+    set_system_colours_in_approximation,
+};
+pub use system::{system_colours, SystemPreset};
+
+#[cfg(feature = "uniffi")]
+uniffi::setup_scaffolding!();
+
+/// The 256-colour ANSI palette.
+///
+/// Each entry holds red, green and blue components of the colour at
+/// corresponding index.  The table can be used in constant expressions, e.g.
+/// to embed the palette (or a transformation of it) in a binary without
+/// calling [`rgb_from_ansi256`] at run time.
+///
+/// The system colours (i.e. the first 16 entries) have default XTerm values.
+/// Unlike [`rgb_from_ansi256`], the table isn’t affected by
+/// [`set_system_colours`].
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::ANSI_PALETTE;
+///
+/// assert_eq!([  0,   0,   0], ANSI_PALETTE[ 16]);
+/// assert_eq!([ 95, 135, 175], ANSI_PALETTE[ 67]);
+/// assert_eq!([238, 238, 238], ANSI_PALETTE[255]);
+///
+/// // Constant table of luma of all palette colours.
+/// const LUMA: [u8; 256] = {
+///     let mut luma = [0; 256];
+///     let mut idx = 0;
+///     while idx < 256 {
+///         let [r, g, b] = ANSI_PALETTE[idx];
+///         luma[idx] = ((r as u32 * 2 + g as u32 * 5 + b as u32) / 8) as u8;
+///         idx += 1;
+///     }
+///     luma
+/// };
+/// assert_eq!(128, LUMA[244]);
+/// ```
+pub const ANSI_PALETTE: [[u8; 3]; 256] = ansi256::PALETTE;
 
 /// Returns sRGB colour corresponding to the index in the 256-colour ANSI
 /// palette.
@@ -84,7 +346,8 @@ mod test;
 /// The first 16 colours (so-called system colours) are not standardised and
 /// terminal emulators often allow them to be customised.  Because of this,
 /// their value should not be relied upon.  For system colours, this function
-/// returns default colours used by XTerm.
+/// returns default colours used by XTerm unless different values were set with
+/// [`set_system_colours`].
 ///
 /// Remaining 240 colours consist of a 6×6×6 colour cube and a 24-step greyscale
 /// ramp.  Those are standardised and thus should be the same on every terminal
@@ -115,15 +378,184 @@ assert_eq!(244, ansi_colours::ansi256_from_rgb(grey));
 /// ```
 #[inline]
 pub fn rgb_from_ansi256(idx: u8) -> (u8, u8, u8) {
-    let rgb = ansi256::ANSI_COLOURS[idx as usize];
+    let rgb = system::palette_colour(idx);
     ((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8)
 }
 
+/// Returns iterator over all entries of the 256-colour ANSI palette.
+///
+/// The iterator yields pairs of index and sRGB colour at that index, in order
+/// of the indexes.  Values of the system colours are captured when the
+/// function is called and take into account values set with
+/// [`set_system_colours`].  This is useful for building colour pickers,
+/// charts and test harnesses.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::{palette, rgb_from_ansi256};
+///
+/// assert_eq!(256, palette().len());
+/// for (idx, rgb) in palette() {
+///     assert_eq!(rgb_from_ansi256(idx), rgb);
+/// }
+///
+/// let greys = palette().filter(|(_, (r, g, b))| r == g && g == b).count();
+/// assert_eq!(34, greys);
+/// assert_eq!(Some((255, (238, 238, 238))), palette().next_back());
+/// ```
+pub fn palette() -> impl DoubleEndedIterator<Item = (u8, (u8, u8, u8))>
+       + ExactSizeIterator
+       + core::iter::FusedIterator
+       + Clone {
+    let system = system::snapshot();
+    (0..=255u8).map(move |idx| {
+        let rgb = match system.get(usize::from(idx)) {
+            Some(&rgb) => rgb,
+            None => ansi256::colour(idx),
+        };
+        (idx, ansi256::to_triple(rgb))
+    })
+}
+
+/// Returns perceptual luminance of colour at given index in the ANSI palette.
+///
+/// The perceptual luminance is expressed as CIE lightness (L*) in the range
+/// from 0 (black) to 100 (white).  Unlike relative luminance (which
+/// [`contrast_ratio`] uses), it’s perceptually uniform, i.e. colours whose
+/// lightness differs by the same amount appear equally different in
+/// brightness.  This makes it suitable for ordering or thresholding indexed
+/// colours (e.g. to decide whether a colour is dark or light).
+///
+/// The value is read from a precomputed table.  For system colours which have
+/// been changed with [`set_system_colours`], the value is calculated.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::luminance_from_ansi256;
+///
+/// assert_eq!(  0, luminance_from_ansi256( 16));
+/// assert_eq!( 55, luminance_from_ansi256( 67));
+/// assert_eq!( 53, luminance_from_ansi256(196));
+/// assert_eq!( 88, luminance_from_ansi256( 46));
+/// assert_eq!(100, luminance_from_ansi256(231));
+///
+/// // Sorting palette by lightness.
+/// let mut colours = [21, 196, 46, 231, 16];
+/// colours.sort_by_key(|&idx| luminance_from_ansi256(idx));
+/// assert_eq!([16, 21, 196, 46, 231], colours);
+/// ```
+#[inline]
+pub fn luminance_from_ansi256(idx: u8) -> u8 {
+    if idx < 16 && system::overridden() {
+        ansi256::lightness(system::colour(idx))
+    } else {
+        ansi256::palette_lightness(idx)
+    }
+}
+
+/// Returns whether given colour is dark, i.e. whether its perceived lightness
+/// is below 50.
+///
+/// The colour may be given as an sRGB colour or as an index in the palette.
+/// Lightness is CIE L* value as returned by [`luminance_from_ansi256`].  See
+/// [`is_dark_with_threshold`] for version with configurable threshold.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::is_dark;
+///
+/// assert!( is_dark(16u8));
+/// assert!( is_dark(21u8));
+/// assert!(!is_dark(67u8));
+/// assert!(!is_dark(231u8));
+/// assert!( is_dark(0x303030u32));
+/// assert!(!is_dark((95u8, 135, 175)));
+/// ```
+#[inline]
+pub fn is_dark<C: Into<Colour>>(colour: C) -> bool {
+    is_dark_with_threshold(colour, 50)
+}
+
+/// Returns whether given colour is light, i.e. whether its perceived lightness
+/// is at least 50.
+///
+/// This is the negation of [`is_dark`].  See [`is_light_with_threshold`] for
+/// version with configurable threshold.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::is_light;
+///
+/// assert!(!is_light(16u8));
+/// assert!( is_light(226u8));
+/// assert!( is_light(0xc0c0c0u32));
+/// ```
+#[inline]
+pub fn is_light<C: Into<Colour>>(colour: C) -> bool {
+    !is_dark(colour)
+}
+
+/// Returns whether perceived lightness of given colour is below given
+/// threshold.
+///
+/// Lightness is CIE L* value in the range from 0 (black) to 100 (white) as
+/// returned by [`luminance_from_ansi256`].  For indexes in the palette the
+/// value is read from a precomputed table.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::is_dark_with_threshold;
+///
+/// assert!( is_dark_with_threshold(67u8, 60));
+/// assert!(!is_dark_with_threshold(67u8, 50));
+/// assert!(!is_dark_with_threshold(16u8,  0));
+/// assert!( is_dark_with_threshold(231u8, 101));
+/// ```
+pub fn is_dark_with_threshold<C: Into<Colour>>(
+    colour: C,
+    threshold: u8,
+) -> bool {
+    let lightness = match colour.into() {
+        Colour::Named(colour) => luminance_from_ansi256(colour.index()),
+        Colour::Fixed(idx) => luminance_from_ansi256(idx),
+        colour => ansi256::lightness(colour.rgb_u32()),
+    };
+    lightness < threshold
+}
+
+/// Returns whether perceived lightness of given colour is at least given
+/// threshold.
+///
+/// This is the negation of [`is_dark_with_threshold`].
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::is_light_with_threshold;
+///
+/// assert!(!is_light_with_threshold(67u8, 60));
+/// assert!( is_light_with_threshold(67u8, 50));
+/// ```
+#[inline]
+pub fn is_light_with_threshold<C: Into<Colour>>(
+    colour: C,
+    threshold: u8,
+) -> bool {
+    !is_dark_with_threshold(colour, threshold)
+}
+
 /// Returns index of a colour in 256-colour ANSI palette approximating given
 /// sRGB colour.
 ///
 /// Because the first 16 colours of the palette are not standardised and usually
-/// user-configurable, the function usually ignores them.
+/// user-configurable, the function usually ignores them.  If actual values of
+/// those colours are known, use [`ansi256_from_rgb_with_system`] to take them
+/// into account.
 ///
 /// The first argument uses [`AsRGB`] trait so that the function can be called in
 /// multiple ways using different representations of RGB colours such as
@@ -157,57 +589,1656 @@ assert_eq!(244, ansi_colours::ansi256_from_rgb(grey));
 pub fn ansi256_from_rgb<C: AsRGB>(rgb: C) -> u8 { rgb.to_ansi256() }
 
 /// Returns index of a colour in 256-colour ANSI palette approximating given
-/// shade of grey.
+/// sRGB colour given as a `0xRRGGBB` integer.  The most significant byte is
+/// ignored.
 ///
-/// This gives the same results as `ansi256_from_rgb((component, component,
-/// component))` but is faster.  Provided that the `rgb` crate feature is
-/// enabled, it is equivalent (in behaviour and performance) to
-/// `ansi256_from_rgb(rgb::alt::Grey(component))`.
+/// This is equivalent to [`ansi256_from_rgb`] called with an integer but
+/// isn’t generic.  It’s meant for tight loops over buffers of packed pixels
+/// and for places where a function pointer is needed.  The colour stays packed
+/// for the whole conversion: components are read with shifts and masks when
+/// needed and candidate palette entries are compared as packed integers.
 ///
 /// # Examples
 ///
+/// ```
+/// use ansi_colours::{ansi256_from_rgb, ansi256_from_u32};
+///
+/// assert_eq!( 16, ansi256_from_u32(0x000000));
+/// assert_eq!( 67, ansi256_from_u32(0x5f87af));
+/// assert_eq!(231, ansi256_from_u32(0xffffff));
+/// assert_eq!(231, ansi256_from_u32(0x12ffffff));
 ///
+/// let pixels = [0x000000, 0x5f87af, 0xffffff];
+/// let indexes = pixels.iter().copied().map(ansi256_from_u32);
+/// assert!(indexes.eq(pixels.iter().map(ansi256_from_rgb)));
 /// ```
-/// assert_eq!( 16, ansi_colours::ansi256_from_grey(0));
-/// assert_eq!( 16, ansi_colours::ansi256_from_grey(1));
-/// assert_eq!(231, ansi_colours::ansi256_from_grey(255));
-#[cfg_attr(
-    feature = "rgb",
-    doc = r#"
+#[inline]
+pub fn ansi256_from_u32(rgb: u32) -> u8 {
+    system::ansi256_from_rgb(rgb)
+}
 
-let grey = rgb::alt::Gray::<u8>(128);
-assert_eq!(244, ansi_colours::ansi256_from_grey(*grey));
-assert_eq!(244, ansi_colours::ansi256_from_rgb(grey));
-"#
-)]
+/// Returns index of a colour in 256-colour ANSI palette approximating given
+/// sRGB colour with 16 bits per component.
+///
+/// Rather than truncating the components to 8 bits, the function measures
+/// distances to candidate colours using full precision of the components.
+/// This matters for high bit depth sources (such as HDR screenshots or
+/// scientific visualisations) whose colours lie close to the boundary between
+/// two palette entries.  For colours whose components are exact 8-bit values
+/// (i.e. multiples of 257), the result is the same as of
+/// [`ansi256_from_rgb`].
+///
+/// Implementations of [`AsRGB`] for 16-bit colour types of the `rgb` crate
+/// use this function.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::{ansi256_from_rgb, ansi256_from_rgb16};
+///
+/// assert_eq!( 16, ansi256_from_rgb16((    0,     0,     0)));
+/// assert_eq!( 67, ansi256_from_rgb16((24415, 34695, 44975)));
+/// assert_eq!(231, ansi256_from_rgb16((65535, 65535, 65535)));
+///
+/// // Rounding the components to 8 bits would give a different colour.
+/// assert_eq!( 60, ansi256_from_rgb16((12207, 29683, 29683)));
+/// assert_eq!(241, ansi256_from_rgb((    47,   115,   115)));
 /// ```
 #[inline]
-pub fn ansi256_from_grey(component: u8) -> u8 {
-    ansi256::ANSI256_FROM_GREY[component as usize]
+pub fn ansi256_from_rgb16(rgb: (u16, u16, u16)) -> u8 {
+    system::ansi256_from_rgb16(rgb)
 }
 
-/// Type which represents a colour convertible to sRGB.  Used to provide
-/// overloaded versions of `ansi256_from_rgb` function.
-pub trait AsRGB {
-    /// Returns representation of the sRGB colour as a 24-bit `0xRRGGBB`
-    /// integer.
-    fn as_u32(&self) -> u32;
+/// Returns index of a colour in 256-colour ANSI palette approximating given
+/// sRGB colour together with the approximation error.
+///
+/// The index is the same as one returned by [`ansi256_from_rgb`].  The error
+/// is ‘redmean’ distance between given colour and the colour at returned index
+/// (as returned by [`rgb_from_ansi256`], i.e. taking into account values set
+/// with [`set_system_colours`]) as described in [Colour metric
+/// article](https://www.compuphase.com/cmetric.htm).  It’s zero if the colour
+/// is exactly represented in the palette and grows as the approximation gets
+/// worse.  Callers may use the error to decide to fall back to true-colour or
+/// dithering or to warn user when the match is poor.
+///
+/// Note that the error isn’t Euclidean distance in sRGB space.  Squared
+/// differences of the components are weighted by factors between two and
+/// four, depending on the redness of the colours, so for example colours which
+/// differ by one in green component only are at distance two.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::ansi256_from_rgb_with_error;
+///
+/// assert_eq!(( 16,  0.0), ansi256_from_rgb_with_error(0x000000));
+/// assert_eq!(( 67,  0.0), ansi256_from_rgb_with_error(( 95, 135, 175)));
+///
+/// let (idx, error) = ansi256_from_rgb_with_error((100, 140, 170));
+/// assert_eq!(67, idx);
+/// assert!(14.9 < error && error < 15.1);
+///
+/// let (idx, error) = ansi256_from_rgb_with_error((40, 40, 220));
+/// assert_eq!(62, idx);
+/// assert!(error > 100.0);
+/// ```
+pub fn ansi256_from_rgb_with_error<C: AsRGB>(rgb: C) -> (u8, f32) {
+    let index = rgb.to_ansi256();
+    let colour = system::palette_colour(index);
+    (index, ansi256::redmean(rgb.as_u32(), colour))
+}
 
-    /// Returns index of a colour in 256-colour ANSI palette approximating given
-    /// sRGB colour.
-    ///
-    /// This is provided by default and uses [`Self::as_u32`] to determine
-    /// 24-bit sRGB representation of the colour.
-    ///
-    /// An implementation should provide its own definition if it can offer
-    /// a more direct approximation.  For example, if `Self` represents shades
-    /// of grey, it’s faster to use [`ansi256_from_grey`] than relay on `to_u32`
-    /// conversion; or if it represents a variant which can store index in the
-    /// palette or an RGB colour, it’s better to either return the index or
-    /// perform approximation depending on the variant.
-    #[inline]
-    fn to_ansi256(&self) -> u8 {
-        crate::ansi256::ansi256_from_rgb(self.as_u32())
+/// Returns distance between two colours.
+///
+/// Each colour may be given as an sRGB colour or as an index in the
+/// 256-colour ANSI palette (or anything else which converts into a
+/// [`Colour`]).  The distance is ‘redmean’ distance described in [Colour
+/// metric article](https://www.compuphase.com/cmetric.htm) which is the same
+/// perceptual model the approximation functions use (and which
+/// [`ansi256_from_rgb_with_error`] reports).  It’s zero for identical colours
+/// but, unlike Euclidean distance in sRGB space, weights squared differences
+/// of the components by factors between two and four.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::{distance, Colour};
+///
+/// assert_eq!(0.0, distance(67u8, (95u8, 135, 175)));
+/// assert_eq!(distance(16u8, 231u8), distance(231u8, 16u8));
+///
+/// let d = distance(Colour::Rgb(100, 140, 170), 67u8);
+/// assert!(14.9 < d && d < 15.1);
+///
+/// // Sorting colours by their distance to a reference colour.
+/// let mut colours = [231u8, 16, 68, 67];
+/// colours.sort_by(|&a, &b| {
+///     distance(a, 0x5f87afu32).total_cmp(&distance(b, 0x5f87afu32))
+/// });
+/// assert_eq!([67, 68, 231, 16], colours);
+/// ```
+pub fn distance<A: Into<Colour>, B: Into<Colour>>(a: A, b: B) -> f32 {
+    ansi256::redmean(a.into().rgb_u32(), b.into().rgb_u32())
+}
+
+/// Returns index of a colour in 256-colour ANSI palette approximating given
+/// translucent sRGB colour composited over given background.
+///
+/// The colour is given as a 32-bit `0xRRGGBBAA` integer.  The background may
+/// be given as an sRGB colour or as an index in the palette (see
+/// [`Background`]).  Compositing is done in linear light which, unlike
+/// blending of gamma-encoded components done by [`AlphaPolicy::Over`], gives
+/// physically correct results for overlays and shadows.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::{ansi256_from_rgba, Background};
+///
+/// let black = Background::Rgb(0x000000);
+/// assert_eq!( 67, ansi256_from_rgba(0x5f87afff, black));
+/// assert_eq!( 16, ansi256_from_rgba(0x5f87af00, black));
+/// assert_eq!(231, ansi256_from_rgba(0x5f87af00, Background::Index(231)));
+///
+/// // Half-transparent black shadow over white background.
+/// let white: (u8, u8, u8) = (255, 255, 255);
+/// assert_eq!(250, ansi256_from_rgba(0x00000080, white));
+/// assert_eq!(250, ansi256_from_rgba(0x00000080, 15u8));
+/// ```
+pub fn ansi256_from_rgba<B: Into<Background>>(rgba: u32, background: B) -> u8 {
+    let background = background.into().to_u32();
+    let rgb = ansi256::composite(rgba >> 8, rgba as u8, background);
+    crate::system::ansi256_from_rgb(rgb)
+}
+
+/// Returns indexes of up to `k` colours in 256-colour ANSI palette which are the
+/// closest to given sRGB colour, ordered by their distance to it.
+///
+/// This is useful for ditherers which need more than one approximation and for
+/// tools which want alternatives when the best match is already used for
+/// something else.  Colours are compared using ‘redmean’ distance, i.e. the
+/// same metric as [`metric::Redmean`], with ties resolved in favour of lower
+/// indexes.  As a consequence, the first candidate may differ from the colour
+/// returned by [`ansi256_from_rgb`] which doesn’t perform an exhaustive search.
+///
+/// Just like `ansi256_from_rgb`, the function doesn’t consider the first 16
+/// colours of the palette and thus returns at most 240 candidates.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::ansi256_candidates;
+///
+/// let candidates: Vec<u8> = ansi256_candidates((95, 135, 175), 4).collect();
+/// assert_eq!(vec![67, 103, 66, 68], candidates);
+/// assert_eq!(240, ansi256_candidates(0x000000, 1000).len());
+/// assert_eq!(None, ansi256_candidates(0x000000, 0).next());
+/// ```
+pub fn ansi256_candidates<C: AsRGB>(rgb: C, k: usize) -> Candidates {
+    Candidates::new(rgb.as_u32(), k)
+}
+
+/// Returns index of a colour in 256-colour ANSI palette approximating given
+/// sRGB colour which is not one of the `excluded` indexes.
+///
+/// This is useful when some colours are already taken, e.g. by the background
+/// or by other syntax tokens, and the approximation must be distinguishable
+/// from them.  If the colour returned by [`ansi256_from_rgb`] is not excluded,
+/// it is returned.  Otherwise, the closest remaining colour in order given by
+/// [`ansi256_candidates`] is returned.  If all colours are excluded, returns
+/// `None`.
+///
+/// Just like `ansi256_from_rgb`, the function doesn’t consider the first 16
+/// colours of the palette (so there’s no need to exclude them) unless enabled
+/// with [`set_system_colours_in_approximation`].  In that case current values
+/// of the system colours are used and, on ties, other colours are preferred.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::ansi256_from_rgb_excluding;
+///
+/// let rgb = (95, 135, 175);
+/// assert_eq!(Some(67), ansi256_from_rgb_excluding(rgb, &[]));
+/// assert_eq!(Some(103), ansi256_from_rgb_excluding(rgb, &[67]));
+/// assert_eq!(Some(66), ansi256_from_rgb_excluding(rgb, &[67, 103]));
+///
+/// let all: Vec<u8> = (16..=255).collect();
+/// assert_eq!(None, ansi256_from_rgb_excluding(rgb, &all));
+/// ```
+pub fn ansi256_from_rgb_excluding<C: AsRGB>(
+    rgb: C,
+    excluded: &[u8],
+) -> Option<u8> {
+    let index = rgb.to_ansi256();
+    if !excluded.contains(&index) {
+        return Some(index);
+    }
+    Candidates::approximation(rgb.as_u32()).find(|idx| !excluded.contains(idx))
+}
+
+/// Returns index of a colour in 256-colour ANSI palette approximating given
+/// sRGB colour chosen from the `allowed` indexes.
+///
+/// This is a complement of [`ansi256_from_rgb_excluding`] useful when only
+/// a subset of the palette may be used, e.g. colours defined by a theme.  The
+/// function compares given colour with every allowed colour using ‘redmean’
+/// distance (see [`metric::Redmean`]) and returns the closest one.  If there
+/// are multiple closest colours, the one which comes first in `allowed` is
+/// returned.  Returns `None` if `allowed` is empty.
+///
+/// Unlike most other functions, this one considers system colours if they
+/// are allowed.  Their default XTerm values are used in such case.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::ansi256_from_rgb_within;
+///
+/// let allowed = [16, 21, 46, 196, 231];
+/// assert_eq!(Some(196), ansi256_from_rgb_within((200, 50, 50), &allowed));
+/// assert_eq!(Some( 21), ansi256_from_rgb_within(0x3030a0, &allowed));
+/// assert_eq!(Some(  9), ansi256_from_rgb_within(0xff0000, &[1, 9]));
+/// assert_eq!(None, ansi256_from_rgb_within(0xff0000, &[]));
+/// ```
+pub fn ansi256_from_rgb_within<C: AsRGB>(rgb: C, allowed: &[u8]) -> Option<u8> {
+    let rgb = ansi256::to_triple(rgb.as_u32());
+    allowed
+        .iter()
+        .copied()
+        .min_by_key(|idx| ansi256::distance(rgb, ansi256::colour(*idx)))
+}
+
+/// Returns contrast ratio, as defined by WCAG, between two colours.
+///
+/// Each colour may be given as an sRGB colour or as an index in the
+/// 256-colour ANSI palette (or anything else which converts into a
+/// [`Colour`]).  This makes it possible to validate themes mixing indexed and
+/// true-colour colours.
+///
+/// The [contrast
+/// ratio](https://www.w3.org/TR/WCAG21/#dfn-contrast-ratio) ranges from 1 (no
+/// contrast) to 21 (black and white) and doesn’t depend on the order of the
+/// arguments.  WCAG recommends ratio of at least 4.5 for normal text and 3 for
+/// large text.  Note that values of the 16 system colours are not
+/// standardised so ratios calculated for them may not reflect what user sees.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::{contrast_ratio, Colour, NamedColour};
+///
+/// let black = Colour::Rgb(0, 0, 0);
+/// let white: u32 = 0xffffff;
+///
+/// assert_eq!(contrast_ratio(16u8, 231u8), contrast_ratio(black, white));
+/// assert_eq!(1.0, contrast_ratio(67u8, [95u8, 135, 175]));
+///
+/// let ratio = contrast_ratio(black, white);
+/// assert!(20.9 < ratio && ratio < 21.1);
+/// let ratio = contrast_ratio(NamedColour::Blue, white);
+/// assert!(9.3 < ratio && ratio < 9.5);
+/// let ratio = contrast_ratio(244u8, black);
+/// assert!(4.5 < ratio && ratio < 7.0);
+/// ```
+pub fn contrast_ratio<A: Into<Colour>, B: Into<Colour>>(a: A, b: B) -> f32 {
+    ansi256::contrast_ratio(a.into().rgb_u32(), b.into().rgb_u32())
+}
+
+/// Returns index of black or white colour, whichever contrasts better with
+/// given background colour.
+///
+/// The background may be given as an sRGB colour or as an index in the
+/// palette (see [`contrast_ratio`]).  The function returns 16 (black) or 231
+/// (white) rather than system colours 0 and 15 since, unlike system colours,
+/// colours of the cube have well-defined values.  For the same reason, system
+/// colours given as background are assumed to have the default XTerm values
+/// unless different values were set with [`set_system_colours`].
+///
+/// This is useful for picking text colour in prompts or status lines whose
+/// background colour is configurable.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::readable_foreground;
+///
+/// assert_eq!(231, readable_foreground(16u8));
+/// assert_eq!( 16, readable_foreground(231u8));
+/// assert_eq!(231, readable_foreground(21u8));
+/// assert_eq!( 16, readable_foreground(226u8));
+/// assert_eq!( 16, readable_foreground((95u8, 135, 175)));
+/// assert_eq!(231, readable_foreground(0x800000u32));
+/// ```
+pub fn readable_foreground<B: Into<Colour>>(background: B) -> u8 {
+    let background = background.into().rgb_u32();
+    let black = ansi256::contrast_ratio(0x000000, background);
+    let white = ansi256::contrast_ratio(0xffffff, background);
+    if black >= white {
+        16
+    } else {
+        231
+    }
+}
+
+/// Returns index of a colour in 256-colour ANSI palette approximating given
+/// foreground sRGB colour which has at least given contrast against the
+/// background colour.
+///
+/// Contrast is measured as [WCAG contrast
+/// ratio](https://www.w3.org/TR/WCAG21/#dfn-contrast-ratio) which ranges from
+/// 1 (no contrast) to 21 (black on white).  WCAG recommends ratio of at least
+/// 4.5 for normal text and 3 for large text.
+///
+/// If the colour returned by [`ansi256_from_rgb`] has sufficient contrast, it
+/// is returned.  Otherwise, the closest colour with sufficient contrast in
+/// order given by [`ansi256_candidates`] is returned which effectively nudges
+/// the result along the colour cube or the greyscale ramp.  If no colour in
+/// the palette has sufficient contrast, the one with the highest contrast is
+/// returned.  Just like in [`ansi256_from_rgb_excluding`], system colours are
+/// considered only if enabled with [`set_system_colours_in_approximation`]
+/// and their contrast is calculated using their current values.
+///
+/// If background is given as an index in the palette, its colour can be
+/// obtained with [`rgb_from_ansi256`].  Note that values of the 16 system
+/// colours are not standardised so using them as background may give
+/// unexpected results.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::{ansi256_from_rgb_with_contrast, rgb_from_ansi256};
+///
+/// let fg = (95, 135, 175);
+/// assert_eq!( 67, ansi256_from_rgb_with_contrast(fg, 0x000000, 4.5));
+/// assert_eq!( 61, ansi256_from_rgb_with_contrast(fg, 0xffffff, 4.5));
+///
+/// let bg = rgb_from_ansi256(60);
+/// assert_eq!(231, ansi256_from_rgb_with_contrast(fg, bg, 30.0));
+/// ```
+pub fn ansi256_from_rgb_with_contrast<C: AsRGB, B: AsRGB>(
+    foreground: C,
+    background: B,
+    min_contrast: f32,
+) -> u8 {
+    let background = background.as_u32();
+    let contrast = |idx: u8| {
+        let colour = system::palette_colour(idx);
+        ansi256::contrast_ratio(colour, background)
+    };
+    let index = foreground.to_ansi256();
+    if contrast(index) >= min_contrast {
+        return index;
+    }
+    let mut best = (contrast(index), index);
+    for idx in Candidates::approximation(foreground.as_u32()) {
+        let ratio = contrast(idx);
+        if ratio >= min_contrast {
+            return idx;
+        } else if ratio > best.0 {
+            best = (ratio, idx);
+        }
+    }
+    best.1
+}
+
+/// Returns index of a colour in 256-colour ANSI palette approximating sRGB
+/// colour given by its linear-light components.
+///
+/// Components are linear intensities in the 0–1 range.  The sRGB transfer
+/// function is applied before approximation.  This is equivalent to
+/// `ansi256_from_rgb(LinearRgb { r, g, b })`; see [`LinearRgb`] for details.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::ansi256_from_linear_rgb;
+///
+/// assert_eq!( 16, ansi256_from_linear_rgb(0.0,   0.0,   0.0));
+/// assert_eq!(196, ansi256_from_linear_rgb(1.0,   0.0,   0.0));
+/// assert_eq!( 67, ansi256_from_linear_rgb(0.114, 0.242, 0.429));
+/// assert_eq!(231, ansi256_from_linear_rgb(1.0,   1.0,   1.0));
+/// ```
+#[inline]
+pub fn ansi256_from_linear_rgb(r: f32, g: f32, b: f32) -> u8 {
+    LinearRgb { r, g, b }.to_ansi256()
+}
+
+/// Returns index of a colour in 256-colour ANSI palette approximating colour
+/// of a black-body radiator at given temperature in kelvins.
+///
+/// This is equivalent to `ansi256_from_rgb(Kelvin(temperature))`; see
+/// [`Kelvin`] for details.  Useful for displaying temperature swatches, e.g.
+/// of light sources.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::ansi256_from_kelvin;
+///
+/// assert_eq!(208, ansi256_from_kelvin( 1900.0));
+/// assert_eq!(215, ansi256_from_kelvin( 2700.0));
+/// assert_eq!(223, ansi256_from_kelvin( 4000.0));
+/// assert_eq!(231, ansi256_from_kelvin( 6500.0));
+/// assert_eq!(153, ansi256_from_kelvin(15000.0));
+/// ```
+#[inline]
+pub fn ansi256_from_kelvin(temperature: f32) -> u8 {
+    Kelvin(temperature).to_ansi256()
+}
+
+/// Returns index of a colour in 256-colour ANSI palette approximating colour
+/// given by its hue, saturation and lightness.
+///
+/// Hue is given in degrees while saturation and lightness are in the 0–1
+/// range.  This is equivalent to `ansi256_from_rgb(Hsl { h, s, l })`; see
+/// [`Hsl`] for details.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::ansi256_from_hsl;
+///
+/// assert_eq!( 16, ansi256_from_hsl(  0.0, 0.0,  0.0));
+/// assert_eq!(196, ansi256_from_hsl(  0.0, 1.0,  0.5));
+/// assert_eq!( 67, ansi256_from_hsl(210.0, 0.33, 0.53));
+/// assert_eq!(231, ansi256_from_hsl(  0.0, 0.0,  1.0));
+/// ```
+#[inline]
+pub fn ansi256_from_hsl(h: f32, s: f32, l: f32) -> u8 {
+    Hsl { h, s, l }.to_ansi256()
+}
+
+/// Returns index of a colour in 256-colour ANSI palette approximating colour
+/// given by its HSLuv coordinates.
+///
+/// Hue is given in degrees while saturation and lightness are in the 0–100
+/// range.  This is equivalent to `ansi256_from_rgb(Hsluv { h, s, l })`; see
+/// [`Hsluv`] for details.
+///
+/// This function is present only if `hsluv` crate feature is enabled.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::ansi256_from_hsluv;
+///
+/// assert_eq!( 16, ansi256_from_hsluv(  0.0,   0.0,   0.0));
+/// assert_eq!(196, ansi256_from_hsluv( 12.2, 100.0,  53.2));
+/// assert_eq!( 67, ansi256_from_hsluv(250.0,  50.0,  55.0));
+/// assert_eq!(231, ansi256_from_hsluv(  0.0,   0.0, 100.0));
+/// ```
+#[cfg(feature = "hsluv")]
+#[inline]
+pub fn ansi256_from_hsluv(h: f32, s: f32, l: f32) -> u8 {
+    Hsluv { h, s, l }.to_ansi256()
+}
+
+/// Returns index of a colour in 256-colour ANSI palette approximating colour
+/// given by its hue, saturation and value.
+///
+/// Hue is given in degrees while saturation and value are in the 0–1 range.
+/// This is equivalent to `ansi256_from_rgb(Hsv { h, s, v })`; see [`Hsv`] for
+/// details.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::ansi256_from_hsv;
+///
+/// assert_eq!( 16, ansi256_from_hsv(  0.0, 0.0,  0.0));
+/// assert_eq!( 46, ansi256_from_hsv(120.0, 1.0,  1.0));
+/// assert_eq!( 67, ansi256_from_hsv(210.0, 0.46, 0.69));
+/// assert_eq!(231, ansi256_from_hsv(  0.0, 0.0,  1.0));
+/// ```
+#[inline]
+pub fn ansi256_from_hsv(h: f32, s: f32, v: f32) -> u8 {
+    Hsv { h, s, v }.to_ansi256()
+}
+
+/// Returns coordinate of the 6×6×6 colour cube and its component value which
+/// approximate given component of an sRGB colour.
+///
+/// The returned coordinate is in the range from 0 to 5 and the value is one
+/// of 0, 95, 135, 175, 215 and 255.  The thresholds are the same as those
+/// used by [`ansi256_from_rgb`] when approximating a colour by an entry of
+/// the cube, which differ slightly between channels.  This lets downstream
+/// quantisers reuse the exact per-channel thresholds.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::{nearest_cube_value, Channel};
+///
+/// assert_eq!((0,   0), nearest_cube_value(Channel::Red,     0));
+/// assert_eq!((0,   0), nearest_cube_value(Channel::Red,    37));
+/// assert_eq!((1,  95), nearest_cube_value(Channel::Red,    38));
+/// assert_eq!((1,  95), nearest_cube_value(Channel::Green,  36));
+/// assert_eq!((3, 175), nearest_cube_value(Channel::Blue,  175));
+/// assert_eq!((5, 255), nearest_cube_value(Channel::Blue,  255));
+/// ```
+#[inline]
+pub fn nearest_cube_value(channel: Channel, component: u8) -> (u8, u8) {
+    let thresholds = ansi256::CUBE_THRESHOLDS[channel as usize];
+    let (step, value) = ansi256::cube_thresholds(component, thresholds);
+    (step, value as u8)
+}
+
+/// Returns index in 256-colour ANSI palette of the 6×6×6 colour cube entry
+/// with given coordinates.
+///
+/// Each coordinate must be in the range from 0 to 5.  The index is
+/// `16 + 36 × r + 6 × g + b`.  [`cube_coords`] performs the inverse
+/// operation.
+///
+/// # Panics
+///
+/// Panics if any of the coordinates is greater than 5.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::cube_index;
+///
+/// assert_eq!( 16, cube_index(0, 0, 0));
+/// assert_eq!( 67, cube_index(1, 2, 3));
+/// assert_eq!(196, cube_index(5, 0, 0));
+/// assert_eq!(231, cube_index(5, 5, 5));
+/// ```
+#[inline]
+pub const fn cube_index(r: u8, g: u8, b: u8) -> u8 {
+    assert!(r < 6 && g < 6 && b < 6, "cube coordinate out of range");
+    16 + 36 * r + 6 * g + b
+}
+
+/// Returns coordinates in the 6×6×6 colour cube of colour at given index in
+/// 256-colour ANSI palette.
+///
+/// Returns `None` if the index doesn’t correspond to an entry of the cube,
+/// i.e. if it’s a system colour (index below 16) or a colour of the greyscale
+/// ramp (index above 231).  This is the inverse of [`cube_index`].
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::cube_coords;
+///
+/// assert_eq!(Some((0, 0, 0)), cube_coords( 16));
+/// assert_eq!(Some((1, 2, 3)), cube_coords( 67));
+/// assert_eq!(Some((5, 5, 5)), cube_coords(231));
+/// assert_eq!(None, cube_coords( 15));
+/// assert_eq!(None, cube_coords(232));
+/// ```
+#[inline]
+pub const fn cube_coords(idx: u8) -> Option<(u8, u8, u8)> {
+    if idx < 16 || idx > 231 {
+        return None;
+    }
+    let idx = idx - 16;
+    Some((idx / 36, idx / 6 % 6, idx % 6))
+}
+
+/// Returns index in 256-colour ANSI palette of the greyscale ramp entry at
+/// given level.
+///
+/// The ramp consists of 24 shades of grey at indexes from 232 to 255.  The
+/// level must be in the range from 0 (darkest) to 23 (lightest).
+/// [`grey_level`] performs the inverse operation and [`grey_value`] returns
+/// the component value of the shade.
+///
+/// # Panics
+///
+/// Panics if the level is greater than 23.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::grey_index;
+///
+/// assert_eq!(232, grey_index( 0));
+/// assert_eq!(244, grey_index(12));
+/// assert_eq!(255, grey_index(23));
+/// ```
+#[inline]
+pub const fn grey_index(level: u8) -> u8 {
+    assert!(level < 24, "grey level out of range");
+    232 + level
+}
+
+/// Returns level in the greyscale ramp of colour at given index in 256-colour
+/// ANSI palette.
+///
+/// Returns `None` if the index doesn’t correspond to an entry of the ramp,
+/// i.e. if it’s below 232.  Note that the ramp doesn’t include black and white
+/// nor the four shades of grey on the diagonal of the 6×6×6 cube.  This is the
+/// inverse of [`grey_index`].
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::grey_level;
+///
+/// assert_eq!(Some( 0), grey_level(232));
+/// assert_eq!(Some(23), grey_level(255));
+/// assert_eq!(None, grey_level(231));
+/// assert_eq!(None, grey_level( 16));
+/// ```
+#[inline]
+pub const fn grey_level(idx: u8) -> Option<u8> {
+    if idx < 232 {
+        None
+    } else {
+        Some(idx - 232)
+    }
+}
+
+/// Returns component value of the greyscale ramp entry at given level.
+///
+/// The shade at level `n` is an sRGB colour with all components equal to
+/// `8 + 10 × n`.  The level must be in the range from 0 to 23.
+///
+/// # Panics
+///
+/// Panics if the level is greater than 23.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::{grey_index, grey_value, rgb_from_ansi256};
+///
+/// assert_eq!(  8, grey_value( 0));
+/// assert_eq!(128, grey_value(12));
+/// assert_eq!(238, grey_value(23));
+///
+/// let v = grey_value(5);
+/// assert_eq!((v, v, v), rgb_from_ansi256(grey_index(5)));
+/// ```
+#[inline]
+pub const fn grey_value(level: u8) -> u8 {
+    assert!(level < 24, "grey level out of range");
+    8 + 10 * level
+}
+
+/// Returns index of given colour in 256-colour ANSI palette if the colour is
+/// exactly representable in the palette.
+///
+/// The colour matches if it’s an entry of the 6×6×6 colour cube, a shade of
+/// the greyscale ramp or one of the system colours (taking into account values
+/// set with [`set_system_colours`]).  Entries of the cube and the ramp take
+/// precedence over system colours, e.g. black is reported as index 16 rather
+/// than 0.  Returns `None` if the colour isn’t in the palette in which case
+/// converting it to an index would be lossy.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::is_palette_colour;
+///
+/// assert_eq!(Some( 16), is_palette_colour(0x000000));
+/// assert_eq!(Some( 67), is_palette_colour(( 95, 135, 175)));
+/// assert_eq!(Some(244), is_palette_colour(0x808080));
+/// assert_eq!(Some(  1), is_palette_colour(0xcd0000));
+/// assert_eq!(None, is_palette_colour(0x5f87ae));
+/// assert_eq!(None, is_palette_colour(0x818181));
+/// ```
+pub fn is_palette_colour<C: AsRGB>(rgb: C) -> Option<u8> {
+    let rgb = rgb.as_u32() & 0xffffff;
+    let (r, g, b) = ansi256::to_triple(rgb);
+    let level = |value: u8| {
+        ansi256::CUBE_LEVELS
+            .iter()
+            .position(|level| *level == value)
+    };
+    if let (Some(r), Some(g), Some(b)) = (level(r), level(g), level(b)) {
+        return Some(cube_index(r as u8, g as u8, b as u8));
+    }
+    if r == g && g == b && (8..=238).contains(&r) && (r - 8) % 10 == 0 {
+        return Some(grey_index((r - 8) / 10));
+    }
+    (0..16).find(|idx| system::colour(*idx) == rgb)
+}
+
+/// Returns canonical index of a shade of grey according to given policy.
+///
+/// If the index is one of near-duplicate shades of grey (see
+/// [`GreyPolicy`]), returns the preferred member of the pair.  Otherwise
+/// returns the index unchanged.  This gives stable indexes to tools which
+/// compare or deduplicate colours.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::{canonical_grey, GreyPolicy};
+///
+/// assert_eq!(241, canonical_grey( 59, GreyPolicy::PreferRamp));
+/// assert_eq!(241, canonical_grey(241, GreyPolicy::PreferRamp));
+/// assert_eq!( 59, canonical_grey(241, GreyPolicy::PreferCube));
+/// assert_eq!(188, canonical_grey(253, GreyPolicy::PreferCube));
+/// assert_eq!(241, canonical_grey(241, GreyPolicy::Any));
+///
+/// // Black and white have no near-duplicates.
+/// assert_eq!( 16, canonical_grey( 16, GreyPolicy::PreferRamp));
+/// assert_eq!(255, canonical_grey(255, GreyPolicy::PreferCube));
+/// // Neither do colours other than greys.
+/// assert_eq!( 67, canonical_grey( 67, GreyPolicy::PreferRamp));
+/// ```
+#[inline]
+pub fn canonical_grey(idx: u8, policy: GreyPolicy) -> u8 {
+    converter::canonical_grey(idx, policy, ansi256::colour)
+}
+
+/// Returns index of a colour in 256-colour ANSI palette approximating given
+/// shade of grey.
+///
+/// This gives the same results as `ansi256_from_rgb((component, component,
+/// component))` but is faster.  Provided that the `rgb` crate feature is
+/// enabled, it is equivalent (in behaviour and performance) to
+/// `ansi256_from_rgb(rgb::alt::Grey(component))`.
+///
+/// # Examples
+///
+///
+/// ```
+/// assert_eq!( 16, ansi_colours::ansi256_from_grey(0));
+/// assert_eq!( 16, ansi_colours::ansi256_from_grey(1));
+/// assert_eq!(231, ansi_colours::ansi256_from_grey(255));
+#[cfg_attr(
+    feature = "rgb",
+    doc = r#"
+
+let grey = rgb::alt::Gray::<u8>(128);
+assert_eq!(244, ansi_colours::ansi256_from_grey(*grey));
+assert_eq!(244, ansi_colours::ansi256_from_rgb(grey));
+"#
+)]
+/// ```
+#[inline]
+pub fn ansi256_from_grey(component: u8) -> u8 {
+    system::ansi256_from_grey(component)
+}
+
+/// Returns index of a colour in 256-colour ANSI palette approximating given
+/// shade of grey with 16-bit precision.
+///
+/// This is meant for high bit depth greyscale sources (such as medical or
+/// scientific imagery).  Rather than truncating or rounding the value to
+/// 8 bits, the function picks shade of grey in the palette which is nearest
+/// to the value at full precision.  The calculation uses integer arithmetic
+/// and is nearly as fast as the 8-bit variant.
+///
+/// Provided that the `rgb` crate feature is enabled, it is equivalent to
+/// `ansi256_from_rgb(rgb::alt::Gray::<u16>(value))`.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::{ansi256_from_grey, ansi256_from_grey16};
+///
+/// assert_eq!( 16, ansi256_from_grey16(    0));
+/// assert_eq!(244, ansi256_from_grey16(32896));
+/// assert_eq!(231, ansi256_from_grey16(65535));
+///
+/// // Truncating the value to 8 bits would give a different colour.
+/// assert_eq!(232, ansi256_from_grey16(1185));
+/// assert_eq!( 16, ansi256_from_grey((1185 >> 8) as u8));
+///
+/// // So would rounding it.  1029 is closer to 8×257 than to zero.
+/// assert_eq!(232, ansi256_from_grey16(1029));
+/// assert_eq!( 16, ansi256_from_grey(4));
+/// ```
+#[inline]
+pub fn ansi256_from_grey16(value: u16) -> u8 {
+    system::ansi256_from_grey16(value)
+}
+
+/// Returns index of a shade of grey in 256-colour ANSI palette approximating
+/// given sRGB colour using only the greyscale ramp.
+///
+/// The function converts the colour to greyscale (by calculating its
+/// luminance) and approximates it using only the 24 colours of the greyscale
+/// ramp (indexes 232–255) as well as black (index 16) and white (index 231).
+/// In contrast, [`ansi256_from_grey`] may also return other greys of the
+/// colour cube.  This is useful when rendering greyscale images or when the
+/// colour cube has been themed while the ramp remains standard.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::{ansi256_from_grey, ansi256_grey_from_rgb};
+///
+/// assert_eq!( 16, ansi256_grey_from_rgb(0x000000));
+/// assert_eq!(232, ansi256_grey_from_rgb(0x080808));
+/// assert_eq!(241, ansi256_grey_from_rgb((95, 95, 95)));
+/// assert_eq!(244, ansi256_grey_from_rgb([ 95, 135, 175]));
+/// assert_eq!(231, ansi256_grey_from_rgb(0xffffff));
+///
+/// assert_eq!( 59, ansi256_from_grey(95));
+/// ```
+pub fn ansi256_grey_from_rgb<C: AsRGB>(rgb: C) -> u8 {
+    let (r, g, b) = ansi256::to_triple(rgb.as_u32());
+    match ansi256::luminance(r, g, b) {
+        0..=3 => 16,
+        247..=255 => 231,
+        // Ramp starts at 8 and has a step of 10.
+        y => 232 + ((y - 3) / 10).min(23),
+    }
+}
+
+/// Returns index of a colour in 256-colour ANSI palette which is the closest to
+/// given sRGB colour as measured by the CIEDE2000 colour difference formula.
+///
+/// Unlike [`ansi256_from_rgb`], which uses heuristics to quickly find a good
+/// approximation, this function calculates ΔE*₀₀ difference to every colour of
+/// the palette and returns the best match.  This gives the most accurate
+/// results at the cost of being orders of magnitude slower.  It’s meant for
+/// situations where speed doesn’t matter, e.g. when designing themes or
+/// generating reference data for tests.
+///
+/// Just like `ansi256_from_rgb`, the function doesn’t consider the first 16
+/// colours of the palette.
+///
+/// This function is present only if `ciede2000` crate feature is enabled.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::{ansi256_from_rgb, ansi256_from_rgb_exact};
+///
+/// assert_eq!( 16, ansi256_from_rgb_exact(0x000000));
+/// assert_eq!( 67, ansi256_from_rgb_exact(( 95, 135, 175)));
+/// assert_eq!(231, ansi256_from_rgb_exact([255, 255, 255]));
+///
+/// assert_eq!( 26, ansi256_from_rgb(0x003dde));
+/// assert_eq!( 21, ansi256_from_rgb_exact(0x003dde));
+/// ```
+#[cfg(feature = "ciede2000")]
+pub fn ansi256_from_rgb_exact<C: AsRGB>(rgb: C) -> u8 {
+    ciede2000::ansi256_from_rgb(rgb.as_u32())
+}
+
+/// Returns index of a colour in 256-colour ANSI palette which is the closest to
+/// given sRGB colour as measured by Euclidean distance in OKLab colour space.
+///
+/// [OKLab](https://bottosson.github.io/posts/oklab/) is a perceptual colour
+/// space which handles hue better than the heuristic used by
+/// [`ansi256_from_rgb`].  This is especially noticeable with saturated colours
+/// which `ansi256_from_rgb` sometimes approximates by colours of a different
+/// hue.  The function compares given colour with every colour of the palette
+/// and is therefore considerably slower than `ansi256_from_rgb`.
+///
+/// Just like `ansi256_from_rgb`, the function doesn’t consider the first 16
+/// colours of the palette.
+///
+/// This function is present only if `oklab` crate feature is enabled.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::{ansi256_from_rgb, ansi256_from_rgb_oklab};
+///
+/// assert_eq!( 16, ansi256_from_rgb_oklab(0x000000));
+/// assert_eq!( 67, ansi256_from_rgb_oklab(( 95, 135, 175)));
+/// assert_eq!(231, ansi256_from_rgb_oklab([255, 255, 255]));
+///
+/// assert_eq!( 26, ansi256_from_rgb(0x003dde));
+/// assert_eq!( 21, ansi256_from_rgb_oklab(0x003dde));
+/// ```
+#[cfg(feature = "oklab")]
+pub fn ansi256_from_rgb_oklab<C: AsRGB>(rgb: C) -> u8 {
+    oklab::ansi256_from_rgb(rgb.as_u32())
+}
+
+/// Returns index of a colour in 256-colour ANSI palette which is the closest to
+/// given sRGB colour according to given distance metric.
+///
+/// The function compares given colour with every colour of the palette and
+/// returns index of the closest one.  If there are multiple closest colours,
+/// the lowest index is returned.  Just like [`ansi256_from_rgb`], the function
+/// doesn’t consider the first 16 colours of the palette.
+///
+/// See [`metric`] module for available metrics.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::ansi256_from_rgb_by;
+/// use ansi_colours::metric::{Redmean, SrgbEuclidean};
+///
+/// assert_eq!( 16, ansi256_from_rgb_by(0x000000, &SrgbEuclidean));
+/// assert_eq!(231, ansi256_from_rgb_by([255, 255, 255], &SrgbEuclidean));
+///
+/// assert_eq!( 17, ansi256_from_rgb_by(0x032446, &SrgbEuclidean));
+/// assert_eq!(235, ansi256_from_rgb_by(0x032446, &Redmean));
+/// ```
+pub fn ansi256_from_rgb_by<C: AsRGB, M: DistanceMetric + ?Sized>(
+    rgb: C,
+    metric: &M,
+) -> u8 {
+    let colours = ansi256::extended_colours();
+    metric::nearest(metric, rgb.as_u32(), colours) + 16
+}
+
+/// Returns index of a colour in 256-colour ANSI palette approximating given
+/// sRGB colour taking into account given values of the system colours.
+///
+/// [`ansi256_from_rgb`] never returns indexes of the first 16 colours since
+/// their values vary between terminals.  If actual values of those colours are
+/// known (e.g. because user’s terminal theme is known), this function can be
+/// used to consider them as candidates as well.  It works like
+/// `ansi256_from_rgb` except that if one of the colours given in the `system`
+/// argument is closer to the approximated colour than the colour picked by
+/// `ansi256_from_rgb`, index of that system colour is returned.  In case of
+/// ties, colours from outside of the system colours range are preferred.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::ansi256_from_rgb_with_system;
+///
+/// // Tango palette used by GNOME Terminal.
+/// let tango: [u32; 16] = [
+///     0x000000, 0xcc0000, 0x4e9a06, 0xc4a000,
+///     0x3465a4, 0x75507b, 0x06989a, 0xd3d7cf,
+///     0x555753, 0xef2929, 0x8ae234, 0xfce94f,
+///     0x729fcf, 0xad7fa8, 0x34e2e2, 0xeeeeec,
+/// ];
+///
+/// assert_eq!(16, ansi256_from_rgb_with_system(0x000000, &tango));
+/// assert_eq!( 1, ansi256_from_rgb_with_system(0xcc0000, &tango));
+/// assert_eq!( 4, ansi256_from_rgb_with_system(0x3465a4, &tango));
+/// assert_eq!(67, ansi256_from_rgb_with_system(0x5f87af, &tango));
+/// assert_eq!(12, ansi256_from_rgb_with_system(0x729fd0, &tango));
+/// ```
+pub fn ansi256_from_rgb_with_system<C: AsRGB, P: AsRGB>(
+    rgb: C,
+    system: &[P; 16],
+) -> u8 {
+    let system = system.each_ref().map(AsRGB::as_u32);
+    ansi256::ansi256_from_rgb_with_system(rgb.as_u32(), &system)
+}
+
+/// Returns iterator over indexes of colours in 256-colour ANSI palette
+/// approximating gradient between two colours.
+///
+/// The gradient is sampled at `steps` evenly spaced points (including both
+/// ends) and each sample is approximated by a colour of the palette.
+/// Consecutive duplicate indexes are skipped so the iterator may yield fewer
+/// than `steps` items.  This makes it possible to split e.g. a progress bar,
+/// heatmap or spark line into segments of uniform colour.
+///
+/// Colours may be given as sRGB colours or as indexes in the palette (or
+/// anything else which converts into a [`Colour`]).  Interpolation is done in
+/// CIE L\*a\*b\* colour space which gives perceptually even transitions,
+/// avoiding e.g. the muddy middle of a naïve interpolation between
+/// complementary colours in sRGB space.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::gradient;
+///
+/// let indexes: Vec<u8> = gradient(16u8, 231u8, 6).collect();
+/// assert_eq!(vec![16, 236, 59, 246, 251, 231], indexes);
+///
+/// let indexes: Vec<u8> = gradient(196u8, 196u8, 10).collect();
+/// assert_eq!(vec![196], indexes);
+///
+/// assert_eq!(None, gradient(16u8, 231u8, 0).next());
+/// assert_eq!(Some(16), gradient(16u8, 231u8, 1).next());
+/// ```
+pub fn gradient<A: Into<Colour>, B: Into<Colour>>(
+    from: A,
+    to: B,
+    steps: usize,
+) -> Gradient {
+    Gradient::new(from.into().rgb_u32(), to.into().rgb_u32(), steps)
+}
+
+/// Returns iterator over indexes of colours in 256-colour ANSI palette
+/// approximating gradient between two colours of the palette interpolated in
+/// OKLCH colour space.
+///
+/// This works like [`gradient`] except that interpolation is done in
+/// [OKLCH](https://bottosson.github.io/posts/oklab/) colour space, i.e. in
+/// lightness, chroma and hue, and each sample is approximated by the closest
+/// colour in OKLab space (like [`ansi256_from_rgb_oklab`] does).  Since hue
+/// changes along the shorter arc of the colour wheel, transitions between
+/// hues are smoother than with interpolation in sRGB or L\*a\*b\* space,
+/// which pass through desaturated colours.
+///
+/// System colours are not considered when approximating the samples.  In
+/// particular, if either end of the gradient is a system colour, the
+/// iterator yields the closest non-system colour in its place.
+///
+/// This function is present only if `oklab` crate feature is enabled.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::gradient_oklch;
+///
+/// let indexes: Vec<u8> = gradient_oklch(196, 21, 5).collect();
+/// assert_eq!(vec![196, 162, 127, 93, 21], indexes);
+///
+/// let indexes: Vec<u8> = gradient_oklch(16, 231, 3).collect();
+/// assert_eq!(vec![16, 241, 231], indexes);
+/// ```
+#[cfg(feature = "oklab")]
+pub fn gradient_oklch(from: u8, to: u8, steps: usize) -> Gradient {
+    let from = system::palette_colour(from);
+    Gradient::new_oklch(from, system::palette_colour(to), steps)
+}
+
+/// Samples a gradient at `steps` evenly spaced points and returns indexes of
+/// colours in 256-colour ANSI palette approximating colours of the gradient.
+///
+/// Consecutive duplicate indexes are removed so the returned vector may be
+/// shorter than `steps`.  This makes it possible to split e.g. a progress bar
+/// into segments of uniform colour.
+///
+/// This function is present only if `colorgrad` crate feature is enabled.
+///
+/// # Examples
+///
+/// ```
+/// use colorgrad::{Color, GradientBuilder, LinearGradient};
+///
+/// let black = Color::from_rgba8(0, 0, 0, 255);
+/// let white = Color::from_rgba8(255, 255, 255, 255);
+/// let red = Color::from_rgba8(255, 0, 0, 255);
+///
+/// let gradient = GradientBuilder::new()
+///     .colors(&[black, white])
+///     .build::<LinearGradient>()
+///     .unwrap();
+/// assert_eq!(vec![16, 238, 244, 250, 231],
+///            ansi_colours::ansi256_from_gradient(&gradient, 5));
+///
+/// let gradient = GradientBuilder::new()
+///     .colors(&[red.clone(), red])
+///     .build::<LinearGradient>()
+///     .unwrap();
+/// assert_eq!(vec![196], ansi_colours::ansi256_from_gradient(&gradient, 10));
+/// ```
+#[cfg(feature = "colorgrad")]
+pub fn ansi256_from_gradient<G: colorgrad::Gradient + ?Sized>(
+    gradient: &G,
+    steps: usize,
+) -> alloc::vec::Vec<u8> {
+    let mut indexes = alloc::vec::Vec::with_capacity(steps);
+    indexes.extend(gradient.colors(steps).map(|colour| colour.to_ansi256()));
+    indexes.dedup();
+    indexes
+}
+
+/// Converts a rectangle of sRGB pixels into indexes of colours in 256-colour
+/// ANSI palette using Floyd–Steinberg error diffusion.
+///
+/// `pixels` holds the image in row-major order with rows of `width` pixels
+/// each.  Index for each pixel is written at the corresponding position in
+/// `output`.  A single row can be converted by passing its length as `width`.
+///
+/// Unlike converting each pixel separately with [`ansi256_from_rgb`], error
+/// diffusion spreads the approximation error of each pixel onto its
+/// neighbours.  This dramatically improves rendering of gradients and
+/// photographs at the cost of introducing a noise pattern.
+///
+/// This function is present only if `alloc` crate feature is enabled.
+///
+/// # Panics
+///
+/// Panics if `width` is zero while `pixels` is not empty, if length of
+/// `pixels` is not a multiple of `width` or if `output` has different length
+/// than `pixels`.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::{ansi256_from_rgb, ansi256_from_rgb_dithered};
+///
+/// // Colours present in the palette are unaffected.
+/// let pixels = [0x5f87afu32; 6];
+/// let mut output = [0; 6];
+/// ansi256_from_rgb_dithered(&pixels, 3, &mut output);
+/// assert_eq!([67; 6], output);
+///
+/// // Colours in between palette entries are approximated by a mixture of them.
+/// let pixels = [(0x70, 0x70, 0x70); 16];
+/// let mut output = [0; 16];
+/// ansi256_from_rgb_dithered(&pixels, 4, &mut output);
+/// assert_eq!(242, ansi256_from_rgb((0x70, 0x70, 0x70)));
+/// assert!(output.contains(&242));
+/// assert!(output.iter().any(|&idx| idx != 242));
+/// ```
+#[cfg(feature = "alloc")]
+pub fn ansi256_from_rgb_dithered<C: AsRGB>(
+    pixels: &[C],
+    width: usize,
+    output: &mut [u8],
+) {
+    assert_eq!(pixels.len(), output.len(), "output has invalid length");
+    if pixels.is_empty() {
+        return;
+    }
+    assert!(width != 0, "width is zero");
+    assert_eq!(0, pixels.len() % width, "pixels don’t form a rectangle");
+    let pixel = |x: usize, y: usize| pixels[y * width + x].as_u32();
+    dither::dither(width, output, pixel)
+}
+
+/// Converts sRGB colours given as `[r, g, b]` triples into indexes of colours
+/// in 256-colour ANSI palette approximating them.
+///
+/// The result is the same as of calling [`ansi256_from_rgb`] for each colour
+/// but the conversion may be faster.  This is useful for converting whole
+/// images or video frames.
+///
+/// With `simd` crate feature enabled, on x86 and x86-64 the colours are
+/// converted using SSE4.1 instructions if the processor supports them.  If
+/// `std` crate feature is enabled, support for the instructions is detected
+/// at run time; otherwise they are used only if enabled at compile time (e.g.
+/// with `-C target-cpu=native` flag).  On other architectures the feature has
+/// no effect.
+///
+/// # Panics
+///
+/// Panics if `output` has different length than `pixels`.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::{ansi256_from_rgb, ansi256_from_rgb_many};
+///
+/// let pixels = [[0, 0, 0], [95, 135, 175], [255, 255, 255], [100, 100, 100]];
+/// let mut output = [0; 4];
+/// ansi256_from_rgb_many(&pixels, &mut output);
+/// assert_eq!([16, 67, 231, 241], output);
+/// for (rgb, idx) in pixels.iter().zip(output.iter()) {
+///     assert_eq!(ansi256_from_rgb(rgb), *idx);
+/// }
+/// ```
+pub fn ansi256_from_rgb_many(pixels: &[[u8; 3]], output: &mut [u8]) {
+    assert_eq!(pixels.len(), output.len(), "output has invalid length");
+    #[cfg(feature = "simd")]
+    let done = simd::ansi256_from_rgb_many(pixels, output);
+    #[cfg(not(feature = "simd"))]
+    let done = 0;
+    for (rgb, out) in pixels[done..].iter().zip(output[done..].iter_mut()) {
+        *out = rgb.to_ansi256();
+    }
+}
+
+/// Converts sRGB colours given as `[r, g, b]` triples into indexes of colours
+/// in 256-colour ANSI palette approximating them using multiple threads.
+///
+/// This is a parallel version of [`ansi256_from_rgb_many`] which splits the
+/// colours into chunks converted on threads of [rayon’s](https://docs.rs/rayon)
+/// global thread pool.  It’s useful for large images (such as screenshots) or
+/// video frames.  For small inputs, the overhead of distributing the work may
+/// outweigh the benefits.
+///
+/// This function is present only if `rayon` crate feature is enabled.
+///
+/// # Panics
+///
+/// Panics if `output` has different length than `pixels`.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::{ansi256_from_rgb_many, ansi256_from_rgb_many_par};
+///
+/// let pixels = (0..1 << 16)
+///     .map(|i: u32| [(i >> 8) as u8, i as u8, (i >> 4) as u8])
+///     .collect::<Vec<_>>();
+/// let mut want = vec![0; pixels.len()];
+/// ansi256_from_rgb_many(&pixels, &mut want);
+/// let mut got = vec![0; pixels.len()];
+/// ansi256_from_rgb_many_par(&pixels, &mut got);
+/// assert_eq!(want, got);
+/// ```
+#[cfg(feature = "rayon")]
+pub fn ansi256_from_rgb_many_par(pixels: &[[u8; 3]], output: &mut [u8]) {
+    use rayon::prelude::*;
+
+    /// Number of colours converted in a single task.
+    const CHUNK: usize = 4096;
+
+    assert_eq!(pixels.len(), output.len(), "output has invalid length");
+    pixels
+        .par_chunks(CHUNK)
+        .zip(output.par_chunks_mut(CHUNK))
+        .for_each(|(pixels, output)| ansi256_from_rgb_many(pixels, output));
+}
+
+/// Converts `rgb` crate’s colours into indexes of colours in 256-colour ANSI
+/// palette approximating them.
+///
+/// This behaves like [`ansi256_from_rgb_many`] but takes pixels in the form
+/// used by image crates which depend on the `rgb` crate.  The slice is
+/// reinterpreted as `[r, g, b]` triples without copying.
+///
+/// This function is present only if `rgb` crate feature is enabled.
+///
+/// # Panics
+///
+/// Panics if `output` has different length than `pixels`.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::ansi256_from_rgb8_many;
+/// use rgb::RGB8;
+///
+/// let pixels = [RGB8::new(0, 0, 0), RGB8::new(95, 135, 175)];
+/// let mut output = [0; 2];
+/// ansi256_from_rgb8_many(&pixels, &mut output);
+/// assert_eq!([16, 67], output);
+/// ```
+#[cfg(feature = "rgb")]
+pub fn ansi256_from_rgb8_many(pixels: &[rgb::RGB8], output: &mut [u8]) {
+    ansi256_from_rgb_many(rgb::bytemuck::cast_slice(pixels), output)
+}
+
+/// Converts `rgb` crate’s colours into indexes of colours in 256-colour ANSI
+/// palette approximating them using multiple threads.  See
+/// [`ansi256_from_rgb8_many`] and [`ansi256_from_rgb_many_par`].
+///
+/// This function is present only if `rgb` and `rayon` crate features are
+/// enabled.
+///
+/// # Panics
+///
+/// Panics if `output` has different length than `pixels`.
+#[cfg(all(feature = "rgb", feature = "rayon"))]
+pub fn ansi256_from_rgb8_many_par(pixels: &[rgb::RGB8], output: &mut [u8]) {
+    ansi256_from_rgb_many_par(rgb::bytemuck::cast_slice(pixels), output)
+}
+
+/// Converts pixels of an image buffer into indexes of colours in 256-colour
+/// ANSI palette approximating them.
+///
+/// The image has `width × height` pixels stored row by row with rows starting
+/// `stride` bytes apart.  Stride may be larger than width of a row in bytes
+/// to account for padding at the end of rows, as is common in framebuffers
+/// and screenshots.  The last row doesn’t need to be padded.  Layout of the
+/// pixels is described by `layout`; alpha channel, if present, is ignored.
+///
+/// Indexes are written to `output` row by row without any padding, i.e. index
+/// of pixel at column `x` of row `y` is written to `output[y * width + x]`.
+///
+/// # Panics
+///
+/// Panics if `stride` is smaller than width of a row in bytes, if `data` is
+/// too short to hold the image or if `output` has length other than `width ×
+/// height`.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::{ansi256_from_image, PixelLayout};
+///
+/// // 2×2 BGRA image whose rows are padded to eight bytes.
+/// let data = [
+///     175, 135,  95, 255,   0,   0,   0, 255,  0, 0, 0, 0,
+///       0,   0, 255, 255, 255, 255, 255, 255,
+/// ];
+/// let mut output = [0; 4];
+/// ansi256_from_image(&data, 2, 2, 12, PixelLayout::Bgra, &mut output);
+/// assert_eq!([67, 16, 196, 231], output);
+/// ```
+pub fn ansi256_from_image(
+    data: &[u8],
+    width: usize,
+    height: usize,
+    stride: usize,
+    layout: PixelLayout,
+    output: &mut [u8],
+) {
+    let row_len = image_row_len(data, width, height, stride, layout, output);
+    if output.is_empty() {
+        return;
+    }
+    for (row, out) in data.chunks(stride).zip(output.chunks_exact_mut(width)) {
+        ansi256_from_image_row(&row[..row_len], layout, out);
+    }
+}
+
+/// Converts pixels of an image buffer into indexes of colours in 256-colour
+/// ANSI palette approximating them using multiple threads.
+///
+/// This is a parallel version of [`ansi256_from_image`] which splits the
+/// image into rows converted on threads of [rayon’s](https://docs.rs/rayon)
+/// global thread pool.  The result is the same as of the sequential version.
+///
+/// This function is present only if `rayon` crate feature is enabled.
+///
+/// # Panics
+///
+/// Panics if `stride` is smaller than width of a row in bytes, if `data` is
+/// too short to hold the image or if `output` has length other than `width ×
+/// height`.
+#[cfg(feature = "rayon")]
+pub fn ansi256_from_image_par(
+    data: &[u8],
+    width: usize,
+    height: usize,
+    stride: usize,
+    layout: PixelLayout,
+    output: &mut [u8],
+) {
+    use rayon::prelude::*;
+
+    let row_len = image_row_len(data, width, height, stride, layout, output);
+    if output.is_empty() {
+        return;
+    }
+    output
+        .par_chunks_exact_mut(width)
+        .enumerate()
+        .for_each(|(y, out)| {
+            let row = &data[y * stride..][..row_len];
+            ansi256_from_image_row(row, layout, out)
+        });
+}
+
+/// Verifies arguments of [`ansi256_from_image`] and returns width of a row in
+/// bytes.
+fn image_row_len(
+    data: &[u8],
+    width: usize,
+    height: usize,
+    stride: usize,
+    layout: PixelLayout,
+    output: &[u8],
+) -> usize {
+    let row_len = width * layout.bytes_per_pixel();
+    assert!(stride >= row_len, "stride is smaller than a row");
+    assert_eq!(width * height, output.len(), "output has invalid length");
+    assert!(
+        output.is_empty() || data.len() >= (height - 1) * stride + row_len,
+        "image data is too short"
+    );
+    row_len
+}
+
+/// Converts a single row of an image buffer.
+fn ansi256_from_image_row(row: &[u8], layout: PixelLayout, output: &mut [u8]) {
+    let pixels = row.chunks_exact(layout.bytes_per_pixel());
+    for (pixel, out) in pixels.zip(output.iter_mut()) {
+        *out = system::ansi256_from_rgb(layout.rgb(pixel));
+    }
+}
+
+/// Returns index of a system colour (i.e. one of the first 16 colours of the
+/// 256-colour ANSI palette) approximating given sRGB colour.
+///
+/// This is useful for terminals which support only 16 colours.  Since system
+/// colours are not standardised, the function uses default colours used by
+/// XTerm unless different values were set with [`set_system_colours`].  To
+/// use different values of system colours in a single call, use
+/// [`ansi16_from_rgb_with_palette`].
+///
+/// Calling the function is equivalent to calling [`AsRGB::to_ansi16`] method.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!( 0, ansi_colours::ansi16_from_rgb(0x000000));
+/// assert_eq!( 1, ansi_colours::ansi16_from_rgb((175,   0,   0)));
+/// assert_eq!( 4, ansi_colours::ansi16_from_rgb([ 20,  20, 200]));
+/// assert_eq!( 8, ansi_colours::ansi16_from_rgb(&( 95, 135, 175)));
+/// assert_eq!(15, ansi_colours::ansi16_from_rgb(&[255, 255, 255]));
+/// ```
+#[inline]
+pub fn ansi16_from_rgb<C: AsRGB>(rgb: C) -> u8 {
+    rgb.to_ansi16()
+}
+
+/// Returns index of a system colour approximating given sRGB colour using given
+/// values of the system colours.
+///
+/// Behaves like [`ansi16_from_rgb`] except that rather than using default
+/// XTerm colours, the function approximates the colour by one of colours given
+/// in the `palette` argument.  This makes it possible to take into account
+/// user’s terminal theme.  If there are multiple closest colours, the lowest
+/// index is returned.
+///
+/// # Examples
+///
+/// ```
+/// // Tango palette used by GNOME Terminal.
+/// let tango: [u32; 16] = [
+///     0x000000, 0xcc0000, 0x4e9a06, 0xc4a000,
+///     0x3465a4, 0x75507b, 0x06989a, 0xd3d7cf,
+///     0x555753, 0xef2929, 0x8ae234, 0xfce94f,
+///     0x729fcf, 0xad7fa8, 0x34e2e2, 0xeeeeec,
+/// ];
+///
+/// let colour = (95, 135, 175);
+/// assert_eq!( 8, ansi_colours::ansi16_from_rgb(colour));
+/// assert_eq!(12, ansi_colours::ansi16_from_rgb_with_palette(colour, &tango));
+/// ```
+pub fn ansi16_from_rgb_with_palette<C: AsRGB, P: AsRGB>(
+    rgb: C,
+    palette: &[P; 16],
+) -> u8 {
+    ansi256::nearest(rgb.as_u32(), palette.iter().map(AsRGB::as_u32))
+}
+
+/// Returns index in the 256-colour ANSI palette of colour set by a legacy
+/// Select Graphic Rendition (SGR) colour parameter.
+///
+/// Recognised parameters are 30–37 (foreground), 40–47 (background), 90–97
+/// (bright foreground) and 100–107 (bright background).  For other
+/// parameters returns `None`.  The returned index is always one of the 16
+/// system colours; its sRGB value can be obtained with [`rgb_from_ansi256`].
+///
+/// `bold` specifies whether the text is bold (i.e. whether SGR parameter 1 is
+/// in effect).  If it is and `policy` is [`BrightPolicy::BoldIsBright`], basic
+/// foreground colours are mapped to their bright variants.  Background colours
+/// are never affected by boldness.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::{ansi256_from_sgr, BrightPolicy};
+///
+/// let bright = BrightPolicy::BoldIsBright;
+/// let bold = BrightPolicy::BoldIsBold;
+///
+/// assert_eq!(Some( 1), ansi256_from_sgr( 31, false, bright));
+/// assert_eq!(Some( 9), ansi256_from_sgr( 31, true, bright));
+/// assert_eq!(Some( 1), ansi256_from_sgr( 31, true, bold));
+/// assert_eq!(Some( 9), ansi256_from_sgr( 91, false, bold));
+/// assert_eq!(Some( 4), ansi256_from_sgr( 44, true, bright));
+/// assert_eq!(Some(12), ansi256_from_sgr(104, false, bright));
+/// assert_eq!(None, ansi256_from_sgr( 38, false, bright));
+/// assert_eq!(None, ansi256_from_sgr(  1, false, bright));
+/// ```
+pub fn ansi256_from_sgr(
+    code: u8,
+    bold: bool,
+    policy: BrightPolicy,
+) -> Option<u8> {
+    let brighten = bold && policy == BrightPolicy::BoldIsBright;
+    match code {
+        30..=37 if brighten => Some(code - 30 + 8),
+        30..=37 => Some(code - 30),
+        40..=47 => Some(code - 40),
+        90..=97 => Some(code - 90 + 8),
+        100..=107 => Some(code - 100 + 8),
+        _ => None,
+    }
+}
+
+/// Returns index of a system colour approximating colour at given index in
+/// the 256-colour ANSI palette.
+///
+/// System colours (i.e. indexes below 16) are returned unchanged.  Remaining
+/// colours are approximated by the closest system colour just like
+/// [`ansi16_from_rgb`] does, i.e. using default XTerm values of the system
+/// colours unless different values were set with [`set_system_colours`].  See
+/// [`ansi16_from_ansi256_with_preset`] for a version which uses values of
+/// a chosen preset.
+///
+/// This is useful for degrading output on terminals supporting only 16
+/// colours.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::ansi16_from_ansi256;
+///
+/// assert_eq!( 1, ansi16_from_ansi256(  1));
+/// assert_eq!( 0, ansi16_from_ansi256( 16));
+/// assert_eq!( 8, ansi16_from_ansi256( 67));
+/// assert_eq!( 9, ansi16_from_ansi256(196));
+/// assert_eq!(15, ansi16_from_ansi256(231));
+/// assert_eq!( 7, ansi16_from_ansi256(254));
+/// ```
+#[inline]
+pub fn ansi16_from_ansi256(idx: u8) -> u8 {
+    if idx < 16 {
+        idx
+    } else {
+        ansi256::ansi16_from_rgb(ansi256::colour(idx))
+    }
+}
+
+/// Returns index of a system colour approximating colour at given index in
+/// the 256-colour ANSI palette assuming system colours have values of given
+/// preset.
+///
+/// Behaves like [`ansi16_from_ansi256`] except that the colour is
+/// approximated using values of the system colours from the preset.  If
+/// there are multiple closest colours, the lowest index is returned.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::{ansi16_from_ansi256_with_preset, SystemPreset};
+///
+/// let tango = SystemPreset::Tango;
+/// assert_eq!( 4, ansi16_from_ansi256_with_preset(  4, tango));
+/// assert_eq!(12, ansi16_from_ansi256_with_preset( 67, tango));
+/// assert_eq!( 1, ansi16_from_ansi256_with_preset(196, tango));
+/// ```
+pub fn ansi16_from_ansi256_with_preset(idx: u8, preset: SystemPreset) -> u8 {
+    if idx < 16 {
+        idx
+    } else {
+        let rgb = ansi256::colour(idx);
+        ansi256::nearest(rgb, preset.colours_rgb().iter().copied())
+    }
+}
+
+/// Returns index of one of the eight basic colours (i.e. the first eight colours
+/// of the 256-colour ANSI palette) approximating given sRGB colour.
+///
+/// This is useful for the most basic terminals and serial consoles which only
+/// support the eight colours selected with SGR 30–37 (for foreground) and
+/// 40–47 (for background) escape sequences.  The returned index can be
+/// directly added to 30 or 40 to get the SGR parameter.  Since the colours
+/// aren’t standardised, the function uses default colours used by XTerm
+/// unless different values were set with [`set_system_colours`].
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(0, ansi_colours::ansi8_from_rgb(0x000000));
+/// assert_eq!(1, ansi_colours::ansi8_from_rgb((255,   0,   0)));
+/// assert_eq!(3, ansi_colours::ansi8_from_rgb([200, 180,  20]));
+/// assert_eq!(4, ansi_colours::ansi8_from_rgb(&( 20,  20, 200)));
+/// assert_eq!(7, ansi_colours::ansi8_from_rgb(&[255, 255, 255]));
+/// ```
+#[inline]
+pub fn ansi8_from_rgb<C: AsRGB>(rgb: C) -> u8 {
+    let colours = system::snapshot();
+    ansi256::nearest(rgb.as_u32(), colours[..8].iter().copied())
+}
+
+/// Returns sRGB colour corresponding to the index in the 88-colour palette used
+/// by rxvt-unicode in 88-colour mode.
+///
+/// Like in the 256-colour palette, the first 16 colours are system colours
+/// and for those this function returns default colours used by XTerm.  They
+/// are followed by a 4×4×4 colour cube and an 8-step greyscale ramp.
+///
+/// # Panics
+///
+/// Panics if `idx` is not less than 88.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!((  0,   0,   0), ansi_colours::rgb_from_ansi88(16));
+/// assert_eq!((139, 139, 205), ansi_colours::rgb_from_ansi88(38));
+/// assert_eq!((255, 255, 255), ansi_colours::rgb_from_ansi88(79));
+/// assert_eq!((231, 231, 231), ansi_colours::rgb_from_ansi88(87));
+/// ```
+#[inline]
+pub fn rgb_from_ansi88(idx: u8) -> (u8, u8, u8) {
+    let rgb = ansi88::ANSI88_COLOURS[usize::from(idx)];
+    ((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8)
+}
+
+/// Returns index of a colour in the 88-colour palette used by rxvt-unicode in
+/// 88-colour mode approximating given sRGB colour.
+///
+/// This works like [`ansi256_from_rgb`] and similarly ignores the system
+/// colours.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(16, ansi_colours::ansi88_from_rgb(0x000000));
+/// assert_eq!(16, ansi_colours::ansi88_from_rgb((  1,   1,   1)));
+/// assert_eq!(38, ansi_colours::ansi88_from_rgb([139, 139, 205]));
+/// assert_eq!(38, ansi_colours::ansi88_from_rgb(&(130, 140, 200)));
+/// assert_eq!(37, ansi_colours::ansi88_from_rgb(&[128, 128, 128]));
+/// assert_eq!(84, ansi_colours::ansi88_from_rgb(&[160, 162, 161]));
+/// assert_eq!(79, ansi_colours::ansi88_from_rgb(0xffffff));
+/// ```
+#[inline]
+pub fn ansi88_from_rgb<C: AsRGB>(rgb: C) -> u8 {
+    ansi88::ansi88_from_rgb(rgb.as_u32())
+}
+
+/// Type which represents a colour convertible to sRGB.  Used to provide
+/// overloaded versions of `ansi256_from_rgb` function.
+pub trait AsRGB {
+    /// Returns representation of the sRGB colour as a 24-bit `0xRRGGBB`
+    /// integer.
+    fn as_u32(&self) -> u32;
+
+    /// Returns index of a colour in 256-colour ANSI palette approximating given
+    /// sRGB colour.
+    ///
+    /// This is provided by default and uses [`Self::as_u32`] to determine
+    /// 24-bit sRGB representation of the colour.
+    ///
+    /// An implementation should provide its own definition if it can offer
+    /// a more direct approximation.  For example, if `Self` represents shades
+    /// of grey, it’s faster to use [`ansi256_from_grey`] than relay on `to_u32`
+    /// conversion; or if it represents a variant which can store index in the
+    /// palette or an RGB colour, it’s better to either return the index or
+    /// perform approximation depending on the variant.
+    #[inline]
+    fn to_ansi256(&self) -> u8 {
+        crate::system::ansi256_from_rgb(self.as_u32())
+    }
+
+    /// Returns index of a system colour (i.e. one of the first 16 colours of
+    /// the palette) approximating given sRGB colour.
+    ///
+    /// This is provided by default and uses [`Self::as_u32`] to determine
+    /// 24-bit sRGB representation of the colour which is then approximated
+    /// using current values of the system colours.  See
+    /// [`ansi16_from_rgb`].
+    #[inline]
+    fn to_ansi16(&self) -> u8 {
+        crate::ansi256::ansi16_from_rgb(self.as_u32())
     }
 }
 
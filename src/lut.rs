@@ -0,0 +1,136 @@
+// ansi_colours – true-colour ↔ ANSI terminal palette converter
+// Copyright 2018 by Michał Nazarewicz <mina86@mina86.com>
+//
+// ansi_colours is free software: you can redistribute it and/or modify it
+// under the terms of the GNU Lesser General Public License as published by
+// the Free Software Foundation; either version 3 of the License, or (at
+// your option) any later version.
+//
+// ansi_colours is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Lesser
+// General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with ansi_colours.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Precomputed lookup table of approximations.
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+use crate::AsRGB;
+
+/// A precomputed three-dimensional lookup table of approximations of sRGB
+/// colours by colours in the 256-colour ANSI palette.
+///
+/// The table samples the sRGB colour cube on a regular grid with `2ᵇⁱᵗˢ`
+/// points along each axis.  A colour is approximated by looking up the grid
+/// point nearest to it (i.e. each component is rounded to the nearest grid
+/// value) and returning index precomputed for that point.  This trades
+/// a small loss of accuracy for speed which matters in batch workloads such
+/// as converting images or video frames.
+///
+/// The table takes `2³ᵇⁱᵗˢ` bytes, i.e. 32 KiB for five bits and 256 KiB
+/// for six bits.  With eight bits the table holds approximation of every
+/// sRGB colour and gives the same results as
+/// [`ansi256_from_rgb`](crate::ansi256_from_rgb) at the cost of 16 MiB of
+/// memory.
+///
+/// The approximations are computed with `ansi256_from_rgb` when the table is
+/// constructed.  Changes made to the system colours (see
+/// [`set_system_colours_in_approximation`]) afterwards don’t affect the table.
+///
+/// This type is present only if `alloc` crate feature is enabled.
+///
+/// [`set_system_colours_in_approximation`]: crate::set_system_colours_in_approximation
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::{ansi256_from_rgb, Lut3d};
+///
+/// let lut = Lut3d::new(5);
+/// assert_eq!(5, lut.bits());
+/// assert_eq!( 16, lut.nearest(0x000000));
+/// assert_eq!(231, lut.nearest((255, 255, 255)));
+/// assert_eq!( 67, lut.nearest([95, 135, 175]));
+///
+/// // Colours close to grid points approximate the same way.
+/// assert_eq!(ansi256_from_rgb((99, 132, 173)), lut.nearest((99, 132, 173)));
+///
+/// let pixels = [[0, 0, 0], [95, 135, 175], [255, 255, 255]];
+/// let mut output = [0; 3];
+/// lut.nearest_many(&pixels, &mut output);
+/// assert_eq!([16, 67, 231], output);
+/// ```
+#[derive(Clone)]
+pub struct Lut3d {
+    /// Indexes for each grid point.  Blue coordinate changes fastest.
+    table: Box<[u8]>,
+    /// Number of bits of each coordinate.
+    bits: u8,
+}
+
+impl Lut3d {
+    /// Constructs a new table with `2ᵇⁱᵗˢ` grid points along each axis.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bits` is zero or greater than eight.
+    pub fn new(bits: u8) -> Self {
+        assert!((1..=8).contains(&bits), "invalid number of bits: {}", bits);
+        let max = (1u32 << bits) - 1;
+        let value = |coord: u32| (coord * 255 * 2 + max) / (max * 2);
+        let table = (0..1u32 << (3 * bits))
+            .map(|point| {
+                let r = value(point >> (2 * bits));
+                let g = value((point >> bits) & max);
+                let b = value(point & max);
+                crate::system::ansi256_from_rgb((r << 16) | (g << 8) | b)
+            })
+            .collect::<Vec<_>>()
+            .into_boxed_slice();
+        Self { table, bits }
+    }
+
+    /// Returns number of bits of each coordinate of the grid.
+    #[inline]
+    pub fn bits(&self) -> u8 {
+        self.bits
+    }
+
+    /// Returns index of a colour in the 256-colour ANSI palette approximating
+    /// given sRGB colour.
+    #[inline]
+    pub fn nearest<C: AsRGB>(&self, rgb: C) -> u8 {
+        let rgb = rgb.as_u32();
+        let max = (1u32 << self.bits) - 1;
+        let coord = |shift: u32| ((rgb >> shift & 0xff) * max + 127) / 255;
+        let point =
+            (coord(16) << (2 * self.bits)) | (coord(8) << self.bits) | coord(0);
+        self.table[point as usize]
+    }
+
+    /// Approximates sRGB colours given as `[r, g, b]` triples and writes
+    /// resulting indexes to `output`.  See also
+    /// [`ansi256_from_rgb_many`](crate::ansi256_from_rgb_many).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `output` has different length than `pixels`.
+    pub fn nearest_many(&self, pixels: &[[u8; 3]], output: &mut [u8]) {
+        assert_eq!(pixels.len(), output.len(), "output has invalid length");
+        for (rgb, out) in pixels.iter().zip(output.iter_mut()) {
+            *out = self.nearest(rgb);
+        }
+    }
+}
+
+impl core::fmt::Debug for Lut3d {
+    fn fmt(&self, fmtr: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        fmtr.debug_struct("Lut3d")
+            .field("bits", &self.bits)
+            .finish()
+    }
+}
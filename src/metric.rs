@@ -0,0 +1,342 @@
+// ansi_colours – true-colour ↔ ANSI terminal palette converter
+// Copyright 2018 by Michał Nazarewicz <mina86@mina86.com>
+//
+// ansi_colours is free software: you can redistribute it and/or modify it
+// under the terms of the GNU Lesser General Public License as published by
+// the Free Software Foundation; either version 3 of the License, or (at
+// your option) any later version.
+//
+// ansi_colours is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Lesser
+// General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with ansi_colours.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Pluggable metrics measuring distance between colours.
+//!
+//! Functions approximating colours by searching through whole palette, such as
+//! [`ansi256_from_rgb_by`](crate::ansi256_from_rgb_by) and
+//! [`Palette::nearest_by`](crate::Palette::nearest_by), are parameterised by
+//! a [`DistanceMetric`].  The module provides a few implementations of the
+//! trait and downstream crates can implement it to plug their own perceptual
+//! models.
+//!
+//! # Examples
+//!
+//! ```
+//! use ansi_colours::ansi256_from_rgb_by;
+//! use ansi_colours::metric::{DistanceMetric, Redmean, SrgbEuclidean};
+//!
+//! /// Compares colours by their green component only.
+//! struct GreenOnly;
+//!
+//! impl DistanceMetric for GreenOnly {
+//!     type Point = u8;
+//!     type Distance = u8;
+//!
+//!     fn point(&self, rgb: u32) -> u8 { (rgb >> 8) as u8 }
+//!     fn distance(&self, x: &u8, y: &u8) -> u8 { x.abs_diff(*y) }
+//! }
+//!
+//! assert_eq!( 67, ansi256_from_rgb_by(( 95, 135, 175), &SrgbEuclidean));
+//! assert_eq!( 67, ansi256_from_rgb_by(( 95, 135, 175), &Redmean));
+//! assert_eq!( 28, ansi256_from_rgb_by(( 95, 135, 175), &GreenOnly));
+//! ```
+
+use crate::ansi256::{self, to_triple};
+
+/// A metric measuring distance between two sRGB colours.
+///
+/// The metric first converts colours into points in its own colour space
+/// (e.g. CIELAB) and then measures distance between the points.  This way
+/// conversion of the colour being approximated needs to be done only once.
+///
+/// Distances need not be proper metric in mathematical sense.  The only
+/// requirement is that smaller distance indicates more similar colours.
+pub trait DistanceMetric {
+    /// Representation of a colour used when measuring distances.
+    type Point;
+
+    /// Type of the distance.
+    type Distance: PartialOrd;
+
+    /// Converts sRGB colour given as `0xRRGGBB` integer into a point.
+    fn point(&self, rgb: u32) -> Self::Point;
+
+    /// Returns distance between two points.
+    fn distance(&self, x: &Self::Point, y: &Self::Point) -> Self::Distance;
+}
+
+/// Squared Euclidean distance in sRGB colour space.
+///
+/// This is the simplest and fastest metric but it poorly matches human
+/// perception.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct SrgbEuclidean;
+
+impl DistanceMetric for SrgbEuclidean {
+    type Point = (u8, u8, u8);
+    type Distance = u32;
+
+    #[inline]
+    fn point(&self, rgb: u32) -> (u8, u8, u8) {
+        to_triple(rgb)
+    }
+
+    fn distance(&self, x: &(u8, u8, u8), y: &(u8, u8, u8)) -> u32 {
+        let r = u32::from(x.0.abs_diff(y.0));
+        let g = u32::from(x.1.abs_diff(y.1));
+        let b = u32::from(x.2.abs_diff(y.2));
+        r * r + g * g + b * b
+    }
+}
+
+/// ‘Redmean’ weighted Euclidean distance in sRGB colour space.
+///
+/// This is the metric used by [`ansi256_from_rgb`](crate::ansi256_from_rgb)
+/// to choose between candidate colours.  See [Colour metric
+/// article](https://www.compuphase.com/cmetric.htm) for description.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Redmean;
+
+impl DistanceMetric for Redmean {
+    type Point = u32;
+    type Distance = u32;
+
+    #[inline]
+    fn point(&self, rgb: u32) -> u32 {
+        rgb & 0xffffff
+    }
+
+    #[inline]
+    fn distance(&self, x: &u32, y: &u32) -> u32 {
+        ansi256::distance(to_triple(*x), *y)
+    }
+}
+
+/// CIE76 colour difference, i.e. squared Euclidean distance in CIELAB colour
+/// space.
+///
+/// This type is present only if `ciede2000` crate feature is enabled.
+#[cfg(feature = "ciede2000")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Lab76;
+
+#[cfg(feature = "ciede2000")]
+impl DistanceMetric for Lab76 {
+    /// Coordinates of the colour in CIELAB colour space.
+    type Point = [f64; 3];
+    type Distance = f64;
+
+    #[inline]
+    fn point(&self, rgb: u32) -> [f64; 3] {
+        De2000.point(rgb)
+    }
+
+    fn distance(&self, x: &[f64; 3], y: &[f64; 3]) -> f64 {
+        let l = x[0] - y[0];
+        let a = x[1] - y[1];
+        let b = x[2] - y[2];
+        l * l + a * a + b * b
+    }
+}
+
+/// CIEDE2000 colour difference.
+///
+/// This is the metric used by
+/// [`ansi256_from_rgb_exact`](crate::ansi256_from_rgb_exact).  It’s the most
+/// accurate but also the slowest of the provided metrics.
+///
+/// This type is present only if `ciede2000` crate feature is enabled.
+#[cfg(feature = "ciede2000")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct De2000;
+
+#[cfg(feature = "ciede2000")]
+impl DistanceMetric for De2000 {
+    /// Coordinates of the colour in CIELAB colour space.
+    type Point = [f64; 3];
+    type Distance = f64;
+
+    fn point(&self, rgb: u32) -> [f64; 3] {
+        let lab = crate::ciede2000::Lab::from_rgb(rgb);
+        [lab.l, lab.a, lab.b]
+    }
+
+    fn distance(&self, x: &[f64; 3], y: &[f64; 3]) -> f64 {
+        use crate::ciede2000::{diff, Lab};
+        let x = Lab {
+            l: x[0],
+            a: x[1],
+            b: x[2],
+        };
+        let y = Lab {
+            l: y[0],
+            a: y[1],
+            b: y[2],
+        };
+        diff(x, y)
+    }
+}
+
+/// Squared Euclidean distance in OKLab colour space.
+///
+/// This is the metric used by
+/// [`ansi256_from_rgb_oklab`](crate::ansi256_from_rgb_oklab).
+///
+/// This type is present only if `oklab` crate feature is enabled.
+#[cfg(feature = "oklab")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Oklab;
+
+#[cfg(feature = "oklab")]
+impl DistanceMetric for Oklab {
+    /// Coordinates of the colour in OKLab colour space.
+    type Point = [f32; 3];
+    type Distance = f32;
+
+    fn point(&self, rgb: u32) -> [f32; 3] {
+        let lab = crate::oklab::Oklab::from_rgb(rgb);
+        [lab.l, lab.a, lab.b]
+    }
+
+    #[inline]
+    fn distance(&self, x: &[f32; 3], y: &[f32; 3]) -> f32 {
+        let lab = crate::oklab::Oklab {
+            l: x[0],
+            a: x[1],
+            b: x[2],
+        };
+        lab.distance(*y)
+    }
+}
+
+/// Weighted squared Euclidean distance in CIELAB colour space which can be
+/// biased towards preserving hue or lightness of approximated colours.
+///
+/// Difference in lightness (L*) is scaled by `lightness` weight while
+/// differences in the chromatic components (a* and b*), i.e. in hue and
+/// chroma, are scaled by `hue` weight.  With equal weights this is the CIE76
+/// colour difference, i.e. the same as [`Lab76`].
+///
+/// Greater `hue` weight keeps colours of distinct hues apart at the cost of
+/// larger error in lightness.  This is useful for example for syntax
+/// highlighting where colours of different tokens should remain
+/// distinguishable.  Greater `lightness` weight does the opposite.
+///
+/// This type is present only if `ciede2000` crate feature is enabled.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::ansi256_from_rgb_by;
+/// use ansi_colours::metric::Weighted;
+///
+/// // Two shades of red collapse into one index with the balanced metric...
+/// let balanced = Weighted::default();
+/// assert_eq!(167, ansi256_from_rgb_by(0xe06c75, &balanced));
+/// assert_eq!(167, ansi256_from_rgb_by(0xbe5046, &balanced));
+///
+/// // ...but remain distinct when hue is preserved.
+/// assert_eq!(210, ansi256_from_rgb_by(0xe06c75, &Weighted::HUE));
+/// assert_eq!(167, ansi256_from_rgb_by(0xbe5046, &Weighted::HUE));
+///
+/// assert_eq!(137, ansi256_from_rgb_by(0xd19a66, &Weighted::HUE));
+/// assert_eq!(173, ansi256_from_rgb_by(0xd19a66, &Weighted::LIGHTNESS));
+/// ```
+#[cfg(feature = "ciede2000")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Weighted {
+    lightness: u8,
+    hue: u8,
+}
+
+#[cfg(feature = "ciede2000")]
+impl Weighted {
+    /// Equal weights, i.e. the CIE76 colour difference.
+    pub const BALANCED: Self = Self::new(1, 1);
+    /// Weights biased towards preserving hue.
+    pub const HUE: Self = Self::new(1, 2);
+    /// Weights biased towards preserving lightness.
+    pub const LIGHTNESS: Self = Self::new(2, 1);
+
+    /// Constructs the metric with given weights of lightness and hue.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the weights is zero.  With zero weight, colours which
+    /// differ only in lightness (or only in hue and chroma) would be at zero
+    /// distance from each other and the metric would approximate colours
+    /// arbitrarily.
+    #[inline]
+    pub const fn new(lightness: u8, hue: u8) -> Self {
+        assert!(lightness != 0 && hue != 0, "weights must be non-zero");
+        Self { lightness, hue }
+    }
+
+    /// Returns weight of the difference in lightness.
+    #[inline]
+    pub const fn lightness(&self) -> u8 {
+        self.lightness
+    }
+
+    /// Returns weight of the differences in the chromatic components.
+    #[inline]
+    pub const fn hue(&self) -> u8 {
+        self.hue
+    }
+}
+
+#[cfg(feature = "ciede2000")]
+impl Default for Weighted {
+    #[inline]
+    fn default() -> Self {
+        Self::BALANCED
+    }
+}
+
+#[cfg(feature = "ciede2000")]
+impl DistanceMetric for Weighted {
+    /// Coordinates of the colour in CIELAB colour space.
+    type Point = [f64; 3];
+    type Distance = f64;
+
+    #[inline]
+    fn point(&self, rgb: u32) -> [f64; 3] {
+        De2000.point(rgb)
+    }
+
+    fn distance(&self, x: &[f64; 3], y: &[f64; 3]) -> f64 {
+        let l = f64::from(self.lightness) * (x[0] - y[0]);
+        let a = f64::from(self.hue) * (x[1] - y[1]);
+        let b = f64::from(self.hue) * (x[2] - y[2]);
+        l * l + a * a + b * b
+    }
+}
+
+/// Returns index of the colour from given sequence which is the closest to
+/// given sRGB colour according to given metric.  If there are multiple closest
+/// colours, returns index of the first one.  Returns zero if the sequence is
+/// empty.
+pub(crate) fn nearest<M: DistanceMetric + ?Sized>(
+    metric: &M,
+    rgb: u32,
+    colours: impl Iterator<Item = u32>,
+) -> u8 {
+    let reference = metric.point(rgb);
+    let mut best: Option<(M::Distance, u8)> = None;
+    for (idx, colour) in colours.enumerate() {
+        let distance = metric.distance(&reference, &metric.point(colour));
+        let better = match &best {
+            Some((best, _)) => distance < *best,
+            None => true,
+        };
+        if better {
+            best = Some((distance, idx as u8));
+        }
+    }
+    best.map_or(0, |(_, idx)| idx)
+}
@@ -0,0 +1,340 @@
+// ansi_colours – true-colour ↔ ANSI terminal palette converter
+// Copyright 2018 by Michał Nazarewicz <mina86@mina86.com>
+//
+// ansi_colours is free software: you can redistribute it and/or modify it
+// under the terms of the GNU Lesser General Public License as published by
+// the Free Software Foundation; either version 3 of the License, or (at
+// your option) any later version.
+//
+// ansi_colours is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Lesser
+// General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with ansi_colours.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Approximation of colours by measuring distance in OKLab colour space.
+//!
+//! This module is present only if `oklab` crate feature is enabled.
+
+// Matrix coefficients are copied verbatim from the reference implementation
+// and tables are generated with more precision than f32 can represent.
+#![allow(clippy::excessive_precision)]
+
+use crate::ansi256::LINEAR;
+
+/// A colour in OKLab colour space.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Oklab {
+    pub(crate) l: f32,
+    pub(crate) a: f32,
+    pub(crate) b: f32,
+}
+
+impl Oklab {
+    /// Converts sRGB colour given as `0xRRGGBB` integer into OKLab colour.
+    pub(crate) fn from_rgb(rgb: u32) -> Self {
+        let r = LINEAR[(rgb >> 16) as u8 as usize];
+        let g = LINEAR[(rgb >> 8) as u8 as usize];
+        let b = LINEAR[rgb as u8 as usize];
+        let l = 0.4122214708 * r + 0.5363325363 * g + 0.0514459929 * b;
+        let m = 0.2119034982 * r + 0.6806995451 * g + 0.1073969566 * b;
+        let s = 0.0883024619 * r + 0.2817188376 * g + 0.6299787005 * b;
+        let (l, m, s) = (libm::cbrtf(l), libm::cbrtf(m), libm::cbrtf(s));
+        Self {
+            l: 0.2104542553 * l + 0.7936177850 * m - 0.0040720468 * s,
+            a: 1.9779984951 * l - 2.4285922050 * m + 0.4505937099 * s,
+            b: 0.0259040371 * l + 0.7827717662 * m - 0.8086757660 * s,
+        }
+    }
+
+    /// Returns lightness, chroma and hue (in radians) of the colour, i.e. its
+    /// representation in OKLCH colour space.
+    pub(crate) fn to_lch(self) -> [f32; 3] {
+        let chroma = libm::sqrtf(self.a * self.a + self.b * self.b);
+        [self.l, chroma, libm::atan2f(self.b, self.a)]
+    }
+
+    /// Constructs colour from its lightness, chroma and hue (in radians).
+    pub(crate) fn from_lch([l, chroma, hue]: [f32; 3]) -> Self {
+        Self {
+            l,
+            a: chroma * libm::cosf(hue),
+            b: chroma * libm::sinf(hue),
+        }
+    }
+
+    /// Returns square of Euclidean distance between two colours.
+    pub(crate) fn distance(self, other: [f32; 3]) -> f32 {
+        let l = self.l - other[0];
+        let a = self.a - other[1];
+        let b = self.b - other[2];
+        l * l + a * a + b * b
+    }
+}
+
+/// Returns index of a colour in 256-colour ANSI palette which is the closest to
+/// given sRGB colour in OKLab colour space.  System colours are not considered.
+pub(crate) fn ansi256_from_rgb(rgb: u32) -> u8 {
+    nearest(Oklab::from_rgb(rgb))
+}
+
+/// Returns index of a colour in 256-colour ANSI palette which is the closest to
+/// given OKLab colour.  System colours are not considered.
+pub(crate) fn nearest(reference: Oklab) -> u8 {
+    let mut best = (f32::INFINITY, 0);
+    for (idx, colour) in PALETTE.iter().enumerate() {
+        let distance = reference.distance(*colour);
+        if distance < best.0 {
+            best = (distance, idx);
+        }
+    }
+    best.1 as u8 + 16
+}
+
+/// Colours of the 256-colour ANSI palette, excluding the 16 system colours,
+/// converted into OKLab colour space.
+#[rustfmt::skip]
+pub(crate) static PALETTE: [[f32; 3]; 240] = [
+    [0.000000,  0.000000,  0.000000],  //  16: #000000
+    [0.219451, -0.015758, -0.151246],  //  17: #00005f
+    [0.281790, -0.020234, -0.194210],  //  18: #000087
+    [0.340826, -0.024473, -0.234898],  //  19: #0000af
+    [0.397396, -0.028535, -0.273886],  //  20: #0000d7
+    [0.452014, -0.032457, -0.311528],  //  21: #0000ff
+    [0.420654, -0.113552,  0.087146],  //  22: #005f00
+    [0.439569, -0.072555, -0.019128],  //  23: #005f5f
+    [0.459084, -0.055260, -0.081797],  //  24: #005f87
+    [0.484907, -0.044125, -0.141145],  //  25: #005faf
+    [0.515824, -0.038224, -0.195811],  //  26: #005fd7
+    [0.550587, -0.036018, -0.246091],  //  27: #005fff
+    [0.540146, -0.145808,  0.111901],  //  28: #008700
+    [0.551833, -0.113177,  0.032740],  //  29: #00875f
+    [0.564434, -0.093165, -0.024561],  //  30: #008787
+    [0.581916, -0.076146, -0.083681],  //  31: #0087af
+    [0.603933, -0.063382, -0.141048],  //  32: #0087d7
+    [0.629922, -0.054718, -0.195386],  //  33: #0087ff
+    [0.653310, -0.176355,  0.135345],  //  34: #00af00
+    [0.661350, -0.150794,  0.075171],  //  35: #00af5f
+    [0.670172, -0.131641,  0.025488],  //  36: #00af87
+    [0.682687, -0.112683, -0.029707],  //  37: #00afaf
+    [0.698880, -0.096042, -0.086168],  //  38: #00afd7
+    [0.718560, -0.082555, -0.141631],  //  39: #00afff
+    [0.761747, -0.205627,  0.157809],  //  40: #00d700
+    [0.767676, -0.185355,  0.110830],  //  41: #00d75f
+    [0.774234, -0.168228,  0.068572],  //  42: #00d787
+    [0.783642, -0.149520,  0.018766],  //  43: #00d7af
+    [0.795999, -0.131386, -0.034638],  //  44: #00d7d7
+    [0.811280, -0.115119, -0.089005],  //  45: #00d7ff
+    [0.866440, -0.233888,  0.179498],  //  46: #00ff00
+    [0.871029, -0.217501,  0.141863],  //  47: #00ff5f
+    [0.876124, -0.202557,  0.106024],  //  48: #00ff87
+    [0.883479, -0.185079,  0.061816],  //  49: #00ffaf
+    [0.893221, -0.166915,  0.012484],  //  50: #00ffd7
+    [0.905399, -0.149444, -0.039398],  //  51: #00ffff
+    [0.304871,  0.109170,  0.061098],  //  52: #5f0000
+    [0.340661,  0.133301, -0.082125],  //  53: #5f005f
+    [0.372896,  0.122497, -0.142492],  //  54: #5f0087
+    [0.411272,  0.104753, -0.195471],  //  55: #5f00af
+    [0.453319,  0.085303, -0.243028],  //  56: #5f00d7
+    [0.497444,  0.066739, -0.286755],  //  57: #5f00ff
+    [0.469953, -0.034649,  0.096405],  //  58: #5f5f00
+    [0.485497,  0.000000,  0.000000],  //  59: #5f5f5f
+    [0.501866,  0.015191, -0.061447],  //  60: #5f5f87
+    [0.523978,  0.024147, -0.121350],  //  61: #5f5faf
+    [0.551029,  0.027281, -0.177434],  //  62: #5f5fd7
+    [0.582061,  0.026063, -0.229438],  //  63: #5f5fff
+    [0.572024, -0.090493,  0.117848],  //  64: #5f8700
+    [0.582555, -0.061045,  0.043482],  //  65: #5f875f
+    [0.593975, -0.042567, -0.012225],  //  66: #5f8787
+    [0.609926, -0.026923, -0.070722],  //  67: #5f87af
+    [0.630177, -0.015575, -0.128172],  //  68: #5f87d7
+    [0.654284, -0.008514, -0.183022],  //  69: #5f87ff
+    [0.675726, -0.135973,  0.139513],  //  70: #5faf00
+    [0.683283, -0.112042,  0.081788],  //  71: #5faf5f
+    [0.691591, -0.093889,  0.033341],  //  72: #5faf87
+    [0.703407, -0.075857, -0.021053],  //  73: #5fafaf
+    [0.718745, -0.060090, -0.077144],  //  74: #5fafd7
+    [0.737456, -0.047498, -0.132573],  //  75: #5fafff
+    [0.778474, -0.174897,  0.160914],  //  76: #5fd700
+    [0.784170, -0.155511,  0.115268],  //  77: #5fd75f
+    [0.790475, -0.139023,  0.073863],  //  78: #5fd787
+    [0.799530, -0.120948,  0.024753],  //  79: #5fd7af
+    [0.811440, -0.103411, -0.028189],  //  80: #5fd7d7
+    [0.826195, -0.087722, -0.082316],  //  81: #5fd7ff
+    [0.879474, -0.209671,  0.181916],  //  82: #5fff00
+    [0.883939, -0.193796,  0.145051],  //  83: #5fff5f
+    [0.888897, -0.179264,  0.109787],  //  84: #5fff87
+    [0.896057, -0.162224,  0.066118],  //  85: #5fffaf
+    [0.905548, -0.144485,  0.017212],  //  86: #5fffd7
+    [0.917423, -0.127422, -0.034378],  //  87: #5fffff
+    [0.391473,  0.140182,  0.078454],  //  88: #870000
+    [0.414512,  0.170348, -0.042265],  //  89: #87005f
+    [0.437430,  0.171167, -0.105453],  //  90: #870087
+    [0.466778,  0.162260, -0.163193],  //  91: #8700af
+    [0.500887,  0.147352, -0.215503],  //  92: #8700d7
+    [0.538323,  0.129673, -0.263363],  //  93: #8700ff
+    [0.514255,  0.018674,  0.104892],  //  94: #875f00
+    [0.527464,  0.049676,  0.017077],  //  95: #875f5f
+    [0.541563,  0.064613, -0.042638],  //  96: #875f87
+    [0.560893,  0.073861, -0.102465],  //  97: #875faf
+    [0.584919,  0.077133, -0.159426],  //  98: #875fd7
+    [0.612912,  0.075502, -0.212750],  //  99: #875fff
+    [0.603449, -0.044492,  0.123790],  // 100: #878700
+    [0.613003, -0.017503,  0.053987],  // 101: #87875f
+    [0.623409,  0.000000,  0.000000],  // 102: #878787
+    [0.638029,  0.015011, -0.057690],  // 103: #8787af
+    [0.656716,  0.025852, -0.115045],  // 104: #8787d7
+    [0.679127,  0.032325, -0.170263],  // 105: #8787ff
+    [0.698891, -0.098495,  0.143860],  // 106: #87af00
+    [0.705995, -0.076016,  0.088593],  // 107: #87af5f
+    [0.713818, -0.058706,  0.041449],  // 108: #87af87
+    [0.724970, -0.041380, -0.012054],  // 109: #87afaf
+    [0.739491, -0.026207, -0.067693],  // 110: #87afd7
+    [0.757268, -0.014173, -0.123020],  // 111: #87afff
+    [0.796217, -0.144566,  0.164229],  // 112: #87d700
+    [0.801682, -0.126030,  0.119965],  // 113: #87d75f
+    [0.807735, -0.110146,  0.079467],  // 114: #87d787
+    [0.816437, -0.092645,  0.031111],  // 115: #87d7af
+    [0.827898, -0.075622, -0.021314],  // 116: #87d7d7
+    [0.842123, -0.060403, -0.075156],  // 117: #87d7ff
+    [0.893517, -0.184872,  0.184531],  // 118: #87ff00
+    [0.897852, -0.169511,  0.148484],  // 119: #87ff5f
+    [0.902669, -0.155391,  0.113836],  // 120: #87ff87
+    [0.909628, -0.138780,  0.070751],  // 121: #87ffaf
+    [0.918860, -0.121449,  0.022315],  // 122: #87ffd7
+    [0.930421, -0.104763, -0.028947],  // 123: #87ffff
+    [0.473489,  0.169551,  0.094890],  // 124: #af0000
+    [0.489616,  0.199144, -0.005628],  // 125: #af005f
+    [0.506474,  0.207407, -0.067725],  // 126: #af0087
+    [0.529074,  0.207028, -0.127547],  // 127: #af00af
+    [0.556513,  0.199294, -0.183185],  // 128: #af00d7
+    [0.587783,  0.186460, -0.234607],  // 129: #af00ff
+    [0.567305,  0.069720,  0.115182],  // 130: #af5f00
+    [0.578324,  0.097317,  0.036918],  // 131: #af5f5f
+    [0.590227,  0.112387, -0.020127],  // 132: #af5f87
+    [0.606775,  0.122835, -0.079194],  // 133: #af5faf
+    [0.627670,  0.127609, -0.136657],  // 134: #af5fd7
+    [0.652407,  0.127187, -0.191182],  // 135: #af5fff
+    [0.643751,  0.005604,  0.131496],  // 136: #af8700
+    [0.652233,  0.030021,  0.067219],  // 137: #af875f
+    [0.661517,  0.046641,  0.015582],  // 138: #af8787
+    [0.674639,  0.061347, -0.040827],  // 139: #af87af
+    [0.691542,  0.072225, -0.097813],  // 140: #af87d7
+    [0.711982,  0.078790, -0.153293],  // 141: #af87ff
+    [0.729876, -0.053814,  0.149725],  // 142: #afaf00
+    [0.736435, -0.033008,  0.097609],  // 143: #afaf5f
+    [0.743672, -0.016631,  0.052232],  // 144: #afaf87
+    [0.754017,  0.000000,  0.000000],  // 145: #afafaf
+    [0.767537,  0.014701, -0.054931],  // 146: #afafd7
+    [0.784158,  0.026378, -0.110021],  // 147: #afafff
+    [0.820577, -0.106200,  0.168810],  // 148: #afd700
+    [0.825747, -0.088715,  0.126384],  // 149: #afd75f
+    [0.831478, -0.073561,  0.087127],  // 150: #afd787
+    [0.839728, -0.056730,  0.039832],  // 151: #afd7af
+    [0.850613, -0.040265, -0.011842],  // 152: #afd7d7
+    [0.864152, -0.025510, -0.065247],  // 153: #afd7ff
+    [0.913121, -0.152265,  0.188202],  // 154: #afff00
+    [0.917286, -0.137567,  0.153268],  // 155: #afff5f
+    [0.921915, -0.123974,  0.119472],  // 156: #afff87
+    [0.928608, -0.107903,  0.077209],  // 157: #afffaf
+    [0.937495, -0.091069,  0.029444],  // 158: #afffd7
+    [0.948637, -0.074825, -0.021340],  // 159: #afffff
+    [0.552079,  0.197693,  0.110640],  // 160: #d70000
+    [0.564070,  0.224542,  0.026859],  // 161: #d7005f
+    [0.576937,  0.236681, -0.031833],  // 162: #d70087
+    [0.594683,  0.242469, -0.091449],  // 163: #d700af
+    [0.616890,  0.241390, -0.148717],  // 164: #d700d7
+    [0.642940,  0.234452, -0.202626],  // 165: #d700ff
+    [0.625725,  0.115878,  0.126615],  // 166: #d75f00
+    [0.634887,  0.140336,  0.057752],  // 167: #d75f5f
+    [0.644877,  0.155448,  0.004135],  // 168: #d75f87
+    [0.658928,  0.167287, -0.053379],  // 169: #d75faf
+    [0.676916,  0.174184, -0.110719],  // 170: #d75fd7
+    [0.698523,  0.175998, -0.166032],  // 171: #d75fff
+    [0.690787,  0.055031,  0.140577],  // 172: #d78700
+    [0.698225,  0.076966,  0.082224],  // 173: #d7875f
+    [0.706401,  0.092773,  0.033444],  // 174: #d78787
+    [0.718027,  0.107402, -0.021186],  // 175: #d787af
+    [0.733113,  0.118758, -0.077415],  // 176: #d787d7
+    [0.751510,  0.126053, -0.132905],  // 177: #d787ff
+    [0.767541, -0.006118,  0.156918],  // 178: #d7af00
+    [0.773516,  0.012949,  0.108401],  // 179: #d7af5f
+    [0.780122,  0.028381,  0.065173],  // 180: #d7af87
+    [0.789593,  0.044388,  0.014584],  // 181: #d7afaf
+    [0.802018,  0.058800, -0.039345],  // 182: #d7afd7
+    [0.817364,  0.070415, -0.093998],  // 183: #d7afff
+    [0.851020, -0.062745,  0.174576],  // 184: #d7d700
+    [0.855854, -0.046420,  0.134346],  // 185: #d7d75f
+    [0.861216, -0.032066,  0.096621],  // 186: #d7d787
+    [0.868947, -0.015936,  0.050678],  // 187: #d7d7af
+    [0.879169,  0.000000,  0.000000],  // 188: #d7d7d7
+    [0.891914,  0.014379, -0.052786],  // 189: #d7d7ff
+    [0.938086, -0.113731,  0.192904],  // 190: #d7ff00
+    [0.942049, -0.099801,  0.159340],  // 191: #d7ff5f
+    [0.946455, -0.086814,  0.126615],  // 192: #d7ff87
+    [0.952831, -0.071354,  0.085401],  // 193: #d7ffaf
+    [0.961305, -0.055061,  0.038512],  // 194: #d7ffd7
+    [0.971944, -0.039267, -0.011629],  // 195: #d7ffff
+    [0.627955,  0.224863,  0.125846],  // 196: #ff0000
+    [0.637274,  0.248540,  0.055521],  // 197: #ff005f
+    [0.647421,  0.262294,  0.001315],  // 198: #ff0087
+    [0.661668,  0.272000, -0.056504],  // 199: #ff00af
+    [0.679866,  0.276090, -0.113919],  // 200: #ff00d7
+    [0.701674,  0.274566, -0.169156],  // 201: #ff00ff
+    [0.687216,  0.157248,  0.138725],  // 202: #ff5f00
+    [0.694874,  0.178853,  0.078465],  // 203: #ff5f5f
+    [0.703282,  0.193712,  0.028723],  // 204: #ff5f87
+    [0.715215,  0.206653, -0.026536],  // 205: #ff5faf
+    [0.730664,  0.215635, -0.083064],  // 206: #ff5fd7
+    [0.749454,  0.219997, -0.138602],  // 207: #ff5fff
+    [0.742636,  0.101583,  0.150667],  // 208: #ff8700
+    [0.749125,  0.121215,  0.098172],  // 209: #ff875f
+    [0.756284,  0.136208,  0.052572],  // 210: #ff8787
+    [0.766516,  0.150805,  0.000169],  // 211: #ff87af
+    [0.779883,  0.162825, -0.054873],  // 212: #ff87d7
+    [0.796311,  0.171219, -0.110021],  // 213: #ff87ff
+    [0.810599,  0.041587,  0.165207],  // 214: #ffaf00
+    [0.815996,  0.058955,  0.120483],  // 215: #ffaf5f
+    [0.821973,  0.073455,  0.079676],  // 216: #ffaf87
+    [0.830566,  0.088897,  0.031054],  // 217: #ffafaf
+    [0.841881,  0.103166, -0.021566],  // 218: #ffafd7
+    [0.855920,  0.114967, -0.075534],  // 219: #ffafff
+    [0.886771, -0.016925,  0.181398],  // 220: #ffd700
+    [0.891249, -0.001795,  0.143595],  // 221: #ffd75f
+    [0.896222,  0.011738,  0.107629],  // 222: #ffd787
+    [0.903401,  0.027170,  0.063295],  // 223: #ffd7af
+    [0.912914,  0.042630,  0.013854],  // 224: #ffd7d7
+    [0.924806,  0.056749, -0.038115],  // 225: #ffd7ff
+    [0.967983, -0.071369,  0.198570],  // 226: #ffff00
+    [0.971722, -0.058265,  0.166575],  // 227: #ffff5f
+    [0.975882, -0.045930,  0.135101],  // 228: #ffff87
+    [0.981905, -0.031116,  0.095144],  // 229: #ffffaf
+    [0.989921, -0.015371,  0.049329],  // 230: #ffffd7
+    [1.000000,  0.000000,  0.000000],  // 231: #ffffff
+    [0.134409,  0.000000,  0.000000],  // 232: #080808
+    [0.182204,  0.000000,  0.000000],  // 233: #121212
+    [0.226450,  0.000000,  0.000000],  // 234: #1c1c1c
+    [0.268618,  0.000000,  0.000000],  // 235: #262626
+    [0.309186,  0.000000,  0.000000],  // 236: #303030
+    [0.348460,  0.000000,  0.000000],  // 237: #3a3a3a
+    [0.386654,  0.000000,  0.000000],  // 238: #444444
+    [0.423926,  0.000000,  0.000000],  // 239: #4e4e4e
+    [0.460396,  0.000000,  0.000000],  // 240: #585858
+    [0.496156,  0.000000,  0.000000],  // 241: #626262
+    [0.531282,  0.000000,  0.000000],  // 242: #6c6c6c
+    [0.565836,  0.000000,  0.000000],  // 243: #767676
+    [0.599871,  0.000000,  0.000000],  // 244: #808080
+    [0.633429,  0.000000,  0.000000],  // 245: #8a8a8a
+    [0.666548,  0.000000,  0.000000],  // 246: #949494
+    [0.699261,  0.000000,  0.000000],  // 247: #9e9e9e
+    [0.731595,  0.000000,  0.000000],  // 248: #a8a8a8
+    [0.763576,  0.000000,  0.000000],  // 249: #b2b2b2
+    [0.795225,  0.000000,  0.000000],  // 250: #bcbcbc
+    [0.826562,  0.000000,  0.000000],  // 251: #c6c6c6
+    [0.857605,  0.000000,  0.000000],  // 252: #d0d0d0
+    [0.888370,  0.000000,  0.000000],  // 253: #dadada
+    [0.918870,  0.000000,  0.000000],  // 254: #e4e4e4
+    [0.949119,  0.000000,  0.000000],  // 255: #eeeeee
+];
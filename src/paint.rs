@@ -0,0 +1,322 @@
+// ansi_colours – true-colour ↔ ANSI terminal palette converter
+// Copyright 2018 by Michał Nazarewicz <mina86@mina86.com>
+//
+// ansi_colours is free software: you can redistribute it and/or modify it
+// under the terms of the GNU Lesser General Public License as published by
+// the Free Software Foundation; either version 3 of the License, or (at
+// your option) any later version.
+//
+// ansi_colours is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Lesser
+// General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with ansi_colours.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Values displayed in colour.
+
+use core::fmt;
+
+use crate::{AsRGB, Colour, ColourDepth, Layer, Sgr};
+
+/// A value displayed with given foreground and background colours.
+///
+/// When formatted, the value is surrounded by escape sequences which set the
+/// colours and then restore terminal’s default colours.  The colours are
+/// converted to ones supported by the terminal according to configured
+/// colour depth: true-colour terminals get the colours as they are, on
+/// 256-colour terminals sRGB colours are approximated with [`ansi256_from_rgb`]
+/// and on 16- and 8-colour terminals all colours are approximated by system
+/// colours.  With [`ColourDepth::Monochrome`] the value is formatted without
+/// any escape sequences.
+///
+/// The type doesn’t allocate memory and formatting options (such as width)
+/// are applied to the value.  It’s meant for small tools which want coloured
+/// output without pulling a full styling crate.  See [`Sgr`] for writing the
+/// escape sequences directly.
+///
+/// This type is present only if `paint` crate feature is enabled.
+///
+/// [`ansi256_from_rgb`]: crate::ansi256_from_rgb
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::{ColourDepth, NamedColour, Painted};
+///
+/// let painted = Painted::new("foo").with_fg((95u8, 135, 175));
+/// assert_eq!("\x1b[38;2;95;135;175mfoo\x1b[39m", painted.to_string());
+///
+/// let painted = painted.with_bg(NamedColour::Red);
+/// let painted = painted.with_depth(ColourDepth::Ansi256);
+/// assert_eq!("\x1b[38;5;67m\x1b[41mfoo\x1b[39;49m", painted.to_string());
+///
+/// let painted = painted.with_depth(ColourDepth::Ansi16);
+/// assert_eq!("\x1b[90m\x1b[41m  foo\x1b[39;49m",
+///            format!("{:>5}", painted));
+///
+/// let painted = painted.with_depth(ColourDepth::Monochrome);
+/// assert_eq!("foo", painted.to_string());
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Painted<T> {
+    /// The value to display.
+    value: T,
+    /// Foreground colour if any.
+    fg: Option<Colour>,
+    /// Background colour if any.
+    bg: Option<Colour>,
+    /// Colour depth supported by the terminal.
+    depth: ColourDepth,
+}
+
+impl<T> Painted<T> {
+    /// Constructs a value displayed without changing colours for a terminal
+    /// supporting true-colour.
+    #[inline]
+    pub const fn new(value: T) -> Self {
+        Self {
+            value,
+            fg: None,
+            bg: None,
+            depth: ColourDepth::TrueColour,
+        }
+    }
+
+    /// Sets foreground colour the value is displayed with.
+    #[inline]
+    pub fn with_fg<C: Into<Colour>>(self, colour: C) -> Self {
+        Self {
+            fg: Some(colour.into()),
+            ..self
+        }
+    }
+
+    /// Sets background colour the value is displayed with.
+    #[inline]
+    pub fn with_bg<C: Into<Colour>>(self, colour: C) -> Self {
+        Self {
+            bg: Some(colour.into()),
+            ..self
+        }
+    }
+
+    /// Sets colour depth supported by the terminal.  With `detect` crate
+    /// feature enabled, the depth can be determined with
+    /// [`detect::detect`](crate::detect::detect).
+    #[inline]
+    pub fn with_depth(self, depth: ColourDepth) -> Self {
+        Self { depth, ..self }
+    }
+
+    /// Returns foreground colour the value is displayed with if any.
+    #[inline]
+    pub const fn fg(&self) -> Option<Colour> {
+        self.fg
+    }
+
+    /// Returns background colour the value is displayed with if any.
+    #[inline]
+    pub const fn bg(&self) -> Option<Colour> {
+        self.bg
+    }
+
+    /// Returns colour depth supported by the terminal.
+    #[inline]
+    pub const fn depth(&self) -> ColourDepth {
+        self.depth
+    }
+
+    /// Returns reference to the value.
+    #[inline]
+    pub const fn get_ref(&self) -> &T {
+        &self.value
+    }
+
+    /// Returns the value.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+
+    /// Converts colour to one supported by the terminal.  Returns `None` if
+    /// the terminal doesn’t support colours.
+    fn convert(&self, colour: Colour) -> Option<Colour> {
+        match (self.depth, colour) {
+            (ColourDepth::Monochrome, _) => None,
+            (_, Colour::Named(_)) => Some(colour),
+            (ColourDepth::Ansi8, Colour::Fixed(idx)) if idx < 8 => {
+                Some(Colour::from_system(idx))
+            }
+            (ColourDepth::Ansi8, _) => {
+                Some(Colour::from_system(crate::ansi8_from_rgb(colour)))
+            }
+            (ColourDepth::Ansi16, Colour::Fixed(idx)) => {
+                Some(Colour::from_system(crate::ansi16_from_ansi256(idx)))
+            }
+            (ColourDepth::Ansi16, _) => {
+                Some(Colour::from_system(crate::ansi16_from_rgb(colour)))
+            }
+            (ColourDepth::Ansi256, Colour::Rgb(..)) => {
+                Some(Colour::Fixed(colour.to_ansi256()))
+            }
+            (ColourDepth::Ansi256 | ColourDepth::TrueColour, _) => Some(colour),
+        }
+    }
+
+    /// Writes escape sequence setting colour of given layer.  On terminals
+    /// supporting only 16 colours, bright system colours are set with
+    /// `ESC[9Nm` (`ESC[10Nm` for background) sequence.
+    fn write_colour(
+        &self,
+        fmtr: &mut fmt::Formatter<'_>,
+        layer: Layer,
+        colour: Colour,
+    ) -> fmt::Result {
+        match colour {
+            Colour::Fixed(idx @ 8..=15)
+                if self.depth < ColourDepth::Ansi256 =>
+            {
+                let base = if layer == Layer::Background { 100 } else { 90 };
+                write!(fmtr, "\x1b[{}m", base + idx - 8)
+            }
+            colour => fmt::Display::fmt(&Sgr::new(layer, colour), fmtr),
+        }
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Painted<T> {
+    fn fmt(&self, fmtr: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let fg = self.fg.and_then(|colour| self.convert(colour));
+        let bg = self.bg.and_then(|colour| self.convert(colour));
+        if let Some(colour) = fg {
+            self.write_colour(fmtr, Layer::Foreground, colour)?;
+        }
+        if let Some(colour) = bg {
+            self.write_colour(fmtr, Layer::Background, colour)?;
+        }
+        self.value.fmt(fmtr)?;
+        match (fg, bg) {
+            (Some(_), Some(_)) => fmtr.write_str("\x1b[39;49m"),
+            (Some(_), None) => fmtr.write_str("\x1b[39m"),
+            (None, Some(_)) => fmtr.write_str("\x1b[49m"),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+/// A colour displayed as a coloured block followed by its index in the
+/// 256-colour palette and its sRGB value.
+///
+/// The block is two spaces whose background is set to the colour as it is,
+/// i.e. sRGB colours use true-colour escape sequence.  The index of sRGB
+/// colours is the index of the colour approximating them.  Index is padded
+/// to three characters so that swatches printed one under the other line up.
+/// This makes it easy to inspect results of conversions in examples, tests
+/// and tools.
+///
+/// This type is present only if `paint` crate feature is enabled.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::{Colour, NamedColour, Swatch};
+///
+/// assert_eq!("\x1b[48;5;67m  \x1b[49m  67 #5f87af",
+///            Swatch(Colour::Fixed(67)).to_string());
+/// assert_eq!("\x1b[48;2;100;140;170m  \x1b[49m  67 #648caa",
+///            Swatch(Colour::Rgb(100, 140, 170)).to_string());
+/// assert_eq!("\x1b[41m  \x1b[49m   1 #cd0000",
+///            Swatch(Colour::Named(NamedColour::Red)).to_string());
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Swatch(pub Colour);
+
+impl fmt::Display for Swatch {
+    fn fmt(&self, fmtr: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rgb = self.0.as_u32();
+        write!(
+            fmtr,
+            "{}  \x1b[49m {:>3} #{:06x}",
+            Sgr::bg(self.0),
+            self.0.to_ansi256(),
+            rgb
+        )
+    }
+}
+
+/// Renders the whole 256-colour palette as text with escape sequences.
+///
+/// The chart has three labelled sections: sixteen system colours in two rows,
+/// the 6×6×6 colour cube as six 6×6 slices (one for each level of red) laid
+/// out three per row and the 24-step grey ramp in two rows.  Each colour is
+/// drawn as its index on background of that colour with black or white text,
+/// whichever is more legible.  The colours are set with 256-colour escape
+/// sequences so the chart shows palette actually used by the terminal.  Lines
+/// are at most 74 characters wide.
+///
+/// This function is present only if `paint` and `alloc` crate features are
+/// enabled.
+///
+/// # Examples
+///
+/// ```
+/// let chart = ansi_colours::palette_chart();
+/// assert_eq!(19, chart.lines().count());
+/// assert!(chart.starts_with("System colours:\n"));
+/// assert!(chart.contains("\x1b[38;5;231m\x1b[48;5;0m   0\x1b[39;49m"));
+/// assert!(chart.contains("\x1b[38;5;16m\x1b[48;5;231m 231\x1b[39;49m"));
+/// ```
+#[cfg(feature = "alloc")]
+pub fn palette_chart() -> alloc::string::String {
+    let mut out = alloc::string::String::new();
+    out.push_str("System colours:\n");
+    for row in 0..2 {
+        for idx in row * 8..row * 8 + 8 {
+            write_chart_cell(&mut out, idx);
+        }
+        out.push('\n');
+    }
+    out.push_str("Colour cube:\n");
+    for first in [0, 3] {
+        for g in 0..6 {
+            for r in first..first + 3 {
+                if r != first {
+                    out.push(' ');
+                }
+                for b in 0..6 {
+                    write_chart_cell(&mut out, 16 + 36 * r + 6 * g + b);
+                }
+            }
+            out.push('\n');
+        }
+    }
+    out.push_str("Greyscale ramp:\n");
+    for row in 0..2 {
+        for idx in 232 + row * 12..=243 + row * 12 {
+            write_chart_cell(&mut out, idx);
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Appends cell of the palette chart showing colour at given index.
+#[cfg(feature = "alloc")]
+fn write_chart_cell(out: &mut alloc::string::String, idx: u8) {
+    use core::fmt::Write;
+
+    let dark = crate::luminance_from_ansi256(idx) < 50;
+    let text = Colour::Fixed(if dark { 231 } else { 16 });
+    // Writing into a String doesn’t fail.
+    let _ = write!(
+        out,
+        "{}{}{:>4}\x1b[39;49m",
+        Sgr::fg(text),
+        Sgr::bg(Colour::Fixed(idx)),
+        idx
+    );
+}
@@ -0,0 +1,224 @@
+// ansi_colours – true-colour ↔ ANSI terminal palette converter
+// Copyright 2018 by Michał Nazarewicz <mina86@mina86.com>
+//
+// ansi_colours is free software: you can redistribute it and/or modify it
+// under the terms of the GNU Lesser General Public License as published by
+// the Free Software Foundation; either version 3 of the License, or (at
+// your option) any later version.
+//
+// ansi_colours is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Lesser
+// General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with ansi_colours.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Approximation of colours using arbitrary palettes.
+
+use crate::ansi256::{distance, to_triple};
+use crate::{AsRGB, DistanceMetric};
+
+/// Maximum number of colours in a palette.
+const MAX_LEN: usize = 256;
+
+/// An arbitrary palette of up to 256 sRGB colours.
+///
+/// The type makes it possible to approximate colours using a palette other
+/// than the default 256-colour ANSI palette, e.g. the actual palette of the
+/// terminal as configured by the user.  Colours are looked up in a k-d tree
+/// built when the palette is constructed so [`Palette::nearest`] doesn’t need
+/// to compare against every colour in the palette.
+///
+/// Colours are compared using the same distance function as the one used by
+/// [`ansi256_from_rgb`](crate::ansi256_from_rgb) when comparing candidates.
+/// Note however that unlike `ansi256_from_rgb`, `Palette` considers all the
+/// colours it holds, including system colours if they were included.
+///
+/// The type doesn’t allocate memory and is thus available in `no_std`
+/// environments without `alloc`.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::Palette;
+///
+/// let palette = Palette::new(&[0x000000, 0xff0000, 0x00ff00, 0xffffff]);
+/// assert_eq!(4, palette.len());
+/// assert_eq!(Some((255, 0, 0)), palette.get(1));
+/// assert_eq!(None, palette.get(4));
+///
+/// assert_eq!(0, palette.nearest((10, 10, 10)));
+/// assert_eq!(1, palette.nearest((200, 30, 30)));
+/// assert_eq!(2, palette.nearest(0x20c020));
+/// assert_eq!(3, palette.nearest([200, 200, 200]));
+/// ```
+#[derive(Clone)]
+pub struct Palette {
+    /// Colours of the palette in the order they were given.
+    colours: [u32; MAX_LEN],
+    /// Colours of the palette, together with their indexes, arranged into an
+    /// implicit k-d tree.  Root of a (sub)tree stored in a slice is the middle
+    /// element of the slice with elements before and after it forming left
+    /// and right subtrees respectively.  Nodes at depth `n` split the space on
+    /// red, green or blue axis depending on whether `n % 3` is 0, 1 or 2.
+    tree: [(u32, u8); MAX_LEN],
+    /// Number of colours in the palette.
+    len: u16,
+}
+
+// Palette is never empty so there’s no point in having `is_empty` method.
+#[allow(clippy::len_without_is_empty)]
+impl Palette {
+    /// Constructs a new palette holding given colours.
+    ///
+    /// Index of each colour in the slice becomes index of that colour in the
+    /// palette.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `colours` is empty or has more than 256 elements.
+    pub fn new<C: AsRGB>(colours: &[C]) -> Self {
+        assert!(
+            !colours.is_empty() && colours.len() <= MAX_LEN,
+            "palette must have between 1 and 256 colours"
+        );
+        let mut palette = Self {
+            colours: [0; MAX_LEN],
+            tree: [(0, 0); MAX_LEN],
+            len: colours.len() as u16,
+        };
+        for (idx, colour) in colours.iter().enumerate() {
+            let rgb = colour.as_u32() & 0xffffff;
+            palette.colours[idx] = rgb;
+            palette.tree[idx] = (rgb, idx as u8);
+        }
+        build(&mut palette.tree[..colours.len()], 0);
+        palette
+    }
+
+    /// Returns number of colours in the palette.
+    #[inline]
+    pub fn len(&self) -> usize {
+        usize::from(self.len)
+    }
+
+    /// Returns colour at given index or `None` if the index is out of range.
+    #[inline]
+    pub fn get(&self, idx: u8) -> Option<(u8, u8, u8)> {
+        self.colours[..self.len()]
+            .get(usize::from(idx))
+            .copied()
+            .map(to_triple)
+    }
+
+    /// Returns index of the colour in the palette which best approximates
+    /// given sRGB colour.
+    ///
+    /// If there are multiple closest colours, returns the lowest index.
+    pub fn nearest<C: AsRGB>(&self, rgb: C) -> u8 {
+        let rgb = rgb.as_u32() & 0xffffff;
+        let mut best = (u32::MAX, 0);
+        search(&self.tree[..self.len()], 0, rgb, &mut best);
+        best.1
+    }
+
+    /// Returns index of the colour in the palette which is the closest to
+    /// given sRGB colour according to given distance metric.
+    ///
+    /// Unlike [`Palette::nearest`], this method performs a linear search
+    /// through all colours of the palette.  If there are multiple closest
+    /// colours, returns the lowest index.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ansi_colours::Palette;
+    /// use ansi_colours::metric::{Redmean, SrgbEuclidean};
+    ///
+    /// let palette = Palette::new(&[0x000000, 0x808080, 0x0000ff]);
+    /// assert_eq!(2, palette.nearest_by(0x007bbc, &SrgbEuclidean));
+    /// assert_eq!(1, palette.nearest_by(0x007bbc, &Redmean));
+    /// ```
+    pub fn nearest_by<C: AsRGB, M: DistanceMetric + ?Sized>(
+        &self,
+        rgb: C,
+        metric: &M,
+    ) -> u8 {
+        let colours = self.colours[..self.len()].iter().copied();
+        crate::metric::nearest(metric, rgb.as_u32(), colours)
+    }
+}
+
+impl core::fmt::Debug for Palette {
+    fn fmt(&self, fmtr: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        fmtr.debug_tuple("Palette")
+            .field(&&self.colours[..self.len()])
+            .finish()
+    }
+}
+
+impl PartialEq for Palette {
+    fn eq(&self, other: &Self) -> bool {
+        self.colours[..self.len()] == other.colours[..other.len()]
+    }
+}
+
+impl Eq for Palette {}
+
+/// Returns component of given colour on axis used at given depth of the tree.
+#[inline]
+fn component(rgb: u32, depth: u32) -> u8 {
+    (rgb >> (16 - 8 * (depth % 3))) as u8
+}
+
+/// Minimum weight of squared difference on given axis used by the [`distance`]
+/// function.  Used to calculate lower bound of distance to colours on the
+/// other side of a splitting plane.
+#[inline]
+fn axis_weight(depth: u32) -> u32 {
+    if depth % 3 == 1 {
+        2048
+    } else {
+        1024
+    }
+}
+
+/// Arranges nodes into an implicit k-d tree.
+fn build(nodes: &mut [(u32, u8)], depth: u32) {
+    if nodes.len() <= 1 {
+        return;
+    }
+    let mid = nodes.len() / 2;
+    nodes.select_nth_unstable_by_key(mid, |&(rgb, _)| component(rgb, depth));
+    let (left, right) = nodes.split_at_mut(mid);
+    build(left, depth + 1);
+    build(&mut right[1..], depth + 1);
+}
+
+/// Searches the tree for the colour closest to given one updating `best` with
+/// `(distance, index)` of the best candidate found so far.
+fn search(nodes: &[(u32, u8)], depth: u32, rgb: u32, best: &mut (u32, u8)) {
+    if nodes.is_empty() {
+        return;
+    }
+    let mid = nodes.len() / 2;
+    let (colour, idx) = nodes[mid];
+    let candidate = (distance(to_triple(rgb), colour), idx);
+    if candidate < *best {
+        *best = candidate;
+    }
+
+    let value = component(rgb, depth);
+    let split = component(colour, depth);
+    let (near, far) = if value < split {
+        (&nodes[..mid], &nodes[mid + 1..])
+    } else {
+        (&nodes[mid + 1..], &nodes[..mid])
+    };
+    search(near, depth + 1, rgb, best);
+    let diff = u32::from(value.abs_diff(split));
+    if axis_weight(depth) * diff * diff <= best.0 {
+        search(far, depth + 1, rgb, best);
+    }
+}
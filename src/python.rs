@@ -0,0 +1,92 @@
+// ansi_colours – true-colour ↔ ANSI terminal palette converter
+// Copyright 2018 by Michał Nazarewicz <mina86@mina86.com>
+//
+// ansi_colours is free software: you can redistribute it and/or modify it
+// under the terms of the GNU Lesser General Public License as published by
+// the Free Software Foundation; either version 3 of the License, or (at
+// your option) any later version.
+//
+// ansi_colours is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Lesser
+// General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with ansi_colours.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Python bindings.
+//!
+//! The module defines `ansi_colours` Python extension module exposing the
+//! conversion functions to Python code.  To build the extension, create
+//! a `cdylib` crate which depends on this crate with the `python` feature
+//! enabled and re-exports [`ansi_colours`], e.g. using
+//! [maturin](https://www.maturin.rs/).  The Python module provides the
+//! following functions:
+//!
+//! - `ansi256_from_rgb(rgb)` where `rgb` is either an `(r, g, b)` tuple or
+//!   a `0xRRGGBB` integer,
+//! - `ansi256_from_grey(component)` and
+//! - `rgb_from_ansi256(index)` returning an `(r, g, b)` tuple.
+//!
+//! They behave exactly like the Rust functions of the same name except that
+//! `ansi256_from_rgb` raises `ValueError` if given an integer greater than
+//! `0xFFFFFF` rather than ignoring its most significant bits.
+//!
+//! ```python
+//! import ansi_colours
+//!
+//! assert ansi_colours.ansi256_from_rgb((95, 135, 175)) == 67
+//! assert ansi_colours.ansi256_from_rgb(0x5f87af) == 67
+//! assert ansi_colours.ansi256_from_grey(128) == 244
+//! assert ansi_colours.rgb_from_ansi256(67) == (95, 135, 175)
+//! ```
+//!
+//! This module is present only if `python` crate feature is enabled.
+
+use pyo3::prelude::*;
+
+/// An sRGB colour passed from Python code.
+#[derive(FromPyObject)]
+enum Rgb {
+    /// Colour given as `(r, g, b)` tuple.
+    Tuple(u8, u8, u8),
+    /// Colour given as `0xRRGGBB` integer.
+    Int(u32),
+}
+
+/// Returns index of a colour in 256-colour ANSI palette approximating given
+/// sRGB colour.  Raises `ValueError` if the colour is given as an integer
+/// greater than `0xFFFFFF`.
+#[pyfunction]
+fn ansi256_from_rgb(rgb: Rgb) -> PyResult<u8> {
+    match rgb {
+        Rgb::Tuple(r, g, b) => Ok(crate::ansi256_from_rgb((r, g, b))),
+        Rgb::Int(rgb) if rgb <= 0xffffff => Ok(crate::ansi256_from_rgb(rgb)),
+        Rgb::Int(rgb) => Err(pyo3::exceptions::PyValueError::new_err(
+            std::format!("colour {:#x} is greater than 0xFFFFFF", rgb),
+        )),
+    }
+}
+
+/// Returns index of a colour in 256-colour ANSI palette approximating given
+/// shade of grey.
+#[pyfunction]
+fn ansi256_from_grey(component: u8) -> u8 {
+    crate::ansi256_from_grey(component)
+}
+
+/// Returns sRGB colour corresponding to the index in the 256-colour ANSI
+/// palette.
+#[pyfunction]
+fn rgb_from_ansi256(idx: u8) -> (u8, u8, u8) {
+    crate::rgb_from_ansi256(idx)
+}
+
+/// Initialises the `ansi_colours` Python module.
+#[pymodule]
+pub fn ansi_colours(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_function(wrap_pyfunction!(ansi256_from_rgb, module)?)?;
+    module.add_function(wrap_pyfunction!(ansi256_from_grey, module)?)?;
+    module.add_function(wrap_pyfunction!(rgb_from_ansi256, module)?)?;
+    Ok(())
+}
@@ -0,0 +1,210 @@
+// ansi_colours – true-colour ↔ ANSI terminal palette converter
+// Copyright 2018 by Michał Nazarewicz <mina86@mina86.com>
+//
+// ansi_colours is free software: you can redistribute it and/or modify it
+// under the terms of the GNU Lesser General Public License as published by
+// the Free Software Foundation; either version 3 of the License, or (at
+// your option) any later version.
+//
+// ansi_colours is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Lesser
+// General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with ansi_colours.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Escape sequences setting colours of the text.
+
+use core::fmt;
+
+use crate::Colour;
+
+/// Part of the text whose colour is set by an [`Sgr`] sequence.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Layer {
+    /// Colour of the text itself.
+    Foreground,
+    /// Colour of the background of the text.
+    Background,
+    /// Colour of the underline.  Not all terminals support it.
+    Underline,
+}
+
+impl Layer {
+    /// Returns SGR parameter which selects extended colour of the layer.
+    fn selector(self) -> &'static [u8] {
+        match self {
+            Self::Foreground => b"38",
+            Self::Background => b"48",
+            Self::Underline => b"58",
+        }
+    }
+}
+
+/// SGR escape sequence setting colour of the text.
+///
+/// The sequence uses the shortest form which can express the colour: named
+/// colours are set with `ESC[3Nm` (`ESC[4Nm` for background) sequence,
+/// indexes in the palette with `ESC[38;5;Nm` sequence and sRGB colours with
+/// `ESC[38;2;R;G;Bm` true-colour sequence (or their `48` background and `58`
+/// underline colour equivalents).  Underline colour can be set only with the
+/// extended forms so named colours are written as indexes in the palette.
+///
+/// The sequence is produced without allocating memory.  It can be written
+/// into a [`fmt::Write`] through the [`Display`](fmt::Display)
+/// implementation or encoded into a fixed-size buffer with [`Self::encode`].
+/// sRGB colours are output as they are; to approximate them use
+/// [`ColourExt::to_256`](crate::ColourExt::to_256) first.
+///
+/// # Examples
+///
+/// ```
+/// use core::fmt::Write;
+///
+/// use ansi_colours::{Colour, ColourExt, NamedColour, Sgr};
+///
+/// let mut out = String::new();
+/// write!(out, "{}foo", Sgr::fg(NamedColour::Red)).unwrap();
+/// write!(out, "{}bar", Sgr::bg(67u8)).unwrap();
+/// write!(out, "{}baz", Sgr::fg((95u8, 135, 175))).unwrap();
+/// let colour = Colour::Rgb(100, 140, 170).to_256();
+/// write!(out, "{}qux", Sgr::fg(colour)).unwrap();
+/// assert_eq!("\x1b[31mfoo\x1b[48;5;67mbar\x1b[38;2;95;135;175mbaz\
+///             \x1b[38;5;67mqux", out);
+///
+/// let mut buf = [0; Sgr::MAX_LEN];
+/// let sgr = Sgr::underline(NamedColour::Red);
+/// assert_eq!("\x1b[58;5;1m", sgr.encode(&mut buf));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Sgr {
+    /// Part of the text whose colour is set.
+    pub layer: Layer,
+    /// The colour to set.
+    pub colour: Colour,
+}
+
+impl Sgr {
+    /// Maximum length of the sequence in bytes, i.e. length of
+    /// `ESC[38;2;255;255;255m` sequence.
+    pub const MAX_LEN: usize = 19;
+
+    /// Constructs sequence setting colour of given layer of the text.
+    #[inline]
+    pub fn new<C: Into<Colour>>(layer: Layer, colour: C) -> Self {
+        Self {
+            layer,
+            colour: colour.into(),
+        }
+    }
+
+    /// Constructs sequence setting foreground colour.
+    #[inline]
+    pub fn fg<C: Into<Colour>>(colour: C) -> Self {
+        Self::new(Layer::Foreground, colour)
+    }
+
+    /// Constructs sequence setting background colour.
+    #[inline]
+    pub fn bg<C: Into<Colour>>(colour: C) -> Self {
+        Self::new(Layer::Background, colour)
+    }
+
+    /// Constructs sequence setting underline colour.
+    #[inline]
+    pub fn underline<C: Into<Colour>>(colour: C) -> Self {
+        Self::new(Layer::Underline, colour)
+    }
+
+    /// Writes the sequence into given buffer and returns the written part of
+    /// the buffer as a string.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ansi_colours::Sgr;
+    ///
+    /// let mut buf = [0; Sgr::MAX_LEN];
+    /// assert_eq!("\x1b[38;2;255;255;255m",
+    ///            Sgr::fg(0xffffffu32).encode(&mut buf));
+    /// assert_eq!("\x1b[48;5;42m", Sgr::bg(42u8).encode(&mut buf));
+    /// ```
+    pub fn encode<'a>(&self, buf: &'a mut [u8; Self::MAX_LEN]) -> &'a str {
+        let mut out = Buffer { buf, len: 0 };
+        out.push(b"\x1b[");
+        match (self.layer, self.colour) {
+            (Layer::Foreground, Colour::Named(colour)) => {
+                out.push_number(30 + colour.index())
+            }
+            (Layer::Background, Colour::Named(colour)) => {
+                out.push_number(40 + colour.index())
+            }
+            (layer, Colour::Named(colour)) => {
+                out.push(layer.selector());
+                out.push(b";5;");
+                out.push_number(colour.index());
+            }
+            (layer, Colour::Fixed(idx)) => {
+                out.push(layer.selector());
+                out.push(b";5;");
+                out.push_number(idx);
+            }
+            (layer, Colour::Rgb(r, g, b)) => {
+                out.push(layer.selector());
+                out.push(b";2;");
+                out.push_number(r);
+                out.push(b";");
+                out.push_number(g);
+                out.push(b";");
+                out.push_number(b);
+            }
+        }
+        out.push(b"m");
+        let len = out.len;
+        // The sequence consists of ASCII characters only.
+        core::str::from_utf8(&buf[..len]).expect("SGR sequence isn’t ASCII")
+    }
+}
+
+impl fmt::Display for Sgr {
+    fn fmt(&self, fmtr: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmtr.write_str(self.encode(&mut [0; Self::MAX_LEN]))
+    }
+}
+
+/// Buffer the sequence is written into.
+struct Buffer<'a> {
+    buf: &'a mut [u8; Sgr::MAX_LEN],
+    len: usize,
+}
+
+impl Buffer<'_> {
+    /// Appends bytes to the buffer.
+    fn push(&mut self, bytes: &[u8]) {
+        self.buf[self.len..self.len + bytes.len()].copy_from_slice(bytes);
+        self.len += bytes.len();
+    }
+
+    /// Appends decimal representation of given number to the buffer.
+    fn push_number(&mut self, num: u8) {
+        self.push(write_decimal(&mut [0; 5], num.into()));
+    }
+}
+
+/// Writes decimal representation of given number into given buffer and
+/// returns the written digits.
+pub(crate) fn write_decimal(buf: &mut [u8; 5], mut value: u16) -> &[u8] {
+    let mut idx = buf.len();
+    loop {
+        idx -= 1;
+        buf[idx] = b'0' + (value % 10) as u8;
+        value /= 10;
+        if value == 0 {
+            break;
+        }
+    }
+    &buf[idx..]
+}
@@ -0,0 +1,211 @@
+// ansi_colours – true-colour ↔ ANSI terminal palette converter
+// Copyright 2018 by Michał Nazarewicz <mina86@mina86.com>
+//
+// ansi_colours is free software: you can redistribute it and/or modify it
+// under the terms of the GNU Lesser General Public License as published by
+// the Free Software Foundation; either version 3 of the License, or (at
+// your option) any later version.
+//
+// ansi_colours is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Lesser
+// General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with ansi_colours.  If not, see <http://www.gnu.org/licenses/>.
+
+//! SIMD implementation of batch conversion.
+//!
+//! The implementation performs exactly the same calculations as
+//! [`crate::ansi256::ansi256_from_rgb`] but on multiple colours at once.
+//! Lookups in the greyscale table are done one colour at a time.
+
+/// Converts leading colours in `pixels` into indexes in the 256-colour ANSI
+/// palette writing them to `output`.  Returns number of converted colours
+/// which may be zero if SIMD instructions aren’t available or if system
+/// colours are considered by the approximation.
+///
+/// `pixels` and `output` must have the same length.
+pub(crate) fn ansi256_from_rgb_many(
+    pixels: &[[u8; 3]],
+    output: &mut [u8],
+) -> usize {
+    if crate::system::approximate() {
+        return 0;
+    }
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    if sse41::available() {
+        // SAFETY: Support for SSE4.1 has just been checked.
+        return unsafe { sse41::ansi256_from_rgb_many(pixels, output) };
+    }
+    #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+    let _ = (pixels, output);
+    0
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+mod sse41 {
+    #[cfg(target_arch = "x86")]
+    use core::arch::x86::*;
+    #[cfg(target_arch = "x86_64")]
+    use core::arch::x86_64::*;
+
+    use crate::ansi256::{ansi256_from_grey, colour, CUBE_THRESHOLDS};
+
+    /// Returns whether SSE4.1 instructions are available.  Without `std`
+    /// crate feature, run-time detection isn’t possible and the instructions
+    /// are used only if they are enabled at compile time.
+    pub(super) fn available() -> bool {
+        #[cfg(target_feature = "sse4.1")]
+        return true;
+        #[cfg(all(not(target_feature = "sse4.1"), feature = "std"))]
+        return std::is_x86_feature_detected!("sse4.1");
+        #[cfg(all(not(target_feature = "sse4.1"), not(feature = "std")))]
+        return false;
+    }
+
+    /// Converts colours four at a time.  Returns number of converted colours.
+    ///
+    /// # Safety
+    ///
+    /// The processor must support SSE4.1 instructions.
+    #[target_feature(enable = "sse4.1")]
+    pub(super) unsafe fn ansi256_from_rgb_many(
+        pixels: &[[u8; 3]],
+        output: &mut [u8],
+    ) -> usize {
+        let chunks = pixels.chunks_exact(4).zip(output.chunks_exact_mut(4));
+        for (pixels, output) in chunks {
+            let indexes = convert(pixels);
+            for (out, idx) in output.iter_mut().zip(indexes.iter()) {
+                *out = *idx as u8;
+            }
+        }
+        pixels.len() / 4 * 4
+    }
+
+    /// Converts four colours.  See [`crate::ansi256::ansi256_from_rgb`].
+    #[target_feature(enable = "sse4.1")]
+    unsafe fn convert(pixels: &[[u8; 3]]) -> [i32; 4] {
+        let r = channel(pixels, 0);
+        let g = channel(pixels, 1);
+        let b = channel(pixels, 2);
+
+        // Luminance.  See crate::ansi256::luminance.  The multiplication
+        // wraps the same way for signed and unsigned integers and the sum
+        // doesn’t overflow 32 bits so logical shift gives the right result.
+        let lum = _mm_add_epi32(
+            _mm_add_epi32(
+                _mm_mullo_epi32(r, _mm_set1_epi32(3567664)),
+                _mm_mullo_epi32(g, _mm_set1_epi32(11998547)),
+            ),
+            _mm_add_epi32(
+                _mm_mullo_epi32(b, _mm_set1_epi32(1211005)),
+                _mm_set1_epi32(1 << 23),
+            ),
+        );
+        let lum = to_array(_mm_srli_epi32::<24>(lum));
+
+        // All entries of the greyscale lookup table are shades of grey so
+        // a single component describes them.
+        let mut grey_index = [0; 4];
+        let mut grey_value = [0; 4];
+        for lane in 0..4 {
+            let idx = ansi256_from_grey(lum[lane] as u8);
+            grey_index[lane] = i32::from(idx);
+            grey_value[lane] = (colour(idx) & 0xff) as i32;
+        }
+        let grey_index = from_array(grey_index);
+        let grey = from_array(grey_value);
+        let grey_distance = distance(r, g, b, grey, grey, grey);
+
+        let (ri, rv) = cube_coordinate(r, CUBE_THRESHOLDS[0]);
+        let (gi, gv) = cube_coordinate(g, CUBE_THRESHOLDS[1]);
+        let (bi, bv) = cube_coordinate(b, CUBE_THRESHOLDS[2]);
+        let cube_index = _mm_add_epi32(
+            _mm_add_epi32(
+                _mm_mullo_epi32(ri, _mm_set1_epi32(36)),
+                _mm_mullo_epi32(gi, _mm_set1_epi32(6)),
+            ),
+            _mm_add_epi32(bi, _mm_set1_epi32(16)),
+        );
+        let cube_distance = distance(r, g, b, rv, gv, bv);
+
+        let use_cube = _mm_cmplt_epi32(cube_distance, grey_distance);
+        to_array(_mm_blendv_epi8(grey_index, cube_index, use_cube))
+    }
+
+    /// Returns given component of four colours.
+    #[target_feature(enable = "sse4.1")]
+    unsafe fn channel(pixels: &[[u8; 3]], chan: usize) -> __m128i {
+        _mm_setr_epi32(
+            i32::from(pixels[0][chan]),
+            i32::from(pixels[1][chan]),
+            i32::from(pixels[2][chan]),
+            i32::from(pixels[3][chan]),
+        )
+    }
+
+    /// Returns coordinate in the 6×6×6 cube and corresponding component
+    /// value for given components.  See [`crate::ansi256::cube_thresholds`].
+    #[target_feature(enable = "sse4.1")]
+    unsafe fn cube_coordinate(
+        v: __m128i,
+        thresholds: [u8; 5],
+    ) -> (__m128i, __m128i) {
+        let mut coord = _mm_setzero_si128();
+        for &threshold in thresholds.iter() {
+            let threshold = _mm_set1_epi32(i32::from(threshold) - 1);
+            // Comparison gives -1 in lanes where v ≥ threshold.
+            coord = _mm_sub_epi32(coord, _mm_cmpgt_epi32(v, threshold));
+        }
+        // Apart from zero, component values are 55 + 40 × coordinate.
+        let value = _mm_add_epi32(
+            _mm_mullo_epi32(coord, _mm_set1_epi32(40)),
+            _mm_set1_epi32(55),
+        );
+        let zero = _mm_cmpeq_epi32(coord, _mm_setzero_si128());
+        (coord, _mm_andnot_si128(zero, value))
+    }
+
+    /// Calculates distance between colours.  See
+    /// [`crate::ansi256::distance`].  The result fits in 31 bits.
+    #[target_feature(enable = "sse4.1")]
+    unsafe fn distance(
+        xr: __m128i,
+        xg: __m128i,
+        xb: __m128i,
+        yr: __m128i,
+        yg: __m128i,
+        yb: __m128i,
+    ) -> __m128i {
+        let r_sum = _mm_add_epi32(xr, yr);
+        let r = _mm_sub_epi32(xr, yr);
+        let g = _mm_sub_epi32(xg, yg);
+        let b = _mm_sub_epi32(xb, yb);
+        let r = _mm_mullo_epi32(
+            _mm_add_epi32(_mm_set1_epi32(1024), r_sum),
+            _mm_mullo_epi32(r, r),
+        );
+        let g = _mm_mullo_epi32(_mm_set1_epi32(2048), _mm_mullo_epi32(g, g));
+        let b = _mm_mullo_epi32(
+            _mm_sub_epi32(_mm_set1_epi32(1534), r_sum),
+            _mm_mullo_epi32(b, b),
+        );
+        _mm_add_epi32(_mm_add_epi32(r, g), b)
+    }
+
+    /// Stores vector into an array.
+    #[target_feature(enable = "sse4.1")]
+    unsafe fn to_array(vector: __m128i) -> [i32; 4] {
+        let mut array = [0; 4];
+        _mm_storeu_si128(array.as_mut_ptr().cast(), vector);
+        array
+    }
+
+    /// Loads vector from an array.
+    #[target_feature(enable = "sse4.1")]
+    unsafe fn from_array(array: [i32; 4]) -> __m128i {
+        _mm_loadu_si128(array.as_ptr().cast())
+    }
+}
@@ -0,0 +1,407 @@
+// ansi_colours – true-colour ↔ ANSI terminal palette converter
+// Copyright 2018 by Michał Nazarewicz <mina86@mina86.com>
+//
+// ansi_colours is free software: you can redistribute it and/or modify it
+// under the terms of the GNU Lesser General Public License as published by
+// the Free Software Foundation; either version 3 of the License, or (at
+// your option) any later version.
+//
+// ansi_colours is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Lesser
+// General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with ansi_colours.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Runtime override of system colours.
+//!
+//! Values of the 16 system colours are not standardised and usually
+//! configurable by the user.  By default the crate assumes they have the
+//! default XTerm values.  Functions in this module let application which knows
+//! the actual values (e.g. because it queried the terminal) change that
+//! assumption for the whole crate.
+//!
+//! The values are stored in static atomic variables and thus the override is
+//! available only on targets which support 32-bit atomic operations.  On other
+//! targets the crate always uses default XTerm values.
+
+use crate::ansi256::SYSTEM_COLOURS;
+
+#[cfg(target_has_atomic = "32")]
+use core::sync::atomic::{fence, AtomicBool, AtomicU32, Ordering};
+
+/// Sequence number of the system colours.  Odd value indicates that the
+/// colours are being modified.
+#[cfg(target_has_atomic = "32")]
+static SEQUENCE: AtomicU32 = AtomicU32::new(0);
+
+/// Whether system colours have been overridden.  If not, values from
+/// [`SYSTEM_COLOURS`] are used.
+#[cfg(target_has_atomic = "32")]
+static OVERRIDDEN: AtomicBool = AtomicBool::new(false);
+
+/// Whether system colours should be considered by the approximation.
+#[cfg(target_has_atomic = "32")]
+static APPROXIMATE: AtomicBool = AtomicBool::new(false);
+
+/// Overridden values of the system colours as `0xRRGGBB` integers.
+#[cfg(target_has_atomic = "32")]
+static COLOURS: [AtomicU32; 16] = [const { AtomicU32::new(0) }; 16];
+
+/// Returns colour of the system colour with given index which must be less
+/// than 16.
+#[inline]
+pub(crate) fn colour(idx: u8) -> u32 {
+    #[cfg(target_has_atomic = "32")]
+    if OVERRIDDEN.load(Ordering::Acquire) {
+        return COLOURS[usize::from(idx)].load(Ordering::Relaxed);
+    }
+    SYSTEM_COLOURS[usize::from(idx)]
+}
+
+/// Returns whether system colours have been overridden.
+#[inline]
+pub(crate) fn overridden() -> bool {
+    #[cfg(target_has_atomic = "32")]
+    return OVERRIDDEN.load(Ordering::Acquire);
+    #[cfg(not(target_has_atomic = "32"))]
+    return false;
+}
+
+/// Returns colour at given index in the 256-colour palette taking into account
+/// overridden system colours.
+///
+/// Only system colours need to check whether they have been overridden (which
+/// is a single atomic load).  Remaining colours are calculated without
+/// touching any of the atomic variables.
+#[inline]
+pub(crate) fn palette_colour(idx: u8) -> u32 {
+    if idx < 16 {
+        colour(idx)
+    } else {
+        crate::ansi256::extended_colour(idx)
+    }
+}
+
+/// Returns consistent snapshot of all system colours.
+pub(crate) fn snapshot() -> [u32; 16] {
+    #[cfg(target_has_atomic = "32")]
+    loop {
+        let seq = SEQUENCE.load(Ordering::Acquire);
+        if seq & 1 == 0 {
+            let mut colours = [0; 16];
+            for (idx, colour) in colours.iter_mut().enumerate() {
+                *colour = self::colour(idx as u8);
+            }
+            fence(Ordering::Acquire);
+            if SEQUENCE.load(Ordering::Relaxed) == seq {
+                return colours;
+            }
+        }
+        core::hint::spin_loop();
+    }
+    #[cfg(not(target_has_atomic = "32"))]
+    {
+        SYSTEM_COLOURS
+    }
+}
+
+/// Returns whether system colours should be considered by the approximation.
+#[inline]
+pub(crate) fn approximate() -> bool {
+    #[cfg(target_has_atomic = "32")]
+    return APPROXIMATE.load(Ordering::Relaxed);
+    #[cfg(not(target_has_atomic = "32"))]
+    return false;
+}
+
+/// Returns index of a colour in 256-colour ANSI palette approximating given
+/// sRGB colour.  Considers system colours if enabled with
+/// [`set_system_colours_in_approximation`].
+#[inline]
+pub(crate) fn ansi256_from_rgb(rgb: u32) -> u8 {
+    if approximate() {
+        crate::ansi256::ansi256_from_rgb_with_system(rgb, &snapshot())
+    } else {
+        crate::ansi256::ansi256_from_rgb(rgb)
+    }
+}
+
+/// Returns index of a colour in 256-colour ANSI palette approximating given
+/// sRGB colour with 16-bit components.  Considers system colours if enabled
+/// with [`set_system_colours_in_approximation`] in which case the colour is
+/// rounded to 8-bit components first.
+#[inline]
+pub(crate) fn ansi256_from_rgb16(rgb: (u16, u16, u16)) -> u8 {
+    if approximate() {
+        let (r, g, b) = rgb;
+        let round = |v: u16| u32::from(crate::ansi256::round16(v));
+        let rgb = (round(r) << 16) | (round(g) << 8) | round(b);
+        crate::ansi256::ansi256_from_rgb_with_system(rgb, &snapshot())
+    } else {
+        crate::ansi256::ansi256_from_rgb16(rgb)
+    }
+}
+
+/// Returns index of a colour in 256-colour ANSI palette approximating given
+/// shade of grey.  Considers system colours if enabled with
+/// [`set_system_colours_in_approximation`].
+#[inline]
+pub(crate) fn ansi256_from_grey(component: u8) -> u8 {
+    if approximate() {
+        let rgb = u32::from(component) * 0x010101;
+        crate::ansi256::ansi256_from_rgb_with_system(rgb, &snapshot())
+    } else {
+        crate::ansi256::ansi256_from_grey(component)
+    }
+}
+
+/// Returns index of a colour in 256-colour ANSI palette approximating given
+/// shade of grey with 16-bit precision.  Considers system colours if enabled
+/// with [`set_system_colours_in_approximation`] in which case the shade is
+/// rounded to 8 bits first.
+#[inline]
+pub(crate) fn ansi256_from_grey16(value: u16) -> u8 {
+    if approximate() {
+        ansi256_from_grey(crate::ansi256::round16(value))
+    } else {
+        crate::ansi256::ansi256_from_grey16(value)
+    }
+}
+
+/// Modifies system colours with given function while holding the lock.
+#[cfg(target_has_atomic = "32")]
+fn update(func: impl FnOnce()) {
+    let mut seq = SEQUENCE.load(Ordering::Relaxed);
+    loop {
+        if seq & 1 == 0 {
+            match SEQUENCE.compare_exchange_weak(
+                seq,
+                seq.wrapping_add(1),
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => break,
+                Err(current) => seq = current,
+            }
+        } else {
+            core::hint::spin_loop();
+            seq = SEQUENCE.load(Ordering::Relaxed);
+        }
+    }
+    fence(Ordering::Release);
+    func();
+    SEQUENCE.store(seq.wrapping_add(2), Ordering::Release);
+}
+
+/// Sets values of the 16 system colours used by the crate.
+///
+/// Values used by popular terminals are available through [`SystemPreset`].
+///
+/// After the call, [`rgb_from_ansi256`](crate::rgb_from_ansi256) returns
+/// given colours for indexes 0–15 and functions approximating colours by
+/// system colours (such as [`ansi16_from_rgb`](crate::ansi16_from_rgb)) use
+/// them instead of default XTerm values.  The colours are swapped atomically,
+/// i.e. other threads observe either all old or all new values.
+///
+/// Whether [`ansi256_from_rgb`](crate::ansi256_from_rgb) considers system
+/// colours is controlled separately by [`set_system_colours_in_approximation`].
+///
+/// This function is present only on targets which support 32-bit atomic
+/// operations.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::*;
+///
+/// assert_eq!((205, 0, 0), rgb_from_ansi256(1));
+/// assert_eq!(3, ansi16_from_rgb((196, 160, 0)));
+///
+/// // Tango palette used by GNOME Terminal.
+/// let mut tango = [(0, 0, 0); 16];
+/// tango[..8].copy_from_slice(&[
+///     (0x00, 0x00, 0x00), (0xcc, 0x00, 0x00), (0x4e, 0x9a, 0x06),
+///     (0xc4, 0xa0, 0x00), (0x34, 0x65, 0xa4), (0x75, 0x50, 0x7b),
+///     (0x06, 0x98, 0x9a), (0xd3, 0xd7, 0xcf),
+/// ]);
+/// tango[8..].copy_from_slice(&[
+///     (0x55, 0x57, 0x53), (0xef, 0x29, 0x29), (0x8a, 0xe2, 0x34),
+///     (0xfc, 0xe9, 0x4f), (0x72, 0x9f, 0xcf), (0xad, 0x7f, 0xa8),
+///     (0x34, 0xe2, 0xe2), (0xee, 0xee, 0xec),
+/// ]);
+/// set_system_colours(tango);
+///
+/// assert_eq!(tango, system_colours());
+/// assert_eq!((204, 0, 0), rgb_from_ansi256(1));
+/// assert_eq!((175, 0, 215), rgb_from_ansi256(128));
+/// assert_eq!(3, ansi16_from_rgb((196, 160, 0)));
+/// assert_eq!(12, ansi16_from_rgb((95, 135, 175)));
+///
+/// reset_system_colours();
+/// assert_eq!((205, 0, 0), rgb_from_ansi256(1));
+/// ```
+#[cfg(target_has_atomic = "32")]
+pub fn set_system_colours(colours: [(u8, u8, u8); 16]) {
+    update(|| {
+        for (atomic, (r, g, b)) in COLOURS.iter().zip(colours) {
+            let rgb = (u32::from(r) << 16) | (u32::from(g) << 8) | u32::from(b);
+            atomic.store(rgb, Ordering::Relaxed);
+        }
+        OVERRIDDEN.store(true, Ordering::Release);
+    })
+}
+
+/// Restores default XTerm values of the system colours.
+///
+/// This undoes effects of [`set_system_colours`].  It doesn’t change whether
+/// system colours are considered by the approximation.
+///
+/// This function is present only on targets which support 32-bit atomic
+/// operations.
+#[cfg(target_has_atomic = "32")]
+pub fn reset_system_colours() {
+    update(|| OVERRIDDEN.store(false, Ordering::Release))
+}
+
+/// Returns values of the 16 system colours used by the crate.
+///
+/// Unless changed with [`set_system_colours`], those are default XTerm
+/// values.
+///
+/// # Examples
+///
+/// ```
+/// let colours = ansi_colours::system_colours();
+/// assert_eq!((  0,   0,   0), colours[0]);
+/// assert_eq!((205,   0,   0), colours[1]);
+/// assert_eq!((255, 255, 255), colours[15]);
+/// ```
+pub fn system_colours() -> [(u8, u8, u8); 16] {
+    snapshot().map(crate::ansi256::to_triple)
+}
+
+/// Sets whether [`ansi256_from_rgb`](crate::ansi256_from_rgb) should consider
+/// system colours as candidates when approximating colours.
+///
+/// By default system colours are never returned by the approximation since
+/// their values are not known.  If an application knows their values and
+/// sets them with [`set_system_colours`], it may enable this option to use
+/// the 16 colours in the approximation.  When enabled,
+/// [`ansi256_from_rgb`](crate::ansi256_from_rgb),
+/// [`ansi256_from_grey`](crate::ansi256_from_grey) and the default
+/// implementation of [`AsRGB::to_ansi256`](crate::AsRGB::to_ansi256) behave
+/// like [`ansi256_from_rgb_with_system`](crate::ansi256_from_rgb_with_system)
+/// with the current system colours.
+///
+/// This function is present only on targets which support 32-bit atomic
+/// operations.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::*;
+///
+/// assert_eq!(160, ansi256_from_rgb((205, 0, 0)));
+/// set_system_colours_in_approximation(true);
+/// assert_eq!(  1, ansi256_from_rgb((205, 0, 0)));
+/// assert_eq!(  7, ansi256_from_grey(229));
+/// set_system_colours_in_approximation(false);
+/// assert_eq!(160, ansi256_from_rgb((205, 0, 0)));
+/// ```
+#[cfg(target_has_atomic = "32")]
+pub fn set_system_colours_in_approximation(enabled: bool) {
+    APPROXIMATE.store(enabled, Ordering::Relaxed)
+}
+
+/// Well-known sets of values of the 16 system colours.
+///
+/// Presets can be used with [`set_system_colours`] to make the crate assume
+/// values of the system colours used by a particular terminal or with
+/// functions which take the system colours as argument such as
+/// [`ansi16_from_rgb_with_palette`](crate::ansi16_from_rgb_with_palette).
+///
+/// Values of the presets are taken from [ANSI escape code Wikipedia
+/// article](https://en.wikipedia.org/wiki/ANSI_escape_code#3-bit_and_4-bit).
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::{ansi16_from_rgb_with_palette, SystemPreset};
+///
+/// assert_eq!((170, 85, 0), SystemPreset::Vga.colours()[3]);
+/// assert_eq!(0xaa5500, SystemPreset::Vga.colours_rgb()[3]);
+///
+/// let rgb = (180, 90, 10);
+/// let xterm = SystemPreset::XTerm.colours_rgb();
+/// let vga = SystemPreset::Vga.colours_rgb();
+/// assert_eq!(1, ansi16_from_rgb_with_palette(rgb, &xterm));
+/// assert_eq!(3, ansi16_from_rgb_with_palette(rgb, &vga));
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[non_exhaustive]
+pub enum SystemPreset {
+    /// Default colours of XTerm.  These are the values the crate assumes by
+    /// default.
+    #[default]
+    XTerm,
+    /// Colours of the VGA text mode (also used by Linux console).
+    Vga,
+    /// Colours of the legacy Windows console (before Windows 10 version
+    /// 1709).
+    WindowsConsole,
+    /// Tango palette used by default by GNOME Terminal.
+    Tango,
+    /// Default colours of iTerm2.
+    ITerm2,
+    /// Default colours of PuTTY.
+    PuTTY,
+}
+
+impl SystemPreset {
+    /// Returns values of the system colours as `(r, g, b)` triples.
+    #[inline]
+    pub fn colours(self) -> [(u8, u8, u8); 16] {
+        self.colours_rgb().map(crate::ansi256::to_triple)
+    }
+
+    /// Returns values of the system colours as `0xRRGGBB` integers.
+    #[rustfmt::skip]
+    pub fn colours_rgb(self) -> [u32; 16] {
+        match self {
+            Self::XTerm => SYSTEM_COLOURS,
+            Self::Vga => [
+                0x000000, 0xaa0000, 0x00aa00, 0xaa5500,
+                0x0000aa, 0xaa00aa, 0x00aaaa, 0xaaaaaa,
+                0x555555, 0xff5555, 0x55ff55, 0xffff55,
+                0x5555ff, 0xff55ff, 0x55ffff, 0xffffff,
+            ],
+            Self::WindowsConsole => [
+                0x000000, 0x800000, 0x008000, 0x808000,
+                0x000080, 0x800080, 0x008080, 0xc0c0c0,
+                0x808080, 0xff0000, 0x00ff00, 0xffff00,
+                0x0000ff, 0xff00ff, 0x00ffff, 0xffffff,
+            ],
+            Self::Tango => [
+                0x000000, 0xcc0000, 0x4e9a06, 0xc4a000,
+                0x3465a4, 0x75507b, 0x06989a, 0xd3d7cf,
+                0x555753, 0xef2929, 0x8ae234, 0xfce94f,
+                0x729fcf, 0xad7fa8, 0x34e2e2, 0xeeeeec,
+            ],
+            Self::ITerm2 => [
+                0x000000, 0xc91b00, 0x00c200, 0xc7c400,
+                0x0225c7, 0xc930c7, 0x00c5c7, 0xc7c7c7,
+                0x676767, 0xff6d67, 0x5ff967, 0xfefb67,
+                0x6871ff, 0xff76ff, 0x5ffdff, 0xfeffff,
+            ],
+            Self::PuTTY => [
+                0x000000, 0xbb0000, 0x00bb00, 0xbbbb00,
+                0x0000bb, 0xbb00bb, 0x00bbbb, 0xbbbbbb,
+                0x555555, 0xff5555, 0x55ff55, 0xffff55,
+                0x5555ff, 0xff55ff, 0x55ffff, 0xffffff,
+            ],
+        }
+    }
+}
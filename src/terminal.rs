@@ -0,0 +1,238 @@
+// ansi_colours – true-colour ↔ ANSI terminal palette converter
+// Copyright 2018 by Michał Nazarewicz <mina86@mina86.com>
+//
+// ansi_colours is free software: you can redistribute it and/or modify it
+// under the terms of the GNU Lesser General Public License as published by
+// the Free Software Foundation; either version 3 of the License, or (at
+// your option) any later version.
+//
+// ansi_colours is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Lesser
+// General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with ansi_colours.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Querying terminal for its actual colours.
+//!
+//! Terminals emulating XTerm report values of colours in their palette in
+//! response to OSC 4 query and values of default foreground and background
+//! colours in response to OSC 10 and OSC 11 queries respectively.  Functions in
+//! this module send such queries and parse the responses so that colours can be
+//! approximated using the palette user actually sees.
+//!
+//! Queries are followed by a Primary Device Attributes (DA1) request which
+//! virtually all terminals answer.  Its response marks the end of responses to
+//! colour queries so that terminals which don’t support them don’t cause the
+//! functions to wait indefinitely.
+//!
+//! The functions operate on arbitrary readers and writers and don’t configure
+//! the terminal.  Before calling them, the caller must switch the terminal
+//! into raw (non-canonical, no echo) mode as otherwise responses are echoed
+//! and not delivered until end of line.
+//!
+//! This module is present only if `terminal` crate feature is enabled.
+
+use std::io::{self, Read, Write};
+use std::vec::Vec;
+
+use crate::Palette;
+
+/// Colours reported by the terminal.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TerminalColours {
+    /// The 256-colour palette.  Entries which the terminal didn’t report have
+    /// values returned by [`rgb_from_ansi256`](crate::rgb_from_ansi256).
+    pub palette: Palette,
+    /// Default foreground colour or `None` if the terminal didn’t report it.
+    pub foreground: Option<(u8, u8, u8)>,
+    /// Default background colour or `None` if the terminal didn’t report it.
+    pub background: Option<(u8, u8, u8)>,
+    /// Number of palette entries reported by the terminal.
+    pub reported: usize,
+}
+
+/// Maximum number of bytes read from the terminal before giving up on
+/// waiting for the response to the DA1 request.
+const MAX_RESPONSE_LEN: usize = 64 * 1024;
+
+/// Writes OSC 4, OSC 10 and OSC 11 queries followed by a DA1 request.
+///
+/// This is the first half of [`query`] and is useful if reading responses
+/// must be done in a different way, e.g. with a timeout.  Once the responses
+/// are read, they can be parsed with [`parse_responses`].
+pub fn write_queries<W: Write + ?Sized>(output: &mut W) -> io::Result<()> {
+    let mut buf = Vec::with_capacity(256 * 12 + 32);
+    for idx in 0..=255u8 {
+        // write! to a Vec never fails.
+        let _ = write!(buf, "\x1b]4;{};?\x1b\\", idx);
+    }
+    buf.extend_from_slice(b"\x1b]10;?\x1b\\\x1b]11;?\x1b\\\x1b[c");
+    output.write_all(&buf)?;
+    output.flush()
+}
+
+/// Queries terminal for its colours.
+///
+/// Writes queries to `output` and reads responses from `input` until response
+/// to the DA1 request is received.  Data following the DA1 response, e.g.
+/// keys typed by the user, may be consumed.  See [module
+/// documentation](self) for requirements regarding terminal configuration.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::terminal::query;
+///
+/// // Simulate terminal which reports colour 1 and default background.
+/// let mut input: &[u8] = b"\x1b]4;1;rgb:cccc/0000/0000\x1b\\\
+///                          \x1b]11;rgb:0000/2b2b/3636\x07\
+///                          \x1b[?62;22c";
+/// let mut output = Vec::new();
+/// let colours = query(&mut input, &mut output).unwrap();
+///
+/// assert!(output.starts_with(b"\x1b]4;0;?\x1b\\\x1b]4;1;?\x1b\\"));
+/// assert_eq!(1, colours.reported);
+/// assert_eq!(Some((204, 0, 0)), colours.palette.get(1));
+/// assert_eq!(Some((0, 0, 0)), colours.palette.get(0));
+/// assert_eq!(None, colours.foreground);
+/// assert_eq!(Some((0, 43, 54)), colours.background);
+/// ```
+pub fn query<R: Read + ?Sized, W: Write + ?Sized>(
+    input: &mut R,
+    output: &mut W,
+) -> io::Result<TerminalColours> {
+    write_queries(output)?;
+    let mut response = Vec::new();
+    let mut buf = [0; 1024];
+    while !contains_da1(&response) {
+        if response.len() > MAX_RESPONSE_LEN {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "no response to device attributes request",
+            ));
+        }
+        let len = match input.read(&mut buf) {
+            Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+            Ok(len) => len,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        };
+        response.extend_from_slice(&buf[..len]);
+    }
+    Ok(parse_responses(&response))
+}
+
+/// Checks whether the data contains a complete DA1 response, i.e.
+/// `CSI ? … c`.
+fn contains_da1(mut data: &[u8]) -> bool {
+    while let Some(start) = find(data, b"\x1b[?") {
+        data = &data[start + 3..];
+        let params = data
+            .iter()
+            .take_while(|byte| byte.is_ascii_digit() || **byte == b';')
+            .count();
+        if data.get(params) == Some(&b'c') {
+            return true;
+        }
+    }
+    false
+}
+
+/// Parses terminal’s responses to OSC 4, OSC 10 and OSC 11 queries.
+///
+/// Responses may be terminated by BEL or ST.  Unrecognised data between
+/// responses is ignored.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::terminal::parse_responses;
+///
+/// let colours = parse_responses(b"\x1b]4;231;rgb:ff/ff/ff\x07\
+///                                 \x1b]10;rgb:eeee/eeee/ecec\x1b\\");
+/// assert_eq!(1, colours.reported);
+/// assert_eq!(Some((238, 238, 236)), colours.foreground);
+/// assert_eq!(None, colours.background);
+/// ```
+pub fn parse_responses(data: &[u8]) -> TerminalColours {
+    let mut palette = [0; 256];
+    for (idx, colour) in palette.iter_mut().enumerate() {
+        *colour = crate::system::palette_colour(idx as u8);
+    }
+    let mut reported = [false; 256];
+    let mut foreground = None;
+    let mut background = None;
+
+    let mut rest = data;
+    while let Some(start) = find(rest, b"\x1b]") {
+        rest = &rest[start + 2..];
+        let end = match rest.iter().position(|b| *b == 0x07 || *b == 0x1b) {
+            Some(end) => end,
+            None => break,
+        };
+        let mut params = rest[..end].split(|b| *b == b';');
+        rest = &rest[end..];
+        match (params.next(), params.next(), params.next()) {
+            (Some(b"4"), Some(idx), Some(spec)) => {
+                let idx = parse_index(idx);
+                if let (Some(idx), Some(rgb)) = (idx, parse_colour_spec(spec)) {
+                    palette[usize::from(idx)] = to_u32(rgb);
+                    reported[usize::from(idx)] = true;
+                }
+            }
+            (Some(b"10"), Some(spec), None) => {
+                foreground = parse_colour_spec(spec).or(foreground);
+            }
+            (Some(b"11"), Some(spec), None) => {
+                background = parse_colour_spec(spec).or(background);
+            }
+            _ => (),
+        }
+    }
+
+    TerminalColours {
+        palette: Palette::new(&palette),
+        foreground,
+        background,
+        reported: reported.iter().filter(|r| **r).count(),
+    }
+}
+
+/// Parses colour specification as used in responses to colour queries.
+///
+/// Supports `rgb:R/G/B` format, where each component has one to four
+/// hexadecimal digits, and `#RGB` format, where all components have the same
+/// number (one to four) of hexadecimal digits.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::terminal::parse_colour_spec;
+///
+/// assert_eq!(Some((255, 0, 128)), parse_colour_spec(b"rgb:ffff/0000/8080"));
+/// assert_eq!(Some((255, 0, 136)), parse_colour_spec(b"rgb:f/00/888"));
+/// assert_eq!(Some((18, 52, 86)), parse_colour_spec(b"#123456"));
+/// assert_eq!(None, parse_colour_spec(b"rgb:ff/ff"));
+/// assert_eq!(None, parse_colour_spec(b"?"));
+/// ```
+pub fn parse_colour_spec(spec: &[u8]) -> Option<(u8, u8, u8)> {
+    crate::formats::parse_colour_spec(spec)
+}
+
+/// Parses palette index in decimal.
+fn parse_index(digits: &[u8]) -> Option<u8> {
+    core::str::from_utf8(digits).ok()?.parse().ok()
+}
+
+fn to_u32((r, g, b): (u8, u8, u8)) -> u32 {
+    (u32::from(r) << 16) | (u32::from(g) << 8) | u32::from(b)
+}
+
+/// Returns position of the first occurrence of `needle` in `haystack`.
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
@@ -75,6 +75,671 @@ fn test_greys_agree() {
     }
 }
 
+/// Tests that greyscale ramp approximation picks the closest shade.
+#[test]
+fn test_ansi256_grey_from_rgb() {
+    for y in 0..=255u8 {
+        let best = core::iter::once(16)
+            .chain(232..=255)
+            .chain(core::iter::once(231))
+            .min_by_key(|idx| to_rgb(*idx).0.abs_diff(y))
+            .unwrap();
+        let got = crate::ansi256_grey_from_rgb((y, y, y));
+        assert_eq!(to_rgb(best).0.abs_diff(y), to_rgb(got).0.abs_diff(y));
+    }
+}
+
+/// Tests that XTerm preset matches the palette.
+#[test]
+fn test_xterm_preset() {
+    let colours = crate::SystemPreset::XTerm.colours_rgb();
+    assert_eq!(&crate::ansi256::ANSI_COLOURS[..16], &colours[..]);
+}
+
+/// Tests parsing of compiled terminfo entries.
+#[test]
+#[cfg(feature = "detect")]
+fn test_parse_terminfo_colours() {
+    use crate::detect::parse_terminfo_colours;
+    use std::vec::Vec;
+
+    fn entry(magic: u16, names: &[u8], bools: u16, colours: &[u8]) -> Vec<u8> {
+        let width = colours.len();
+        let mut data = Vec::new();
+        for value in [magic, names.len() as u16, bools, 14, 0, 0] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data.extend_from_slice(names);
+        data.resize(data.len() + usize::from(bools), 1);
+        data.resize(data.len() + data.len() % 2, 0);
+        data.resize(data.len() + 13 * width, 0xff);
+        data.extend_from_slice(colours);
+        data
+    }
+
+    let data = entry(0o432, b"xterm-256color\0", 3, &[0, 1]);
+    assert_eq!(Some(Some(256)), parse_terminfo_colours(&data));
+    let data = entry(0o432, b"xterm\0", 2, &[8, 0]);
+    assert_eq!(Some(Some(8)), parse_terminfo_colours(&data));
+    let data = entry(0o432, b"vt100\0", 2, &[0xff, 0xff]);
+    assert_eq!(Some(None), parse_terminfo_colours(&data));
+    let mut data = entry(0o432, b"vt52\0", 2, &[8, 0]);
+    data[6] = 13;
+    assert_eq!(Some(None), parse_terminfo_colours(&data));
+    let data = entry(0o1036, b"xterm-direct\0", 2, &[0, 0, 0, 1]);
+    assert_eq!(Some(Some(1 << 24)), parse_terminfo_colours(&data));
+    let data = entry(0o1036, b"xterm-direct\0", 2, &[0, 0]);
+    assert_eq!(None, parse_terminfo_colours(&data));
+    assert_eq!(None, parse_terminfo_colours(b"\x1a\x01"));
+    assert_eq!(None, parse_terminfo_colours(b""));
+}
+
+/// Tests alpha compositing in linear light.
+#[test]
+fn test_composite() {
+    use crate::ansi256::composite;
+
+    for v in 0..=255u32 {
+        let rgb = v * 0x010101;
+        assert_eq!(rgb, composite(rgb, 255, 0xffffff - rgb));
+        assert_eq!(0xffffff - rgb, composite(rgb, 0, 0xffffff - rgb));
+    }
+    // Half of white’s intensity is much brighter than 0x808080.
+    assert_eq!(0xbcbcbc, composite(0xffffff, 128, 0x000000));
+    assert_eq!(0xbc0000, composite(0xff00ff, 128, 0x000000) & 0xff0000);
+}
+
+/// Tests that dithering preserves average colour of uniform areas.
+#[test]
+#[cfg(feature = "alloc")]
+fn test_ansi256_from_rgb_dithered() {
+    for value in [0x000000, 0x707070, 0x123456, 0x5f87af, 0xc0ffee, 0xfe8000] {
+        let pixels = [value; 256];
+        let mut output = [0; 256];
+        crate::ansi256_from_rgb_dithered(&pixels, 16, &mut output);
+        for shift in [16, 8, 0] {
+            let sum: u32 = output
+                .iter()
+                .map(|&idx| crate::ansi256::ANSI_COLOURS[idx as usize])
+                .map(|colour| (colour >> shift) & 0xff)
+                .sum();
+            let want = (value >> shift) & 0xff;
+            let got = sum / 256;
+            let diff = got.abs_diff(want);
+            assert!(diff <= 2, "{:06x}: {} vs {}", value, got, want);
+        }
+    }
+
+    let mut output = [];
+    crate::ansi256_from_rgb_dithered(&[0u32; 0], 0, &mut output);
+}
+
+/// Tests that HSL and HSV representations of palette colours convert back to
+/// the same colours.
+#[test]
+fn test_hsl_hsv() {
+    use crate::{AsRGB, Hsl, Hsv};
+
+    for idx in 0..=255 {
+        let (r, g, b) = to_rgb(idx);
+        let (r, g, b) = (r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0);
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let chroma = max - min;
+        let h = if chroma == 0.0 {
+            0.0
+        } else if max == r {
+            60.0 * ((g - b) / chroma)
+        } else if max == g {
+            60.0 * ((b - r) / chroma + 2.0)
+        } else {
+            60.0 * ((r - g) / chroma + 4.0)
+        };
+        let l = (max + min) / 2.0;
+        let s = if l == 0.0 || l == 1.0 {
+            0.0
+        } else {
+            chroma / (1.0 - (2.0 * l - 1.0).abs())
+        };
+        let rgb = crate::ansi256::ANSI_COLOURS[usize::from(idx)];
+        assert_eq!(rgb, Hsl { h, s, l }.as_u32(), "idx: {}", idx);
+        let s = if max == 0.0 { 0.0 } else { chroma / max };
+        let v = max;
+        assert_eq!(rgb, Hsv { h, s, v }.as_u32(), "idx: {}", idx);
+    }
+}
+
+/// Tests that the named colours table is sorted and uses normalised names.
+#[test]
+#[cfg(feature = "names")]
+fn test_names_table() {
+    use crate::names::{rgb_from_name, NAMES};
+
+    for pair in NAMES.windows(2) {
+        assert!(pair[0].0 < pair[1].0, "{} ≥ {}", pair[0].0, pair[1].0);
+    }
+    for (name, rgb) in NAMES.iter() {
+        let valid = |b: u8| b.is_ascii_lowercase() || b.is_ascii_digit();
+        assert!(name.bytes().all(valid), "{}", name);
+        assert_eq!(Some(crate::ansi256::to_triple(*rgb)), rgb_from_name(name));
+    }
+    assert_eq!(Some((255, 255, 255)), rgb_from_name("  WHITE  "));
+    assert_eq!(None, rgb_from_name(""));
+    assert_eq!(None, rgb_from_name("whitesmoke1"));
+}
+
+/// Tests that the precomputed lightness table matches calculated values.
+#[test]
+fn test_lightness() {
+    for idx in 0..=255 {
+        let rgb = crate::ansi256::ANSI_COLOURS[usize::from(idx)];
+        assert_eq!(
+            crate::ansi256::lightness(rgb),
+            crate::luminance_from_ansi256(idx),
+            "idx: {}",
+            idx
+        );
+    }
+}
+
+/// Tests that contrast ratio is symmetric and treats indexes and their colours
+/// the same.
+#[test]
+fn test_contrast_ratio() {
+    use crate::contrast_ratio;
+
+    for a in (0..=255).step_by(7) {
+        for b in (0..=255).step_by(5) {
+            let ratio = contrast_ratio(a, b);
+            assert!((1.0..=21.0).contains(&ratio), "{} {}: {}", a, b, ratio);
+            assert_eq!(ratio, contrast_ratio(b, a));
+            assert_eq!(ratio, contrast_ratio(to_rgb(a), to_rgb(b)));
+        }
+        assert_eq!(1.0, contrast_ratio(a, to_rgb(a)));
+    }
+}
+
+/// Tests that readable foreground has at least 4.5 contrast with every colour
+/// in the palette.
+#[test]
+fn test_readable_foreground() {
+    use crate::{contrast_ratio, readable_foreground};
+
+    for idx in 0..=255u8 {
+        let fg = readable_foreground(idx);
+        assert!(fg == 16 || fg == 231, "{}: {}", idx, fg);
+        assert!(contrast_ratio(fg, idx) >= 4.5, "{}: {}", idx, fg);
+        assert_eq!(fg, readable_foreground(to_rgb(idx)));
+    }
+}
+
+/// Tests that dark/light classification is consistent for indexes and their
+/// colours.
+#[test]
+fn test_is_dark() {
+    use crate::{is_dark_with_threshold, is_light_with_threshold};
+
+    for idx in 0..=255u8 {
+        for threshold in [0, 25, 50, 75, 100] {
+            let dark = is_dark_with_threshold(idx, threshold);
+            assert_eq!(dark, is_dark_with_threshold(to_rgb(idx), threshold));
+            assert_eq!(!dark, is_light_with_threshold(idx, threshold));
+        }
+    }
+}
+
+/// Tests that distance is symmetric and matches error reported by
+/// `ansi256_from_rgb_with_error`.
+#[test]
+fn test_distance() {
+    use crate::{ansi256_from_rgb_with_error, distance};
+
+    for rgb in (0..0x1000000u32).step_by(0x10305) {
+        let (idx, error) = ansi256_from_rgb_with_error(rgb);
+        assert_eq!(error, distance(rgb, idx), "{:06x}", rgb);
+        assert_eq!(error, distance(idx, rgb), "{:06x}", rgb);
+    }
+}
+
+/// Tests that `ansi256_from_rgb_with_error` returns the same index as
+/// `ansi256_from_rgb` for colours with 16-bit components.
+#[test]
+#[cfg(feature = "rgb")]
+fn test_ansi256_from_rgb16_with_error() {
+    use crate::{ansi256_from_rgb, ansi256_from_rgb_with_error};
+
+    // Rounding components of this colour to 8 bits would give index 241.
+    let rgb = rgb::RGB16::new(12207, 29683, 29683);
+    assert_eq!(60, ansi256_from_rgb_with_error(rgb).0);
+    for value in (0..1u64 << 48).step_by(0x0305_070b_0d11) {
+        let [_, _, r0, r1, g0, g1, b0, b1] = value.to_be_bytes();
+        let rgb = rgb::RGB16 {
+            r: u16::from_be_bytes([r0, r1]),
+            g: u16::from_be_bytes([g0, g1]),
+            b: u16::from_be_bytes([b0, b1]),
+        };
+        let (idx, _) = ansi256_from_rgb_with_error(rgb);
+        assert_eq!(ansi256_from_rgb(rgb), idx, "{:?}", rgb);
+    }
+}
+
+/// Tests that `ansi256_from_rgb_excluding` agrees with `ansi256_from_rgb` when
+/// nothing is excluded and otherwise returns the closest remaining colour.
+#[test]
+fn test_ansi256_from_rgb_excluding() {
+    use crate::{ansi256_from_rgb, ansi256_from_rgb_excluding, distance};
+
+    for rgb in (0..0x1000000u32).step_by(0x10305) {
+        let idx = ansi256_from_rgb(rgb);
+        assert_eq!(Some(idx), ansi256_from_rgb_excluding(rgb, &[]));
+        let next = ansi256_from_rgb_excluding(rgb, &[idx]).unwrap();
+        assert_ne!(idx, next);
+        for other in (16..=255).filter(|&other| other != idx) {
+            assert!(distance(rgb, next) <= distance(rgb, other), "{:06x}", rgb);
+        }
+    }
+}
+
+/// Tests that integer square root used by redmean distance is exact.
+#[test]
+fn test_isqrt() {
+    use crate::ansi256::isqrt;
+
+    for root in (0..1u64 << 20).step_by(97).chain([(1 << 32) - 1]) {
+        assert_eq!(root as u32, isqrt(root * root), "{}", root);
+        assert_eq!(root as u32, isqrt(root * root + 2 * root), "{}", root);
+        if root != 0 {
+            assert_eq!(root as u32 - 1, isqrt(root * root - 1), "{}", root);
+        }
+    }
+}
+
+/// Tests that conversion to CIE L*a*b* and back gives the same colour.
+#[test]
+fn test_lab_round_trip() {
+    use crate::gradient::{lab_from_rgb, rgb_from_lab};
+
+    for rgb in (0..0x1000000u32).step_by(0x10305) {
+        assert_eq!(rgb, rgb_from_lab(lab_from_rgb(rgb)), "{:06x}", rgb);
+    }
+    for rgb in crate::ansi256::ANSI_COLOURS.iter().copied() {
+        assert_eq!(rgb, rgb_from_lab(lab_from_rgb(rgb)), "{:06x}", rgb);
+    }
+}
+
+/// Tests that OKLCH gradients between cube colours start and end at given
+/// colours.
+#[test]
+#[cfg(feature = "oklab")]
+fn test_gradient_oklch() {
+    use crate::gradient_oklch;
+
+    for from in (16..=255).step_by(7) {
+        for to in (16..=255).step_by(11) {
+            let mut buf = [0; 8];
+            let mut len = 0;
+            for idx in gradient_oklch(from, to, buf.len()) {
+                buf[len] = idx;
+                len += 1;
+            }
+            let indexes = &buf[..len];
+            assert_eq!(Some(&from), indexes.first());
+            assert_eq!(Some(&to), indexes.last());
+            assert!(indexes.windows(2).all(|pair| pair[0] != pair[1]));
+        }
+    }
+}
+
+/// Tests that nearest cube values are monotonic and consistent with steps.
+#[test]
+fn test_nearest_cube_value() {
+    use crate::{nearest_cube_value, Channel};
+
+    for channel in [Channel::Red, Channel::Green, Channel::Blue] {
+        let mut prev = 0;
+        for v in 0..=255 {
+            let (step, value) = nearest_cube_value(channel, v);
+            assert_eq!(CUBE_VALUES[usize::from(step)], value);
+            assert!(step == prev || step == prev + 1, "{:?} {}", channel, v);
+            prev = step;
+        }
+        assert_eq!(5, prev);
+    }
+}
+
+/// Tests that cube coordinates round-trip and match colours of the cube.
+#[test]
+fn test_cube_coords() {
+    use crate::{cube_coords, cube_index};
+
+    for idx in 0..=255 {
+        let Some((r, g, b)) = cube_coords(idx) else {
+            assert!(!(16..=231).contains(&idx), "{}", idx);
+            continue;
+        };
+        assert_eq!(idx, cube_index(r, g, b));
+        let rgb = (
+            CUBE_VALUES[usize::from(r)],
+            CUBE_VALUES[usize::from(g)],
+            CUBE_VALUES[usize::from(b)],
+        );
+        assert_eq!(rgb, to_rgb(idx));
+    }
+}
+
+/// Tests that greyscale ramp helpers round-trip and match colours of the ramp.
+#[test]
+fn test_grey_ramp() {
+    use crate::{grey_index, grey_level, grey_value};
+
+    for idx in 0..=255 {
+        let Some(level) = grey_level(idx) else {
+            assert!(idx < 232, "{}", idx);
+            continue;
+        };
+        assert_eq!(idx, grey_index(level));
+        let v = grey_value(level);
+        assert_eq!((v, v, v), to_rgb(idx));
+    }
+}
+
+/// Tests that canonical greys are idempotent and only replace greys with
+/// near-duplicate greys.
+#[test]
+fn test_canonical_grey() {
+    use crate::{canonical_grey, GreyPolicy};
+
+    let policies = [
+        GreyPolicy::Any,
+        GreyPolicy::PreferRamp,
+        GreyPolicy::PreferCube,
+    ];
+    for policy in policies {
+        let mut changed = 0;
+        for idx in 0..=255 {
+            let canonical = canonical_grey(idx, policy);
+            assert_eq!(canonical, canonical_grey(canonical, policy));
+            if canonical != idx {
+                let (r, g, b) = to_rgb(idx);
+                let (cr, cg, cb) = to_rgb(canonical);
+                assert!(r == g && g == b && cr == cg && cg == cb);
+                assert!(r.abs_diff(cr) < 5, "{:?} {}", policy, idx);
+                changed += 1;
+            }
+        }
+        let want = if policy == GreyPolicy::Any { 0 } else { 4 };
+        assert_eq!(want, changed, "{:?}", policy);
+    }
+}
+
+/// Tests that 256→16 conversion with XTerm preset matches the default one.
+#[test]
+fn test_ansi16_from_ansi256() {
+    use crate::{ansi16_from_ansi256, ansi16_from_ansi256_with_preset};
+
+    for idx in 0..=255 {
+        let want = ansi16_from_ansi256(idx);
+        assert!(want < 16);
+        let xterm = crate::SystemPreset::XTerm;
+        let got = ansi16_from_ansi256_with_preset(idx, xterm);
+        assert_eq!(want, got, "{}", idx);
+        assert_eq!(crate::ansi16_from_rgb(to_rgb(idx)), want, "{}", idx);
+    }
+}
+
+/// Tests that `Converter` with weighted metric agrees with the metric, that
+/// colours of the palette are approximated by themselves and that balanced
+/// weights give the same results as CIE76 colour difference.
+#[test]
+#[cfg(feature = "ciede2000")]
+fn test_weighted_metric() {
+    use crate::metric::{Lab76, Weighted};
+    use crate::{ansi256_from_rgb_by, Converter, Metric};
+
+    for rgb in (0..0x1000000).step_by(0x010307) {
+        assert_eq!(
+            ansi256_from_rgb_by(rgb, &Lab76),
+            ansi256_from_rgb_by(rgb, &Weighted::BALANCED),
+            "{:06x}",
+            rgb
+        );
+    }
+    for weighted in [Weighted::BALANCED, Weighted::HUE, Weighted::LIGHTNESS] {
+        let converter = Converter::new(Metric::Weighted(weighted));
+        for rgb in (0..0x1000000).step_by(0x010307) {
+            assert_eq!(
+                ansi256_from_rgb_by(rgb, &weighted),
+                converter.ansi256_from_rgb(rgb),
+                "{:?} {:06x}",
+                weighted,
+                rgb
+            );
+        }
+        for idx in 16..=255 {
+            let rgb = crate::rgb_from_ansi256(idx);
+            let got = converter.ansi256_from_rgb(rgb);
+            assert_eq!(rgb, crate::rgb_from_ansi256(got), "{:?}", weighted);
+        }
+    }
+}
+
+/// Tests that weighted metric with zero weight cannot be constructed.
+#[test]
+#[cfg(feature = "ciede2000")]
+#[should_panic(expected = "weights must be non-zero")]
+fn test_weighted_metric_zero() {
+    crate::metric::Weighted::new(0, 1);
+}
+
+/// Tests that colours of the palette are recognised as such and that colours
+/// recognised as palette colours are at returned indexes.
+#[test]
+fn test_is_palette_colour() {
+    for idx in 0..=255 {
+        let rgb = crate::rgb_from_ansi256(idx);
+        let got = crate::is_palette_colour(rgb).unwrap();
+        assert_eq!(rgb, crate::rgb_from_ansi256(got), "{}", idx);
+        if idx >= 16 {
+            assert_eq!(idx, got);
+        }
+    }
+    for rgb in (0..0x1000000).step_by(0x010307) {
+        if let Some(idx) = crate::is_palette_colour(rgb) {
+            let rgb = crate::ansi256::to_triple(rgb);
+            assert_eq!(rgb, crate::rgb_from_ansi256(idx));
+        }
+    }
+}
+
+/// Tests that classification methods of `PaletteIndex` agree with each other
+/// and with corresponding free functions.
+#[test]
+fn test_palette_index() {
+    use crate::PaletteIndex;
+
+    for idx in 0..=255 {
+        let index = PaletteIndex::from(idx);
+        let kinds = [index.is_system(), index.is_cube(), index.is_grey()];
+        assert_eq!(1, kinds.iter().filter(|kind| **kind).count(), "{}", idx);
+        assert_eq!(crate::cube_coords(idx).is_some(), index.is_cube());
+        assert_eq!(crate::grey_level(idx).is_some(), index.is_grey());
+        assert_eq!(crate::rgb_from_ansi256(idx), index.to_rgb());
+        assert_eq!(crate::luminance_from_ansi256(idx), index.luminance());
+        assert_eq!(idx, u8::from(index));
+    }
+}
+
+/// Tests that converting `Colour` to colour types of other crates and back
+/// gives the same colour.
+#[cfg(all(
+    feature = "anes",
+    feature = "ansi_term",
+    feature = "anstyle",
+    feature = "termcolor"
+))]
+#[test]
+fn test_colour_interop_round_trip() {
+    use core::convert::TryFrom;
+
+    use crate::{Colour, NamedColour};
+
+    let named = NamedColour::ALL.iter().copied().map(Colour::Named);
+    let fixed = (0..=255).map(Colour::Fixed);
+    let rgb = (0..0x1000000u32).step_by(0x010307).map(Colour::from);
+    for colour in named.chain(fixed).chain(rgb) {
+        let got = Colour::from(ansi_term::Colour::from(colour));
+        assert_eq!(colour, got);
+        let got = Colour::from(termcolor::Color::from(colour));
+        assert_eq!(colour, got);
+        let got = Colour::from(anstyle::Color::from(colour));
+        assert_eq!(colour, got);
+        let got = Colour::try_from(anes::Color::from(colour));
+        assert_eq!(Ok(colour), got);
+    }
+}
+
+/// Tests that converting `Colour` to `crossterm` colour and back gives the
+/// same colour.
+#[cfg(feature = "crossterm")]
+#[test]
+fn test_crossterm_round_trip() {
+    use core::convert::TryFrom;
+
+    use crate::{Colour, NamedColour};
+
+    let named = NamedColour::ALL.iter().copied().map(Colour::Named);
+    let fixed = (0..=255).map(Colour::Fixed);
+    let rgb = (0..0x1000000u32).step_by(0x010307).map(Colour::from);
+    for colour in named.chain(fixed).chain(rgb) {
+        let got = Colour::try_from(crossterm::style::Color::from(colour));
+        assert_eq!(Ok(colour), got);
+    }
+}
+
+/// Tests that 16-bit components are rounded correctly and that colours with
+/// 8-bit components scaled to 16 bits are approximated the same way as the
+/// original colours.
+#[test]
+fn test_ansi256_from_rgb16() {
+    use crate::ansi256::round16;
+
+    for value in 0..=u16::MAX {
+        let want = (f32::from(value) / 257.0).round() as u8;
+        assert_eq!(want, round16(value), "{}", value);
+    }
+    for rgb in (0..0x1000000).step_by(0x010307) {
+        let (r, g, b) = crate::ansi256::to_triple(rgb);
+        let scale = |v: u8| u16::from(v) * 257;
+        assert_eq!(
+            crate::ansi256_from_rgb(rgb),
+            crate::ansi256_from_rgb16((scale(r), scale(g), scale(b))),
+            "{:06x}",
+            rgb
+        );
+    }
+}
+
+/// Tests that encoding linear intensities into sRGB components inverts
+/// linearisation, clamps out of range values and rounds in gamma-encoded space.
+#[test]
+fn test_encode_srgb() {
+    use crate::ansi256::{encode_srgb, LINEAR};
+
+    for (value, &linear) in LINEAR.iter().enumerate() {
+        assert_eq!(value as u8, encode_srgb(linear));
+    }
+    assert_eq!(0, encode_srgb(-1.0));
+    assert_eq!(255, encode_srgb(2.0));
+    // Rounding happens in gamma-encoded space where mean of intensities of
+    // two consecutive values is closer to the brighter one.
+    let mid = (LINEAR[127] + LINEAR[128]) / 2.0;
+    assert_eq!(128, encode_srgb(mid));
+}
+
+/// Tests that colour temperatures get bluer and less red as temperature
+/// increases.
+#[test]
+fn test_kelvin() {
+    use crate::{AsRGB, Kelvin};
+
+    // Colours get bluer and less red as temperature increases.
+    let mut prev = Kelvin(1000.0).as_u32();
+    for temperature in (1100..=26000).step_by(100) {
+        let rgb = Kelvin(temperature as f32).as_u32();
+        let (r, _, b) = crate::ansi256::to_triple(rgb);
+        let (prev_r, _, prev_b) = crate::ansi256::to_triple(prev);
+        assert!(r <= prev_r && b >= prev_b, "{} {:06x}", temperature, rgb);
+        assert!(r == 255 || b == 255, "{} {:06x}", temperature, rgb);
+        prev = rgb;
+    }
+}
+
+/// Tests conversion of HSLuv colours, including clamping of out of range values
+/// and wrapping of hue.
+#[test]
+#[cfg(feature = "hsluv")]
+fn test_hsluv() {
+    use crate::{luminance_from_ansi256, AsRGB, Hsluv};
+
+    // Zero saturation gives greys regardless of hue.
+    for h in [0.0, 90.0, 180.0, 270.0, 400.0, -30.0] {
+        assert_eq!(0x000000, Hsluv { h, s: 0.0, l: 0.0 }.as_u32());
+        let (s, l) = (100.0, 100.0);
+        assert_eq!(0xffffff, Hsluv { h, s, l }.as_u32());
+        let rgb = Hsluv { h, s: 0.0, l: 50.0 }.as_u32();
+        assert_eq!(0x777777, rgb, "h: {}", h);
+    }
+
+    // Out of range values are clamped and hue wraps around.
+    let hsluv = |h, s, l| Hsluv { h, s, l }.as_u32();
+    assert_eq!(hsluv(12.177, 100.0, 53.237), hsluv(372.177, 150.0, 53.237));
+    assert_eq!(0x000000, hsluv(0.0, 100.0, -5.0));
+    assert_eq!(0xffffff, hsluv(0.0, 100.0, 105.0));
+
+    // Lightness is monotonic.
+    let mut prev = 16;
+    for l in 0..=100 {
+        let idx = crate::ansi256_from_hsluv(0.0, 0.0, l as f32);
+        let (prev_lum, lum) =
+            (luminance_from_ansi256(prev), luminance_from_ansi256(idx));
+        assert!(prev_lum <= lum, "l: {}", l);
+        prev = idx;
+    }
+}
+
+/// Tests that 16-bit shades of grey are approximated by the nearest grey in
+/// the palette found by brute force at 16-bit precision.
+#[test]
+#[cfg_attr(miri, ignore = "runs too slow on Miri")]
+fn test_ansi256_from_grey16() {
+    let mut greys = [(0, 0); 30];
+    let mut len = 0;
+    for idx in 16..=255u8 {
+        let (r, g, b) = to_rgb(idx);
+        if r == g && g == b {
+            greys[len] = (idx, i32::from(r) * 257);
+            len += 1;
+        }
+    }
+    assert_eq!(greys.len(), len);
+
+    for v in 0..=u16::MAX {
+        let distance = |&(_, level): &(u8, i32)| (level - i32::from(v)).abs();
+        let best = greys.iter().map(distance).min().unwrap();
+        let mut nearest = greys.iter().filter(|grey| distance(grey) == best);
+        let got = crate::ansi256_from_grey16(v);
+        if v % 257 == 0 {
+            // At exact 8-bit values ties are broken the same way as in 8-bit
+            // variant.
+            assert_eq!(crate::ansi256_from_grey((v / 257) as u8), got);
+            assert!(nearest.any(|&(idx, _)| idx == got), "v: {}", v);
+        } else {
+            assert_eq!(Some(got), nearest.next().map(|&(idx, _)| idx));
+            assert_eq!(None, nearest.next(), "v: {}", v);
+        }
+    }
+}
+
 /// Tests that converting colour which exists in the palette gives index of that
 /// colour in the palette.
 #[test]
@@ -96,6 +761,223 @@ fn test_to_ansi_approx() {
     assert_eq!( 64, to_ansi(( 97, 134,   8)));
 }
 
+/// Tests that system colours are approximated by themselves and other colours
+/// by one of the system colours.
+#[test]
+fn test_ansi16_exact() {
+    for idx in 0..16 {
+        assert_eq!(idx, crate::ansi16_from_rgb(to_rgb(idx)));
+    }
+    for idx in 16..=255 {
+        assert!(crate::ansi16_from_rgb(to_rgb(idx)) < 16);
+    }
+}
+
+/// Tests that colours of the 88-colour palette other than the system colours
+/// are approximated by themselves, except for grey at index 83 which
+/// duplicates cube colour at index 37 and thus maps to it.
+#[test]
+fn test_ansi88_exact() {
+    for idx in 16..88 {
+        let want = if idx == 83 { 37 } else { idx };
+        let got = crate::ansi88_from_rgb(crate::rgb_from_ansi88(idx));
+        assert_eq!(want, got, "idx={}", idx);
+    }
+}
+
+/// Tests that the eight basic colours are approximated by themselves and that
+/// every other colour of the 256-colour palette maps to one of them.
+#[test]
+fn test_ansi8_exact() {
+    for idx in 0..8 {
+        assert_eq!(idx, crate::ansi8_from_rgb(to_rgb(idx)));
+    }
+    for idx in 8..=255 {
+        assert!(crate::ansi8_from_rgb(to_rgb(idx)) < 8);
+    }
+}
+
+/// Tests that k-d tree lookup in a palette agrees with a linear search.
+#[test]
+fn test_palette_nearest() {
+    fn check(colours: &[u32]) {
+        let palette = crate::Palette::new(colours);
+        for rgb in (0..(1 << 24)).step_by(7919) {
+            let want = crate::ansi256::nearest(rgb, colours.iter().copied());
+            assert_eq!(want, palette.nearest(rgb), "rgb={:06x}", rgb);
+            let got = palette.nearest_by(rgb, &crate::metric::Redmean);
+            assert_eq!(want, got, "rgb={:06x}", rgb);
+        }
+    }
+
+    check(&crate::ansi256::ANSI_COLOURS);
+    check(&crate::ansi256::ANSI_COLOURS[..16]);
+    check(&[0x123456]);
+
+    // Pseudo-random palettes, some with duplicate entries.
+    let mut state = 1u32;
+    for len in [2, 3, 17, 100, 256] {
+        let mut colours = [0; 256];
+        for colour in colours[..len].iter_mut() {
+            state = state.wrapping_mul(1664525).wrapping_add(1013904223);
+            *colour = (state >> 8) & if len == 100 { 0xc0c0c0 } else { !0 };
+        }
+        check(&colours[..len]);
+    }
+}
+
+/// Tests ΔE*₀₀ implementation against test data from ‘The CIEDE2000
+/// Color-Difference Formula: Implementation Notes, Supplementary Test Data,
+/// and Mathematical Observations’ by Gaurav Sharma, Wencheng Wu and Edul N.
+/// Dalal.
+#[test]
+#[cfg(feature = "ciede2000")]
+#[rustfmt::skip]
+fn test_ciede2000_diff() {
+    use crate::ciede2000::{diff, Lab};
+
+    let data = [
+        ((50.0000,   2.6772, -79.7751), (50.0000,   0.0000, -82.7485),  2.0425),
+        ((50.0000,   0.0000,   0.0000), (50.0000,  -1.0000,   2.0000),  2.3669),
+        ((50.0000,   2.4900,  -0.0010), (50.0000,  -2.4900,   0.0011),  7.2195),
+        ((50.0000,   2.5000,   0.0000), (73.0000,  25.0000, -18.0000), 27.1492),
+        ((50.0000,   2.5000,   0.0000), (50.0000,   3.1736,   0.5854),  1.0000),
+        ((60.2574, -34.0099,  36.2677), (60.4626, -34.1751,  39.4387),  1.2644),
+        (( 2.0776,   0.0795,  -1.1350), ( 0.9033,  -0.0636,  -0.5514),  0.9082),
+    ];
+    for ((l1, a1, b1), (l2, a2, b2), want) in data {
+        let x = Lab { l: l1, a: a1, b: b1 };
+        let y = Lab { l: l2, a: a2, b: b2 };
+        for got in [diff(x, y), diff(y, x)] {
+            assert!((want - got).abs() < 0.0001, "{} vs {}", want, got);
+        }
+    }
+}
+
+/// Tests that exact approximation finds colour with the smallest ΔE*₀₀ as
+/// calculated by `empfindung` crate.
+#[test]
+#[cfg(feature = "ciede2000")]
+fn test_ansi256_from_rgb_exact() {
+    for idx in 16..=255 {
+        assert_eq!(idx, crate::ansi256_from_rgb_exact(to_rgb(idx)));
+    }
+
+    let delta_e = |x: u32, y: u32| {
+        let x = crate::ansi256::to_triple(x);
+        let y = crate::ansi256::to_triple(y);
+        let x = rgb::RGB8::new(x.0, x.1, x.2);
+        let y = rgb::RGB8::new(y.0, y.1, y.2);
+        empfindung::cie00::diff(x, y)
+    };
+    for rgb in (0..(1 << 24)).step_by(104729) {
+        let best = crate::ansi256::ANSI_COLOURS[16..]
+            .iter()
+            .map(|colour| delta_e(rgb, *colour))
+            .fold(f32::INFINITY, f32::min);
+        let got = crate::ansi256_from_rgb_exact(rgb);
+        let got = delta_e(rgb, crate::ansi256::ANSI_COLOURS[got as usize]);
+        assert!(got - best < 0.01, "rgb={:06x} {} vs {}", rgb, got, best);
+    }
+}
+
+/// Tests that precomputed OKLab values of the palette match values calculated
+/// at run time and that colours in the palette are approximated by themselves.
+#[test]
+#[cfg(feature = "oklab")]
+fn test_ansi256_from_rgb_oklab() {
+    use crate::oklab::{Oklab, PALETTE};
+
+    for idx in 16..=255 {
+        let want = PALETTE[idx - 16];
+        let got = Oklab::from_rgb(crate::ansi256::ANSI_COLOURS[idx]);
+        for (want, got) in want.iter().zip([got.l, got.a, got.b]) {
+            assert!((want - got).abs() < 0.00001, "idx={}", idx);
+        }
+        assert_eq!(idx as u8, crate::ansi256_from_rgb_oklab(to_rgb(idx as u8)));
+    }
+}
+
+/// Tests that redmean metric never gives worse approximation, as measured by
+/// the redmean distance, than the heuristic.
+#[test]
+fn test_converter_redmean() {
+    use crate::ansi256::{distance, to_triple, ANSI_COLOURS};
+
+    let converter = crate::Converter::new(crate::Metric::Redmean);
+    for idx in 16..=255 {
+        assert_eq!(idx, converter.ansi256_from_rgb(to_rgb(idx)));
+    }
+    for rgb in (0..(1 << 24)).step_by(7919) {
+        let heuristic = ANSI_COLOURS[crate::ansi256_from_rgb(rgb) as usize];
+        let redmean = ANSI_COLOURS[converter.ansi256_from_rgb(rgb) as usize];
+        let triple = to_triple(rgb);
+        assert!(
+            distance(triple, redmean) <= distance(triple, heuristic),
+            "rgb={:06x}",
+            rgb
+        );
+    }
+}
+
+/// Tests that converter with custom cube levels approximates colours in its
+/// palette by themselves.
+#[test]
+fn test_converter_cube_levels() {
+    for metric in [crate::Metric::Heuristic, crate::Metric::Redmean] {
+        let converter = crate::Converter::new(metric)
+            .with_cube_levels([0, 51, 102, 153, 204, 255]);
+        for idx in 16..=255 {
+            let rgb = converter.rgb_from_ansi256(idx);
+            assert_eq!(idx, converter.ansi256_from_rgb(rgb), "{:?}", metric);
+        }
+    }
+
+    let converter = crate::Converter::default();
+    for idx in 0..=255 {
+        assert_eq!(to_rgb(idx), converter.rgb_from_ansi256(idx));
+    }
+}
+
+/// Tests that candidates are ordered by distance and that the first one is the
+/// same as the result of exhaustive search.
+#[test]
+fn test_ansi256_candidates() {
+    use crate::ansi256::{distance, to_triple, ANSI_COLOURS};
+
+    let converter = crate::Converter::new(crate::Metric::Redmean);
+    for rgb in (0..(1 << 24)).step_by(104729) {
+        let mut candidates = crate::ansi256_candidates(rgb, 10);
+        assert_eq!(10, candidates.len());
+        let first = candidates.next().unwrap();
+        assert_eq!(converter.ansi256_from_rgb(rgb), first);
+        let mut prev = distance(to_triple(rgb), ANSI_COLOURS[first as usize]);
+        for idx in candidates {
+            let dist = distance(to_triple(rgb), ANSI_COLOURS[idx as usize]);
+            assert!(prev <= dist, "rgb={:06x}", rgb);
+            prev = dist;
+        }
+    }
+}
+
+/// Tests that contrast-aware approximation guarantees requested contrast.
+/// Ratio of 4.5 is always achievable since either black or white has contrast
+/// of at least √21 against any colour.
+#[test]
+fn test_ansi256_from_rgb_with_contrast() {
+    use crate::ansi256::{contrast_ratio, ANSI_COLOURS};
+
+    for fg in (0..(1 << 24)).step_by(1046527) {
+        for bg in (0..(1 << 24)).step_by(999983) {
+            let idx = crate::ansi256_from_rgb_with_contrast(fg, bg, 4.5);
+            let ratio = contrast_ratio(ANSI_COLOURS[idx as usize], bg);
+            assert!(ratio >= 4.5, "fg={:06x} bg={:06x}", fg, bg);
+        }
+        let idx = crate::ansi256_from_rgb_with_contrast(fg, fg, 1.0);
+        assert_eq!(crate::ansi256_from_rgb(fg), idx, "fg={:06x}", fg);
+    }
+}
+
 /// Calculates RGB→ANSI for all colours and calculates a checksum of them
 /// comparing it to known value.  This is meant to see whether refactoring of
 /// the code does not change the behaviour.  If the computation is changed on
@@ -113,3 +995,1226 @@ fn from_rgb_checksum() {
     }
     assert_eq!(3373856917329536106, checksum);
 }
+
+/// Tests that batch conversion and conversion of packed colours give the same
+/// results as converting colours one at a time.
+#[test]
+#[cfg_attr(miri, ignore = "runs too slow on Miri")]
+fn test_ansi256_from_rgb_many() {
+    // Odd length so that the remainder not handled by SIMD code is tested.
+    let mut pixels = [[0; 3]; 4099];
+    let mut output = [0; 4099];
+    for start in (0..(1u32 << 24)).step_by(pixels.len()) {
+        for (i, pixel) in pixels.iter_mut().enumerate() {
+            let (r, g, b) = crate::ansi256::to_triple(start + i as u32);
+            *pixel = [r, g, b];
+        }
+        crate::ansi256_from_rgb_many(&pixels, &mut output);
+        for (i, (pixel, idx)) in pixels.iter().zip(output.iter()).enumerate() {
+            assert_eq!(to_ansi((pixel[0], pixel[1], pixel[2])), *idx);
+            let rgb = (start + i as u32) | (i as u32) << 24;
+            assert_eq!(*idx, crate::ansi256_from_u32(rgb));
+        }
+    }
+    crate::ansi256_from_rgb_many(&[], &mut []);
+}
+
+/// Tests that batch conversion of `rgb` crate’s pixels gives the same results
+/// as converting colours one at a time.
+#[test]
+#[cfg(feature = "rgb")]
+fn test_ansi256_from_rgb8_many() {
+    let mut pixels = [rgb::RGB8::new(0, 0, 0); 4099];
+    for (i, pixel) in pixels.iter_mut().enumerate() {
+        let (r, g, b) = crate::ansi256::to_triple(i as u32 * 4093);
+        *pixel = rgb::RGB8::new(r, g, b);
+    }
+    let mut output = [0; 4099];
+    crate::ansi256_from_rgb8_many(&pixels, &mut output);
+    for (pixel, idx) in pixels.iter().zip(output.iter()) {
+        assert_eq!(crate::ansi256_from_rgb(pixel), *idx);
+    }
+    #[cfg(feature = "rayon")]
+    {
+        let mut got = [0; 4099];
+        crate::ansi256_from_rgb8_many_par(&pixels, &mut got);
+        assert!(output[..] == got[..]);
+    }
+}
+
+/// Tests conversion of image buffers with different layouts and strides.
+#[test]
+fn test_ansi256_from_image() {
+    use crate::PixelLayout;
+
+    const WIDTH: usize = 7;
+    const HEIGHT: usize = 5;
+    let rgb = |x: usize, y: usize| (x * 0x250b37 + y * 0x0c5e2d) as u32;
+    let mut want = [0; WIDTH * HEIGHT];
+    for (i, out) in want.iter_mut().enumerate() {
+        *out = crate::ansi256_from_rgb(rgb(i % WIDTH, i / WIDTH));
+    }
+
+    let mut data = [0u8; 4 * WIDTH * HEIGHT + 3 * HEIGHT];
+    let mut output = [0; WIDTH * HEIGHT];
+    for &layout in &[
+        PixelLayout::Rgb,
+        PixelLayout::Bgr,
+        PixelLayout::Rgba,
+        PixelLayout::Bgra,
+    ] {
+        let bpp = layout.bytes_per_pixel();
+        for &stride in &[bpp * WIDTH, bpp * WIDTH + 3] {
+            for y in 0..HEIGHT {
+                for x in 0..WIDTH {
+                    let [_, r, g, b] = rgb(x, y).to_be_bytes();
+                    let pixel = &mut data[y * stride + x * bpp..][..bpp];
+                    let components = match layout {
+                        PixelLayout::Rgb | PixelLayout::Rgba => [r, g, b],
+                        PixelLayout::Bgr | PixelLayout::Bgra => [b, g, r],
+                    };
+                    pixel[..3].copy_from_slice(&components);
+                }
+            }
+            let len = (HEIGHT - 1) * stride + WIDTH * bpp;
+            output.iter_mut().for_each(|out| *out = 0);
+            crate::ansi256_from_image(
+                &data[..len],
+                WIDTH,
+                HEIGHT,
+                stride,
+                layout,
+                &mut output,
+            );
+            assert_eq!(want, output, "{:?} stride: {}", layout, stride);
+            #[cfg(feature = "rayon")]
+            {
+                output.iter_mut().for_each(|out| *out = 0);
+                crate::ansi256_from_image_par(
+                    &data[..len],
+                    WIDTH,
+                    HEIGHT,
+                    stride,
+                    layout,
+                    &mut output,
+                );
+                assert_eq!(want, output, "{:?} stride: {}", layout, stride);
+            }
+        }
+    }
+
+    crate::ansi256_from_image(&[], 0, 3, 0, PixelLayout::Rgb, &mut []);
+    crate::ansi256_from_image(&[], 3, 0, 9, PixelLayout::Rgb, &mut []);
+    #[cfg(feature = "rayon")]
+    {
+        crate::ansi256_from_image_par(&[], 0, 3, 0, PixelLayout::Rgb, &mut []);
+        crate::ansi256_from_image_par(&[], 3, 0, 9, PixelLayout::Rgb, &mut []);
+    }
+}
+
+/// Tests drawing of images with half-block characters.
+#[test]
+#[cfg(feature = "image")]
+fn test_render_half_blocks() {
+    use crate::image::{render, Image, RenderOptions};
+    use crate::PixelLayout;
+
+    let options = RenderOptions::new();
+    let render = |data: &[u8], width, height| {
+        render(&Image::new(data, width, height, PixelLayout::Rgb), &options)
+    };
+
+    assert_eq!("", render(&[], 3, 0));
+    assert_eq!("\n", render(&[], 0, 1));
+    // Colours which don’t change aren’t set again and swapped colours are
+    // drawn with lower half block.
+    let data = [
+        [0, 0, 0, 0, 0, 0, 255, 255, 255, 0, 0, 0],
+        [255, 255, 255, 255, 255, 255, 0, 0, 0, 0, 0, 0],
+        [95, 135, 175, 95, 135, 175, 95, 135, 175, 95, 135, 175],
+    ]
+    .concat();
+    assert_eq!(
+        "\x1b[38;5;16m\x1b[48;5;231m▀▀▄\x1b[48;5;16m▀\x1b[39;49m\n\
+         \x1b[38;5;67m▀▀▀▀\x1b[39m\n",
+        render(&data, 4, 3)
+    );
+
+    let options = options.with_true_colour(true);
+    let image = Image::new(&data[12..], 1, 2, PixelLayout::Bgr).with_stride(12);
+    let want = "\x1b[38;2;255;255;255m\x1b[48;2;175;135;95m▀\x1b[39;49m\n";
+    assert_eq!(want, crate::image::render(&image, &options));
+}
+
+/// Tests drawing images with quadrant blocks and Braille patterns.
+#[test]
+#[cfg(feature = "image")]
+fn test_render_modes() {
+    use crate::image::{render, Image, RenderMode, RenderOptions};
+    use crate::PixelLayout;
+
+    let render = |data: &[u8], width, height, mode| {
+        let image = Image::new(data, width, height, PixelLayout::Rgb);
+        render(&image, &RenderOptions::new().with_mode(mode))
+    };
+
+    // Missing column is filled with edge of the image and cells of a single
+    // colour are drawn with full blocks.
+    let data = [
+        [0, 0, 0, 255, 255, 255, 95, 135, 175],
+        [0, 0, 0, 255, 255, 255, 95, 135, 175],
+    ]
+    .concat();
+    assert_eq!(
+        "\x1b[38;5;231m\x1b[48;5;16m▐\x1b[38;5;67m█\x1b[39;49m\n",
+        render(&data, 3, 2, RenderMode::Quadrants)
+    );
+    assert_eq!("", render(&[], 0, 0, RenderMode::Quadrants));
+
+    // Missing pixels are left without dots.
+    let data = [255; 3 * 5 * 3];
+    for mode in [RenderMode::Braille, RenderMode::DitheredBraille] {
+        assert_eq!("⣿⡇\n⠉⠁\n", render(&data, 3, 5, mode));
+    }
+    let data = [0; 3 * 5 * 3];
+    assert_eq!("⠀⠀\n⠀⠀\n", render(&data, 3, 5, RenderMode::Braille));
+
+    // Dithering draws mid-grey with about half of the dots.
+    let data = [128; 8 * 8 * 3];
+    let count = |text: alloc::string::String| {
+        text.chars()
+            .filter(|&chr| chr != '\n')
+            .map(|chr| (u32::from(chr) - 0x2800).count_ones())
+            .sum::<u32>()
+    };
+    assert_eq!(64, count(render(&data, 8, 8, RenderMode::Braille)));
+    let dots = count(render(&data, 8, 8, RenderMode::DitheredBraille));
+    assert!((24..=40).contains(&dots), "dots: {}", dots);
+}
+
+/// Tests that quantizing an image gives the same results as dithering its
+/// pixels.
+#[test]
+#[cfg(feature = "image")]
+fn test_quantize_image() {
+    use crate::image::{quantize_image, Image};
+    use crate::PixelLayout;
+
+    let mut data = [0u8; 4 * 9 * 5];
+    let mut pixels = [0u32; 7 * 5];
+    for (i, rgb) in pixels.iter_mut().enumerate() {
+        let (x, y) = (i % 7, i / 7);
+        *rgb = (i as u32 * 0x0b3d95) & 0xffffff;
+        let [a, r, g, b] = (*rgb | 0x7f000000).to_be_bytes();
+        data[y * 36 + x * 4..][..4].copy_from_slice(&[b, g, r, a]);
+    }
+    let mut want = [0; 7 * 5];
+    crate::ansi256_from_rgb_dithered(&pixels, 7, &mut want);
+    let image = Image::new(&data, 7, 5, PixelLayout::Bgra).with_stride(36);
+    assert_eq!(&want[..], &quantize_image(&image)[..]);
+
+    let image = Image::new(&[], 0, 5, PixelLayout::Rgb);
+    assert!(quantize_image(&image).is_empty());
+}
+
+/// Tests that parallel quantization dithers bands of 64 rows the same way as
+/// sequential quantization of each band.
+#[test]
+#[cfg(all(feature = "image", feature = "rayon"))]
+fn test_quantize_image_par() {
+    use crate::image::{quantize_image, quantize_image_par, Image};
+    use crate::PixelLayout;
+
+    let mut data = [0u8; 3 * 7 * 150];
+    for (i, pixel) in data.chunks_exact_mut(3).enumerate() {
+        let [_, r, g, b] = (i as u32 * 0x0b3d95).to_be_bytes();
+        pixel.copy_from_slice(&[r, g, b]);
+    }
+    let image = Image::new(&data[..3 * 7 * 64], 7, 64, PixelLayout::Rgb);
+    assert_eq!(quantize_image(&image), quantize_image_par(&image));
+
+    let image = Image::new(&data, 7, 150, PixelLayout::Rgb);
+    let got = quantize_image_par(&image);
+    for (band, got) in got.chunks(7 * 64).enumerate() {
+        let data = &data[band * 3 * 7 * 64..];
+        let height = got.len() / 7;
+        let image = Image::new(data, 7, height, PixelLayout::Rgb);
+        assert_eq!(quantize_image(&image), got, "band: {}", band);
+    }
+
+    let image = Image::new(&[], 0, 5, PixelLayout::Rgb);
+    assert!(quantize_image_par(&image).is_empty());
+}
+
+/// Tests that parallel batch conversion gives the same results as sequential
+/// one.
+#[test]
+#[cfg(feature = "rayon")]
+fn test_ansi256_from_rgb_many_par() {
+    let mut pixels = [[0; 3]; 40961];
+    for (i, pixel) in pixels.iter_mut().enumerate() {
+        let (r, g, b) = crate::ansi256::to_triple(i as u32 * 409);
+        *pixel = [r, g, b];
+    }
+    let mut want = [0; 40961];
+    let mut got = [0; 40961];
+    crate::ansi256_from_rgb_many(&pixels, &mut want);
+    crate::ansi256_from_rgb_many_par(&pixels, &mut got);
+    assert!(want[..] == got[..]);
+    crate::ansi256_from_rgb_many_par(&[], &mut []);
+}
+
+/// Tests that colours, their lightness and approximations of greys calculated
+/// or decoded from packed tables match the lookup tables.
+#[test]
+fn test_packed_tables() {
+    use crate::ansi256::{
+        ansi256_from_grey, colour, palette_lightness, ANSI256_FROM_GREY,
+        ANSI_COLOURS, LIGHTNESS,
+    };
+
+    for idx in 0..=255u8 {
+        let i = usize::from(idx);
+        assert_eq!(LIGHTNESS[i], palette_lightness(idx), "lightness {}", i);
+        assert_eq!(ANSI_COLOURS[i], colour(idx), "colour {}", i);
+        assert_eq!(ANSI256_FROM_GREY[i], ansi256_from_grey(idx), "grey {}", i);
+    }
+}
+
+/// Tests that cache gives the same results as approximation without it.
+#[test]
+#[cfg(feature = "cache")]
+fn test_cache() {
+    let mut cache = crate::Cache::<16>::new();
+    assert!(cache.is_empty());
+    for round in 0..3 {
+        for rgb in (0..(1u32 << 24)).step_by(1 << 18) {
+            let want = crate::ansi256_from_rgb(rgb);
+            let got = cache.nearest(rgb);
+            assert_eq!(want, got, "{:06x} in round {}", rgb, round);
+        }
+    }
+    assert!(!cache.is_empty() && cache.len() <= 16);
+
+    let mut cache = crate::Cache::<1>::new();
+    assert_eq!(16, cache.nearest(0));
+    assert_eq!(231, cache.nearest(0xffffff));
+    assert_eq!(16, cache.nearest(0));
+    assert_eq!(1, cache.len());
+}
+
+/// Tests that lookup table gives exact approximations at grid points and, with
+/// eight bits, for all colours.
+#[test]
+#[cfg(feature = "alloc")]
+#[cfg_attr(miri, ignore = "too slow for Miri")]
+fn test_lut3d() {
+    let lut = crate::Lut3d::new(5);
+    for r in 0..32 {
+        for g in 0..32 {
+            for b in 0..32 {
+                let value = |coord: u32| ((coord * 255 + 15) / 31) as u8;
+                let rgb = (value(r), value(g), value(b));
+                assert_eq!(to_ansi(rgb), lut.nearest(rgb), "{:?}", rgb);
+            }
+        }
+    }
+
+    let lut = crate::Lut3d::new(8);
+    for rgb in (0..(1u32 << 24)).step_by(97) {
+        let want = crate::ansi256_from_rgb(rgb);
+        assert_eq!(want, lut.nearest(rgb), "{:06x}", rgb);
+    }
+
+    let lut = crate::Lut3d::new(1);
+    assert_eq!([16, 231], [lut.nearest(0x7f7f7f), lut.nearest(0x808080)]);
+}
+
+/// Tests downgrading of escape sequences in corner cases.
+#[test]
+#[cfg(all(feature = "downgrade", feature = "alloc"))]
+fn test_downgrade_corner_cases() {
+    use crate::downgrade::downgrade_str;
+
+    // Sequences other than SGR are left untouched.
+    assert_eq!(
+        "\x1b]0;38;2;1;2;3m\x07",
+        downgrade_str("\x1b]0;38;2;1;2;3m\x07")
+    );
+    assert_eq!("\x1b[38;2;1;2;3H", downgrade_str("\x1b[38;2;1;2;3H"));
+    // So are incomplete and invalid colour specifications.
+    assert_eq!("\x1b[38;2;1;2m", downgrade_str("\x1b[38;2;1;2m"));
+    assert_eq!("\x1b[38;2;1;2;256m", downgrade_str("\x1b[38;2;1;2;256m"));
+    assert_eq!("\x1b[38;2;1;2", downgrade_str("\x1b[38;2;1;2"));
+    // Sequence interrupted by a control character cannot be replaced.
+    assert_eq!("\x1b[38;2;1\n;2;3m", downgrade_str("\x1b[38;2;1\n;2;3m"));
+    // Multiple colours in a single sequence and multiple sequences.
+    let input = "zażółć \x1b[38;2;0;0;0;48;2;255;255;255mgęślą\x1b[m \
+                 \x1b[0;38;2;95;135;175mjaźń";
+    let want = "zażółć \x1b[38;5;16;48;5;231mgęślą\x1b[m \x1b[0;38;5;67mjaźń";
+    assert_eq!(want, downgrade_str(input));
+    // Aborted sequence followed by a complete one.
+    assert_eq!(
+        "\x1b[38;2\x1b[38;5;16m",
+        downgrade_str("\x1b[38;2\x1b[38;2;0;0;0m")
+    );
+    // Index of a 256-colour colour isn’t confused with a selector.
+    assert_eq!(
+        "\x1b[38;5;38;2;1;2;3m",
+        downgrade_str("\x1b[38;5;38;2;1;2;3m")
+    );
+    // Empty parameters are zeros and sub-parameters are left untouched.
+    assert_eq!("\x1b[;38;5;16;m", downgrade_str("\x1b[;38;2;;;;m"));
+    assert_eq!("\x1b[4:3;38;5;16m", downgrade_str("\x1b[4:3;38;2;0;0;0m"));
+    assert_eq!("\x1b[38;2;0:0;0m", downgrade_str("\x1b[38;2;0:0;0m"));
+    // Private sequences are left untouched.
+    assert_eq!("\x1b[?38;2;0;0;0m", downgrade_str("\x1b[?38;2;0;0;0m"));
+}
+
+/// Feeds given input to the state machine and collects the output in a fixed
+/// buffer.  Returns the output or `None` if it didn’t fit in the buffer.
+#[cfg(feature = "downgrade")]
+fn downscale<'a>(
+    mut downscaler: crate::downgrade::Downscaler,
+    input: &[u8],
+    buf: &'a mut [u8],
+) -> Option<&'a [u8]> {
+    let mut len = 0;
+    let mut push = |bytes: &[u8]| {
+        buf.get_mut(len..len + bytes.len())?.copy_from_slice(bytes);
+        len += bytes.len();
+        Some(())
+    };
+    for &byte in input {
+        push(downscaler.feed(byte))?;
+    }
+    push(downscaler.finish())?;
+    Some(&buf[..len])
+}
+
+/// Tests the state machine without allocating memory.
+#[test]
+#[cfg(feature = "downgrade")]
+fn test_downscaler_no_alloc() {
+    use crate::downgrade::{Downscaler, Target};
+
+    let mut buf = [0; 256];
+    let mut check = |want: &[u8], downscaler: Downscaler, input: &[u8]| {
+        let got = downscale(downscaler, input, &mut buf);
+        assert_eq!(Some(want), got, "{:?}", input);
+    };
+    check(
+        b"foo \x1b[38;5;67mbar\x1b[0m baz\x1b",
+        Downscaler::new(),
+        b"foo \x1b[38;2;95;135;175mbar\x1b[0m baz\x1b",
+    );
+    check(
+        b"\x1b[1;38;2;95;135;175m",
+        Downscaler::new().with_target(Target::TrueColour),
+        b"\x1b[1;38;5;67m",
+    );
+    check(
+        b"\x1b[1;34m",
+        Downscaler::new().with_target(Target::Ansi16),
+        b"\x1b[1;38;2;0;0;200m",
+    );
+    check(
+        b"a\x1b[1mb",
+        Downscaler::new().with_target(Target::Monochrome),
+        b"a\x1b[1;38;5;67mb",
+    );
+    check(b"\x1b[38;2;1;2", Downscaler::new(), b"\x1b[38;2;1;2");
+
+    // Sequences longer than the internal buffer are passed through.
+    let mut input = [b';'; 200];
+    input[..2].copy_from_slice(b"\x1b[");
+    input[2..16].copy_from_slice(b"38;2;95;135;17");
+    input[199] = b'm';
+    check(&input, Downscaler::new(), &input);
+
+    // The state machine keeps statistics.
+    let mut downscaler = Downscaler::new();
+    for &byte in b"\x1b[38;2;95;135;175mfoo\x1b[m\x1b[48;2;0;0;0m" {
+        downscaler.feed(byte);
+    }
+    let stats = downscaler.stats();
+    assert_eq!((3, 2, 2), (stats.sequences, stats.rewritten, stats.colours));
+}
+
+/// Tests rewriting escape sequences recognised with `anstyle-parse`.
+#[test]
+#[cfg(all(
+    feature = "downgrade",
+    feature = "anstyle-parse",
+    feature = "alloc"
+))]
+fn test_parse_and_rewrite_bytes() {
+    use crate::downgrade::{
+        downgrade_bytes, parse_and_rewrite_bytes, Downscaler, Target,
+    };
+
+    // Where sequences are recognised the same way, the results match those
+    // of the state machine.
+    for input in [
+        &b"\x1b]0;38;2;1;2;3m\x07"[..],
+        b"\x1b[38;2;1;2;3H",
+        b"\x1b[38;2;1;2m",
+        b"\x1b[38;2;1;2;256m",
+        b"\x1b[38;2;1;2",
+        b"\x1b[38;2;1\n;2;3m",
+        "zażółć \x1b[38;2;0;0;0;48;2;255;255;255mgęślą\x1b[m jaźń".as_bytes(),
+        b"\x1b[38;2\x1b[38;2;0;0;0m",
+        b"\x1b[38;5;38;2;1;2;3m",
+        b"\x1b[4:3;38;2;0;0;0m",
+        b"\x1b[?38;2;0;0;0m",
+        b"\xff\x1b[48;2;0;0;0m\xfe",
+    ] {
+        assert_eq!(
+            downgrade_bytes(input),
+            parse_and_rewrite_bytes(input, Downscaler::new()),
+            "{:?}",
+            input
+        );
+    }
+
+    let upscaler = Downscaler::new().with_target(Target::TrueColour);
+    assert_eq!(
+        &b"\x1b[1;38:2::95:135:175m"[..],
+        parse_and_rewrite_bytes(b"\x1b[1;38:5:67m", upscaler)
+    );
+}
+
+/// Tests that `DowngradeWriter` gives the same output as `downgrade_bytes`
+/// regardless of how the input is split into chunks.
+#[test]
+#[cfg(all(feature = "downgrade", feature = "std"))]
+fn test_downgrade_writer() {
+    use std::io::Write;
+
+    use crate::downgrade::{downgrade_bytes, DowngradeWriter};
+
+    let input = b"foo\x1b[38;2;1;2;3mbar\x1b[48;2;255;255;255mbaz\x1b[0m\x1b[1";
+    for chunk in 1..input.len() {
+        let mut writer = DowngradeWriter::new(std::vec::Vec::new());
+        for piece in input.chunks(chunk) {
+            writer.write_all(piece).unwrap();
+        }
+        writer.flush().unwrap();
+        assert!(!writer.get_ref().ends_with(b"\x1b[1"));
+        let output = writer.finish().unwrap();
+        assert_eq!(downgrade_bytes(input), output, "chunk: {}", chunk);
+    }
+}
+
+/// Tests that `DowngradeFmtWriter` gives the same output as `downgrade_str`
+/// regardless of where the input is split.
+#[test]
+#[cfg(all(feature = "downgrade", feature = "alloc"))]
+fn test_downgrade_fmt_writer() {
+    use core::fmt::Write;
+
+    use crate::downgrade::{downgrade_str, DowngradeFmtWriter};
+
+    // Multi-byte characters interrupt escape sequences so the state machine
+    // outputs them split across multiple slices.
+    let input = "zażółć\x1bżółw\x1b[ąę\x1b[38;2;1;2;3mgęślą\x1b[38;2;1;2;3\
+                 \u{1f600}\x1b[0m\x1b[1";
+    let want = downgrade_str(input);
+    for split in 0..=input.len() {
+        if !input.is_char_boundary(split) {
+            continue;
+        }
+        let mut writer = DowngradeFmtWriter::new(alloc::string::String::new());
+        writer.write_str(&input[..split]).unwrap();
+        writer.write_str(&input[split..]).unwrap();
+        assert_eq!(want, writer.finish().unwrap(), "split: {}", split);
+    }
+}
+
+/// Tests that `AsyncDowngradeWriter` gives the same output as
+/// `downgrade_bytes`.
+#[test]
+#[cfg(feature = "tokio")]
+fn test_async_downgrade_writer() {
+    use core::pin::Pin;
+    use core::task::{Context, Poll};
+    use std::vec::Vec;
+
+    use tokio::io::AsyncWrite;
+
+    use crate::downgrade::{downgrade_bytes, AsyncDowngradeWriter};
+
+    /// Writer which accepts at most three bytes at a time and is ready only
+    /// every other time it’s polled.
+    #[derive(Default)]
+    struct Throttled {
+        data: Vec<u8>,
+        ready: bool,
+    }
+
+    impl AsyncWrite for Throttled {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            data: &[u8],
+        ) -> Poll<std::io::Result<usize>> {
+            self.ready = !self.ready;
+            if !self.ready {
+                return Poll::Pending;
+            }
+            let len = data.len().min(3);
+            self.data.extend_from_slice(&data[..len]);
+            Poll::Ready(Ok(len))
+        }
+
+        fn poll_flush(
+            self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+        ) -> Poll<std::io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_shutdown(
+            self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+        ) -> Poll<std::io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    let input = b"foo\x1b[38;2;1;2;3mbar\x1b[48;2;255;255;255mbaz\x1b[0m\x1b[1";
+    let mut cx = Context::from_waker(core::task::Waker::noop());
+    let mut writer = AsyncDowngradeWriter::new(Throttled::default());
+    for chunk in input.chunks(5) {
+        let mut chunk = chunk;
+        while !chunk.is_empty() {
+            match Pin::new(&mut writer).poll_write(&mut cx, chunk) {
+                Poll::Ready(Ok(len)) => chunk = &chunk[len..],
+                Poll::Ready(Err(err)) => panic!("{}", err),
+                Poll::Pending => (),
+            }
+        }
+    }
+    while Pin::new(&mut writer).poll_shutdown(&mut cx).is_pending() {}
+    assert_eq!(downgrade_bytes(input), writer.get_ref().data);
+}
+
+/// Tests rewriting indexed colours into true-colour ones.
+#[test]
+#[cfg(all(feature = "downgrade", feature = "std"))]
+fn test_downscaler_true_colour() {
+    use crate::downgrade::{rewrite_str, Downscaler, Target};
+    use crate::SystemPreset;
+
+    let upscaler = Downscaler::new().with_target(Target::TrueColour);
+    let vga = upscaler.clone().with_system_colours(SystemPreset::Vga);
+    assert_eq!(Target::TrueColour, vga.target());
+    assert_eq!(Some(SystemPreset::Vga), vga.system_colours());
+    assert_eq!(None, upscaler.system_colours());
+    for idx in 0..=255u8 {
+        let input = std::format!("\x1b[1;48;5;{}m", idx);
+        let (r, g, b) = crate::rgb_from_ansi256(idx);
+        let want = std::format!("\x1b[1;48;2;{};{};{}m", r, g, b);
+        assert_eq!(want, rewrite_str(&input, upscaler.clone()));
+
+        let (r, g, b) = match idx {
+            0..=15 => SystemPreset::Vga.colours()[usize::from(idx)],
+            _ => crate::rgb_from_ansi256(idx),
+        };
+        let want = std::format!("\x1b[1;48;2;{};{};{}m", r, g, b);
+        assert_eq!(want, rewrite_str(&input, vga.clone()));
+    }
+
+    // True-colour colours and invalid indexes are left untouched.
+    let input = "\x1b[38;2;1;2;3;48;5;256m";
+    assert_eq!(input, rewrite_str(input, upscaler.clone()));
+
+    // Sequence which would be too long after rewriting is left untouched.
+    let input = std::format!("\x1b[{}m", "48;5;7;".repeat(15));
+    assert_eq!(input, rewrite_str(&input, upscaler));
+}
+
+/// Tests rewriting colours into the 16 system colours.
+#[test]
+#[cfg(all(feature = "downgrade", feature = "std"))]
+fn test_downscaler_ansi16() {
+    use crate::downgrade::{rewrite_str, Downscaler, Target};
+    use crate::SystemPreset;
+
+    let sgr = |idx: u8, base: u8| {
+        if idx < 8 {
+            base + idx
+        } else {
+            base + 52 + idx
+        }
+    };
+    let downscaler = Downscaler::new().with_target(Target::Ansi16);
+    let tango = downscaler.clone().with_system_colours(SystemPreset::Tango);
+    for idx in 0..=255u8 {
+        let input = std::format!("\x1b[1;38;5;{};48;5;{}m", idx, idx);
+        let sys = crate::ansi16_from_ansi256(idx);
+        let want = std::format!("\x1b[1;{};{}m", sgr(sys, 30), sgr(sys, 40));
+        assert_eq!(want, rewrite_str(&input, downscaler.clone()));
+
+        let preset = SystemPreset::Tango;
+        let sys = crate::ansi16_from_ansi256_with_preset(idx, preset);
+        let want = std::format!("\x1b[1;{};{}m", sgr(sys, 30), sgr(sys, 40));
+        assert_eq!(want, rewrite_str(&input, tango.clone()));
+
+        let (r, g, b) = crate::rgb_from_ansi256(idx);
+        let input = std::format!("\x1b[38;2;{};{};{}m", r, g, b);
+        let sys = crate::ansi16_from_rgb((r, g, b));
+        let want = std::format!("\x1b[{}m", sgr(sys, 30));
+        assert_eq!(want, rewrite_str(&input, downscaler.clone()));
+    }
+
+    // System colours specified with single parameters are left untouched.
+    let input = "\x1b[31;42;93;104m";
+    assert_eq!(input, rewrite_str(input, downscaler));
+}
+
+/// Tests removing colours and, with plain target, whole SGR sequences.
+#[test]
+#[cfg(all(feature = "downgrade", feature = "alloc"))]
+fn test_downscaler_monochrome() {
+    use crate::downgrade::{rewrite_str, Downscaler, Target};
+
+    let monochrome = Downscaler::new().with_target(Target::Monochrome);
+    let plain = Downscaler::new().with_target(Target::Plain);
+    let rewrite =
+        |downscaler: &Downscaler, input| rewrite_str(input, downscaler.clone());
+
+    // Colours are removed, other attributes and resets are kept.
+    let input = "\x1b[1;30;4;39;40;49;7;97;107;38;2;1;2;3;48;5;4m";
+    assert_eq!("\x1b[1;4;7m", rewrite(&monochrome, input));
+    assert_eq!("\x1b[0;1m", rewrite(&monochrome, "\x1b[0;1;31m"));
+    assert_eq!("\x1b[;1m", rewrite(&monochrome, "\x1b[;1;31m"));
+    assert_eq!("\x1b[m\x1b[0m", rewrite(&monochrome, "\x1b[m\x1b[0m"));
+    // Sequences with colours only are removed entirely.
+    assert_eq!(
+        "foo",
+        rewrite(&monochrome, "\x1b[31;48;2;1;2;3mfoo\x1b[39m")
+    );
+    // Invalid colours and other sequences are left untouched.
+    assert_eq!("\x1b[38;5;256m", rewrite(&monochrome, "\x1b[38;5;256m"));
+    assert_eq!(
+        "\x1b[31H\x1b[?31m",
+        rewrite(&monochrome, "\x1b[31H\x1b[?31m")
+    );
+
+    // All SGR sequences are removed.
+    assert_eq!("foobar", rewrite(&plain, "\x1b[1;31mfoo\x1b[mbar\x1b[0m"));
+    assert_eq!("\x1b[31H\x1b[?31m", rewrite(&plain, "\x1b[31H\x1b[?31m"));
+}
+
+/// Tests that colours are left untouched with passthrough target.
+#[test]
+#[cfg(all(feature = "downgrade", feature = "alloc"))]
+fn test_downscaler_passthrough() {
+    use crate::downgrade::{rewrite_str, Downscaler, Target, UnderlineColour};
+
+    let passthrough = Downscaler::new().with_target(Target::Passthrough);
+    let rewrite =
+        |downscaler: &Downscaler, input| rewrite_str(input, downscaler.clone());
+
+    for input in [
+        "\x1b[1;38;2;95;135;175;48;5;1m",
+        "\x1b[38:2::1:2:3;31;101m",
+        "\x1b[58;2;1;2;3m",
+    ] {
+        assert_eq!(input, rewrite(&passthrough, input));
+    }
+    let drop = passthrough.with_underline_colour(UnderlineColour::Drop);
+    assert_eq!(
+        "\x1b[4;38;2;1;2;3m",
+        rewrite(&drop, "\x1b[4;58;2;1;2;3;38;2;1;2;3m")
+    );
+}
+
+/// Tests choosing target based on colour depth of the terminal.
+#[test]
+#[cfg(all(feature = "detect", feature = "downgrade"))]
+fn test_target_from_depth() {
+    use crate::detect::ColourDepth;
+    use crate::downgrade::Target;
+
+    assert_eq!(Target::Monochrome, Target::from(ColourDepth::Monochrome));
+    assert_eq!(Target::Ansi16, Target::from(ColourDepth::Ansi8));
+    assert_eq!(Target::Ansi16, Target::from(ColourDepth::Ansi16));
+    assert_eq!(Target::Ansi256, Target::from(ColourDepth::Ansi256));
+    assert_eq!(Target::Passthrough, Target::from(ColourDepth::TrueColour));
+}
+
+/// Tests rewriting of colours specified with colon-separated sub-parameters.
+#[test]
+#[cfg(all(feature = "downgrade", feature = "alloc"))]
+fn test_downscaler_colon_form() {
+    use crate::downgrade::{rewrite_str, Downscaler, Separator, Target};
+
+    let rewrite =
+        |downscaler: &Downscaler, input| rewrite_str(input, downscaler.clone());
+    let ansi = Downscaler::new();
+    let colon = ansi.clone().with_separator(Separator::Colon);
+    let semicolon = ansi.clone().with_separator(Separator::Semicolon);
+
+    // With and without colour space identifier.
+    assert_eq!(
+        "\x1b[1;38:5:67m",
+        rewrite(&ansi, "\x1b[1;38:2::95:135:175m")
+    );
+    assert_eq!("\x1b[1;48:5:67m", rewrite(&ansi, "\x1b[1;48:2:95:135:175m"));
+    assert_eq!("\x1b[38:5:67m", rewrite(&ansi, "\x1b[38:2:1:95:135:175m"));
+    assert_eq!(
+        "\x1b[38;5;67m",
+        rewrite(&semicolon, "\x1b[38:2::95:135:175m")
+    );
+    assert_eq!("\x1b[38:5:67m", rewrite(&colon, "\x1b[38;2;95;135;175m"));
+    // Colours which don’t need to be rewritten keep their form.
+    assert_eq!(
+        "\x1b[38:5:67;48;5;1m",
+        rewrite(&ansi, "\x1b[38:5:67;48;5;1m")
+    );
+
+    // Other targets.
+    let target = |target, separator| {
+        Downscaler::new()
+            .with_target(target)
+            .with_separator(separator)
+    };
+    let downscaler = target(Target::TrueColour, Separator::Preserve);
+    assert_eq!(
+        "\x1b[38:2::95:135:175m",
+        rewrite(&downscaler, "\x1b[38:5:67m")
+    );
+    let downscaler = target(Target::TrueColour, Separator::Semicolon);
+    assert_eq!(
+        "\x1b[38;2;95;135;175m",
+        rewrite(&downscaler, "\x1b[38:5:67m")
+    );
+    let downscaler = target(Target::Ansi16, Separator::Colon);
+    let input = "\x1b[38:5:1;48:2::0:255:0m";
+    assert_eq!("\x1b[31;102m", rewrite(&downscaler, input));
+    let downscaler = target(Target::Monochrome, Separator::Colon);
+    let input = "\x1b[38:5:1;4:3;48:2::1:2:3m";
+    assert_eq!("\x1b[4:3m", rewrite(&downscaler, input));
+
+    // Invalid colours are left untouched.
+    for input in [
+        "\x1b[38:2:1:2m",
+        "\x1b[38:2::1:2:3:4m",
+        "\x1b[38:5:256m",
+        "\x1b[38:2::1:2:256m",
+        "\x1b[39:2::1:2:3m",
+        "\x1b[38:5;1m",
+        "\x1b[38;5:1m",
+    ] {
+        assert_eq!(input, rewrite(&ansi, input));
+        assert_eq!(input, rewrite(&colon, input));
+    }
+}
+
+/// Tests rewriting and dropping of underline colour.
+#[test]
+#[cfg(all(feature = "downgrade", feature = "alloc"))]
+fn test_downscaler_underline_colour() {
+    use crate::downgrade::{rewrite_str, Downscaler, Target, UnderlineColour};
+
+    let rewrite = |downscaler, input| rewrite_str(input, downscaler);
+    let target = |target| Downscaler::new().with_target(target);
+    let drop = |target: Target| {
+        Downscaler::new()
+            .with_target(target)
+            .with_underline_colour(UnderlineColour::Drop)
+    };
+
+    assert_eq!(
+        "\x1b[4;58;5;67m",
+        rewrite(target(Target::Ansi256), "\x1b[4;58;2;95;135;175m")
+    );
+    assert_eq!(
+        "\x1b[58:5:67m",
+        rewrite(target(Target::Ansi256), "\x1b[58:2::95:135:175m")
+    );
+    assert_eq!(
+        "\x1b[58;2;95;135;175m",
+        rewrite(target(Target::TrueColour), "\x1b[58;5;67m")
+    );
+    assert_eq!(
+        "\x1b[58;5;1;58:5:4m",
+        rewrite(target(Target::Ansi16), "\x1b[58;5;88;58:2::0:0:255m")
+    );
+    assert_eq!(
+        "\x1b[58;5;1m",
+        rewrite(target(Target::Ansi16), "\x1b[58;5;1m")
+    );
+    assert_eq!("\x1b[4;59m", rewrite(target(Target::Ansi16), "\x1b[4;59m"));
+    assert_eq!(
+        "\x1b[4m",
+        rewrite(target(Target::Monochrome), "\x1b[4;58;5;1;59m")
+    );
+    assert_eq!("", rewrite(target(Target::Monochrome), "\x1b[58;2;1;2;3m"));
+
+    for target in [Target::Ansi256, Target::TrueColour, Target::Ansi16] {
+        assert_eq!("\x1b[4;31m", rewrite(drop(target), "\x1b[4;58;5;1;31m"));
+        assert_eq!("\x1b[4:3m", rewrite(drop(target), "\x1b[4:3;58:2::1:2:3m"));
+        assert_eq!("x", rewrite(drop(target), "\x1b[59mx"));
+    }
+    assert_eq!("\x1b[m", rewrite(drop(Target::Ansi256), "\x1b[m"));
+}
+
+/// Tests handling of OSC sequences changing the palette according to OSC
+/// policy.
+#[test]
+#[cfg(all(feature = "downgrade", feature = "alloc"))]
+fn test_downscaler_osc() {
+    use crate::downgrade::{rewrite_str, Downscaler, OscPolicy, Target};
+
+    let rewrite = |downscaler, input| rewrite_str(input, downscaler);
+    let policy = |target, policy| {
+        Downscaler::new()
+            .with_target(target)
+            .with_osc_policy(policy)
+    };
+    let drop = policy(Target::Ansi256, OscPolicy::Drop);
+    let record = policy(Target::Ansi16, OscPolicy::Record);
+
+    // Passing through is the default.
+    assert_eq!(OscPolicy::Pass, Downscaler::new().osc_policy());
+    for input in [
+        "\x1b]4;1;#cc0000\x07",
+        "\x1b]10;rgb:ff/ff/ff\x1b\\",
+        "\x1b]104\x07",
+    ] {
+        assert_eq!(input, rewrite(Downscaler::new(), input));
+        assert_eq!(input, rewrite(record.clone(), input));
+        assert_eq!("", rewrite(drop.clone(), input));
+    }
+
+    // Queries, other OSC sequences, aborted and incomplete ones are passed.
+    for input in [
+        "\x1b]4;1;?\x07",
+        "\x1b]12;?\x1b\\",
+        "\x1b]0;4;1;#000\x07",
+        "\x1b]8;;http://x\x07",
+        "\x1b]41;#000\x07",
+        "\x1b]4;1;#000\x18",
+        "\x1b]4;1;#000\x1bx",
+        "\x1b]4;1;#000",
+    ] {
+        assert_eq!(input, rewrite(drop.clone(), input));
+    }
+    assert_eq!(
+        "\x1b]4;1;#000\x1b[38;5;16m",
+        rewrite(drop.clone(), "\x1b]4;1;#000\x1b[38;2;0;0;0m")
+    );
+
+    // Recorded system colours are used for approximations.
+    assert_eq!("\x1b[91m", rewrite(record.clone(), "\x1b[38;2;240;0;0m"));
+    let osc = "\x1b]4;1;rgb:ff/00/00;9;rgb:80/00/00\x07";
+    let input = std::format!("{}\x1b[38;2;240;0;0m", osc);
+    let want = std::format!("{}\x1b[31m", osc);
+    assert_eq!(want, rewrite(record.clone(), &input));
+    let osc = "\x1b]4;1;#f00;200;#f00\x07\x1b]104;1\x07";
+    let input = std::format!("{}\x1b[38;2;240;0;0m", osc);
+    let want = std::format!("{}\x1b[91m", osc);
+    assert_eq!(want, rewrite(record.clone(), &input));
+    let true_colour = policy(Target::TrueColour, OscPolicy::Record);
+    assert_eq!(
+        "\x1b]4;3;#123456\x07\x1b[48;2;18;52;86m",
+        rewrite(true_colour, "\x1b]4;3;#123456\x07\x1b[48;5;3m")
+    );
+
+    // Sequences too long to be buffered are passed through untouched.
+    let long = alloc::format!("\x1b]4;1;#f00;{}\x07x", "2;#f00;".repeat(30));
+    assert_eq!(long, rewrite(drop, &long));
+}
+
+/// Tests statistics of rewriting kept by the state machine.
+#[test]
+#[cfg(all(feature = "downgrade", feature = "std"))]
+fn test_downscaler_stats() {
+    use std::io::Write;
+
+    use crate::downgrade::{DowngradeWriter, Downscaler, Stats, Target};
+
+    let feed = |downscaler: &mut Downscaler, input: &str| {
+        for &byte in input.as_bytes() {
+            downscaler.feed(byte);
+        }
+    };
+
+    let mut downscaler = Downscaler::new();
+    assert_eq!(Stats::default(), *downscaler.stats());
+    assert_eq!(0.0, downscaler.stats().mean_error());
+    let input = "\x1b[38;2;95;135;175;48;2;0;0;0m\x1b[1m\x1b[2J";
+    feed(&mut downscaler, input);
+    let want = Stats {
+        sequences: 2,
+        rewritten: 1,
+        colours: 2,
+        total_error: 0.0,
+        max_error: 0.0,
+    };
+    assert_eq!(want, downscaler.take_stats());
+    assert_eq!(Stats::default(), *downscaler.stats());
+
+    feed(&mut downscaler, "\x1b[38;2;255;0;0m\x1b[38;2;40;40;220m");
+    let error = |rgb: u32, idx: u8| crate::distance(rgb, idx);
+    let (red, blue) = (error(0xff0000, 196), error(0x2828dc, 62));
+    let stats = downscaler.stats();
+    assert_eq!((2, 2, 2), (stats.sequences, stats.rewritten, stats.colours));
+    assert_eq!(red.max(blue), stats.max_error);
+    assert_eq!((red + blue) / 2.0, stats.mean_error());
+
+    // Upscaling is lossless.
+    let mut upscaler = Downscaler::new().with_target(Target::TrueColour);
+    feed(&mut upscaler, "\x1b[38;5;67;48;5;1m");
+    assert_eq!(2, upscaler.stats().colours);
+    assert_eq!(0.0, upscaler.stats().max_error);
+
+    // Sequence which would be too long after rewriting isn’t counted as
+    // rewritten and neither are its colours.
+    let input = std::format!("\x1b[{}m", "48;5;7;".repeat(15));
+    feed(&mut upscaler, &input);
+    let stats = upscaler.stats();
+    assert_eq!((2, 1, 2), (stats.sequences, stats.rewritten, stats.colours));
+
+    // Removed colours aren’t converted.
+    let mut writer = DowngradeWriter::with_downscaler(
+        std::vec::Vec::new(),
+        Downscaler::new().with_target(Target::Monochrome),
+    );
+    writer.write_all(b"\x1b[1;31mfoo\x1b[38;5;1m").unwrap();
+    let stats = *writer.downscaler().stats();
+    assert_eq!((2, 2, 0), (stats.sequences, stats.rewritten, stats.colours));
+    assert_eq!(stats, writer.downscaler_mut().take_stats());
+    assert_eq!(0, writer.downscaler().stats().sequences);
+}
+
+/// Tests that downgrading gives the same result regardless of how the input is
+/// split into chunks.
+#[test]
+#[cfg(all(feature = "downgrade", feature = "alloc"))]
+fn test_downscaler_chunks() {
+    use crate::downgrade::{
+        downgrade_bytes, rewrite_bytes, Downscaler, OscPolicy, Target,
+    };
+
+    let input = "zażółć \x1b[38;2;0;0;0;48;2;255;255;255mgęślą\x1b[m \
+                 \x1b\x1b[0;38;2;95;135;175mjaźń\x1b[2J\x1b]0;title\x07\
+                 \x1b]4;9;#f00\x1b\\\x1b[38;5;196m\x1b]104\x07\x1b]4;1;x\x1b\
+                 \x1b[1;38;2;1;2";
+    let input = input.as_bytes();
+    let record = Downscaler::new()
+        .with_target(Target::Ansi16)
+        .with_osc_policy(OscPolicy::Record);
+    for mut downscaler in [Downscaler::new(), record] {
+        let want = rewrite_bytes(input, downscaler.clone());
+        assert_ne!(input, &want[..]);
+
+        let mut got = alloc::vec::Vec::new();
+        for &byte in input {
+            got.extend_from_slice(downscaler.feed(byte));
+        }
+        got.extend_from_slice(downscaler.finish());
+        assert_eq!(want, got);
+
+        for split in 0..input.len() {
+            got.clear();
+            let mut sink = |bytes: &[u8]| {
+                got.extend_from_slice(bytes);
+                Ok::<(), core::convert::Infallible>(())
+            };
+            downscaler.feed_bytes(&input[..split], &mut sink).unwrap();
+            downscaler.feed_bytes(&input[split..], &mut sink).unwrap();
+            got.extend_from_slice(downscaler.finish());
+            assert_eq!(want, got, "split: {}", split);
+        }
+    }
+
+    // Sequences too long to be buffered are passed through untouched.
+    let mut input = alloc::vec::Vec::from(&b"\x1b[38;2;1;2;3"[..]);
+    input.resize(200, b';');
+    input.extend_from_slice(b"m\x1b[38;2;0;0;0mfoo");
+    let mut want = input.clone();
+    want.truncate(201);
+    want.extend_from_slice(b"\x1b[38;5;16mfoo");
+    assert_eq!(want, downgrade_bytes(&input));
+}
+
+#[cfg(feature = "proptest")]
+proptest::proptest! {
+    /// Tests that expanding RGB565 colour and packing it back gives the same
+    /// value.
+    #[test]
+    fn test_rgb565_expansion(colour: crate::Rgb565) {
+        use crate::AsRGB;
+        let rgb = colour.as_u32();
+        let packed = ((rgb >> 8) & 0xf800) |
+            ((rgb >> 5) & 0x07e0) |
+            ((rgb >> 3) & 0x001f);
+        proptest::prop_assert_eq!(colour.0, packed as u16);
+    }
+
+    /// Tests that formatting `Colour` and parsing it back gives the same
+    /// colour.
+    #[test]
+    fn test_colour_string_round_trip(colour: crate::Colour) {
+        extern crate std;
+        use std::string::ToString;
+        let string = colour.to_string();
+        proptest::prop_assert_eq!(Ok(colour), string.parse());
+    }
+}
+
+/// Tests that `Sgr` sequences match ones formatted with `format!` and that
+/// downgrading them gives sequences of downgraded colours.
+#[test]
+#[cfg(feature = "std")]
+fn test_sgr() {
+    use std::string::ToString;
+
+    #[cfg(feature = "downgrade")]
+    use crate::ColourExt;
+    use crate::{Colour, Layer, NamedColour, Sgr};
+
+    let mut buf = [0; Sgr::MAX_LEN];
+    for &(layer, selector, base) in &[
+        (Layer::Foreground, 38, Some(30)),
+        (Layer::Background, 48, Some(40)),
+        (Layer::Underline, 58, None),
+    ] {
+        for (idx, colour) in NamedColour::ALL.iter().copied().enumerate() {
+            let want = match base {
+                Some(base) => std::format!("\x1b[{}m", base + idx),
+                None => std::format!("\x1b[{};5;{}m", selector, idx),
+            };
+            assert_eq!(want, Sgr::new(layer, colour).encode(&mut buf));
+        }
+        for idx in 0..=255u8 {
+            let want = std::format!("\x1b[{};5;{}m", selector, idx);
+            assert_eq!(want, Sgr::new(layer, idx).encode(&mut buf));
+            assert_eq!(want, Sgr::new(layer, idx).to_string());
+        }
+        for &(r, g, b) in &[(0, 0, 0), (9, 10, 99), (100, 200, 255)] {
+            let want = std::format!("\x1b[{};2;{};{};{}m", selector, r, g, b);
+            assert_eq!(want, Sgr::new(layer, (r, g, b)).encode(&mut buf));
+        }
+    }
+    let longest = Sgr::underline(Colour::Rgb(255, 255, 255));
+    assert_eq!(Sgr::MAX_LEN, longest.encode(&mut buf).len());
+
+    #[cfg(feature = "downgrade")]
+    for rgb in (0..(1u32 << 24)).step_by(1048573) {
+        let colour = Colour::from(rgb);
+        let got = crate::downgrade::downgrade_str(&Sgr::bg(colour).to_string());
+        assert_eq!(Sgr::bg(colour.to_256()).to_string(), got);
+    }
+}
+
+/// Tests escape sequences `Painted` emits for each colour depth.
+#[test]
+#[cfg(all(feature = "paint", feature = "std"))]
+fn test_painted() {
+    use std::string::ToString;
+
+    use crate::{Colour, ColourDepth, NamedColour, Painted};
+
+    let paint = |depth, fg: Colour, bg: Colour| {
+        Painted::new(42)
+            .with_fg(fg)
+            .with_bg(bg)
+            .with_depth(depth)
+            .to_string()
+    };
+    let (red, rgb) = (Colour::Named(NamedColour::Red), Colour::Rgb(255, 0, 0));
+    let (grey, bright) = (Colour::Fixed(244), Colour::Fixed(12));
+
+    let depth = ColourDepth::TrueColour;
+    let want = "\x1b[38;2;255;0;0m\x1b[48;5;244m42\x1b[39;49m";
+    assert_eq!(want, paint(depth, rgb, grey));
+    let want = "\x1b[31m\x1b[48;5;12m42\x1b[39;49m";
+    assert_eq!(want, paint(depth, red, bright));
+
+    let depth = ColourDepth::Ansi256;
+    let want = "\x1b[38;5;196m\x1b[48;5;244m42\x1b[39;49m";
+    assert_eq!(want, paint(depth, rgb, grey));
+    let want = "\x1b[31m\x1b[48;5;12m42\x1b[39;49m";
+    assert_eq!(want, paint(depth, red, bright));
+
+    let depth = ColourDepth::Ansi16;
+    assert_eq!("\x1b[91m\x1b[100m42\x1b[39;49m", paint(depth, rgb, grey));
+    assert_eq!("\x1b[31m\x1b[104m42\x1b[39;49m", paint(depth, red, bright));
+
+    let depth = ColourDepth::Ansi8;
+    assert_eq!("\x1b[31m\x1b[46m42\x1b[39;49m", paint(depth, rgb, grey));
+    assert_eq!("\x1b[31m\x1b[44m42\x1b[39;49m", paint(depth, red, bright));
+
+    assert_eq!("42", paint(ColourDepth::Monochrome, rgb, grey));
+    assert_eq!("42", Painted::new(42).to_string());
+    let want = "\x1b[31m42\x1b[39m";
+    assert_eq!(want, Painted::new(42).with_fg(red).to_string());
+    let want = "\x1b[48;5;12m42\x1b[49m";
+    assert_eq!(want, Painted::new(42).with_bg(bright).to_string());
+}
+
+/// Tests that palette chart shows every colour once and has expected layout.
+#[test]
+#[cfg(all(feature = "paint", feature = "std"))]
+fn test_palette_chart() {
+    let chart = crate::palette_chart();
+    for idx in 0..=255 {
+        let cell = std::format!("\x1b[48;5;{}m{:>4}\x1b[39;49m", idx, idx);
+        assert_eq!(1, chart.matches(&cell).count(), "{}", idx);
+    }
+    let lines = chart.lines().collect::<std::vec::Vec<_>>();
+    assert_eq!(19, lines.len());
+    let want = ["System colours:", "Colour cube:", "Greyscale ramp:"];
+    assert_eq!(want, [lines[0], lines[3], lines[16]]);
+    // Second line of the cube has green level one for red levels 0–2.
+    let cells = lines[5]
+        .split("\x1b[39;49m")
+        .filter_map(|cell| cell.rsplit('m').next()?.trim().parse().ok())
+        .collect::<std::vec::Vec<u8>>();
+    let want = [
+        22, 23, 24, 25, 26, 27, 58, 59, 60, 61, 62, 63, 94, 95, 96, 97, 98, 99,
+    ];
+    assert_eq!(&want[..], &cells[..]);
+}
+
+/// Tests that `Swatch` shows colours of the palette with their index and hex
+/// value.
+#[test]
+#[cfg(all(feature = "paint", feature = "std"))]
+fn test_swatch() {
+    use std::string::ToString;
+
+    use crate::{Colour, Swatch};
+
+    for idx in 0..=255u8 {
+        let (r, g, b) = crate::rgb_from_ansi256(idx);
+        let hex = std::format!("#{:02x}{:02x}{:02x}", r, g, b);
+        let sgr = std::format!("\x1b[48;5;{}m", idx);
+        let want = std::format!("{}  \x1b[49m {:>3} {}", sgr, idx, hex);
+        assert_eq!(want, Swatch(Colour::Fixed(idx)).to_string());
+        let sgr = std::format!("\x1b[48;2;{};{};{}m", r, g, b);
+        let approx = crate::ansi256_from_rgb((r, g, b));
+        let want = std::format!("{}  \x1b[49m {:>3} {}", sgr, approx, hex);
+        assert_eq!(want, Swatch(Colour::Rgb(r, g, b)).to_string());
+    }
+}
+
+/// Tests that querying terminal stops once DA1 response is received even if
+/// more data follows it.
+#[test]
+#[cfg(feature = "terminal")]
+fn test_terminal_query_trailing_data() {
+    let mut input: &[u8] = b"\x1b]4;1;rgb:cc/00/00\x07\x1b[?62;22cabc";
+    let colours = crate::terminal::query(&mut input, &mut std::io::sink());
+    assert_eq!(1, colours.unwrap().reported);
+
+    // Incomplete DA1 response isn’t accepted.
+    let mut input: &[u8] = b"\x1b]4;1;rgb:cc/00/00\x07\x1b[?62;22";
+    let err = crate::terminal::query(&mut input, &mut std::io::sink());
+    assert_eq!(std::io::ErrorKind::UnexpectedEof, err.unwrap_err().kind());
+}
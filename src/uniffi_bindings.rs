@@ -0,0 +1,67 @@
+// ansi_colours – true-colour ↔ ANSI terminal palette converter
+// Copyright 2018 by Michał Nazarewicz <mina86@mina86.com>
+//
+// ansi_colours is free software: you can redistribute it and/or modify it
+// under the terms of the GNU Lesser General Public License as published by
+// the Free Software Foundation; either version 3 of the License, or (at
+// your option) any later version.
+//
+// ansi_colours is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Lesser
+// General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with ansi_colours.  If not, see <http://www.gnu.org/licenses/>.
+
+//! UniFFI bindings.
+//!
+//! The module exports the conversion functions through
+//! [UniFFI](https://mozilla.github.io/uniffi-rs/) so that bindings for
+//! languages such as Swift and Kotlin can be generated with `uniffi-bindgen`
+//! from a `cdylib` crate depending on this one.  The exported functions are
+//! `ansi256_from_rgb`, `ansi256_from_grey` and `rgb_from_ansi256` (named
+//! according to conventions of the target language, e.g. `ansi256FromRgb` in
+//! Swift and Kotlin) with sRGB colours represented by [`Rgb`] record.
+//!
+//! This module is present only if `uniffi` crate feature is enabled.
+
+/// An sRGB colour.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, uniffi::Record)]
+pub struct Rgb {
+    /// Red component.
+    pub r: u8,
+    /// Green component.
+    pub g: u8,
+    /// Blue component.
+    pub b: u8,
+}
+
+impl crate::AsRGB for Rgb {
+    #[inline]
+    fn as_u32(&self) -> u32 {
+        (self.r, self.g, self.b).as_u32()
+    }
+}
+
+/// Returns index of a colour in 256-colour ANSI palette approximating given
+/// sRGB colour.
+#[uniffi::export]
+pub fn ansi256_from_rgb(rgb: Rgb) -> u8 {
+    crate::ansi256_from_rgb(rgb)
+}
+
+/// Returns index of a colour in 256-colour ANSI palette approximating given
+/// shade of grey.
+#[uniffi::export]
+pub fn ansi256_from_grey(component: u8) -> u8 {
+    crate::ansi256_from_grey(component)
+}
+
+/// Returns sRGB colour corresponding to the index in the 256-colour ANSI
+/// palette.
+#[uniffi::export]
+pub fn rgb_from_ansi256(idx: u8) -> Rgb {
+    let (r, g, b) = crate::rgb_from_ansi256(idx);
+    Rgb { r, g, b }
+}
@@ -0,0 +1,70 @@
+// ansi_colours – true-colour ↔ ANSI terminal palette converter
+// Copyright 2018 by Michał Nazarewicz <mina86@mina86.com>
+//
+// ansi_colours is free software: you can redistribute it and/or modify it
+// under the terms of the GNU Lesser General Public License as published by
+// the Free Software Foundation; either version 3 of the License, or (at
+// your option) any later version.
+//
+// ansi_colours is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Lesser
+// General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with ansi_colours.  If not, see <http://www.gnu.org/licenses/>.
+
+//! WebAssembly bindings.
+//!
+//! The module exports the conversion functions to JavaScript through
+//! [wasm-bindgen](https://crates.io/crates/wasm-bindgen).  Since JavaScript
+//! has no tuples, sRGB colours are passed as `0xRRGGBB` numbers.  The
+//! exported functions are:
+//!
+//! - `ansi256FromRgb(rgb)`,
+//! - `ansi256FromComponents(r, g, b)`,
+//! - `ansi256FromGrey(component)` and
+//! - `rgbFromAnsi256(index)`.
+//!
+//! They behave exactly like the Rust functions with corresponding names.
+//!
+//! ```js
+//! import * as ansiColours from 'ansi_colours';
+//!
+//! console.assert(ansiColours.ansi256FromRgb(0x5f87af) === 67);
+//! console.assert(ansiColours.ansi256FromComponents(95, 135, 175) === 67);
+//! console.assert(ansiColours.ansi256FromGrey(128) === 244);
+//! console.assert(ansiColours.rgbFromAnsi256(67) === 0x5f87af);
+//! ```
+//!
+//! This module is present only if `wasm` crate feature is enabled.
+
+use wasm_bindgen::prelude::*;
+
+/// Returns index of a colour in 256-colour ANSI palette approximating given
+/// sRGB colour specified as a `0xRRGGBB` number.
+#[wasm_bindgen(js_name = ansi256FromRgb)]
+pub fn ansi256_from_rgb(rgb: u32) -> u8 {
+    crate::ansi256_from_rgb(rgb)
+}
+
+/// Returns index of a colour in 256-colour ANSI palette approximating given
+/// sRGB colour specified by its components.
+#[wasm_bindgen(js_name = ansi256FromComponents)]
+pub fn ansi256_from_components(r: u8, g: u8, b: u8) -> u8 {
+    crate::ansi256_from_rgb((r, g, b))
+}
+
+/// Returns index of a colour in 256-colour ANSI palette approximating given
+/// shade of grey.
+#[wasm_bindgen(js_name = ansi256FromGrey)]
+pub fn ansi256_from_grey(component: u8) -> u8 {
+    crate::ansi256_from_grey(component)
+}
+
+/// Returns sRGB colour, as a `0xRRGGBB` number, corresponding to the index in
+/// the 256-colour ANSI palette.
+#[wasm_bindgen(js_name = rgbFromAnsi256)]
+pub fn rgb_from_ansi256(idx: u8) -> u32 {
+    crate::system::palette_colour(idx)
+}
@@ -0,0 +1,190 @@
+// ansi_colours – true-colour ↔ ANSI terminal palette converter
+// Copyright 2018 by Michał Nazarewicz <mina86@mina86.com>
+//
+// ansi_colours is free software: you can redistribute it and/or modify it
+// under the terms of the GNU Lesser General Public License as published by
+// the Free Software Foundation; either version 3 of the License, or (at
+// your option) any later version.
+//
+// ansi_colours is distributed in the hope that it will be useful, but
+// WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU Lesser
+// General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with ansi_colours.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Conversions between Windows colour representations and the ANSI palette.
+//!
+//! Windows API represents RGB colours as `COLORREF` values, that is 32-bit
+//! `0x00BBGGRR` integers, which can be wrapped in [`ColorRef`] type to be used
+//! with functions of this crate.
+//!
+//! Legacy Windows console uses 16 colours described by character attributes.
+//! The four least significant bits of an attribute specify the foreground
+//! colour and next four bits the background colour.  The colours correspond to
+//! the 16 ANSI system colours but have blue and red bits swapped.
+//!
+//! This module is present only if `windows` crate feature is enabled.  Note
+//! that the feature doesn’t depend on any Windows crates and is available on
+//! all platforms.
+
+use crate::AsRGB;
+
+/// An sRGB colour represented as Windows `COLORREF` value, i.e. a 32-bit
+/// `0x00BBGGRR` integer.
+///
+/// The most significant byte of the value is ignored.
+///
+/// The type is `#[repr(transparent)]` and thus has the same layout as `u32`.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::{ansi256_from_rgb, AsRGB};
+/// use ansi_colours::windows::ColorRef;
+///
+/// assert_eq!(0x123456, ColorRef(0x00563412).as_u32());
+/// assert_eq!(0x123456, ColorRef(0xff563412).as_u32());
+///
+/// assert_eq!( 16, ansi256_from_rgb(ColorRef(0x000000)));
+/// assert_eq!( 67, ansi256_from_rgb(ColorRef(0xaf875f)));
+/// assert_eq!(231, ansi256_from_rgb(ColorRef(0xffffff)));
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "bytemuck", derive(bytemuck::Pod, bytemuck::Zeroable))]
+#[cfg_attr(
+    feature = "zerocopy",
+    derive(
+        zerocopy::FromBytes,
+        zerocopy::IntoBytes,
+        zerocopy::Immutable,
+        zerocopy::KnownLayout
+    )
+)]
+#[repr(transparent)]
+pub struct ColorRef(pub u32);
+
+impl ColorRef {
+    /// Returns colour at given index in the 256-colour ANSI palette.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ansi_colours::windows::ColorRef;
+    ///
+    /// assert_eq!(ColorRef(0x000000), ColorRef::from_ansi256( 16));
+    /// assert_eq!(ColorRef(0xaf875f), ColorRef::from_ansi256( 67));
+    /// assert_eq!(ColorRef(0xeeeeee), ColorRef::from_ansi256(255));
+    /// ```
+    #[inline]
+    pub fn from_ansi256(idx: u8) -> Self {
+        let (r, g, b) = crate::rgb_from_ansi256(idx);
+        Self(((b as u32) << 16) | ((g as u32) << 8) | (r as u32))
+    }
+}
+
+impl AsRGB for ColorRef {
+    #[inline]
+    fn as_u32(&self) -> u32 {
+        let v = self.0;
+        ((v & 0xff) << 16) | (v & 0xff00) | ((v >> 16) & 0xff)
+    }
+}
+
+/// Swaps blue and red bits of a 4-bit colour number.  This converts between
+/// ANSI system colour index and Windows console colour.
+#[inline(always)]
+fn swap_red_blue(colour: u8) -> u8 {
+    (colour & 0b1010) | ((colour & 1) << 2) | ((colour >> 2) & 1)
+}
+
+/// Returns index in the ANSI palette corresponding to given Windows console
+/// colour.
+///
+/// Only four least significant bits of the argument are considered so the
+/// returned index is always one of the 16 system colours.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::windows::ansi256_from_console_colour;
+///
+/// assert_eq!( 0, ansi256_from_console_colour(0x0));  // black
+/// assert_eq!( 4, ansi256_from_console_colour(0x1));  // blue
+/// assert_eq!( 1, ansi256_from_console_colour(0x4));  // red
+/// assert_eq!(14, ansi256_from_console_colour(0xb));  // bright cyan
+/// assert_eq!(15, ansi256_from_console_colour(0xf));  // bright white
+/// ```
+#[inline]
+pub fn ansi256_from_console_colour(colour: u8) -> u8 {
+    swap_red_blue(colour & 0xf)
+}
+
+/// Returns Windows console colour approximating colour at given index in the
+/// ANSI palette.
+///
+/// System colours (i.e. indexes below 16) are mapped directly.  Remaining
+/// colours are approximated by the closest system colour using default XTerm
+/// values of the system colours.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::windows::console_colour_from_ansi256;
+///
+/// assert_eq!(0x0, console_colour_from_ansi256(  0));  // black
+/// assert_eq!(0x1, console_colour_from_ansi256(  4));  // blue
+/// assert_eq!(0x4, console_colour_from_ansi256(  1));  // red
+/// assert_eq!(0xc, console_colour_from_ansi256(196));  // bright red
+/// assert_eq!(0xf, console_colour_from_ansi256(231));  // bright white
+/// ```
+#[inline]
+pub fn console_colour_from_ansi256(idx: u8) -> u8 {
+    swap_red_blue(crate::ansi16_from_ansi256(idx))
+}
+
+/// Returns Windows console character attributes with given foreground and
+/// background colours specified as indexes in the ANSI palette.
+///
+/// The colours are converted using [`console_colour_from_ansi256`].
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::windows::console_attributes_from_ansi256;
+///
+/// assert_eq!(0x07, console_attributes_from_ansi256(7, 0));
+/// assert_eq!(0x1f, console_attributes_from_ansi256(15, 4));
+/// assert_eq!(0xc0, console_attributes_from_ansi256(16, 196));
+/// ```
+#[inline]
+pub fn console_attributes_from_ansi256(fg: u8, bg: u8) -> u16 {
+    let fg = console_colour_from_ansi256(fg) as u16;
+    let bg = console_colour_from_ansi256(bg) as u16;
+    (bg << 4) | fg
+}
+
+/// Returns foreground and background colours, as indexes in the ANSI palette,
+/// described by given Windows console character attributes.
+///
+/// Bits of the attributes other than the eight least significant ones are
+/// ignored.
+///
+/// # Examples
+///
+/// ```
+/// use ansi_colours::windows::ansi256_from_console_attributes;
+///
+/// assert_eq!((7, 0), ansi256_from_console_attributes(0x07));
+/// assert_eq!((15, 4), ansi256_from_console_attributes(0x1f));
+/// assert_eq!((0, 9), ansi256_from_console_attributes(0x8c0));
+/// ```
+#[inline]
+pub fn ansi256_from_console_attributes(attributes: u16) -> (u8, u8) {
+    (
+        ansi256_from_console_colour(attributes as u8),
+        ansi256_from_console_colour((attributes >> 4) as u8),
+    )
+}
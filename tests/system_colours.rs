@@ -0,0 +1,158 @@
+//! Tests of the runtime override of system colours.
+//!
+//! The override is global state.  Changing it in unit tests would race with
+//! tests which expect default XTerm values so the tests are kept in this
+//! separate test binary and serialised with [`LOCK`].
+
+#![cfg(target_has_atomic = "32")]
+
+use std::sync::{Mutex, MutexGuard};
+
+use ansi_colours::*;
+
+static LOCK: Mutex<()> = Mutex::new(());
+
+/// Takes the lock serialising tests in this file and makes sure they start
+/// with default state.
+fn lock() -> MutexGuard<'static, ()> {
+    let guard = LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    reset_system_colours();
+    set_system_colours_in_approximation(false);
+    guard
+}
+
+/// Tests that functions reading the palette observe overridden system colours
+/// and return default XTerm values again after a reset.
+#[test]
+fn test_set_system_colours() {
+    let _guard = lock();
+    let xterm = SystemPreset::XTerm.colours();
+    let vga = SystemPreset::Vga.colours();
+
+    assert_eq!(xterm, system_colours());
+    assert_eq!((205, 205, 0), rgb_from_ansi256(3));
+    assert_eq!(80, luminance_from_ansi256(3));
+    assert_eq!(1, ansi16_from_rgb((180, 90, 10)));
+
+    set_system_colours(vga);
+    assert_eq!(vga, system_colours());
+    for idx in 0..16 {
+        assert_eq!(vga[usize::from(idx)], rgb_from_ansi256(idx));
+    }
+    assert!(palette().take(16).map(|(_, rgb)| rgb).eq(vga));
+    assert_eq!((175, 0, 215), rgb_from_ansi256(128));
+    assert_eq!(46, luminance_from_ansi256(3));
+    assert_eq!(70, luminance_from_ansi256(7));
+    assert_eq!(3, ansi16_from_rgb((180, 90, 10)));
+
+    reset_system_colours();
+    assert_eq!(xterm, system_colours());
+    for idx in 0..16 {
+        assert_eq!(xterm[usize::from(idx)], rgb_from_ansi256(idx));
+    }
+    assert_eq!(80, luminance_from_ansi256(3));
+    assert_eq!(1, ansi16_from_rgb((180, 90, 10)));
+}
+
+/// Tests that approximation considers overridden system colours only when
+/// enabled.
+#[test]
+fn test_system_colours_in_approximation() {
+    let _guard = lock();
+    set_system_colours(SystemPreset::Vga.colours());
+
+    assert_eq!(130, ansi256_from_rgb((170, 85, 0)));
+    set_system_colours_in_approximation(true);
+    assert_eq!(3, ansi256_from_rgb((170, 85, 0)));
+    assert_eq!(8, ansi256_from_grey(85));
+    assert_eq!(130, ansi256_from_rgb((175, 95, 0)));
+
+    reset_system_colours();
+    assert_eq!(1, ansi256_from_rgb((205, 0, 0)));
+    assert_eq!(130, ansi256_from_rgb((170, 85, 0)));
+    set_system_colours_in_approximation(false);
+    assert_eq!(160, ansi256_from_rgb((205, 0, 0)));
+}
+
+/// Tests that readers never observe a mix of old and new system colours while
+/// another thread keeps changing them.
+#[test]
+fn test_system_colours_swapped_atomically() {
+    let _guard = lock();
+    let xterm = SystemPreset::XTerm.colours();
+    let vga = SystemPreset::Vga.colours();
+
+    let writer = std::thread::spawn(move || {
+        for _ in 0..10_000 {
+            set_system_colours(vga);
+            reset_system_colours();
+        }
+    });
+    while !writer.is_finished() {
+        let colours = system_colours();
+        assert!(colours == xterm || colours == vga, "{:?}", colours);
+    }
+    writer.join().unwrap();
+    assert_eq!(xterm, system_colours());
+}
+
+/// Tests that `ansi256_from_rgb_with_error` returns the same index as
+/// `ansi256_from_rgb` when system colours are considered by the approximation
+/// and measures the error against their overridden values.
+#[test]
+fn test_with_error_uses_system_colours() {
+    let _guard = lock();
+    set_system_colours(SystemPreset::Vga.colours());
+    set_system_colours_in_approximation(true);
+
+    assert_eq!((3, 0.0), ansi256_from_rgb_with_error((170, 85, 0)));
+    for rgb in (0..0x1000000u32).step_by(0x10305) {
+        let (idx, error) = ansi256_from_rgb_with_error(rgb);
+        assert_eq!(ansi256_from_rgb(rgb), idx, "{:06x}", rgb);
+        assert_eq!(distance(rgb, rgb_from_ansi256(idx)), error, "{:06x}", rgb);
+    }
+
+    set_system_colours_in_approximation(false);
+    assert_eq!(130, ansi256_from_rgb_with_error((170, 85, 0)).0);
+}
+
+/// Tests that `ansi256_from_rgb_excluding` considers system colours, with their
+/// overridden values, when they are considered by the approximation.
+#[test]
+fn test_excluding_uses_system_colours() {
+    let _guard = lock();
+    set_system_colours(SystemPreset::Vga.colours());
+    set_system_colours_in_approximation(true);
+
+    assert_eq!(Some(3), ansi256_from_rgb_excluding((170, 85, 0), &[]));
+    assert_eq!(Some(130), ansi256_from_rgb_excluding((170, 85, 0), &[3]));
+    assert_eq!(Some(3), ansi256_from_rgb_excluding((175, 95, 0), &[130]));
+    for rgb in (0..0x1000000u32).step_by(0x10305) {
+        let idx = ansi256_from_rgb(rgb);
+        assert_eq!(Some(idx), ansi256_from_rgb_excluding(rgb, &[]));
+    }
+
+    set_system_colours_in_approximation(false);
+    assert_eq!(Some(94), ansi256_from_rgb_excluding((175, 95, 0), &[130]));
+}
+
+/// Tests that `ansi256_from_rgb_with_contrast` starts from the same index as
+/// `ansi256_from_rgb` and measures contrast of system colours using their
+/// overridden values.
+#[test]
+fn test_with_contrast_uses_system_colours() {
+    let _guard = lock();
+    set_system_colours(SystemPreset::Vga.colours());
+    set_system_colours_in_approximation(true);
+
+    let orange = (170, 85, 0);
+    assert_eq!(3, ansi256_from_rgb_with_contrast(orange, 0x000000, 1.0));
+    for rgb in (0..0x1000000u32).step_by(0x10305) {
+        let idx = ansi256_from_rgb_with_contrast(rgb, 0xffffff, 4.5);
+        let ratio = contrast_ratio(rgb_from_ansi256(idx), 0xffffffu32);
+        assert!(ratio >= 4.5, "{:06x}: {} {}", rgb, idx, ratio);
+    }
+
+    set_system_colours_in_approximation(false);
+    assert_eq!(130, ansi256_from_rgb_with_contrast(orange, 0x000000, 1.0));
+}